@@ -0,0 +1,57 @@
+//! Derive `FixedWidthCode` para enums de códigos (tabelas de domínio do layout).
+//!
+//! Cada variante (sem dados) corresponde a um código fixo do campo; exemplo na
+//! documentação de `cnab_fixedwidth::FixedWidthCode`, que reexporta a macro.
+
+use quote::quote;
+use std::collections::HashSet;
+use syn::{Data, DeriveInput, Fields};
+
+/// Gera a implementação de `FixedWidthCode` (código -> variante e variante -> código).
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => return Err(syn::Error::new_spanned(name, "FixedWidthCode só se aplica a enums")),
+    };
+
+    let mut seen = HashSet::new();
+    let mut idents = Vec::new();
+    let mut codes = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(variant, "variantes de FixedWidthCode não carregam dados"));
+        }
+        let mut code = None;
+        for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("code")) {
+            let lit: syn::LitStr = attr.parse_args()?;
+            if code.is_some() {
+                return Err(syn::Error::new_spanned(lit, "variante com mais de um #[code(...)]"));
+            }
+            if !seen.insert(lit.value()) {
+                return Err(syn::Error::new_spanned(&lit, format!("código '{}' repetido", lit.value())));
+            }
+            code = Some(lit);
+        }
+        let code = code.ok_or_else(|| syn::Error::new_spanned(&variant.ident, "variante sem #[code(\"...\")]"))?;
+        idents.push(&variant.ident);
+        codes.push(code);
+    }
+
+    Ok(quote! {
+        impl cnab_fixedwidth::FixedWidthCode for #name {
+            fn from_code(code: &str) -> Option<Self> {
+                match code {
+                    #( #codes => Some(Self::#idents), )*
+                    _ => None,
+                }
+            }
+
+            fn code(&self) -> &'static str {
+                match self {
+                    #( Self::#idents => #codes, )*
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,122 @@
+//! Geração de testes de fronteira a partir do layout (`#[fw_record(gen_tests)]`).
+//!
+//! Os testes são emitidos em um módulo `#[cfg(test)]` ao lado da struct e usam linhas
+//! montadas em tempo de compilação a partir das posições declaradas:
+//! - linha toda em branco deve ser aceita (numéricos viram 0, opcionais viram `None`);
+//! - linha uma posição menor que o fim do último campo deve falhar com `LineTooShort`
+//!   (o `width` declarado pode ir além dos campos, e o resto não é exigido);
+//! - numéricos com todos os dígitos em 9 devem caber nos tipos da struct;
+//! - com `#[fw_record(write)]`, a linha escrita a partir do parse deve ser lida e escrita
+//!   de novo sem mudanças.
+//!
+//! Os testes de linha em branco e de valores máximos não são gerados quando há campos com
+//! `parse_with`, `code` ou `bool` com `falsy` (o formato aceito pelo campo é desconhecido), compactados
+//! (`packed`, binários) ou blocos aninhados (`nested`).
+
+use crate::{FieldKindMacro, ParsedField, SignMacro};
+use quote::{format_ident, quote};
+
+/// Gera o módulo de testes para a struct `name`.
+pub(crate) fn expand(name: &syn::Ident, fields: &[ParsedField], width: Option<usize>, write: bool) -> proc_macro2::TokenStream {
+    let record_len = width.unwrap_or_else(|| fields.iter().map(|f| f.pos_end).max().unwrap_or(0));
+
+    // Linha com o maior valor de cada campo: '9' em numéricos, 'X' em textos
+    let mut max_line = vec![b' '; record_len];
+    for f in fields.iter().filter(|f| f.nested.is_none()) {
+        let fill = match f.kind {
+            FieldKindMacro::Numeric | FieldKindMacro::Decimal { .. } | FieldKindMacro::Zoned { .. } => b'9',
+            // Compactados são binários: sem testes de valores (veja `value_tests`)
+            FieldKindMacro::Packed { .. } => b' ',
+            // Com charset, o primeiro caractere aceito (ex: 'A' em "A-Z0-9")
+            FieldKindMacro::Alpha => match f.charset.as_deref().and_then(|charset| charset.bytes().next()) {
+                Some(first) if first.is_ascii() => first,
+                Some(_) => b' ',
+                None => b'X',
+            },
+            FieldKindMacro::Filler => b' ',
+        };
+        max_line[f.pos_start - 1..f.pos_end].fill(fill);
+    }
+    // Indicadores D/C como crédito, para que o valor máximo seja positivo
+    for f in fields {
+        if let SignMacro::Indicator { start, end, credit, .. } = &f.sign {
+            let region = &mut max_line[start - 1..(*end).min(record_len)];
+            region.fill(b' ');
+            let n = credit.len().min(region.len());
+            region[..n].copy_from_slice(&credit.as_bytes()[..n]);
+        }
+    }
+    let max_line = String::from_utf8(max_line).expect("linha ASCII");
+    let blank_line = " ".repeat(record_len);
+    // Fim do último campo (indicadores de sinal e blocos aninhados incluídos), sem o `width`
+    let field_ends = fields.iter().map(|f| {
+        match (&f.sign, f.nested_end()) {
+            (_, Some(nested)) => nested,
+            (SignMacro::Indicator { end, .. }, None) => {
+                let end = f.pos_end.max(*end);
+                quote!(#end)
+            }
+            (_, None) => {
+                let end = f.pos_end;
+                quote!(#end)
+            }
+        }
+    });
+
+    let value_tests = fields
+        .iter()
+        .all(|f| {
+            let strict_flag = f.flag.as_ref().is_some_and(|flag| flag.falsy.is_some());
+            f.parse_with.is_none()
+                && !f.code
+                && !strict_flag
+                && f.nested.is_none()
+                && !matches!(f.kind, FieldKindMacro::Packed { .. })
+        })
+        .then(|| quote! {
+            #[test]
+            fn blank_line_parses() {
+                #name::parse(#blank_line).expect("linha em branco deveria ser aceita");
+            }
+
+            #[test]
+            fn max_values_fit_field_types() {
+                #name::parse(#max_line).expect("valores máximos deveriam caber nos tipos dos campos");
+            }
+        });
+
+    let round_trip_tests = (write && value_tests.is_some()).then(|| quote! {
+        #[test]
+        fn written_lines_round_trip() {
+            use cnab_fixedwidth::FixedWidthWrite as _;
+            for line in [#blank_line, #max_line] {
+                let written = #name::parse(line).expect("linha do teste").to_line().expect("escrita do registro");
+                let reparsed = #name::parse(&written).expect("a linha escrita deveria ser lida");
+                assert_eq!(reparsed.to_line().expect("escrita do registro"), written);
+            }
+        }
+    });
+
+    let module = format_ident!("__fw_generated_tests_{}", name.to_string().to_lowercase());
+
+    quote! {
+        #[cfg(test)]
+        mod #module {
+            use super::*;
+            use cnab_fixedwidth::FixedWidthParse as _;
+
+            #value_tests
+
+            #round_trip_tests
+
+            #[test]
+            fn short_line_is_rejected() {
+                let last_end = [#(#field_ends),*].into_iter().max().unwrap_or(0);
+                assert!(matches!(
+                    #name::parse(&" ".repeat(last_end.saturating_sub(1))),
+                    Err(cnab_fixedwidth::FixedWidthError::LineTooShort { .. })
+                ));
+            }
+        }
+    }
+}
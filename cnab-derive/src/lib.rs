@@ -22,6 +22,10 @@
 //! ```
 
 
+mod code;
+mod gen_tests;
+mod record_enum;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput, Data, Fields};
@@ -39,6 +43,143 @@ struct ParsedField {
     pos_end: usize,
     /// Tipo de formatação CNAB (Alpha, Numeric, Decimal).
     kind: FieldKindMacro,
+    /// Campo opcional (`Option<T>` ou `#[fw(optional)]`): branco vira `None`.
+    optional: bool,
+    /// Função de conversão do usuário (`#[fw(parse_with = "path::to_fn")]`).
+    parse_with: Option<syn::Path>,
+    /// Tabela de códigos (`#[fw(code)]`): o tipo implementa `FixedWidthCode`.
+    code: bool,
+    /// Indicador booleano (`#[fw(bool, truthy = "S", falsy = "N")]`).
+    flag: Option<FlagMacro>,
+    /// Convenção de sinal (`signed` ou `sign_pos`/`credit`/`debit`).
+    sign: SignMacro,
+    /// Bloco reutilizável (`#[fw(nested, offset = N)]`): outra struct `FixedWidth`
+    /// cujas posições são relativas ao deslocamento `N`.
+    nested: Option<usize>,
+    /// Grupo repetido (`#[fw(occurs = 5, stride = 25)]`) de blocos aninhados.
+    occurs: Option<Occurs>,
+    /// Caractere de preenchimento (`#[fw(pad = '0')]`).
+    pad: Option<u8>,
+    /// Alinhamento fora da convenção do tipo (`#[fw(align = "right")]`).
+    align: Option<AlignMacro>,
+    /// Tratamento do texto na escrita (`#[fw(truncate, uppercase, strip_accents)]`).
+    policy: PolicyMacro,
+    /// Caracteres aceitos (`#[fw(charset = "A-Z0-9 ")]`).
+    charset: Option<String>,
+    /// Sentido em que o campo é usado (`#[fw(read_only)]` / `#[fw(write_only)]`).
+    direction: DirectionMacro,
+    /// Conferências do valor lido (`#[fw(validate(min = 1, max = 999))]`).
+    validate: Vec<ValidateMacro>,
+}
+
+/// Conferência aplicada ao valor do campo depois do parse.
+enum ValidateMacro {
+    /// `not_blank`: valor informado e não só espaços.
+    NotBlank,
+    /// `min = ..`/`max = ..`: faixa numérica, limites inclusivos (expressões convertidas para `f64`).
+    Range { min: Option<Box<syn::Expr>>, max: Option<Box<syn::Expr>> },
+    /// `one_of = "01,02,03"`: valores aceitos.
+    OneOf(Vec<String>),
+    /// `cpf_cnpj`: CPF ou CNPJ com DVs válidos (exige a feature `docs-br` do cnab-fixedwidth).
+    CpfCnpj,
+}
+
+/// Sentido em que um campo é usado, para structs que servem à remessa e ao retorno.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DirectionMacro {
+    Both,
+    /// Só no retorno: lido no parse, escrito em branco (zeros em numéricos).
+    ReadOnly,
+    /// Só na remessa: escrito normalmente, ignorado no parse (recebe o valor padrão).
+    WriteOnly,
+}
+
+/// Política de escrita de campos alfanuméricos, declarada no campo ou em
+/// `#[fw_record(write(...))]` (as duas se somam).
+#[derive(Clone, Copy, Default)]
+struct PolicyMacro {
+    truncate: bool,
+    uppercase: bool,
+    strip_accents: bool,
+    /// Exige a feature `translit` do cnab-fixedwidth.
+    normalize: bool,
+}
+
+impl PolicyMacro {
+    /// Lê uma das opções da política; `false` se `name` não for uma delas.
+    fn set(&mut self, name: &str) -> bool {
+        match name {
+            "truncate" => self.truncate = true,
+            "uppercase" => self.uppercase = true,
+            "strip_accents" => self.strip_accents = true,
+            "normalize" => self.normalize = true,
+            _ => return false,
+        }
+        true
+    }
+
+    fn is_set(&self) -> bool {
+        self.truncate || self.uppercase || self.strip_accents || self.normalize
+    }
+
+    fn merge(self, other: PolicyMacro) -> PolicyMacro {
+        PolicyMacro {
+            truncate: self.truncate || other.truncate,
+            uppercase: self.uppercase || other.uppercase,
+            strip_accents: self.strip_accents || other.strip_accents,
+            normalize: self.normalize || other.normalize,
+        }
+    }
+}
+
+/// Valores de um campo `bool`: `truthy` é `true`; sem `falsy`, qualquer outro valor é
+/// `false`, e com ele, valores fora dos dois são erro.
+struct FlagMacro {
+    truthy: String,
+    falsy: Option<String>,
+}
+
+/// Alinhamento declarado em `#[fw(align = "left" | "right")]`.
+#[derive(Clone, Copy)]
+enum AlignMacro {
+    Left,
+    Right,
+}
+
+/// Ocorrências de um bloco aninhado em `Vec<T>` ou `[T; N]`.
+struct Occurs {
+    /// Número de ocorrências.
+    count: usize,
+    /// Distância, em posições, entre o início de duas ocorrências.
+    stride: usize,
+    /// Tipo de cada ocorrência (a struct `FixedWidth` interna).
+    elem: syn::Type,
+    /// `[T; N]` (`true`) ou `Vec<T>` (`false`).
+    array: bool,
+}
+
+impl ParsedField {
+    /// Tipo da struct aninhada (a de cada ocorrência, em grupos repetidos).
+    fn nested_ty(&self) -> &syn::Type {
+        self.occurs.as_ref().map_or(&self.ty, |occurs| &occurs.elem)
+    }
+
+    /// Fim (posição inclusiva) do bloco aninhado, calculado pelo compilador.
+    fn nested_end(&self) -> Option<proc_macro2::TokenStream> {
+        let offset = self.nested?;
+        let last = offset + self.occurs.as_ref().map_or(0, |o| (o.count - 1) * o.stride);
+        let ty = self.nested_ty();
+        Some(quote!(#last + <#ty as cnab_fixedwidth::RecordSpan>::END))
+    }
+}
+
+/// Convenção de sinal de campos numéricos/decimais.
+enum SignMacro {
+    Unsigned,
+    /// `#[fw(signed)]`: sinal no próprio campo.
+    Inline,
+    /// `#[fw(sign_pos = "x..y", credit = "C", debit = "D")]`: indicador D/C em outra posição.
+    Indicator { start: usize, end: usize, credit: String, debit: String },
 }
 
 /// Representação interna dos tipos de campos suportados pela macro.
@@ -46,13 +187,97 @@ enum FieldKindMacro {
     Alpha,
     Numeric,
     Decimal { scale: u8 },
+    Zoned { scale: u8 },
+    Packed { scale: u8 },
+    Filler,
+}
+
+/// Opções declaradas na própria struct (ex: `#[fw(width = 240)]`).
+#[derive(Default)]
+struct RecordOptions {
+    /// Tamanho do registro, usado para resolver posições abertas (`"230..*"`).
+    width: Option<usize>,
+    /// Rejeita linhas com tamanho diferente de `width` (`#[fw(width = 240, strict)]`).
+    strict: bool,
+    /// Posições contadas em caracteres, não em bytes (`#[fw(unit = "chars")]`).
+    chars: bool,
+    /// Campos da chave de negócio (`#[fw_record(key(fields = "a, b"))]`).
+    key: Option<(syn::LitStr, Vec<syn::Ident>)>,
+    /// Posição do tipo de registro em enums (`#[fw(tag_pos = "8..8")]`).
+    tag_pos: Option<(usize, usize)>,
+    /// Gera testes de fronteira do layout (`#[fw_record(gen_tests)]`).
+    gen_tests: bool,
+    /// Gera `parse_lenient`, que coleta os erros de todos os campos (`#[fw_record(lenient)]`).
+    lenient: bool,
+    /// Implementa `FixedWidthWrite` (`#[fw_record(write)]`).
+    write: bool,
+    /// Política de escrita dos campos alfanuméricos (`#[fw_record(write(truncate, uppercase))]`).
+    policy: PolicyMacro,
 }
 
+/// Se o tipo for `Option<T>`, retorna `T`.
+///
+/// A detecção é sintática (último segmento do caminho é `Option`); para aliases
+/// use `#[fw(optional)]`.
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    generic_inner(ty, "Option")
+}
+
+/// Extrai `T` de `Vec<T>` (grupos repetidos).
+fn vec_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    generic_inner(ty, "Vec")
+}
+
+/// Extrai o argumento de um tipo genérico de um parâmetro (ex: `Option<T>`).
+fn generic_inner<'a>(ty: &'a syn::Type, wrapper: &str) -> Option<&'a syn::Type> {
+    let syn::Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Verifica se o tipo é `&str` (campo emprestado da linha no parse sem alocação).
+fn is_str_ref(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Reference(r) if r.mutability.is_none() && matches!(&*r.elem, syn::Type::Path(p) if p.path.is_ident("str")))
+}
+
+/// Valida o tipo de um grupo repetido: `Vec<T>`, ou `[T; N]` com `N` igual a `occurs`.
+fn occurs_of(ty: &syn::Type, count: usize, stride: usize) -> syn::Result<Occurs> {
+    if let Some(elem) = vec_inner(ty) {
+        return Ok(Occurs { count, stride, elem: elem.clone(), array: false });
+    }
+    if let syn::Type::Array(array) = ty {
+        let len = match &array.len {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit), .. }) => lit.base10_parse::<usize>()?,
+            len => return Err(syn::Error::new_spanned(len, "tamanho do array deve ser um literal (igual a occurs)")),
+        };
+        if len != count {
+            return Err(syn::Error::new_spanned(
+                &array.len,
+                format!("array de {} elementos para occurs = {}", len, count),
+            ));
+        }
+        return Ok(Occurs { count, stride, elem: (*array.elem).clone(), array: true });
+    }
+    Err(syn::Error::new_spanned(ty, "grupo repetido (occurs) exige Vec<T> ou [T; N]"))
+}
+
+/// Posição como escrita no atributo; `end` é `None` para posições abertas (`"230..*"`).
+type RawPos = (usize, Option<usize>);
+
 /// Helper para parsear a string de posição "start..end".
 ///
-/// Espera o formato "1..10" (inclusive).
+/// Espera o formato "1..10" (inclusive), ou "230..*" para ir até o fim do registro.
 /// Retorna erro se o formato for inválido ou se start for 0.
-fn parse_pos(lit: &syn::LitStr) -> syn::Result<(usize, usize)> {
+fn parse_pos(lit: &syn::LitStr) -> syn::Result<RawPos> {
     let s = lit.value();
     let parts: Vec<_> = s.split("..").collect();
 
@@ -60,38 +285,191 @@ fn parse_pos(lit: &syn::LitStr) -> syn::Result<(usize, usize)> {
         return Err(syn::Error::new_spanned(lit, "pos deve estar no formato start..end"));
     }
     let start = parts[0].parse::<usize>().map_err(|_| syn::Error::new_spanned(lit, "start inválido"))?;
+    if start == 0 {
+        return Err(syn::Error::new_spanned(lit, "pos inválido: start deve ser >=1 e end >= start"));
+    }
+    if parts[1] == "*" {
+        return Ok((start, None));
+    }
     let end = parts[1].parse::<usize>().map_err(|_| syn::Error::new_spanned(lit, "end inválido"))?;
 
-    if start == 0 || end < start {
+    if end < start {
         return Err(syn::Error::new_spanned(lit, "pos inválido: start deve ser >=1 e end >= start"));
     }
-    Ok((start, end))
+    Ok((start, Some(end)))
+}
+
+/// Valida a sintaxe de `charset`: não vazio e com faixas em ordem (`A-Z`, não `Z-A`).
+fn check_charset(lit: &syn::LitStr) -> syn::Result<()> {
+    let chars: Vec<char> = lit.value().chars().collect();
+    if chars.is_empty() {
+        return Err(syn::Error::new_spanned(lit, "charset vazio"));
+    }
+    for window in chars.windows(3) {
+        if let [first, '-', last] = window
+            && first > last
+        {
+            return Err(syn::Error::new_spanned(lit, format!("faixa inválida em charset: {first}-{last}")));
+        }
+    }
+    Ok(())
+}
+
+/// Lê os atributos `#[fw(...)]` e `#[fw_record(...)]` declarados na struct.
+fn parse_record_options(attrs: &[syn::Attribute]) -> syn::Result<RecordOptions> {
+    let mut options = RecordOptions::default();
+
+    for attr in attrs {
+        if attr.path().is_ident("fw_record") {
+            attr.parse_nested_meta(|meta| {
+                let name = meta.path.get_ident().map(|i| i.to_string());
+                match name.as_deref() {
+                    // Atributo: key(fields = "nosso_numero, numero_documento")
+                    Some("key") => meta.parse_nested_meta(|inner| {
+                        if !inner.path.is_ident("fields") {
+                            return Err(syn::Error::new_spanned(inner.path, "use key(fields = \"a, b\")"));
+                        }
+                        let lit: syn::LitStr = inner.value()?.parse()?;
+                        let idents = lit
+                            .value()
+                            .split(',')
+                            .map(|f| syn::parse_str::<syn::Ident>(f.trim()))
+                            .collect::<syn::Result<Vec<_>>>()
+                            .map_err(|_| syn::Error::new_spanned(&lit, "lista de campos inválida"))?;
+                        if idents.is_empty() {
+                            return Err(syn::Error::new_spanned(&lit, "a chave precisa de ao menos um campo"));
+                        }
+                        options.key = Some((lit, idents));
+                        Ok(())
+                    })?,
+                    // Atributo: gen_tests
+                    Some("gen_tests") => options.gen_tests = true,
+                    // Atributo: lenient
+                    Some("lenient") => options.lenient = true,
+                    // Atributo: write ou write(truncate, uppercase, strip_accents)
+                    Some("write") => {
+                        options.write = true;
+                        if meta.input.peek(syn::token::Paren) {
+                            meta.parse_nested_meta(|inner| {
+                                let name = inner.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+                                if !options.policy.set(&name) {
+                                    return Err(syn::Error::new_spanned(
+                                        inner.path,
+                                        "use write(truncate, uppercase, strip_accents, normalize)",
+                                    ));
+                                }
+                                Ok(())
+                            })?;
+                        }
+                    }
+                    _ => return Err(syn::Error::new_spanned(meta.path, "atributo fw_record desconhecido")),
+                }
+                Ok(())
+            })?;
+        }
+
+        if attr.path().is_ident("fw") {
+            attr.parse_nested_meta(|meta| {
+                let name = meta.path.get_ident().map(|i| i.to_string());
+                match name.as_deref() {
+                    // Atributo: width = 240
+                    Some("width") => {
+                        let lit: syn::LitInt = meta.value()?.parse()?;
+                        options.width = Some(lit.base10_parse::<usize>()?);
+                    }
+                    // Atributo: strict (exige linha exatamente com `width` posições)
+                    Some("strict") => options.strict = true,
+                    // Atributo: unit = "chars" (posições em caracteres, para arquivos convertidos para UTF-8)
+                    Some("unit") => {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        options.chars = match lit.value().as_str() {
+                            "bytes" => false,
+                            "chars" => true,
+                            _ => return Err(syn::Error::new_spanned(lit, "unit deve ser \"bytes\" ou \"chars\"")),
+                        };
+                    }
+                    // Atributo: tag_pos = "8..8" (enums de registros)
+                    Some("tag_pos") => {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        match parse_pos(&lit)? {
+                            (start, Some(end)) => options.tag_pos = Some((start, end)),
+                            (_, None) => return Err(syn::Error::new_spanned(lit, "tag_pos não aceita posição aberta")),
+                        }
+                    }
+                    _ => return Err(syn::Error::new_spanned(meta.path, "atributo fw de struct desconhecido")),
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    if options.strict && options.width.is_none() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "strict exige o tamanho do registro: #[fw(width = N, strict)]",
+        ));
+    }
+
+    Ok(options)
 }
 
 // --- A MACRO ---
 
 /// Ponto de entrada da Macro Derive.
 ///
-/// Esta função:
+/// Em enums, gera o despacho por tipo de registro (veja `record_enum`).
+/// Em structs, esta função:
 /// 1. Lê a struct de entrada.
 /// 2. Itera sobre os campos procurando atributos `#[fw(...)]`.
 /// 3. Valida se há sobreposição de posições.
 /// 4. Gera o código Rust que implementa `FixedWidthParse`.
-#[proc_macro_derive(FixedWidth, attributes(fw))]
+#[proc_macro_derive(FixedWidth, attributes(fw, fw_record))]
 pub fn derive_fixed_width(input: TokenStream) -> TokenStream {
     // 1. Parse da entrada (Código do usuário)
     let input = parse_macro_input!(input as DeriveInput);
+
+    expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Implementa `FixedWidthCode` para um enum de códigos, com `#[code("1")]` em cada variante.
+///
+/// Campos do enum são declarados com `#[fw(pos = "18..18", code)]` na struct do registro.
+#[proc_macro_derive(FixedWidthCode, attributes(code))]
+pub fn derive_fixed_width_code(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    code::expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Gera a implementação, propagando erros de atributos como erros de compilação.
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = &input.ident;
+    let record = parse_record_options(&input.attrs)?;
 
-    // Garante que é aplicado apenas em Structs com campos nomeados
+    // Garante que é aplicado apenas em Structs com campos nomeados (ou enums de registros)
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
             Fields::Named(fields) => &fields.named,
-            _ => return syn::Error::new_spanned(&input.ident, "Apenas campos nomeados suportados").to_compile_error().into(),
+            _ => return Err(syn::Error::new_spanned(&input.ident, "Apenas campos nomeados suportados")),
         },
-        _ => return syn::Error::new_spanned(&input.ident, "Apenas structs suportadas").to_compile_error().into(),
+        Data::Enum(data) => return record_enum::expand(input, data, record.tag_pos),
+        _ => return Err(syn::Error::new_spanned(&input.ident, "Apenas structs e enums suportados")),
     };
 
+    // Struct com tempo de vida: parse sem alocação (`FixedWidthParseRef`), com os campos
+    // `&'a str` apontando para a linha
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let lifetime = input.generics.lifetimes().next().map(|param| &param.lifetime);
+    if input.generics.params.len() > usize::from(lifetime.is_some()) {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "apenas um parâmetro de tempo de vida é suportado (parse sem alocação)",
+        ));
+    }
+    if lifetime.is_some() && (record.lenient || record.gen_tests) {
+        return Err(syn::Error::new_spanned(name, "lenient e gen_tests não se aplicam a structs com tempo de vida"));
+    }
+
     let mut parsed_fields = Vec::new();
 
     // 2. Extração dos Metadados
@@ -100,6 +478,26 @@ pub fn derive_fixed_width(input: TokenStream) -> TokenStream {
         let ty = field.ty.clone();
         let mut pos = None;
         let mut kind = None;
+        let mut optional = option_inner(&ty).is_some();
+        let mut parse_with = None;
+        let mut code = None;
+        let mut flag = None;
+        let mut truthy = None;
+        let mut falsy = None;
+        let mut signed = false;
+        let mut sign_pos = None;
+        let mut credit = None;
+        let mut debit = None;
+        let mut nested = false;
+        let mut offset = None;
+        let mut occurs = None;
+        let mut stride = None;
+        let mut pad = None;
+        let mut align = None;
+        let mut policy = PolicyMacro::default();
+        let mut charset = None;
+        let mut direction = None;
+        let mut validate = Vec::new();
 
         // Itera sobre os atributos do campo (ex: #[fw(...)])
         for attr in &field.attrs {
@@ -121,24 +519,373 @@ pub fn derive_fixed_width(input: TokenStream) -> TokenStream {
                             let lit: syn::LitInt = meta.value()?.parse()?;
                             kind = Some(FieldKindMacro::Decimal { scale: lit.base10_parse::<u8>()? });
                         }
+                        // Atributo: zoned = 2 (decimal com sinal sobreposto, de mainframe)
+                        Some("zoned") => {
+                            let lit: syn::LitInt = meta.value()?.parse()?;
+                            kind = Some(FieldKindMacro::Zoned { scale: lit.base10_parse::<u8>()? });
+                        }
+                        // Atributo: packed = 2 (decimal compactado COMP-3, binário)
+                        Some("packed") => {
+                            let lit: syn::LitInt = meta.value()?.parse()?;
+                            kind = Some(FieldKindMacro::Packed { scale: lit.base10_parse::<u8>()? });
+                        }
+                        // Atributo: filler (região ignorada no parse)
+                        Some("filler") => kind = Some(FieldKindMacro::Filler),
+                        // Atributo: pad = '0' (caractere de preenchimento)
+                        Some("pad") => {
+                            let lit: syn::LitChar = meta.value()?.parse()?;
+                            if !lit.value().is_ascii() {
+                                return Err(syn::Error::new_spanned(lit, "pad deve ser um caractere ASCII"));
+                            }
+                            pad = Some((lit.value() as u8, lit));
+                        }
+                        // Atributo: align = "right" (lado do conteúdo; o preenchimento vai do outro)
+                        Some("align") => {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            align = Some(match lit.value().as_str() {
+                                "left" => (AlignMacro::Left, lit),
+                                "right" => (AlignMacro::Right, lit),
+                                _ => return Err(syn::Error::new_spanned(lit, "align deve ser \"left\" ou \"right\"")),
+                            });
+                        }
+                        // Atributos: truncate, uppercase, strip_accents, normalize (tratamento do texto na escrita)
+                        Some(flag @ ("truncate" | "uppercase" | "strip_accents" | "normalize")) => {
+                            policy.set(flag);
+                        }
+                        // Atributo: charset = "A-Z0-9 " (caracteres aceitos no conteúdo)
+                        Some("charset") => {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            check_charset(&lit)?;
+                            charset = Some(lit);
+                        }
+                        // Atributos: read_only / write_only (campo usado em um só sentido)
+                        Some(name @ ("read_only" | "write_only")) => {
+                            if direction.is_some() {
+                                return Err(syn::Error::new_spanned(meta.path, "use read_only ou write_only, não ambos"));
+                            }
+                            let value = if name == "read_only" { DirectionMacro::ReadOnly } else { DirectionMacro::WriteOnly };
+                            direction = Some((value, meta.path.clone()));
+                        }
+                        // Atributo: optional (força branco -> None, ex: aliases de Option)
+                        Some("optional") => optional = true,
+                        // Atributo: signed (aceita `-`/`+` no início ou no fim)
+                        Some("signed") => signed = true,
+                        // Atributo: sign_pos = "25..25" (indicador D/C do valor)
+                        Some("sign_pos") => {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            match parse_pos(&lit)? {
+                                (start, Some(end)) => sign_pos = Some((lit, start, end)),
+                                (_, None) => return Err(syn::Error::new_spanned(lit, "sign_pos não aceita posição aberta")),
+                            }
+                        }
+                        // Atributo: credit = "C" (valor do indicador que significa crédito)
+                        Some("credit") => {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            credit = Some(lit);
+                        }
+                        // Atributo: debit = "D" (valor do indicador escrito em negativos)
+                        Some("debit") => {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            debit = Some(lit);
+                        }
+                        // Atributo: nested (campo é outra struct FixedWidth)
+                        Some("nested") => nested = true,
+                        // Atributo: offset = 17 (posição 1 do bloco vira a posição 18 do registro)
+                        Some("offset") => {
+                            let lit: syn::LitInt = meta.value()?.parse()?;
+                            offset = Some(lit.base10_parse::<usize>()?);
+                        }
+                        // Atributo: occurs = 5 (bloco aninhado repetido em Vec<T> ou [T; 5])
+                        Some("occurs") => {
+                            let lit: syn::LitInt = meta.value()?.parse()?;
+                            occurs = Some((lit.base10_parse::<usize>()?, lit));
+                        }
+                        // Atributo: stride = 25 (distância entre o início de duas ocorrências)
+                        Some("stride") => {
+                            let lit: syn::LitInt = meta.value()?.parse()?;
+                            stride = Some((lit.base10_parse::<usize>()?, lit));
+                        }
+                        // Atributo: code (enum de códigos que implementa FixedWidthCode)
+                        Some("code") => code = Some(meta.path.clone()),
+                        // Atributo: bool (indicador S/N, A/N, 1/0...)
+                        Some("bool") => flag = Some(meta.path.clone()),
+                        // Atributo: truthy = "S" (valor que significa `true`; padrão "S")
+                        Some("truthy") => truthy = Some(meta.value()?.parse::<syn::LitStr>()?),
+                        // Atributo: falsy = "N" (valor que significa `false`; os demais viram erro)
+                        Some("falsy") => falsy = Some(meta.value()?.parse::<syn::LitStr>()?),
+                        // Atributo: parse_with = "path::to_fn" (fn(&str) -> Result<T, E>)
+                        Some("parse_with") => {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            parse_with = Some(lit.parse::<syn::Path>()?);
+                        }
+                        // Atributos: validate(min = 1, max = 999, one_of = "01,02", not_blank, cpf_cnpj)
+                        // ou validate = "cpf_cnpj" (conferências do valor lido)
+                        Some("validate") => {
+                            if meta.input.peek(syn::Token![=]) {
+                                let lit: syn::LitStr = meta.value()?.parse()?;
+                                if lit.value() != "cpf_cnpj" {
+                                    return Err(syn::Error::new_spanned(lit, "validador desconhecido (use \"cpf_cnpj\")"));
+                                }
+                                validate.push((ValidateMacro::CpfCnpj, meta.path.clone()));
+                                return Ok(());
+                            }
+                            let (mut min, mut max) = (None, None);
+                            meta.parse_nested_meta(|inner| {
+                                let name = inner.path.get_ident().map(|i| i.to_string());
+                                match name.as_deref() {
+                                    Some("not_blank") => validate.push((ValidateMacro::NotBlank, inner.path.clone())),
+                                    Some("cpf_cnpj") => validate.push((ValidateMacro::CpfCnpj, inner.path.clone())),
+                                    Some("min") => min = Some(Box::new(inner.value()?.parse::<syn::Expr>()?)),
+                                    Some("max") => max = Some(Box::new(inner.value()?.parse::<syn::Expr>()?)),
+                                    Some("one_of") => {
+                                        let lit: syn::LitStr = inner.value()?.parse()?;
+                                        let items: Vec<String> = lit.value().split(',').map(|item| item.trim().to_string()).collect();
+                                        if items.iter().any(String::is_empty) {
+                                            return Err(syn::Error::new_spanned(lit, "one_of espera valores separados por vírgula (ex: \"01,02\")"));
+                                        }
+                                        validate.push((ValidateMacro::OneOf(items), inner.path.clone()));
+                                    }
+                                    _ => {
+                                        return Err(syn::Error::new_spanned(
+                                            inner.path,
+                                            "use validate(min = .., max = .., one_of = \"..\", not_blank, cpf_cnpj)",
+                                        ));
+                                    }
+                                }
+                                Ok(())
+                            })?;
+                            if min.is_some() || max.is_some() {
+                                validate.push((ValidateMacro::Range { min, max }, meta.path.clone()));
+                            }
+                        }
                         _ => return Err(syn::Error::new_spanned(meta.path, "atributo fw desconhecido")),
                     }
                     Ok(())
-                }).expect("parse failed");
+                })?;
+            }
+        }
+
+        // Bloco aninhado: sem pos/tipo próprios, a extensão vem da struct interna
+        // (em grupos repetidos, `occurs` já implica `nested`)
+        if nested || offset.is_some() || occurs.is_some() || stride.is_some() {
+            let own_layout =
+                pos.is_some() || kind.is_some() || optional || parse_with.is_some() || code.is_some() || flag.is_some();
+            let formatting = signed
+                || sign_pos.is_some()
+                || pad.is_some()
+                || align.is_some()
+                || policy.is_set()
+                || charset.is_some()
+                || direction.is_some()
+                || !validate.is_empty();
+            if !(nested || occurs.is_some()) || own_layout || formatting {
+                return Err(syn::Error::new_spanned(
+                    &ident,
+                    "bloco aninhado usa apenas #[fw(nested, offset = N)] em um campo de struct FixedWidth (sem Option)",
+                ));
             }
+            if lifetime.is_some() {
+                return Err(syn::Error::new_spanned(&ident, "blocos aninhados não se aplicam a structs com tempo de vida"));
+            }
+            let offset = offset.unwrap_or(0);
+            let occurs = match (occurs, stride) {
+                (None, None) => None,
+                (None, Some((_, lit))) => return Err(syn::Error::new_spanned(lit, "stride exige occurs = N")),
+                (Some((_, lit)), None) => {
+                    return Err(syn::Error::new_spanned(lit, "occurs exige stride = N (distância entre ocorrências)"));
+                }
+                (Some((0, lit)), _) | (_, Some((0, lit))) => {
+                    return Err(syn::Error::new_spanned(lit, "occurs e stride devem ser maiores que zero"));
+                }
+                (Some((count, _)), Some((stride, _))) => Some(occurs_of(&ty, count, stride)?),
+            };
+            parsed_fields.push(ParsedField {
+                ident,
+                ty,
+                pos_start: offset + 1,
+                pos_end: offset + 1,
+                kind: FieldKindMacro::Filler,
+                optional: false,
+                parse_with: None,
+                code: false,
+                flag: None,
+                sign: SignMacro::Unsigned,
+                nested: Some(offset),
+                occurs,
+                pad: None,
+                align: None,
+                policy: PolicyMacro::default(),
+                charset: None,
+                direction: DirectionMacro::Both,
+                validate: Vec::new(),
+            });
+            continue;
         }
 
         // Valida se os atributos obrigatórios foram preenchidos
-        let (start, end) = pos.expect("campo sem pos definido (ex: pos = \"1..10\")");
-        let kind = kind.expect("campo sem tipo definido (use alpha, numeric ou decimal)");
+        let (start, end) = pos.ok_or_else(|| {
+            syn::Error::new_spanned(&ident, "campo sem pos definido (ex: pos = \"1..10\")")
+        })?;
+        // Com parse_with o campo é texto: a conversão fica a cargo da função do usuário
+        let kind = match (kind, &parse_with) {
+            (None, Some(_)) | (Some(FieldKindMacro::Alpha), Some(_)) => FieldKindMacro::Alpha,
+            (Some(_), Some(path)) => {
+                return Err(syn::Error::new_spanned(path, "parse_with recebe o texto do campo; use alpha ou omita o tipo"));
+            }
+            (Some(kind), None) => kind,
+            (None, None) if code.is_some() || flag.is_some() => FieldKindMacro::Alpha,
+            (None, None) => {
+                return Err(syn::Error::new_spanned(
+                    &ident,
+                    "campo sem tipo definido (use alpha, numeric, decimal, zoned, packed ou filler)",
+                ));
+            }
+        };
+        // Códigos são lidos como texto e convertidos pela tabela do enum
+        if let Some(path) = &code
+            && (parse_with.is_some() || !matches!(kind, FieldKindMacro::Alpha))
+        {
+            return Err(syn::Error::new_spanned(path, "code lê o texto do campo: use alpha ou omita o tipo, sem parse_with"));
+        }
+        let code = code.is_some();
+        // Booleanos também são texto, comparado com truthy/falsy
+        let flag = match (flag, truthy, falsy) {
+            (None, Some(lit), _) | (None, None, Some(lit)) => {
+                return Err(syn::Error::new_spanned(lit, "truthy/falsy exigem #[fw(bool)]"));
+            }
+            (None, None, None) => None,
+            (Some(path), truthy, falsy) => {
+                if code || parse_with.is_some() || !matches!(kind, FieldKindMacro::Alpha) {
+                    return Err(syn::Error::new_spanned(path, "bool lê o texto do campo: use alpha ou omita o tipo, sem parse_with/code"));
+                }
+                for lit in truthy.iter().chain(&falsy) {
+                    if end.is_some_and(|end| lit.value().len() > end - start + 1) {
+                        return Err(syn::Error::new_spanned(lit, "valor maior que a largura do campo"));
+                    }
+                }
+                Some(FlagMacro {
+                    truthy: truthy.map_or_else(|| "S".to_string(), |lit| lit.value()),
+                    falsy: falsy.map(|lit| lit.value()),
+                })
+            }
+        };
+
+        // Sinal: só em numéricos/decimais, e um único formato por campo
+        if sign_pos.is_none()
+            && let Some(lit) = credit.as_ref().or(debit.as_ref())
+        {
+            return Err(syn::Error::new_spanned(lit, "credit e debit exigem sign_pos"));
+        }
+        let sign = match (signed, sign_pos) {
+            (false, None) => SignMacro::Unsigned,
+            (true, None) => SignMacro::Inline,
+            (false, Some((lit, start, end))) => {
+                if let Some(width) = record.width
+                    && end > width
+                {
+                    return Err(syn::Error::new_spanned(lit, format!("sign_pos fora do registro de {} posições", width)));
+                }
+                let credit = credit.map(|c| c.value()).unwrap_or_else(|| "C".to_string());
+                let debit = debit.map(|d| d.value()).unwrap_or_else(|| "D".to_string());
+                SignMacro::Indicator { start, end, credit, debit }
+            }
+            (true, Some((lit, ..))) => {
+                return Err(syn::Error::new_spanned(lit, "use signed ou sign_pos, não ambos"));
+            }
+        };
+        if !matches!(sign, SignMacro::Unsigned)
+            && !matches!(kind, FieldKindMacro::Numeric | FieldKindMacro::Decimal { .. })
+        {
+            return Err(syn::Error::new_spanned(&ident, "signed/sign_pos só se aplicam a campos numeric ou decimal"));
+        }
+
+        // Preenchimento/alinhamento: só em campos de texto ou dígitos (zoned e packed têm formato próprio)
+        let pad = match pad {
+            Some((_, lit)) if matches!(kind, FieldKindMacro::Zoned { .. } | FieldKindMacro::Packed { .. }) => {
+                return Err(syn::Error::new_spanned(lit, "pad não se aplica a campos zoned ou packed"));
+            }
+            pad => pad.map(|(byte, _)| byte),
+        };
+        let align = match align {
+            Some((_, lit)) if !matches!(kind, FieldKindMacro::Alpha | FieldKindMacro::Numeric | FieldKindMacro::Decimal { .. }) => {
+                return Err(syn::Error::new_spanned(lit, "align só se aplica a campos alpha, numeric ou decimal"));
+            }
+            align => align.map(|(align, _)| align),
+        };
+        // Política de escrita: só em texto; a da struct vale para todos os campos alpha
+        let policy = match kind {
+            FieldKindMacro::Alpha => policy.merge(record.policy),
+            _ if policy.is_set() => {
+                return Err(syn::Error::new_spanned(&ident, "truncate/uppercase/strip_accents/normalize só se aplicam a campos alpha"));
+            }
+            _ => policy,
+        };
+        let charset = match charset {
+            Some(lit) if !matches!(kind, FieldKindMacro::Alpha) => {
+                return Err(syn::Error::new_spanned(lit, "charset só se aplica a campos alpha"));
+            }
+            charset => charset.map(|lit| lit.value()),
+        };
+
+        // Sentido único: só faz sentido em campos com dados, e write_only exige a escrita
+        let direction = match direction {
+            None => DirectionMacro::Both,
+            Some((_, path)) if matches!(kind, FieldKindMacro::Filler) => {
+                return Err(syn::Error::new_spanned(path, "read_only/write_only não se aplicam a fillers"));
+            }
+            Some((DirectionMacro::WriteOnly, path)) if !record.write => {
+                return Err(syn::Error::new_spanned(path, "write_only exige #[fw_record(write)]"));
+            }
+            Some((direction, _)) => direction,
+        };
+
+        // Conferências: faixas só em números; valores aceitos e CPF/CNPJ em texto ou número
+        for (check, path) in &validate {
+            let message = match check {
+                _ if matches!(kind, FieldKindMacro::Filler) => "validate não se aplica a fillers",
+                ValidateMacro::Range { .. } if matches!(kind, FieldKindMacro::Alpha) => {
+                    "min/max só se aplicam a campos numeric, decimal, zoned ou packed"
+                }
+                ValidateMacro::OneOf(_) | ValidateMacro::CpfCnpj
+                    if !matches!(kind, FieldKindMacro::Alpha | FieldKindMacro::Numeric) =>
+                {
+                    "one_of/cpf_cnpj só se aplicam a campos alpha ou numeric"
+                }
+                _ => continue,
+            };
+            return Err(syn::Error::new_spanned(path, message));
+        }
+        let validate = validate.into_iter().map(|(check, _)| check).collect();
+
+        // Resolve posições abertas ("230..*") contra o tamanho declarado na struct
+        let end = match (end, record.width) {
+            (Some(end), _) => end,
+            (None, Some(width)) => width,
+            (None, None) => {
+                return Err(syn::Error::new_spanned(
+                    &ident,
+                    "pos aberto (\"start..*\") exige o tamanho do registro na struct: #[fw(width = N)]",
+                ));
+            }
+        };
+        if let Some(width) = record.width
+            && (start > end || end > width)
+        {
+            return Err(syn::Error::new_spanned(
+                &ident,
+                format!("campo '{}' ocupa {}..{}, fora do registro de {} posições", ident, start, end, width),
+            ));
+        }
 
-        parsed_fields.push(ParsedField { ident, ty, pos_start: start, pos_end: end, kind });
+        parsed_fields.push(ParsedField { ident, ty, pos_start: start, pos_end: end, kind, optional, parse_with, code, flag, sign, nested: None, occurs: None, pad, align, policy, charset, direction, validate });
     }
 
     // 3. Validação de Sobreposição (Overlap Check)
     // Compara cada campo com todos os campos subsequentes para garantir integridade.
-    for (i, f1) in parsed_fields.iter().enumerate() {
-        for f2 in &parsed_fields[i + 1..] {
+    // Blocos aninhados são verificados em tempo de compilação do código gerado (passo 8).
+    let direct_fields: Vec<_> = parsed_fields.iter().filter(|f| f.nested.is_none()).collect();
+    for (i, f1) in direct_fields.iter().enumerate() {
+        for f2 in &direct_fields[i + 1..] {
 
             let overlap_start = std::cmp::max(f1.pos_start, f2.pos_start);
             let overlap_end = std::cmp::min(f1.pos_end, f2.pos_end);
@@ -155,16 +902,17 @@ pub fn derive_fixed_width(input: TokenStream) -> TokenStream {
                     )
                 );
 
-                return err.to_compile_error().into();
+                return Err(err);
             }
         }
     }
 
     // --- GERAÇÃO DO CÓDIGO FINAL ---
 
-    // 4. Gera o vetor de FieldSpec (Definição do Layout)
-    // Isso cria o `vec![ FieldSpec { ... }, ... ]` que será usado em tempo de execução.
-    let field_specs = parsed_fields.iter().map(|f| {
+    // 4. Gera a lista estática de FieldSpec (Definição do Layout)
+    // Isso cria o `const FIELDS: &[FieldSpec] = &[ FieldSpec::new(...), ... ]`,
+    // montado em tempo de compilação (nenhuma alocação por chamada de `parse`).
+    let field_specs = direct_fields.iter().map(|f| {
         let name = f.ident.to_string(); // String em compile-time
         let start = f.pos_start;
         let end = f.pos_end;
@@ -173,69 +921,743 @@ pub fn derive_fixed_width(input: TokenStream) -> TokenStream {
             FieldKindMacro::Alpha => quote!(cnab_fixedwidth::FieldKind::Alpha),
             FieldKindMacro::Numeric => quote!(cnab_fixedwidth::FieldKind::Numeric),
             FieldKindMacro::Decimal { scale } => quote!(cnab_fixedwidth::FieldKind::Decimal { scale: #scale }),
+            FieldKindMacro::Zoned { scale } => quote!(cnab_fixedwidth::FieldKind::Zoned { scale: #scale }),
+            FieldKindMacro::Packed { scale } => quote!(cnab_fixedwidth::FieldKind::Packed { scale: #scale }),
+            FieldKindMacro::Filler => quote!(cnab_fixedwidth::FieldKind::Filler),
+        };
+
+        let optional = f.optional.then(|| quote!(.optional()));
+        let pad = f.pad.map(|pad| quote!(.pad(#pad)));
+        let align = f.align.map(|align| match align {
+            AlignMacro::Left => quote!(.align(cnab_fixedwidth::Align::Left)),
+            AlignMacro::Right => quote!(.align(cnab_fixedwidth::Align::Right)),
+        });
+        let charset = f.charset.as_ref().map(|charset| quote!(.charset(#charset)));
+        let policy = f.policy.is_set().then(|| {
+            let PolicyMacro { truncate, uppercase, strip_accents, normalize } = f.policy;
+            let truncate = truncate.then(|| quote!(.truncate()));
+            let uppercase = uppercase.then(|| quote!(.uppercase()));
+            let strip_accents = strip_accents.then(|| quote!(.strip_accents()));
+            // `normalize()` só existe com a feature `translit` do cnab-fixedwidth
+            let normalize = normalize.then(|| quote!(.normalize()));
+            quote! {
+                .write_policy(cnab_fixedwidth::WritePolicy::DEFAULT #truncate #uppercase #strip_accents #normalize)
+            }
+        });
+        let sign = match &f.sign {
+            SignMacro::Unsigned => quote!(),
+            SignMacro::Inline => quote!(.signed()),
+            SignMacro::Indicator { start, end, credit, debit } => quote! {
+                .sign_indicator_with(cnab_fixedwidth::FieldPos::new_const(#start, #end), #credit, #debit)
+            },
         };
 
         // Note o uso de `#name` direto, resultando em &'static str no código final
         quote! {
-            cnab_fixedwidth::FieldSpec {
-                name: #name,
-                pos: cnab_fixedwidth::FieldPos { start: #start, end: #end },
-                kind: #kind,
-            }
+            cnab_fixedwidth::FieldSpec::new(
+                #name,
+                cnab_fixedwidth::FieldPos::new_const(#start, #end),
+                #kind,
+            )#optional #sign #pad #align #policy #charset
         }
     });
 
-    // 5. Gera a inicialização da Struct (Mapeamento Value -> Struct Field)
+    // 5. Gera a expressão de cada campo (Mapeamento Value -> Struct Field)
     // Converte os valores genéricos (Value::Numeric) para os tipos concretos (u32, i64, f64).
-    let field_inits = parsed_fields.iter().map(|f| {
-        let ident = &f.ident;
-        let name = ident.to_string();
-        let ty = &f.ty;
+    // Índice de cada campo direto em FIELDS (blocos aninhados não têm FieldSpec próprio)
+    let spec_indexes: Vec<_> = parsed_fields
+        .iter()
+        .scan(0usize, |next, f| {
+            Some(f.nested.is_none().then(|| {
+                *next += 1;
+                *next - 1
+            }))
+        })
+        .collect();
+    let field_values: Vec<_> = parsed_fields.iter().zip(&spec_indexes).map(|(f, index)| {
+        let name = f.ident.to_string();
+
+        // Bloco aninhado: a struct interna lê a linha a partir do deslocamento
+        if let Some(offset) = f.nested {
+            let ty = f.nested_ty();
+            let parse_at = |offset: proc_macro2::TokenStream| quote! {
+                <#ty as cnab_fixedwidth::FixedWidthParse>::parse_bytes(
+                    cnab_fixedwidth::nested_line(line, #offset, options)?,
+                    &options.nested(),
+                )
+                .map_err(|e| e.shifted(#offset))
+            };
+            return match &f.occurs {
+                None => {
+                    let parse = parse_at(quote!(#offset));
+                    quote!(#parse?)
+                }
+                // Grupo repetido: cada ocorrência começa `stride` posições após a anterior
+                Some(Occurs { count, stride, array: false, .. }) => {
+                    let parse = parse_at(quote!(offset));
+                    quote! {
+                        {
+                            let mut items = Vec::with_capacity(#count);
+                            for i in 0..#count {
+                                let offset = #offset + i * #stride;
+                                items.push(#parse?);
+                            }
+                            items
+                        }
+                    }
+                }
+                Some(Occurs { count, stride, array: true, .. }) => {
+                    let items = (0..*count).map(|i| {
+                        let parse = parse_at(quote!(offset));
+                        let offset = offset + i * stride;
+                        quote!({ let offset = #offset; #parse? })
+                    });
+                    quote!([ #(#items),* ])
+                }
+            };
+        }
+        let index = index.expect("campo direto tem FieldSpec");
 
-        match f.kind {
+        // Campo só de remessa: não é lido, recebe o valor padrão do tipo
+        if f.direction == DirectionMacro::WriteOnly {
+            return quote!(::core::default::Default::default());
+        }
+
+        // Em campos opcionais a conversão mira o tipo interno de `Option<T>`.
+        // Em aliases (`#[fw(optional)]`) o tipo interno é inferido pelo compilador.
+        let inner = option_inner(&f.ty);
+        let target = match (f.optional, inner) {
+            (false, _) => { let ty = &f.ty; quote!(#ty) }
+            (true, Some(inner)) => quote!(#inner),
+            (true, None) => quote!(_),
+        };
+        let target_name = match (f.optional, inner) {
+            (true, Some(inner)) => quote!(stringify!(#inner)),
+            _ => { let ty = &f.ty; quote!(stringify!(#ty)) }
+        };
+
+        // Conversão do usuário: recebe o texto bruto do campo; branco em opcional vira `None`
+        if let Some(path) = &f.parse_with {
+            let convert = quote! {
+                #path(&text).map_err(|e| cnab_fixedwidth::FixedWidthError::Conversion {
+                    field: #name,
+                    message: e.to_string(),
+                })?
+            };
+            return if f.optional {
+                quote! {
+                    {
+                        let text = cnab_fixedwidth::field_text(line, &FIELDS[#index], options)?;
+                        if text.trim().is_empty() { None } else { Some(#convert) }
+                    }
+                }
+            } else {
+                quote! {
+                    {
+                        let text = cnab_fixedwidth::field_text(line, &FIELDS[#index], options)?;
+                        #convert
+                    }
+                }
+            };
+        }
+
+        // Parse sem alocação: `&'a str` aponta direto para a linha; branco em opcional vira `None`
+        let borrowed = if f.optional { inner } else { Some(&f.ty) }.is_some_and(is_str_ref);
+        if lifetime.is_some() && borrowed && matches!(f.kind, FieldKindMacro::Alpha) && !f.code && f.flag.is_none() {
+            let text = quote!(cnab_fixedwidth::field_str(text_line, &FIELDS[#index], options)?);
+            return if f.optional {
+                quote!({ let text = #text; if text.is_empty() { None } else { Some(text) } })
+            } else {
+                text
+            };
+        }
+
+        let convert = match f.kind {
+            // Código desconhecido é erro, com o texto lido para diagnóstico
+            // Indicador: o texto é comparado com truthy/falsy
+            FieldKindMacro::Alpha if f.flag.is_some() => {
+                let FlagMacro { truthy, falsy } = f.flag.as_ref().expect("campo bool");
+                let falsy = match falsy {
+                    Some(falsy) => quote! {
+                        if code == #falsy {
+                            false
+                        } else {
+                            return Err(cnab_fixedwidth::FixedWidthError::UnknownCode { field: #name, code });
+                        }
+                    },
+                    None => quote!(false),
+                };
+                quote! {
+                    {
+                        let code = value.into_string().ok_or(cnab_fixedwidth::FixedWidthError::InvalidUtf8)?;
+                        if code == #truthy { true } else { #falsy }
+                    }
+                }
+            }
+            FieldKindMacro::Alpha if f.code => quote! {
+                {
+                    let code = value.into_string().ok_or(cnab_fixedwidth::FixedWidthError::InvalidUtf8)?;
+                    <#target as cnab_fixedwidth::FixedWidthCode>::from_code(&code)
+                        .ok_or(cnab_fixedwidth::FixedWidthError::UnknownCode { field: #name, code })?
+                }
+            },
             FieldKindMacro::Alpha => quote! {
-                // Extrai string, garante UTF-8 válido e converte para String owned
-                #ident: parsed[#name].as_str()
+                // Extrai a String owned já decodificada (sem cópia)
+                value.into_string()
                     .ok_or(cnab_fixedwidth::FixedWidthError::InvalidUtf8)?
-                    .to_string()
             },
-            FieldKindMacro::Numeric => quote! {
-                // Extrai i64 e faz cast para o tipo do campo (ex: u32, i32, usize)
+            FieldKindMacro::Numeric | FieldKindMacro::Packed { scale: 0 } => quote! {
+                // Extrai i64 e converte para o tipo do campo (ex: u32, i32, usize) via `TryFrom`,
+                // sem truncar silenciosamente valores fora da faixa do tipo.
                 // Se falhar o tipo no core (ex: Alpha onde devia ser Num), retorna erro InvalidNumeric
-                #ident: parsed[#name].as_i64().ok_or(
-                    cnab_fixedwidth::FixedWidthError::InvalidNumeric {
-                        field: #name,
-                        snippet: String::new(),
-                    }
-                )? as #ty
+                {
+                    let value = value.as_i64().ok_or(
+                        cnab_fixedwidth::FixedWidthError::InvalidNumeric {
+                            field: #name,
+                            snippet: String::new(),
+                        }
+                    )?;
+                    // Com `options.saturate`, valores fora da faixa viram o limite do tipo
+                    cnab_fixedwidth::saturating_try_from::<#target>(
+                        value,
+                        &FIELDS[#index],
+                        options,
+                        cnab_fixedwidth::FixedWidthError::NumericOverflow {
+                            field: #name,
+                            value,
+                            target_type: #target_name,
+                        },
+                    )?
+                }
             },
-            FieldKindMacro::Decimal { scale: _ } => quote! {
-                // Extrai f64 (já ajustado pela escala no core)
-                #ident: parsed[#name].as_f64().ok_or(
-                    cnab_fixedwidth::FixedWidthError::InvalidNumeric {
-                        field: #name,
-                        snippet: String::new(),
-                    }
-                )?
+            FieldKindMacro::Decimal { .. } | FieldKindMacro::Zoned { .. } | FieldKindMacro::Packed { .. } => quote! {
+                // Extrai inteiro bruto + escala e converte para o tipo do campo
+                // (f64, ou rust_decimal::Decimal sem perdas) via `FromDecimal`
+                {
+                    let (raw, scale) = value.as_decimal_parts().ok_or(
+                        cnab_fixedwidth::FixedWidthError::InvalidNumeric {
+                            field: #name,
+                            snippet: String::new(),
+                        }
+                    )?;
+                    <#target as cnab_fixedwidth::FromDecimal>::from_decimal(raw, scale)
+                }
             },
+            FieldKindMacro::Filler => {
+                // Filler não carrega dados: só valida o tamanho da linha,
+                // e o campo recebe o valor padrão do seu tipo
+                return quote! {
+                    {
+                        cnab_fixedwidth::parse_field(line, &FIELDS[#index], options)?;
+                        ::core::default::Default::default()
+                    }
+                };
+            }
+        };
+
+        // Fatia e converte o campo direto da linha, sem HashMap intermediário
+        if f.optional {
+            // Campo opcional: `Value::Null` (branco) vira `None`
+            quote! {
+                match cnab_fixedwidth::parse_field(line, &FIELDS[#index], options)? {
+                    cnab_fixedwidth::Value::Null => None,
+                    value => Some(#convert),
+                }
+            }
+        } else {
+            quote! {
+                {
+                    let value = cnab_fixedwidth::parse_field(line, &FIELDS[#index], options)?;
+                    #convert
+                }
+            }
+        }
+    }).collect();
+    // Conferências declaradas em `validate`, sobre o valor já convertido
+    let field_values: Vec<_> = parsed_fields
+        .iter()
+        .zip(field_values)
+        .map(|(f, value)| {
+            if f.validate.is_empty() || f.direction == DirectionMacro::WriteOnly {
+                return value;
+            }
+            let name = f.ident.to_string();
+            let checks = f.validate.iter().map(|check| match check {
+                ValidateMacro::NotBlank => quote!(cnab_fixedwidth::validation::not_blank(#name, &value)?;),
+                ValidateMacro::Range { min, max } => {
+                    // Inteiros sem sufixo viram `i128`, para não estourar o `i32` padrão
+                    let bound = |expr: &Option<Box<syn::Expr>>| match expr.as_deref() {
+                        Some(syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. })) if int.suffix().is_empty() => {
+                            let int = syn::LitInt::new(&format!("{}i128", int.base10_digits()), int.span());
+                            quote!(::core::option::Option::Some(cnab_fixedwidth::validation::Number::Int(#int)))
+                        }
+                        Some(expr) => quote!(::core::option::Option::Some(cnab_fixedwidth::validation::Number::from(#expr))),
+                        None => quote!(::core::option::Option::None),
+                    };
+                    let (min, max) = (bound(min), bound(max));
+                    quote!(cnab_fixedwidth::validation::range(#name, &value, #min, #max)?;)
+                }
+                ValidateMacro::OneOf(items) => quote!(cnab_fixedwidth::validation::one_of(#name, &value, &[#(#items),*])?;),
+                ValidateMacro::CpfCnpj => quote!(cnab_fixedwidth::docs_br::check_field(#name, &value)?;),
+            });
+            quote!({ let value = #value; #(#checks)* value })
+        })
+        .collect();
+    let field_specs: Vec<_> = field_specs.collect();
+    let field_idents: Vec<_> = parsed_fields.iter().map(|f| &f.ident).collect();
+
+    // 6. Chave de negócio opcional (Eq/Hash baseados apenas nos campos da chave)
+    let business_key = match &record.key {
+        Some((lit, key_fields)) => {
+            let mut key_types = Vec::new();
+            for key in key_fields {
+                let field = fields
+                    .iter()
+                    .find(|f| f.ident.as_ref() == Some(key))
+                    .ok_or_else(|| syn::Error::new_spanned(lit, format!("campo '{}' não existe na struct", key)))?;
+                key_types.push(&field.ty);
+            }
+
+            quote! {
+                impl #impl_generics #name #ty_generics #where_clause {
+                    /// Chave de negócio do registro, declarada em `#[fw_record(key(...))]`.
+                    pub fn business_key(&self) -> ( #(&#key_types,)* ) {
+                        ( #(&self.#key_fields,)* )
+                    }
+                }
+
+                impl #impl_generics ::core::cmp::PartialEq for #name #ty_generics #where_clause {
+                    fn eq(&self, other: &Self) -> bool {
+                        self.business_key() == other.business_key()
+                    }
+                }
+
+                impl #impl_generics ::core::cmp::Eq for #name #ty_generics #where_clause {}
+
+                impl #impl_generics ::core::hash::Hash for #name #ty_generics #where_clause {
+                    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                        self.business_key().hash(state);
+                    }
+                }
+            }
+        }
+        None => quote!(),
+    };
+
+    // 7. Testes gerados a partir do layout (opcional)
+    if record.gen_tests && record.width.is_none() && parsed_fields.iter().any(|f| f.nested.is_some()) {
+        return Err(syn::Error::new_spanned(name, "gen_tests com blocos aninhados exige #[fw(width = N)]"));
+    }
+    let generated_tests = if record.gen_tests {
+        gen_tests::expand(name, &parsed_fields, record.width, record.write)
+    } else {
+        quote!()
+    };
+
+    // 8. Tamanho declarado do registro: constante pública e, em modo strict, validação da linha
+    let width_const = record.width.map(|width| quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Tamanho do registro declarado em `#[fw(width = N)]`.
+            pub const WIDTH: usize = #width;
         }
     });
+    // Extensão do layout (maior posição usada), para uso da struct como bloco aninhado
+    let span_parts = direct_fields
+        .iter()
+        .map(|f| {
+            let end = match &f.sign {
+                SignMacro::Indicator { end, .. } => f.pos_end.max(*end),
+                _ => f.pos_end,
+            };
+            quote!(#end)
+        })
+        .chain(record.width.map(|width| quote!(#width)))
+        .chain(parsed_fields.iter().filter_map(ParsedField::nested_end));
+    // Posições declaradas, para a auditoria de lacunas (`spec_gaps`)
+    let mapped_direct = direct_fields.iter().flat_map(|f| {
+        let (start, end) = (f.pos_start, f.pos_end);
+        let sign = match &f.sign {
+            SignMacro::Indicator { start, end, .. } => Some(quote!(cnab_fixedwidth::FieldPos::new_const(#start, #end))),
+            _ => None,
+        };
+        std::iter::once(quote!(cnab_fixedwidth::FieldPos::new_const(#start, #end))).chain(sign)
+    });
+    let mapped_nested = parsed_fields.iter().filter_map(|f| {
+        let offset = f.nested?;
+        let ty = f.nested_ty();
+        let (count, stride) = f.occurs.as_ref().map_or((1, 0), |o| (o.count, o.stride));
+        let offsets = (0..count).map(|i| offset + i * stride);
+        Some(quote! {
+            for offset in [#(#offsets),*] {
+                mapped.extend(
+                    <#ty as cnab_fixedwidth::RecordSpan>::mapped()
+                        .into_iter()
+                        .map(|pos| cnab_fixedwidth::FieldPos::new_const(pos.start() + offset, pos.end() + offset)),
+                );
+            }
+        })
+    });
+    let span = quote! {
+        impl #impl_generics cnab_fixedwidth::RecordSpan for #name #ty_generics #where_clause {
+            const END: usize = {
+                let mut end = 0;
+                #( end = cnab_fixedwidth::const_max(end, #span_parts); )*
+                end
+            };
 
-    // 6. Bloco final de implementação
-    quote! {
-        impl cnab_fixedwidth::FixedWidthParse for #name {
-            fn parse(line: &str) -> cnab_fixedwidth::Result<Self> {
-                // Criação da lista de especificações (barato pois são literais estáticos)
-                let fields = vec![ #(#field_specs),* ];
+            fn mapped() -> Vec<cnab_fixedwidth::FieldPos> {
+                #[allow(unused_mut)]
+                let mut mapped = vec![ #(#mapped_direct),* ];
+                #(#mapped_nested)*
+                mapped
+            }
+        }
 
-                // Chamada ao parser genérico do Core
-                let parsed = cnab_fixedwidth::parse_line(line, &fields)?;
+        impl #impl_generics cnab_fixedwidth::FixedWidthSpec for #name #ty_generics #where_clause {
+            fn spec() -> &'static [cnab_fixedwidth::FieldSpec] {
+                const FIELDS: &[cnab_fixedwidth::FieldSpec] = &[ #(#field_specs),* ];
+                FIELDS
+            }
 
-                // Construção da Struct segura
-                Ok(Self {
-                    #(#field_inits),*
-                })
+            fn total_width() -> usize {
+                <Self as cnab_fixedwidth::RecordSpan>::END
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Faixas do registro não cobertas por nenhum campo ou filler.
+            pub fn spec_gaps() -> Vec<cnab_fixedwidth::FieldPos> {
+                cnab_fixedwidth::spec_gaps(
+                    <Self as cnab_fixedwidth::RecordSpan>::mapped(),
+                    <Self as cnab_fixedwidth::RecordSpan>::END,
+                )
+            }
+        }
+    };
+
+    // Blocos aninhados: sobreposição e limite do registro verificados em tempo de compilação,
+    // pois a extensão da struct interna só é conhecida pelo compilador
+    let nested_checks = parsed_fields.iter().filter_map(|f| {
+        let offset = f.nested?;
+        let nested_end = f.nested_end()?;
+        let overlaps = parsed_fields.iter().filter(|other| other.ident != f.ident).map(|other| {
+            let message = format!("Conflito de Posição detectado! O bloco aninhado '{}' sobrepõe o campo '{}'", f.ident, other.ident);
+            // Início exclusivo (posição anterior ao campo), para comparar só com `<`
+            let (other_before, other_end) = match (other.nested, other.nested_end()) {
+                (Some(other_offset), Some(other_end)) => (quote!(#other_offset), other_end),
+                _ => {
+                    let (before, end) = (other.pos_start - 1, other.pos_end);
+                    (quote!(#before), quote!(#end))
+                }
+            };
+            quote! {
+                assert!(!(#offset < #other_end && #other_before < #nested_end), #message);
+            }
+        });
+        let width_check = record.width.map(|width| {
+            let message = format!("bloco aninhado '{}' ultrapassa o registro de {} posições", f.ident, width);
+            quote!(assert!(#nested_end <= #width, #message);)
+        });
+        // Ocorrências de um grupo repetido não podem se sobrepor umas às outras
+        let stride_check = f.occurs.as_ref().filter(|o| o.count > 1).map(|occurs| {
+            let (stride, ty) = (occurs.stride, &occurs.elem);
+            let message = format!("ocorrências de '{}' se sobrepõem: stride = {} menor que o bloco", f.ident, stride);
+            quote!(assert!(<#ty as cnab_fixedwidth::RecordSpan>::END <= #stride, #message);)
+        });
+        Some(quote! {
+            const _: () = {
+                #(#overlaps)*
+                #width_check
+                #stride_check
+            };
+        })
+    });
+
+    // Capacidade do tipo: um numérico com mais dígitos do que o tipo inteiro comporta
+    // (ex: 20 dígitos em u32) nunca faria parse de valores cheios do campo
+    let capacity_checks = direct_fields.iter().filter_map(|f| {
+        let digits = match f.kind {
+            FieldKindMacro::Numeric => f.pos_end - f.pos_start + 1,
+            FieldKindMacro::Packed { scale: 0 } => (f.pos_end - f.pos_start + 1) * 2 - 1,
+            _ => return None,
+        };
+        let ty = if f.optional { option_inner(&f.ty)? } else { &f.ty };
+        let ident = match ty {
+            syn::Type::Path(path) if path.qself.is_none() => path.path.get_ident()?,
+            _ => return None,
+        };
+        const INTEGERS: &[&str] = &["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize"];
+        if !INTEGERS.contains(&ident.to_string().as_str()) {
+            return None;
+        }
+        let message = format!(
+            "campo '{}' tem {} dígitos, mais do que o tipo {} comporta",
+            f.ident, digits, ident
+        );
+        Some(quote! {
+            assert!(#digits <= cnab_fixedwidth::max_digits(#ident::MAX as u128), #message);
+        })
+    });
+    let capacity_checks = quote! {
+        const _: () = {
+            #(#capacity_checks)*
+        };
+    };
+
+    // Posições em caracteres: vale para qualquer `ParseOptions` recebido
+    let unit_override = record.chars.then(|| quote! {
+        let options = &cnab_fixedwidth::ParseOptions { unit: cnab_fixedwidth::PositionUnit::Chars, ..options.clone() };
+    });
+
+    let strict_check = match (record.strict, record.width) {
+        (true, Some(width)) => quote! {
+            let len = cnab_fixedwidth::record_len(line, options.unit);
+            if len != #width {
+                return Err(cnab_fixedwidth::FixedWidthError::WrongLength { len, expected: #width });
+            }
+        },
+        _ => quote!(),
+    };
+    // Largura declarada sem `strict`: o excesso no fim da linha pode virar aviso
+    let trailing_check = match (record.strict, record.width) {
+        (false, Some(width)) => quote!(cnab_fixedwidth::check_trailing(line, #width, options)?;),
+        _ => quote!(),
+    };
+
+    // 9. Modo leniente: cada campo com erro recebe o valor padrão e o erro é coletado
+    let lenient = record.lenient.then(|| {
+        let field_types = parsed_fields.iter().map(|f| &f.ty);
+        let field_errors = parsed_fields.iter().zip(&spec_indexes).map(|(f, index)| match (f.nested, index) {
+            (Some(offset), _) => {
+                let name = f.ident.to_string();
+                let nested_end = f.nested_end();
+                quote! {
+                    cnab_fixedwidth::FieldError {
+                        field: #name,
+                        pos: cnab_fixedwidth::FieldPos::new_const(
+                            #offset + 1,
+                            #nested_end,
+                        ),
+                        kind: ::core::option::Option::None,
+                        error,
+                    }
+                }
+            }
+            (None, Some(index)) => quote!(cnab_fixedwidth::FieldError::new(&FIELDS[#index], error)),
+            (None, None) => unreachable!("campo direto tem FieldSpec"),
+        });
+        quote! {
+            impl #name {
+                /// Faz o parse de uma linha já decodificada (UTF-8) sem parar no primeiro erro.
+                ///
+                /// Veja [`Self::parse_lenient_bytes`].
+                pub fn parse_lenient(line: &str) -> (Self, Vec<cnab_fixedwidth::FieldError>) {
+                    Self::parse_lenient_bytes(
+                        line.as_bytes(),
+                        &cnab_fixedwidth::ParseOptions::with_encoding(cnab_fixedwidth::Encoding::Utf8),
+                    )
+                }
+
+                /// Faz o parse de todos os campos, mesmo com erros: campos inválidos recebem
+                /// o valor padrão do tipo e os problemas são devolvidos na ordem do layout.
+                ///
+                /// A validação de tamanho de `strict` não se aplica; campos que não couberem
+                /// na linha aparecem como `LineTooShort`.
+                pub fn parse_lenient_bytes(
+                    line: &[u8],
+                    options: &cnab_fixedwidth::ParseOptions,
+                ) -> (Self, Vec<cnab_fixedwidth::FieldError>) {
+                    const FIELDS: &[cnab_fixedwidth::FieldSpec] = &[ #(#field_specs),* ];
+
+                    #unit_override
+                    let line = cnab_fixedwidth::trim_record(line, options);
+                    let mut errors = Vec::new();
+                    let record = Self {
+                        #(
+                            #field_idents: match (|| -> cnab_fixedwidth::Result<#field_types> { Ok(#field_values) })() {
+                                Ok(value) => value,
+                                Err(error) => {
+                                    errors.push(#field_errors);
+                                    ::core::default::Default::default()
+                                }
+                            }
+                        ),*
+                    };
+                    (record, errors)
+                }
+            }
+        }
+    });
+
+    // 10. Escrita (opcional): cada campo formatado na sua posição, sobre uma linha em branco
+    let write = if record.write {
+        if record.chars {
+            return Err(syn::Error::new_spanned(name, "#[fw_record(write)] não suporta unit = \"chars\""));
+        }
+        if let Some(f) = parsed_fields.iter().find(|f| f.parse_with.is_some() && f.direction != DirectionMacro::ReadOnly) {
+            return Err(syn::Error::new_spanned(
+                &f.ident,
+                "campos com parse_with não suportam #[fw_record(write)] (exceto read_only)",
+            ));
+        }
+        let writes = parsed_fields.iter().zip(&spec_indexes).map(|(f, index)| {
+            let ident = &f.ident;
+            match (f.nested, &f.occurs, index) {
+                (Some(offset), None, _) => quote! {
+                    cnab_fixedwidth::writer::write_nested(
+                        &mut line,
+                        #offset,
+                        &cnab_fixedwidth::FixedWidthWrite::write_bytes(&self.#ident, options)?,
+                    )?;
+                },
+                (Some(offset), Some(occurs), _) => {
+                    let (count, stride) = (occurs.count, occurs.stride);
+                    let name = ident.to_string();
+                    let len_check = (!occurs.array).then(|| quote! {
+                        if self.#ident.len() > #count {
+                            return Err(cnab_fixedwidth::FixedWidthError::Conversion {
+                                field: #name,
+                                message: format!("{} ocorrências, o layout comporta {}", self.#ident.len(), #count),
+                            });
+                        }
+                    });
+                    quote! {
+                        #len_check
+                        for (i, item) in self.#ident.iter().enumerate() {
+                            cnab_fixedwidth::writer::write_nested(
+                                &mut line,
+                                #offset + i * #stride,
+                                &cnab_fixedwidth::FixedWidthWrite::write_bytes(item, options)?,
+                            )?;
+                        }
+                    }
+                }
+                // Campo só de retorno: brancos em texto, zeros em numéricos
+                (None, _, Some(index)) if f.direction == DirectionMacro::ReadOnly => quote! {
+                    cnab_fixedwidth::writer::write_blank(&mut line, &FIELDS[#index], options)?;
+                },
+                (None, _, Some(index)) if matches!(f.kind, FieldKindMacro::Filler) => quote! {
+                    cnab_fixedwidth::writer::write_field(&mut line, &FIELDS[#index], &cnab_fixedwidth::Value::Null, options)?;
+                },
+                (None, _, Some(index)) if f.flag.is_some() => {
+                    let FlagMacro { truthy, falsy } = f.flag.as_ref().expect("campo bool");
+                    // Sem falsy declarado, `false` é escrito em branco
+                    let falsy = match falsy {
+                        Some(falsy) => quote!(cnab_fixedwidth::Value::Alpha(#falsy.to_string())),
+                        None => quote!(cnab_fixedwidth::Value::Null),
+                    };
+                    let value = quote! {
+                        if flag { cnab_fixedwidth::Value::Alpha(#truthy.to_string()) } else { #falsy }
+                    };
+                    let value = if f.optional {
+                        quote!(self.#ident.map_or(cnab_fixedwidth::Value::Null, |flag| #value))
+                    } else {
+                        quote!({ let flag = self.#ident; #value })
+                    };
+                    quote! {
+                        cnab_fixedwidth::writer::write_field(&mut line, &FIELDS[#index], &#value, options)?;
+                    }
+                }
+                (None, _, Some(index)) if f.code => {
+                    let value = quote!(cnab_fixedwidth::Value::Alpha(cnab_fixedwidth::FixedWidthCode::code(code).to_string()));
+                    let value = if f.optional {
+                        quote!(self.#ident.as_ref().map_or(cnab_fixedwidth::Value::Null, |code| #value))
+                    } else {
+                        quote!({ let code = &self.#ident; #value })
+                    };
+                    quote! {
+                        cnab_fixedwidth::writer::write_field(&mut line, &FIELDS[#index], &#value, options)?;
+                    }
+                }
+                (None, _, Some(index)) => quote! {
+                    cnab_fixedwidth::writer::write_field(
+                        &mut line,
+                        &FIELDS[#index],
+                        &cnab_fixedwidth::writer::ToValue::to_value(&self.#ident, FIELDS[#index].kind),
+                        options,
+                    )?;
+                },
+                (None, _, None) => unreachable!("campo direto tem FieldSpec"),
+            }
+        });
+        quote! {
+            impl #impl_generics cnab_fixedwidth::FixedWidthWrite for #name #ty_generics #where_clause {
+                fn write_bytes(&self, options: &cnab_fixedwidth::WriteOptions) -> cnab_fixedwidth::Result<Vec<u8>> {
+                    const FIELDS: &[cnab_fixedwidth::FieldSpec] = &[ #(#field_specs),* ];
+
+                    let mut line = vec![b' '; <Self as cnab_fixedwidth::RecordSpan>::END];
+                    #(#writes)*
+                    Ok(line)
+                }
             }
         }
-    }.into()
-}
\ No newline at end of file
+    } else {
+        quote!()
+    };
+
+    // 11. Parse: com tempo de vida, os campos de texto são emprestados da linha (UTF-8)
+    let parse = match lifetime {
+        Some(lifetime) => quote! {
+            impl #impl_generics cnab_fixedwidth::FixedWidthParseRef<#lifetime> for #name #ty_generics #where_clause {
+                fn parse_ref(text_line: &#lifetime str) -> cnab_fixedwidth::Result<Self> {
+                    const FIELDS: &[cnab_fixedwidth::FieldSpec] = &[ #(#field_specs),* ];
+
+                    let options = &cnab_fixedwidth::ParseOptions::with_encoding(cnab_fixedwidth::Encoding::Utf8);
+                    #unit_override
+                    let line = cnab_fixedwidth::trim_record(text_line.as_bytes(), options);
+                    // O fim de linha removido é ASCII: o corte cai numa fronteira de caractere
+                    let text_line = &text_line[..line.len()];
+                    #strict_check
+
+                    Ok(Self {
+                        #(#field_idents: #field_values),*
+                    })
+                }
+            }
+        },
+        None => quote! {
+            impl cnab_fixedwidth::FixedWidthParse for #name {
+                fn parse_bytes(
+                    line: &[u8],
+                    options: &cnab_fixedwidth::ParseOptions,
+                ) -> cnab_fixedwidth::Result<Self> {
+                    // Lista de especificações estática (construída em tempo de compilação)
+                    const FIELDS: &[cnab_fixedwidth::FieldSpec] = &[ #(#field_specs),* ];
+
+                    #unit_override
+                    let line = cnab_fixedwidth::trim_record(line, options);
+                    #strict_check
+                    #trailing_check
+                    // Caminho rápido: uma única validação de tamanho para todos os campos
+                    if options.fast
+                        && options.unit == cnab_fixedwidth::PositionUnit::Bytes
+                        && line.len() < <Self as cnab_fixedwidth::RecordSpan>::END
+                    {
+                        return Err(cnab_fixedwidth::FixedWidthError::LineTooShort {
+                            len: line.len(),
+                            needed: <Self as cnab_fixedwidth::RecordSpan>::END,
+                        });
+                    }
+
+                    // Construção da Struct segura, campo a campo
+                    Ok(Self {
+                        #(#field_idents: #field_values),*
+                    })
+                }
+            }
+        },
+    };
+
+    // 12. Bloco final de implementação
+    Ok(quote! {
+        #business_key
+        #generated_tests
+        #width_const
+        #lenient
+        #span
+        #write
+        #(#nested_checks)*
+        #capacity_checks
+        #parse
+    })
+}
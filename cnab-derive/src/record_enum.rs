@@ -0,0 +1,124 @@
+//! Derive `FixedWidth` para enums de registros.
+//!
+//! Cada variante embrulha o tipo de um registro e é escolhida pelo conteúdo
+//! de uma posição discriminadora (ex: tipo de registro na posição 8 do CNAB 240).
+//!
+//! Exemplo na documentação de `cnab_fixedwidth::FixedWidthParse`, que reexporta a macro.
+
+use quote::quote;
+use std::collections::HashSet;
+use syn::{DataEnum, DeriveInput, Fields};
+
+/// Variante do enum e como ela é selecionada.
+enum VariantKind {
+    /// Selecionada quando a posição discriminadora contém um destes valores.
+    Tagged(Vec<syn::LitStr>),
+    /// Catch-all para tipos de registro desconhecidos.
+    Other,
+}
+
+/// Gera a implementação de `FixedWidthParse` para um enum de registros.
+pub(crate) fn expand(
+    input: &DeriveInput,
+    data: &DataEnum,
+    tag_pos: Option<(usize, usize)>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let (start, end) = tag_pos.ok_or_else(|| {
+        syn::Error::new_spanned(name, "enum de registros exige a posição do tipo: #[fw(tag_pos = \"8..8\")]")
+    })?;
+
+    let mut seen = HashSet::new();
+    let mut arms = Vec::new();
+    let mut other = None;
+
+    for variant in &data.variants {
+        let ident = &variant.ident;
+        let mut kind = None;
+
+        for attr in &variant.attrs {
+            if attr.path().is_ident("fw") {
+                attr.parse_nested_meta(|meta| {
+                    let name = meta.path.get_ident().map(|i| i.to_string());
+                    match name.as_deref() {
+                        // Atributo: tag = "3" (pode repetir para aceitar vários valores)
+                        Some("tag") => {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            if lit.value().len() != end - start + 1 {
+                                return Err(syn::Error::new_spanned(&lit, "tag deve ter a largura de tag_pos"));
+                            }
+                            // A mesma tag em duas variantes deixaria a segunda inalcançável
+                            if !seen.insert(lit.value()) {
+                                return Err(syn::Error::new_spanned(&lit, "tag repetida"));
+                            }
+                            match &mut kind {
+                                Some(VariantKind::Tagged(tags)) => tags.push(lit),
+                                _ => kind = Some(VariantKind::Tagged(vec![lit])),
+                            }
+                        }
+                        // Atributo: other (catch-all)
+                        Some("other") => kind = Some(VariantKind::Other),
+                        _ => return Err(syn::Error::new_spanned(meta.path, "atributo fw de variante desconhecido")),
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+
+        match kind {
+            Some(VariantKind::Tagged(tags)) => {
+                let inner = match &variant.fields {
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+                    _ => return Err(syn::Error::new_spanned(ident, "variante deve embrulhar um único registro: Variante(Registro)")),
+                };
+                arms.push(quote! {
+                    #(#tags)|* => Ok(Self::#ident(
+                        <#inner as cnab_fixedwidth::FixedWidthParse>::parse_bytes(line, options)?
+                    )),
+                });
+            }
+            Some(VariantKind::Other) => {
+                if other.is_some() {
+                    return Err(syn::Error::new_spanned(ident, "apenas uma variante pode ser #[fw(other)]"));
+                }
+                if start != end {
+                    return Err(syn::Error::new_spanned(ident, "#[fw(other)] exige tag_pos de uma posição"));
+                }
+                match &variant.fields {
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 2 => {}
+                    _ => return Err(syn::Error::new_spanned(ident, "variante other deve ser Variante(char, String)")),
+                }
+                other = Some(ident);
+            }
+            None => return Err(syn::Error::new_spanned(ident, "variante sem #[fw(tag = \"...\")] ou #[fw(other)]")),
+        }
+    }
+
+    // Tipo desconhecido: catch-all (tipo + linha decodificada) ou erro
+    let fallback = match other {
+        Some(ident) => quote! {
+            tag => Ok(Self::#ident(
+                tag.chars().next().unwrap_or(' '),
+                options.encoding.decode(cnab_fixedwidth::trim_record(line, options))?.into_owned(),
+            )),
+        },
+        None => quote! {
+            tag => Err(cnab_fixedwidth::FixedWidthError::UnknownRecordType { tag: tag.to_string() }),
+        },
+    };
+
+    Ok(quote! {
+        impl cnab_fixedwidth::FixedWidthParse for #name {
+            fn parse_bytes(
+                line: &[u8],
+                options: &cnab_fixedwidth::ParseOptions,
+            ) -> cnab_fixedwidth::Result<Self> {
+                let pos = cnab_fixedwidth::FieldPos::new_const(#start, #end);
+                match cnab_fixedwidth::read_tag(line, pos, options)?.as_ref() {
+                    #(#arms)*
+                    #fallback
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,272 @@
+//! # Anonimização (feature `anonymize`)
+//!
+//! Troca os dados pessoais de um arquivo de produção (nomes, CPF/CNPJ, endereços) por
+//! dados fictícios, para mandar o arquivo ao suporte do banco ou usá-lo como fixture de
+//! teste sem expor clientes:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::anonymize::{self, AnonymizeRules};
+//!
+//! # let registry = cnab_fixedwidth::LayoutRegistry::new();
+//! // Ex: `layouts::cobranca240::registry()`, com a feature `layouts-240`
+//! let file = std::fs::read("retorno.ret")?;
+//! let anonimo = anonymize::anonymize(&file, &registry, &AnonymizeRules::new())?;
+//! std::fs::write("retorno-anonimo.ret", anonimo)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Só os campos sensíveis são reescritos, nas suas posições; valores, datas, códigos,
+//! agência/conta e sequenciais ficam intactos, então os totais dos trailers continuam
+//! batendo. CPFs e CNPJs fictícios têm DVs válidos, e a troca é determinística: o mesmo
+//! valor original vira sempre o mesmo valor fictício (para a mesma semente), em qualquer
+//! campo e registro, o que preserva o vínculo entre os segmentos de um título.
+
+use crate::docs_br::{self, Documento};
+use crate::writer::{self, WriteOptions};
+use crate::{FieldKind, FieldSpec, LayoutRegistry, ParseOptions, Result, Value};
+
+/// Tipo de dado de um campo sensível, que define o valor fictício.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sensitive {
+    /// Nome de pessoa ou empresa.
+    Nome,
+    /// CPF ou CNPJ, trocado por outro do mesmo tipo com DVs válidos.
+    Documento,
+    /// Logradouro, bairro, cidade ou CEP.
+    Endereco,
+    /// Texto livre: letras e dígitos trocados, mantendo o tamanho.
+    Texto,
+}
+
+/// Quais campos anonimizar, e com que semente.
+///
+/// Por padrão, os campos são reconhecidos pelo nome, como nos layouts do crate:
+/// - `Nome`: nomes com `nome` (ex: `pagador_nome`, `nome_empresa`), exceto `nome_banco`;
+/// - `Documento`: `*inscricao`, exceto `*tipo_inscricao`;
+/// - `Endereco`: nomes com `endereco`, `logradouro`, `bairro`, `cidade` ou `cep`.
+///
+/// [`field`](Self::field) marca outros campos e [`keep`](Self::keep) preserva um campo
+/// reconhecido pelo nome.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnonymizeRules {
+    /// Campos marcados ou preservados explicitamente (`None` = preservar).
+    overrides: Vec<(String, Option<Sensitive>)>,
+    seed: u64,
+}
+
+impl AnonymizeRules {
+    /// Regras padrão, com os campos reconhecidos pelo nome e semente 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Anonimiza o campo `name` como `kind`.
+    pub fn field(mut self, name: impl Into<String>, kind: Sensitive) -> Self {
+        self.overrides.push((name.into(), Some(kind)));
+        self
+    }
+
+    /// Mantém o campo `name` como está.
+    pub fn keep(mut self, name: impl Into<String>) -> Self {
+        self.overrides.push((name.into(), None));
+        self
+    }
+
+    /// Semente dos valores fictícios: sementes diferentes geram valores diferentes para
+    /// o mesmo original.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Tipo de dado do campo, ou `None` se ele não é anonimizado.
+    pub fn classify(&self, field: &FieldSpec) -> Option<Sensitive> {
+        if let Some((_, kind)) = self.overrides.iter().rev().find(|(name, _)| name == field.name) {
+            return *kind;
+        }
+        let name = field.name;
+        if matches!(field.kind, FieldKind::Filler) {
+            None
+        } else if name.ends_with("inscricao") && !name.ends_with("tipo_inscricao") {
+            Some(Sensitive::Documento)
+        } else if name.contains("nome") && !name.contains("banco") {
+            Some(Sensitive::Nome)
+        } else if ["endereco", "logradouro", "bairro", "cidade", "cep"].iter().any(|part| name.contains(part)) {
+            Some(Sensitive::Endereco)
+        } else {
+            None
+        }
+    }
+}
+
+/// Anonimiza um arquivo, resolvendo o layout de cada registro em `layouts`.
+///
+/// Campos em branco (ou zerados) continuam assim, textos fictícios são cortados na
+/// largura do campo e números ocupam todos os dígitos do campo. Quebras de linha,
+/// registros sem layout e campos não sensíveis são preservados byte a byte.
+pub fn anonymize(file: &[u8], layouts: &LayoutRegistry, rules: &AnonymizeRules) -> Result<Vec<u8>> {
+    let options = WriteOptions::default();
+    let mut out = Vec::with_capacity(file.len());
+    for line in file.split_inclusive(|&b| b == b'\n') {
+        let content = crate::trim_line_ending(line);
+        let mut record = content.to_vec();
+        if let Some(layout) = layouts.resolve(content) {
+            for field in &layout.fields {
+                let Some(kind) = rules.classify(field) else { continue };
+                if let Some(value) = fake(content, field, kind, rules.seed)? {
+                    writer::write_field(&mut record, field, &value, &options)?;
+                }
+            }
+        }
+        out.extend_from_slice(&record);
+        out.extend_from_slice(&line[content.len()..]);
+    }
+    Ok(out)
+}
+
+const NOMES: [&str; 12] =
+    ["ANA", "BRUNO", "CARLA", "DANIEL", "ELISA", "FABIO", "GABRIELA", "HUGO", "ISABEL", "JORGE", "LUCIA", "MARCOS"];
+const SOBRENOMES: [&str; 10] =
+    ["ALMEIDA", "BARBOSA", "CARDOSO", "DIAS", "FERREIRA", "GOMES", "LIMA", "MOREIRA", "PEREIRA", "SOUZA"];
+const LOGRADOUROS: [&str; 8] =
+    ["RUA DAS FLORES", "AV BRASIL", "RUA DO COMERCIO", "RUA SETE", "AV CENTRAL", "RUA DA PAZ", "RUA NOVA", "AV DO SOL"];
+
+/// Valor fictício do campo, ou `None` se o original está em branco ou zerado.
+fn fake(line: &[u8], field: &FieldSpec, kind: Sensitive, seed: u64) -> Result<Option<Value>> {
+    let raw = line.get(field.pos.as_range()).unwrap_or_default();
+    if raw.iter().all(|&b| matches!(b, b' ' | b'0')) {
+        return Ok(None);
+    }
+    // Sem brancos nas pontas nem zeros à esquerda: o mesmo CPF num campo de 14 ou 15
+    // posições gera o mesmo fictício
+    let key = raw.trim_ascii();
+    let key = &key[key.iter().take_while(|&&b| b == b'0').count()..];
+    let mut rng = Rng::new(seed, kind, key);
+    let width = field.pos.width();
+    let numeric = !matches!(field.kind, FieldKind::Alpha);
+
+    let text = match kind {
+        Sensitive::Documento => {
+            let original = crate::parse_field(line, field, &ParseOptions::default())?;
+            let digits = match &original {
+                Value::Numeric(n) => n.to_string(),
+                other => other.as_str().map(|text| text.trim().to_string()).unwrap_or_default(),
+            };
+            let documento = match docs_br::validate_cnpj(&digits) {
+                Ok(()) => Documento::Cnpj,
+                Err(_) if digits.trim_start_matches('0').len() > Documento::Cpf.width() => Documento::Cnpj,
+                Err(_) => Documento::Cpf,
+            };
+            let base = match documento {
+                Documento::Cpf => rng.digits(9),
+                // Raiz de 8 dígitos e filial 0001
+                Documento::Cnpj => format!("{}0001", rng.digits(8)),
+            };
+            format!("{base}{}", docs_br::check_digits(documento, &base))
+        }
+        _ if numeric => rng.digits(width),
+        Sensitive::Nome => format!("{} {} {}", rng.pick(&NOMES), rng.pick(&SOBRENOMES), rng.pick(&SOBRENOMES)),
+        Sensitive::Endereco => format!("{} {}", rng.pick(&LOGRADOUROS), 1 + rng.next() % 999),
+        Sensitive::Texto => raw
+            .iter()
+            .map(|&b| match b {
+                b'0'..=b'9' => char::from(b'0' + (rng.next() % 10) as u8),
+                b if b.is_ascii_alphabetic() => char::from(b'A' + (rng.next() % 26) as u8),
+                b => char::from(b),
+            })
+            .collect(),
+    };
+
+    // Dígitos que não cabem no campo são descartados à esquerda
+    let number = || text[text.len().saturating_sub(width.min(18))..].parse().unwrap_or(0);
+    Ok(Some(match field.kind {
+        FieldKind::Alpha => Value::Alpha(text.chars().take(width).collect::<String>().trim_end().to_string()),
+        FieldKind::Decimal { scale } | FieldKind::Zoned { scale } | FieldKind::Packed { scale } if scale > 0 => {
+            Value::Decimal { raw: number(), scale }
+        }
+        _ => Value::Numeric(number()),
+    }))
+}
+
+/// Gerador determinístico (xorshift) semeado com o valor original.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64, kind: Sensitive, key: &[u8]) -> Self {
+        // FNV-1a: estável entre versões do Rust, ao contrário do `DefaultHasher`
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64 ^ seed;
+        for &b in [kind as u8].iter().chain(key) {
+            hash = (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3);
+        }
+        Self(hash | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn pick<'a>(&mut self, options: &[&'a str]) -> &'a str {
+        options[(self.next() % options.len() as u64) as usize]
+    }
+
+    /// `n` dígitos, o primeiro diferente de zero.
+    fn digits(&mut self, n: usize) -> String {
+        (0..n)
+            .map(|i| char::from(b'0' + (if i == 0 { 1 + self.next() % 9 } else { self.next() % 10 }) as u8))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldPos, FixedWidth, RecordLayout};
+
+    #[derive(FixedWidth)]
+    #[fw(width = 60)]
+    #[allow(dead_code)]
+    struct Pagador {
+        #[fw(pos = "1..1", numeric)]
+        tipo_inscricao: u8,
+        #[fw(pos = "2..16", numeric)]
+        pagador_inscricao: u64,
+        #[fw(pos = "17..36", alpha)]
+        pagador_nome: String,
+        #[fw(pos = "37..44", numeric)]
+        pagador_cep: u32,
+        #[fw(pos = "45..54", numeric)]
+        valor: i64,
+        #[fw(pos = "55..60", alpha)]
+        nome_banco: String,
+    }
+
+    #[test]
+    fn replaces_sensitive_fields_keeping_the_rest() {
+        let mut layouts = LayoutRegistry::new();
+        layouts.register(RecordLayout::of::<Pagador>("pagador").when(FieldPos::new_const(1, 1), "1"));
+        let file = "1000052998224725JOSE DA SILVA       010010000000012345ITAU  \r\n\
+                    1000052998224725JOSE DA SILVA       000000000000000100ITAU  \r\n\
+                    2000000000000000JOSE DA SILVA       010010000000012345ITAU  \r\n";
+
+        let out = String::from_utf8(anonymize(file.as_bytes(), &layouts, &AnonymizeRules::new()).unwrap()).unwrap();
+        let lines: Vec<_> = out.split("\r\n").collect();
+        assert_eq!(out.len(), file.len());
+        assert_ne!(&lines[0][1..36], &file[1..36]);
+        assert!(docs_br::validate_cpf(&lines[0][1..16]).is_ok(), "{}", &lines[0][1..16]);
+        assert!(lines[0][16..36].chars().all(|c| c.is_ascii_uppercase() || c == ' '));
+        assert_eq!((&lines[0][44..], &lines[0][..1]), ("0000012345ITAU  ", "1"));
+        // Mesmo original, mesmo fictício; CEP zerado continua zerado
+        assert_eq!(&lines[1][..36], &lines[0][..36]);
+        assert_eq!(&lines[1][36..44], "00000000");
+        // Registro sem layout fica intacto
+        assert_eq!(lines[2], file.split("\r\n").nth(2).unwrap());
+
+        let rules = AnonymizeRules::new().keep("pagador_nome").seed(7);
+        let out = anonymize(file.as_bytes(), &layouts, &rules).unwrap();
+        assert_eq!(&out[16..36], &file.as_bytes()[16..36]);
+        assert_ne!(&out[1..16], &lines[0].as_bytes()[1..16]);
+    }
+}
@@ -0,0 +1,314 @@
+//! # Arrow e Parquet (features `arrow` e `parquet`)
+//!
+//! Para análises em larga escala, os registros lidos viram `RecordBatch`es do Arrow, com
+//! o schema derivado dos `FieldSpec`s do layout:
+//!
+//! | Campo | Tipo Arrow |
+//! |---|---|
+//! | `Alpha` | `Utf8` |
+//! | `Numeric`, `Packed` sem escala | `Int64` |
+//! | `Decimal`, `Zoned`, `Packed` com escala | `Decimal128(precisão, escala)` |
+//!
+//! A precisão é a quantidade de dígitos que cabe no campo, e os valores vão sem passar
+//! por `f64`: `Decimal { raw: 1050, scale: 2 }` é gravado como `1050` com escala 2.
+//! Fillers ficam de fora e só campos `optional` aceitam nulos.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::arrow::{self, BatchBuilder};
+//! use cnab_fixedwidth::{DynRecord, FieldSpec, ParseOptions};
+//! use std::fs::File;
+//!
+//! # fn exemplo(fields: Vec<FieldSpec>, records: Vec<cnab_fixedwidth::Result<DynRecord>>, reader: impl std::io::BufRead, options: ParseOptions) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut batches = BatchBuilder::new(&fields);
+//! for record in records {
+//!     batches.push(&record?)?;
+//! }
+//! let batch = batches.finish()?;
+//!
+//! // Com a feature `parquet`, direto do arquivo CNAB:
+//! # #[cfg(feature = "parquet")]
+//! arrow::write_parquet(reader, File::create("detalhes.parquet")?, &fields, &options, 8192)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::reader::{Framing, RecordReader};
+use crate::{parse_line_bytes_with, DynRecord, FieldKind, FieldSpec, FixedWidthError, ParseOptions, Result, Value};
+use arrow_array::builder::{ArrayBuilder, Decimal128Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, DECIMAL128_MAX_PRECISION};
+use std::io::BufRead;
+use std::sync::Arc;
+
+/// Tipo Arrow do campo (`None` para fillers).
+pub fn data_type(field: &FieldSpec) -> Option<DataType> {
+    let decimal = |digits: usize, scale: u8| {
+        let precision = digits.clamp(usize::from(scale).max(1), usize::from(DECIMAL128_MAX_PRECISION));
+        DataType::Decimal128(precision as u8, scale as i8)
+    };
+    match field.kind {
+        FieldKind::Alpha => Some(DataType::Utf8),
+        FieldKind::Numeric | FieldKind::Packed { scale: 0 } => Some(DataType::Int64),
+        FieldKind::Decimal { scale } | FieldKind::Zoned { scale } => Some(decimal(field.pos.width(), scale)),
+        // Dois dígitos por byte, menos o meio byte do sinal
+        FieldKind::Packed { scale } => Some(decimal(field.pos.width() * 2 - 1, scale)),
+        FieldKind::Filler => None,
+    }
+}
+
+/// Schema Arrow dos campos, na ordem do layout (sem os fillers).
+pub fn schema(fields: &[FieldSpec]) -> Schema {
+    Schema::new(
+        fields
+            .iter()
+            .filter_map(|field| data_type(field).map(|data_type| Field::new(field.name, data_type, field.optional)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Acumula registros e monta `RecordBatch`es com o schema de [`schema`].
+pub struct BatchBuilder {
+    fields: Vec<FieldSpec>,
+    schema: SchemaRef,
+    columns: Vec<Column>,
+}
+
+/// Construtor de uma coluna.
+enum Column {
+    Utf8(StringBuilder),
+    Int64(Int64Builder),
+    Decimal(Decimal128Builder),
+}
+
+impl Column {
+    fn new(data_type: &DataType) -> Result<Self> {
+        Ok(match data_type {
+            DataType::Utf8 => Column::Utf8(StringBuilder::new()),
+            DataType::Decimal128(precision, scale) => Column::Decimal(
+                Decimal128Builder::new().with_precision_and_scale(*precision, *scale).map_err(export_error)?,
+            ),
+            _ => Column::Int64(Int64Builder::new()),
+        })
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Column::Utf8(builder) => builder.len(),
+            Column::Int64(builder) => builder.len(),
+            Column::Decimal(builder) => builder.len(),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Column::Utf8(builder) => Arc::new(builder.finish()),
+            Column::Int64(builder) => Arc::new(builder.finish()),
+            Column::Decimal(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+impl BatchBuilder {
+    /// Construtor para os campos de um layout.
+    pub fn new(fields: &[FieldSpec]) -> Self {
+        let fields: Vec<_> = fields.iter().filter(|field| !matches!(field.kind, FieldKind::Filler)).cloned().collect();
+        let schema = Arc::new(schema(&fields));
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| Column::new(field.data_type()).expect("precisão e escala limitadas em data_type"))
+            .collect();
+        Self { fields, schema, columns }
+    }
+
+    /// Schema dos batches.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Acrescenta um registro. Os valores são buscados pelo nome do campo; campos
+    /// ausentes do registro são nulos.
+    pub fn push(&mut self, record: &DynRecord) -> Result<()> {
+        // Confere o registro inteiro antes de tocar nas colunas, que ficam sempre alinhadas
+        let values: Vec<_> = self.fields.iter().map(|field| record.get(field.name).unwrap_or(&Value::Null)).collect();
+        for (field, value) in self.fields.iter().zip(&values) {
+            let compatible = match value {
+                Value::Null => field.optional,
+                _ => field.matches(value),
+            };
+            if !compatible {
+                let message = format!("valor {value:?} incompatível com a coluna {}", field.kind);
+                return Err(FixedWidthError::Conversion { field: field.name, message });
+            }
+        }
+
+        for (column, value) in self.columns.iter_mut().zip(values) {
+            match (column, value) {
+                (Column::Utf8(builder), Value::Alpha(text)) => builder.append_value(text),
+                (Column::Int64(builder), Value::Numeric(n) | Value::Decimal { raw: n, .. }) => builder.append_value(*n),
+                (Column::Decimal(builder), Value::Decimal { raw, .. }) => builder.append_value(i128::from(*raw)),
+                (Column::Utf8(builder), _) => builder.append_null(),
+                (Column::Int64(builder), _) => builder.append_null(),
+                (Column::Decimal(builder), _) => builder.append_null(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Registros acumulados desde o último [`finish`](Self::finish).
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, Column::len)
+    }
+
+    /// `true` se não há registros acumulados.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Monta o batch com os registros acumulados e recomeça do zero.
+    pub fn finish(&mut self) -> Result<RecordBatch> {
+        let columns = self.columns.iter_mut().map(Column::finish).collect();
+        RecordBatch::try_new(self.schema.clone(), columns).map_err(export_error)
+    }
+}
+
+/// Lê um arquivo com um único tipo de registro em batches de até `batch_size` registros.
+///
+/// Linhas vazias são ignoradas; erros de parse saem com o número da linha e o campo.
+pub fn to_record_batches<R: BufRead>(
+    reader: R,
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+    batch_size: usize,
+) -> Result<Vec<RecordBatch>> {
+    let mut batches = Vec::new();
+    for_each_batch(reader, fields, options, batch_size, |batch| {
+        batches.push(batch);
+        Ok(())
+    })?;
+    Ok(batches)
+}
+
+/// Converte um arquivo com um único tipo de registro para Parquet, em row groups de até
+/// `batch_size` registros.
+///
+/// # Retorno
+/// Quantidade de registros escritos.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<R: BufRead, W: std::io::Write + Send>(
+    reader: R,
+    writer: W,
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+    batch_size: usize,
+) -> Result<u64> {
+    let mut parquet =
+        parquet::arrow::ArrowWriter::try_new(writer, Arc::new(schema(fields)), None).map_err(export_error)?;
+    let written = for_each_batch(reader, fields, options, batch_size, |batch| {
+        parquet.write(&batch).map_err(export_error)?;
+        parquet.flush().map_err(export_error)
+    })?;
+    parquet.close().map_err(export_error)?;
+    Ok(written)
+}
+
+/// Lê o arquivo e entrega cada batch completo (e o último, parcial) a `f`.
+fn for_each_batch<R: BufRead>(
+    reader: R,
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+    batch_size: usize,
+    mut f: impl FnMut(RecordBatch) -> Result<()>,
+) -> Result<u64> {
+    let mut records = RecordReader::new(reader, Framing::Lines);
+    let mut line = Vec::new();
+    let mut builder = BatchBuilder::new(fields);
+    let mut read = 0u64;
+
+    while records.read_record(&mut line)? {
+        let line_number = records.records_read();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = parse_line_bytes_with(&line, fields, options)
+            .map_err(|e| e.at_line_with(line_number, &line, fields, options.redaction))?;
+        builder.push(&parsed)?;
+        read += 1;
+        if builder.len() >= batch_size.max(1) {
+            f(builder.finish()?)?;
+        }
+    }
+    if !builder.is_empty() {
+        f(builder.finish()?)?;
+    }
+    Ok(read)
+}
+
+fn export_error(error: impl std::fmt::Display) -> FixedWidthError {
+    FixedWidthError::Export(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldPos;
+    use arrow_array::{Array, Decimal128Array, Int64Array, StringArray};
+
+    fn fields() -> Vec<FieldSpec> {
+        vec![
+            FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("brancos", FieldPos::new_const(4, 5), FieldKind::Filler),
+            FieldSpec::new("nome", FieldPos::new_const(6, 10), FieldKind::Alpha),
+            FieldSpec::new("valor", FieldPos::new_const(11, 17), FieldKind::Decimal { scale: 2 }).optional(),
+        ]
+    }
+
+    #[test]
+    fn builds_record_batches_with_decimal_columns() {
+        let schema = schema(&fields());
+        let types: Vec<_> = schema.fields().iter().map(|f| (f.name().as_str(), f.data_type().clone(), f.is_nullable())).collect();
+        assert_eq!(
+            types,
+            [
+                ("banco", DataType::Int64, false),
+                ("nome", DataType::Utf8, false),
+                ("valor", DataType::Decimal128(7, 2), true),
+            ]
+        );
+
+        let input = b"341  ANA  0001050\n\n237  BIA         \n001  CAIO 0000007\n" as &[u8];
+        let batches = to_record_batches(input, &fields(), &ParseOptions::default(), 2).unwrap();
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).collect::<Vec<_>>(), [2, 1]);
+
+        let first = &batches[0];
+        let banco = first.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+        let nome = first.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        let valor = first.column(2).as_any().downcast_ref::<Decimal128Array>().unwrap();
+        assert_eq!((banco.value(0), nome.value(1)), (341, "BIA"));
+        assert_eq!((valor.value(0), valor.value_as_string(0)), (1050, "10.50".to_string()));
+        assert!(valor.is_null(1));
+
+        let mut builder = BatchBuilder::new(&fields());
+        let record: DynRecord = [("banco", Value::Numeric(1)), ("nome", Value::Numeric(2))].into_iter().collect();
+        assert!(matches!(builder.push(&record), Err(FixedWidthError::Conversion { field: "nome", .. })));
+        assert!(builder.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "parquet")]
+    fn writes_parquet_files() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path = std::env::temp_dir().join(format!("cnab-arrow-{}.parquet", std::process::id()));
+        let input = b"341  ANA  0001050\n237  BIA         \n" as &[u8];
+        let file = std::fs::File::create(&path).unwrap();
+        assert_eq!(write_parquet(input, file, &fields(), &ParseOptions::default(), 1).unwrap(), 2);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let batches: Vec<_> = reader.build().unwrap().collect::<std::result::Result<_, _>>().unwrap();
+        let valor = batches[0].column(2).as_any().downcast_ref::<Decimal128Array>().unwrap();
+        assert_eq!(valor.value_as_string(0), "10.50");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+//! # Bancos
+//!
+//! O layout base FEBRABAN deixa alguns trechos a critério de cada banco: a composição e a
+//! posição do nosso número, a carteira, os dígitos verificadores. Em vez de manter uma
+//! cópia das structs de [`crate::cnab240`] e `layouts::cobranca400` por banco, um
+//! [`Bank`] informa onde ficam esses campos no registro padrão e como interpretá-los.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::banks::Bank;
+//! use cnab_fixedwidth::{cnab240, parse_line_bytes_with, FixedWidthParse, ParseOptions};
+//!
+//! # let (first, line, options) = (Vec::new(), Vec::new(), ParseOptions::default());
+//! let header = cnab240::HeaderArquivo::parse_bytes(&first, &options)?;
+//! let bank = Bank::from_codigo(header.banco).ok_or("banco não suportado")?;
+//! // Segmento P/T (240 posições) ou detalhe CNAB 400
+//! let nosso_numero = bank.nosso_numero(&line)?;
+//! let campos = parse_line_bytes_with(&line, &bank.fields(line.len()), &options)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::nosso_numero::{NossoNumero, NossoNumeroError, NossoNumeroRule, NossoNumeroStrategy};
+use crate::{FieldKind, FieldPos, FieldSpec};
+
+/// Bancos com preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bank {
+    Itau,
+    Bradesco,
+    BancoDoBrasil,
+    Caixa,
+    Santander,
+    Sicredi,
+}
+
+/// Desvios de um banco em relação ao layout base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BankPreset {
+    /// Composição do nosso número.
+    pub nosso_numero: NossoNumeroRule,
+    /// Nosso número nos segmentos P e T do CNAB 240 (dentro do campo 38..57).
+    pub nosso_numero_240: FieldPos,
+    /// Carteira no CNAB 240: posição 58 no padrão, ou dentro do campo do nosso número.
+    pub carteira_240: FieldPos,
+    /// Nosso número no detalhe do CNAB 400; `None` quando o CNAB 400 do banco não segue
+    /// a base de `layouts::cobranca400`.
+    pub nosso_numero_400: Option<FieldPos>,
+}
+
+impl Bank {
+    /// Todos os bancos com preset.
+    pub const ALL: [Bank; 6] =
+        [Bank::Itau, Bank::Bradesco, Bank::BancoDoBrasil, Bank::Caixa, Bank::Santander, Bank::Sicredi];
+
+    /// Código do banco na compensação (campo `banco` dos headers).
+    pub const fn codigo(self) -> u16 {
+        match self {
+            Bank::Itau => 341,
+            Bank::Bradesco => 237,
+            Bank::BancoDoBrasil => 1,
+            Bank::Caixa => 104,
+            Bank::Santander => 33,
+            Bank::Sicredi => 748,
+        }
+    }
+
+    /// Banco pelo código da compensação.
+    pub fn from_codigo(codigo: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|bank| bank.codigo() == codigo)
+    }
+
+    /// Posições e regras próprias do banco.
+    pub const fn preset(self) -> BankPreset {
+        let pos = FieldPos::new_const;
+        let carteira_padrao = pos(58, 58);
+        match self {
+            // Carteira (3) + nosso número (8) + DAC
+            Bank::Itau => BankPreset {
+                nosso_numero: NossoNumeroRule::itau(),
+                nosso_numero_240: pos(41, 48),
+                carteira_240: pos(38, 40),
+                nosso_numero_400: Some(pos(63, 70)),
+            },
+            // Carteira (3) + zeros + nosso número (11) + DV
+            Bank::Bradesco => BankPreset {
+                nosso_numero: NossoNumeroRule::bradesco(),
+                nosso_numero_240: pos(46, 57),
+                carteira_240: pos(38, 40),
+                nosso_numero_400: Some(pos(71, 82)),
+            },
+            // Convênio (7) + sequencial (10), alinhado à esquerda; no CNAB 400, registro tipo 7
+            Bank::BancoDoBrasil => BankPreset {
+                nosso_numero: NossoNumeroRule::banco_do_brasil(),
+                nosso_numero_240: pos(38, 54),
+                carteira_240: carteira_padrao,
+                nosso_numero_400: Some(pos(64, 80)),
+            },
+            Bank::Caixa => BankPreset {
+                nosso_numero: NossoNumeroRule::caixa(),
+                nosso_numero_240: pos(41, 57),
+                carteira_240: carteira_padrao,
+                nosso_numero_400: None,
+            },
+            Bank::Santander => BankPreset {
+                nosso_numero: NossoNumeroRule::santander(),
+                nosso_numero_240: pos(45, 57),
+                carteira_240: carteira_padrao,
+                nosso_numero_400: None,
+            },
+            // Ano + byte + sequencial, seguidos do DV (posição 46)
+            Bank::Sicredi => BankPreset {
+                nosso_numero: NossoNumeroRule::sicredi(),
+                nosso_numero_240: pos(38, 45),
+                carteira_240: carteira_padrao,
+                nosso_numero_400: None,
+            },
+        }
+    }
+
+    /// Campos do banco em um registro de `record_width` posições (segmento P/T do CNAB
+    /// 240 ou detalhe do CNAB 400), para usar com [`crate::parse_line_bytes_with`]:
+    /// `nosso_numero` e, no CNAB 240, `carteira`.
+    pub fn fields(self, record_width: usize) -> Vec<FieldSpec> {
+        let preset = self.preset();
+        match record_width {
+            240 => vec![
+                FieldSpec::new("nosso_numero", preset.nosso_numero_240, FieldKind::Alpha),
+                FieldSpec::new("carteira", preset.carteira_240, FieldKind::Numeric),
+            ],
+            400 => preset
+                .nosso_numero_400
+                .map(|pos| FieldSpec::new("nosso_numero", pos, FieldKind::Alpha))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Nosso número de um registro de título: segmento P/T (240 posições) ou detalhe do
+    /// CNAB 400 (400 posições), decomposto pela regra do banco.
+    ///
+    /// Registros de outro tamanho, ou de um CNAB 400 sem preset, resultam em
+    /// `NossoNumeroError::InvalidFormat`.
+    pub fn nosso_numero(self, record: &[u8]) -> Result<NossoNumero, NossoNumeroError> {
+        let record = crate::trim_line_ending(record);
+        let preset = self.preset();
+        let pos = match record.len() {
+            240 => Some(preset.nosso_numero_240),
+            400 => preset.nosso_numero_400,
+            _ => None,
+        };
+        // Latin-1: qualquer byte vira um caractere, e a regra rejeita o que não for dígito
+        let text = |pos: FieldPos| record[pos.as_range()].iter().map(|&b| b as char).collect::<String>();
+        match pos {
+            Some(pos) => preset.nosso_numero.parse(&text(pos)),
+            None => Err(NossoNumeroError::InvalidFormat { value: String::from_utf8_lossy(record).into_owned() }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::record;
+    use crate::{ParseOptions, Value};
+
+    #[test]
+    fn presets_locate_bank_specific_fields() {
+        assert_eq!(Bank::from_codigo(341), Some(Bank::Itau));
+        assert_eq!(Bank::from_codigo(999), None);
+        for bank in Bank::ALL {
+            let preset = bank.preset();
+            assert!(preset.nosso_numero_240.start() >= 38 && preset.nosso_numero_240.end() <= 57, "{bank:?}");
+            assert!(preset.nosso_numero_240.width() >= preset.nosso_numero.width(), "{bank:?}");
+        }
+
+        // Itaú 240: carteira e nosso número dividem o campo 38..57
+        let segmento_t = record(240, &[(14, "T"), (38, "109000012347           ")]);
+        let nosso_numero = Bank::Itau.nosso_numero(segmento_t.as_bytes()).unwrap();
+        assert_eq!(nosso_numero.numero, "00001234");
+        let campos = crate::parse_line_bytes_with(segmento_t.as_bytes(), &Bank::Itau.fields(240), &ParseOptions::default()).unwrap();
+        assert_eq!(campos.get("carteira"), Some(&Value::Numeric(109)));
+
+        // Bradesco 400: o DV vem junto e é conferido quando há carteira
+        let detalhe = record(400, &[(71, "000000000028")]);
+        assert_eq!(Bank::Bradesco.nosso_numero(detalhe.as_bytes()).unwrap().key(), "00000000002");
+        let detalhe_bb = record(400, &[(64, "12345670000000042")]);
+        assert_eq!(Bank::BancoDoBrasil.nosso_numero(detalhe_bb.as_bytes()).unwrap().prefix, "1234567");
+        assert!(Bank::Caixa.nosso_numero(detalhe.as_bytes()).is_err());
+        assert!(Bank::Caixa.fields(400).is_empty());
+    }
+}
@@ -0,0 +1,261 @@
+//! # `cnab` — Linha de Comando
+//!
+//! Depuração de arquivos bancários sem escrever código (feature `cli`):
+//!
+//! ```text
+//! cnab detect retorno.ret
+//! cnab inspect retorno.ret --line 3
+//! cnab validate retorno.ret
+//! cnab convert retorno.ret --to json > retorno.jsonl
+//! cnab convert retorno.ret --to csv --out csv/
+//! cnab anonymize retorno.ret --out retorno-anonimo.ret
+//! ```
+//!
+//! Os layouts saem do formato detectado no primeiro registro: cobrança FEBRABAN 240,
+//! cobrança CNAB 400 (remessa ou retorno, conforme o header) e débito automático
+//! FEBRABAN 150, com ou sem quebras de linha entre os registros. Com `--layout`,
+//! `inspect`, `convert` e `anonymize` usam um arquivo de definição de layout em JSON (um
+//! único tipo de registro).
+
+use clap::{Parser, Subcommand, ValueEnum};
+use cnab_fixedwidth::anonymize::{self, AnonymizeRules};
+use cnab_fixedwidth::banks::Bank;
+use cnab_fixedwidth::convert::{self, CsvOptions, JsonLinesOptions};
+use cnab_fixedwidth::debug;
+use cnab_fixedwidth::detect::{self, Detected};
+use cnab_fixedwidth::layouts::{cobranca240, cobranca400, debito150, extrato240};
+use cnab_fixedwidth::reader::{Framing, RecordReader};
+use cnab_fixedwidth::{cnab240, CnabFormat, LayoutRegistry, ParseOptions, RecordLayout};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(name = "cnab", version, about = "Inspeciona, valida e converte arquivos CNAB 240, 400 e 150")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Identifica o formato e o banco pelo primeiro registro.
+    Detect { file: PathBuf },
+    /// Mostra campo a campo um registro do arquivo.
+    Inspect {
+        file: PathBuf,
+        /// Número do registro (1 = primeiro).
+        #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        line: u64,
+        /// Layout de definição em JSON, no lugar dos layouts do formato detectado.
+        #[arg(long)]
+        layout: Option<PathBuf>,
+    },
+    /// Confere a estrutura do arquivo e os totais dos trailers.
+    Validate { file: PathBuf },
+    /// Converte o arquivo para JSON Lines ou CSV.
+    Convert {
+        file: PathBuf,
+        #[arg(long, value_enum)]
+        to: Target,
+        /// Arquivo de saída (JSON; padrão: saída padrão) ou diretório dos CSVs (padrão: `.`).
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Layout de definição em JSON, no lugar dos layouts do formato detectado.
+        #[arg(long)]
+        layout: Option<PathBuf>,
+    },
+    /// Troca nomes, CPF/CNPJ e endereços por dados fictícios, mantendo posições e totais.
+    Anonymize {
+        file: PathBuf,
+        /// Arquivo de saída (padrão: saída padrão).
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Semente dos dados fictícios.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Campo reconhecido pelo nome que deve ser mantido (pode repetir).
+        #[arg(long)]
+        keep: Vec<String>,
+        /// Layout de definição em JSON, no lugar dos layouts do formato detectado.
+        #[arg(long)]
+        layout: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+    /// Um objeto JSON por registro.
+    Json,
+    /// Um CSV por tipo de registro.
+    Csv,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("erro: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> CliResult<()> {
+    let mut stdout = std::io::stdout().lock();
+    match command {
+        Command::Detect { file } => {
+            let detected = detect_file(&file)?;
+            let format = match detected.format {
+                CnabFormat::Cnab240 => "CNAB 240",
+                CnabFormat::Cnab400 => "CNAB 400",
+                CnabFormat::Cnab150 => "FEBRABAN 150",
+            };
+            match Bank::from_codigo(detected.bank_code) {
+                Some(bank) => writeln!(stdout, "{format}, banco {:03} ({bank:?})", detected.bank_code)?,
+                None => writeln!(stdout, "{format}, banco {:03}", detected.bank_code)?,
+            }
+        }
+        Command::Inspect { file, line, layout } => {
+            let (registry, framing) = layouts(&file, layout.as_deref())?;
+            stdout.write_all(inspect(open(&file)?, &registry, framing, line)?.as_bytes())?;
+        }
+        Command::Validate { file } => writeln!(stdout, "{}", validate(&file)?)?,
+        Command::Convert { file, to, out, layout } => {
+            let (registry, framing) = layouts(&file, layout.as_deref())?;
+            let options = ParseOptions::default();
+            let json = JsonLinesOptions { framing, ..Default::default() };
+            match (to, out) {
+                (Target::Json, Some(out)) => {
+                    convert::to_json_lines_with(open(&file)?, File::create(out)?, &registry, &options, &json)?;
+                }
+                (Target::Json, None) => {
+                    convert::to_json_lines_with(open(&file)?, &mut stdout, &registry, &options, &json)?;
+                }
+                (Target::Csv, out) => {
+                    let dir = out.unwrap_or_else(|| PathBuf::from("."));
+                    std::fs::create_dir_all(&dir)?;
+                    let csv = CsvOptions { framing, ..Default::default() };
+                    for (path, records) in convert::to_csv(open(&file)?, &dir, &registry, &options, &csv)? {
+                        writeln!(stdout, "{}: {records} registros", path.display())?;
+                    }
+                }
+            }
+        }
+        Command::Anonymize { file, out, seed, keep, layout } => {
+            let (registry, _) = layouts(&file, layout.as_deref())?;
+            let rules = keep.into_iter().fold(AnonymizeRules::new().seed(seed), AnonymizeRules::keep);
+            let anonimo = anonymize::anonymize(&std::fs::read(&file)?, &registry, &rules)?;
+            match out {
+                Some(out) => std::fs::write(out, anonimo)?,
+                None => stdout.write_all(&anonimo)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn open(path: &Path) -> CliResult<BufReader<File>> {
+    File::open(path).map(BufReader::new).map_err(|e| format!("{}: {e}", path.display()).into())
+}
+
+fn detect_file(path: &Path) -> CliResult<Detected> {
+    detect::peek(&mut open(path)?)?.ok_or_else(|| format!("{}: formato não reconhecido", path.display()).into())
+}
+
+/// Layouts para o arquivo: os do formato detectado ou o de `--layout`, com o framing.
+fn layouts(path: &Path, layout: Option<&Path>) -> CliResult<(LayoutRegistry, Framing)> {
+    let mut registry = LayoutRegistry::new();
+    if let Some(layout) = layout {
+        let source = std::fs::read_to_string(layout).map_err(|e| format!("{}: {e}", layout.display()))?;
+        let layout = cnab_fixedwidth::layout::from_json(&source)?;
+        // Registros de tamanho fixo só em arquivos blocados (sem quebra no primeiro bloco)
+        let blocked = !open(path)?.fill_buf()?.contains(&b'\n');
+        let framing = layout.width().filter(|_| blocked).map_or(Framing::Lines, Framing::FixedLength);
+        registry.register(RecordLayout::new("registro", layout.into_fields()));
+        return Ok((registry, framing));
+    }
+
+    let detected = detect_file(path)?;
+    let registry = match detected.format {
+        CnabFormat::Cnab240 => cobranca240::registry(),
+        // Posição 2 do header: 1 = remessa, 2 = retorno
+        CnabFormat::Cnab400 if open(path)?.fill_buf()?.get(1) == Some(&b'1') => cobranca400::remessa_registry(),
+        CnabFormat::Cnab400 => cobranca400::retorno_registry(),
+        CnabFormat::Cnab150 => debito150::registry(),
+    };
+    Ok((registry, detected.framing()))
+}
+
+/// Registro `line` com a régua e campo a campo (veja [`debug::annotate_bytes`]).
+fn inspect<R: BufRead>(reader: R, registry: &LayoutRegistry, framing: Framing, line: u64) -> CliResult<String> {
+    let mut reader = RecordReader::new(reader, framing);
+    let mut buf = Vec::new();
+    while reader.records_read() < line {
+        if !reader.read_record(&mut buf)? {
+            return Err(format!("o arquivo tem só {} registros", reader.records_read()).into());
+        }
+    }
+    let layout = registry.resolve(&buf).ok_or_else(|| format!("registro {line}: nenhum layout se aplica"))?;
+
+    let mut out = format!("registro {line}: {} ({} posições)\n", layout.name, buf.len());
+    out.push_str(&debug::annotate_bytes(&buf, &layout.fields, &ParseOptions::default()));
+    Ok(out)
+}
+
+/// Confere o arquivo com o leitor completo do formato e resume o que foi lido.
+fn validate(path: &Path) -> CliResult<String> {
+    let detected = detect_file(path)?;
+    let summary = match detected.format {
+        CnabFormat::Cnab240 => {
+            // Serviço no header do primeiro lote (posições 10..11): 04 = extrato
+            let file = std::fs::read(path)?;
+            let mut records = RecordReader::new(file.as_slice(), Framing::FixedLength(240));
+            let mut buf = Vec::new();
+            let extrato = records.read_record(&mut buf)? && records.read_record(&mut buf)? && buf.get(9..11) == Some(b"04");
+            if extrato {
+                let extrato = extrato240::parse_extrato(file.as_slice())?;
+                format!("OK: extrato com {} contas", extrato.contas.len())
+            } else {
+                let retorno = cnab240::parse_retorno_cobranca(file.as_slice())?;
+                format!("OK: {} lotes, {} títulos", retorno.lotes.len(), retorno.titulos().count())
+            }
+        }
+        CnabFormat::Cnab400 => format!("OK: {} registros", cobranca400::validate(open(path)?)?),
+        CnabFormat::Cnab150 => {
+            let arquivo = debito150::parse_arquivo(open(path)?)?;
+            format!("OK: {} registros", arquivo.registros.len() + 2)
+        }
+    };
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inspects_a_record_field_by_field() {
+        let header = format!("0{:<393}000001", "1REMESSA01COBRANCA");
+        let detalhe = format!("1{:<393}00000X", "");
+        let file = format!("{header}\r\n{detalhe}\r\n");
+        let registry = cobranca400::remessa_registry();
+
+        let dump = inspect(file.as_bytes(), &registry, Framing::FixedLength(400), 1).unwrap();
+        assert!(dump.starts_with("registro 1: header_arquivo (400 posições)\n"), "{dump}");
+        assert!(dump.contains("\n     3..9  literal_operacao  [REMESSA]  'REMESSA'\n"), "{dump}");
+
+        let dump = inspect(file.as_bytes(), &registry, Framing::FixedLength(400), 2).unwrap();
+        assert!(dump.contains("395..400  sequencial") && dump.contains("[00000X]  ERRO:"), "{dump}");
+        assert!(inspect(file.as_bytes(), &registry, Framing::FixedLength(400), 3).is_err());
+
+        // Arquivo blocado: os mesmos registros, sem quebras de linha
+        let blocked = format!("{header}{detalhe}");
+        let dump = inspect(blocked.as_bytes(), &registry, Framing::FixedLength(400), 2).unwrap();
+        assert!(dump.starts_with("registro 2: remessa_tipo1 (400 posições)\n"), "{dump}");
+    }
+}
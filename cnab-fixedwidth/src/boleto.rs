@@ -0,0 +1,284 @@
+//! # Boleto: Código de Barras e Linha Digitável
+//!
+//! Monta e interpreta o código de barras (44 dígitos) e a linha digitável (47 dígitos)
+//! do boleto a partir de banco, moeda, fator de vencimento, valor e campo livre, com os
+//! DVs de módulo 10 (campos da linha) e módulo 11 (DV geral).
+//!
+//! ```no_run
+//! use cnab_fixedwidth::boleto::{self, Boleto};
+//!
+//! let boleto = Boleto {
+//!     banco: 341,
+//!     moeda: 9,
+//!     fator_vencimento: boleto::fator_vencimento(10112026)?,
+//!     valor: 15_000,
+//!     campo_livre: "1090000123412345678901000".into(),
+//! };
+//! println!("{}", boleto.linha_digitavel_formatada()?);
+//! let lido = Boleto::from_linha_digitavel("34191.09008 ...")?;
+//! let vencimento = boleto::data_vencimento(lido.fator_vencimento, 16102026);
+//! # Ok::<(), boleto::BoletoError>(())
+//! ```
+//!
+//! O fator de vencimento conta os dias desde 07/10/1997 e recomeçou em 1000 no dia
+//! 22/02/2025, quando passou de 9999; por isso a data de um fator depende de uma data de
+//! referência (ex: a do processamento).
+
+use crate::nosso_numero::CheckDigit;
+use thiserror::Error;
+
+/// Erros ao montar ou interpretar um código de barras ou uma linha digitável.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BoletoError {
+    /// Código com outra quantidade de dígitos.
+    #[error("código com {found} dígitos, esperados {expected}")]
+    Length { expected: usize, found: usize },
+
+    /// Código com caracteres que não são dígitos (fora os separadores da linha digitável).
+    #[error("'{value}' não é uma sequência de dígitos")]
+    InvalidDigits { value: String },
+
+    /// Um dos DVs não confere.
+    #[error("{campo}: dígito verificador {found}, esperado {expected}")]
+    CheckDigit { campo: &'static str, expected: char, found: char },
+
+    /// Campo do título que não cabe no código (ex: campo livre sem 25 dígitos).
+    #[error("{field} inválido para o boleto: {value}")]
+    InvalidField { field: &'static str, value: String },
+
+    /// Data inválida ou fora da faixa do fator de vencimento.
+    #[error("data {data:08} sem fator de vencimento")]
+    Vencimento { data: u32 },
+}
+
+/// DV geral do código de barras: módulo 11, pesos 2 a 9; restos 0 e 1 viram `1`.
+const DV_GERAL: CheckDigit = CheckDigit::Mod11 { max_weight: 9, on_ten: '1', on_eleven: '1' };
+
+/// Dados que compõem o código de barras.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Boleto {
+    /// Código do banco na compensação.
+    pub banco: u16,
+    /// Código da moeda (9 = real).
+    pub moeda: u8,
+    /// Fator de vencimento (0 = sem vencimento; veja [`fator_vencimento`]).
+    pub fator_vencimento: u16,
+    /// Valor, em centavos (0 = valor informado no pagamento).
+    pub valor: i64,
+    /// Campo livre: 25 dígitos na composição do banco.
+    pub campo_livre: String,
+}
+
+impl Boleto {
+    /// Código de barras: banco, moeda, DV geral, fator, valor e campo livre.
+    pub fn codigo_barras(&self) -> Result<String, BoletoError> {
+        let invalid = |field, value: String| Err(BoletoError::InvalidField { field, value });
+        if self.banco > 999 {
+            return invalid("banco", self.banco.to_string());
+        }
+        if self.moeda > 9 {
+            return invalid("moeda", self.moeda.to_string());
+        }
+        if !(0..10_000_000_000).contains(&self.valor) {
+            return invalid("valor", self.valor.to_string());
+        }
+        if self.fator_vencimento > 9999 {
+            return invalid("fator de vencimento", self.fator_vencimento.to_string());
+        }
+        if self.campo_livre.len() != 25 || !is_digits(&self.campo_livre) {
+            return invalid("campo livre", format!("'{}'", self.campo_livre));
+        }
+        let sem_dv = format!(
+            "{:03}{}{:04}{:010}{}",
+            self.banco, self.moeda, self.fator_vencimento, self.valor, self.campo_livre
+        );
+        let dv = compute(DV_GERAL, &sem_dv);
+        Ok(format!("{}{dv}{}", &sem_dv[..4], &sem_dv[4..]))
+    }
+
+    /// Linha digitável, só com os 47 dígitos.
+    pub fn linha_digitavel(&self) -> Result<String, BoletoError> {
+        let codigo = self.codigo_barras()?;
+        let campo = |digits: String| {
+            let dv = compute(CheckDigit::Mod10, &digits);
+            format!("{digits}{dv}")
+        };
+        Ok(format!(
+            "{}{}{}{}{}",
+            campo(format!("{}{}", &codigo[..4], &codigo[19..24])),
+            campo(codigo[24..34].to_string()),
+            campo(codigo[34..44].to_string()),
+            &codigo[4..5],
+            &codigo[5..19],
+        ))
+    }
+
+    /// Linha digitável como impressa no boleto (`AAAAA.AAAAA BBBBB.BBBBBB CCCCC.CCCCCC D EEEEEEEEEEEEEE`).
+    pub fn linha_digitavel_formatada(&self) -> Result<String, BoletoError> {
+        let linha = self.linha_digitavel()?;
+        Ok(format!(
+            "{}.{} {}.{} {}.{} {} {}",
+            &linha[..5],
+            &linha[5..10],
+            &linha[10..15],
+            &linha[15..21],
+            &linha[21..26],
+            &linha[26..32],
+            &linha[32..33],
+            &linha[33..]
+        ))
+    }
+
+    /// Interpreta um código de barras, conferindo o DV geral.
+    pub fn from_codigo_barras(codigo: &str) -> Result<Self, BoletoError> {
+        let codigo = codigo.trim();
+        check_digits(codigo, 44)?;
+        let sem_dv = format!("{}{}", &codigo[..4], &codigo[5..]);
+        check(DV_GERAL, "código de barras", &sem_dv, &codigo[4..5])?;
+        let number = |range: std::ops::Range<usize>| codigo[range].parse::<i64>().unwrap_or_default();
+        Ok(Self {
+            banco: number(0..3) as u16,
+            moeda: number(3..4) as u8,
+            fator_vencimento: number(5..9) as u16,
+            valor: number(9..19),
+            campo_livre: codigo[19..].to_string(),
+        })
+    }
+
+    /// Interpreta uma linha digitável, com ou sem pontos e espaços, conferindo os DVs dos
+    /// três primeiros campos e o DV geral.
+    pub fn from_linha_digitavel(linha: &str) -> Result<Self, BoletoError> {
+        let linha: String = linha.chars().filter(|c| !matches!(c, ' ' | '.')).collect();
+        check_digits(&linha, 47)?;
+        for (campo, range) in [("campo 1", 0..10), ("campo 2", 10..21), ("campo 3", 21..32)] {
+            let (digits, dv) = linha[range.clone()].split_at(range.len() - 1);
+            check(CheckDigit::Mod10, campo, digits, dv)?;
+        }
+        let codigo = format!("{}{}{}{}{}", &linha[..4], &linha[32..47], &linha[4..9], &linha[10..20], &linha[21..31]);
+        Self::from_codigo_barras(&codigo)
+    }
+}
+
+/// Fator de vencimento da data (`DDMMAAAA`), a partir de 03/07/2000 (fator 1000); desde
+/// 22/02/2025 o fator volta a 1000 a cada 9000 dias.
+pub fn fator_vencimento(data: u32) -> Result<u16, BoletoError> {
+    let dias = dias_desde_base(data).ok_or(BoletoError::Vencimento { data })?;
+    match dias {
+        1000..=9999 => Ok(dias as u16),
+        10_000.. => Ok(((dias - 10_000) % 9000 + 1000) as u16),
+        _ => Err(BoletoError::Vencimento { data }),
+    }
+}
+
+/// Data de vencimento (`DDMMAAAA`) do fator, no ciclo mais próximo de `referencia`;
+/// `None` para o fator 0 (sem vencimento), fatores abaixo de 1000 ou referência inválida.
+pub fn data_vencimento(fator: u16, referencia: u32) -> Option<u32> {
+    if !(1000..=9999).contains(&fator) {
+        return None;
+    }
+    let referencia = dias_desde_base(referencia)?;
+    // O ciclo k vence `fator + 9000 * k` dias depois da base
+    let ciclo = (referencia - i64::from(fator) + 4500).div_euclid(9000).max(0);
+    Some(data_de_dias(i64::from(fator) + 9000 * ciclo))
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn check_digits(codigo: &str, expected: usize) -> Result<(), BoletoError> {
+    if !is_digits(codigo) {
+        return Err(BoletoError::InvalidDigits { value: codigo.to_string() });
+    }
+    if codigo.len() != expected {
+        return Err(BoletoError::Length { expected, found: codigo.len() });
+    }
+    Ok(())
+}
+
+fn compute(algorithm: CheckDigit, digits: &str) -> char {
+    algorithm.compute(digits).unwrap_or('0')
+}
+
+fn check(algorithm: CheckDigit, campo: &'static str, digits: &str, dv: &str) -> Result<(), BoletoError> {
+    let expected = compute(algorithm, digits);
+    let found = dv.chars().next().unwrap_or(' ');
+    if expected != found {
+        return Err(BoletoError::CheckDigit { campo, expected, found });
+    }
+    Ok(())
+}
+
+/// Dias desde 07/10/1997 (base do fator de vencimento), ou `None` se a data não existe.
+fn dias_desde_base(data: u32) -> Option<i64> {
+    let (dia, mes, ano) = (data / 1_000_000, data / 10_000 % 100, data % 10_000);
+    let dias = dias_civis(i64::from(ano), i64::from(mes), i64::from(dia));
+    (data_de_dias(dias - BASE) == data).then_some(dias - BASE)
+}
+
+/// Dias civis de 07/10/1997 contados a partir de 01/01/1970.
+const BASE: i64 = 10_141;
+
+/// Dias desde 01/01/1970 no calendário gregoriano (algoritmo de Howard Hinnant).
+fn dias_civis(ano: i64, mes: i64, dia: i64) -> i64 {
+    let ano = if mes <= 2 { ano - 1 } else { ano };
+    let era = ano.div_euclid(400);
+    let ano_da_era = ano - era * 400;
+    let dia_do_ano = (153 * ((mes + 9) % 12) + 2) / 5 + dia - 1;
+    let dia_da_era = ano_da_era * 365 + ano_da_era / 4 - ano_da_era / 100 + dia_do_ano;
+    era * 146_097 + dia_da_era - 719_468
+}
+
+/// Data (`DDMMAAAA`) a `dias` da base do fator.
+fn data_de_dias(dias: i64) -> u32 {
+    let z = dias + BASE + 719_468;
+    let era = z.div_euclid(146_097);
+    let dia_da_era = z - era * 146_097;
+    let ano_da_era = (dia_da_era - dia_da_era / 1460 + dia_da_era / 36_524 - dia_da_era / 146_096) / 365;
+    let dia_do_ano = dia_da_era - (365 * ano_da_era + ano_da_era / 4 - ano_da_era / 100);
+    let mp = (5 * dia_do_ano + 2) / 153;
+    let dia = dia_do_ano - (153 * mp + 2) / 5 + 1;
+    let mes = if mp < 10 { mp + 3 } else { mp - 9 };
+    let ano = ano_da_era + era * 400 + i64::from(mes <= 2);
+    (dia * 1_000_000 + mes * 10_000 + ano) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_and_parses_barcodes_and_linha_digitavel() {
+        assert_eq!(fator_vencimento(3072000), Ok(1000));
+        assert_eq!(fator_vencimento(21022025), Ok(9999));
+        assert_eq!(fator_vencimento(22022025), Ok(1000));
+        assert_eq!(fator_vencimento(10112026), Ok(1626));
+        assert_eq!(fator_vencimento(31022026), Err(BoletoError::Vencimento { data: 31022026 }));
+        assert_eq!(data_vencimento(1626, 16102026), Some(10112026));
+        assert_eq!(data_vencimento(9990, 16102026), Some(12022025));
+        assert_eq!(data_vencimento(1626, 1012001), Some(21032002));
+
+        let boleto = Boleto {
+            banco: 341,
+            moeda: 9,
+            fator_vencimento: 1626,
+            valor: 15_000,
+            campo_livre: "1090000123412345678901000".into(),
+        };
+        let codigo = boleto.codigo_barras().unwrap();
+        assert_eq!(codigo, "34191162600000150001090000123412345678901000");
+        assert_eq!(boleto.linha_digitavel().unwrap(), "34191090080012341234856789010008116260000015000");
+        let linha = boleto.linha_digitavel_formatada().unwrap();
+        assert_eq!(linha, "34191.09008 00123.412348 56789.010008 1 16260000015000");
+        assert_eq!(Boleto::from_codigo_barras(&codigo).unwrap(), boleto);
+        assert_eq!(Boleto::from_linha_digitavel(&linha).unwrap(), boleto);
+
+        let err = Boleto::from_linha_digitavel(&linha.replace("00123.412348", "00123.412349")).unwrap_err();
+        assert_eq!(err.to_string(), "campo 2: dígito verificador 9, esperado 8");
+        let err = Boleto::from_codigo_barras(&codigo.replacen("341911", "341921", 1)).unwrap_err();
+        assert!(matches!(err, BoletoError::CheckDigit { campo: "código de barras", .. }), "{err}");
+        assert_eq!(Boleto::from_codigo_barras("341"), Err(BoletoError::Length { expected: 44, found: 3 }));
+        let sem_campo_livre = Boleto { campo_livre: "123".into(), ..boleto };
+        assert!(matches!(sem_campo_livre.codigo_barras(), Err(BoletoError::InvalidField { field: "campo livre", .. })));
+    }
+}
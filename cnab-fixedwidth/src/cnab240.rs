@@ -0,0 +1,1043 @@
+//! # CNAB 240 — Retorno de Cobrança
+//!
+//! Leitura completa de um retorno de cobrança FEBRABAN 240 em uma chamada: framing de
+//! 240 posições (com ou sem quebras de linha), Latin-1, identificação de cada registro,
+//! agrupamento dos segmentos T + U por título e conferência das quantidades dos trailers.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::cnab240;
+//!
+//! let file = std::io::BufReader::new(std::fs::File::open("retorno.ret")?);
+//! let retorno = cnab240::parse_retorno_cobranca(file)?;
+//! for event in retorno.events() {
+//!     println!("{event:?}");
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Os layouts seguem o manual FEBRABAN 240 (v10); valores monetários são lidos em
+//! centavos e datas no formato `DDMMAAAA`, como no arquivo.
+//!
+//! Os segmentos da remessa (P, Q e R) ficam em `layouts::cobranca240`, com a
+//! feature `layouts-240`.
+//!
+//! Na escrita, [`FileWriter`] monta o arquivo a partir dos headers e dos detalhes: número
+//! do lote, sequencial de cada registro no lote e trailers (quantidades e somatórias) são
+//! calculados pelo escritor.
+
+use crate::banks::Bank;
+use crate::dv;
+use crate::ocorrencias::{self, Ocorrencia};
+use crate::reader::{Framing, RecordReader};
+use crate::retorno::{MovimentoRetorno, RetornoEvent};
+use crate::segments::SegmentGroup;
+use crate::writer::{self, FixedWidthWrite, WriteOptions};
+use crate::{
+    CnabFormat, FieldKind, FieldPos, FieldSpec, FixedWidth, FixedWidthError, FixedWidthParse, FixedWidthSpec,
+    ParseOptions, Result, TotalsCheck, Value,
+};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+
+/// Header de arquivo (registro tipo `0`).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct HeaderArquivo {
+    /// Código do banco na compensação.
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..17", filler)]
+    pub uso_febraban: (),
+    /// 1 = CPF, 2 = CNPJ.
+    #[fw(pos = "18..18", numeric)]
+    pub tipo_inscricao: u8,
+    #[fw(pos = "19..32", numeric)]
+    pub numero_inscricao: u64,
+    #[fw(pos = "33..52", alpha)]
+    pub convenio: String,
+    #[fw(pos = "53..57", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "58..58", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "59..70", numeric)]
+    pub conta: u64,
+    #[fw(pos = "71..71", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "72..72", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "73..102", alpha)]
+    pub nome_empresa: String,
+    #[fw(pos = "103..132", alpha)]
+    pub nome_banco: String,
+    #[fw(pos = "133..142", filler)]
+    pub uso_febraban_2: (),
+    /// 1 = remessa, 2 = retorno.
+    #[fw(pos = "143..143", numeric)]
+    pub codigo_remessa_retorno: u8,
+    #[fw(pos = "144..151", numeric)]
+    pub data_geracao: u32,
+    #[fw(pos = "152..157", numeric)]
+    pub hora_geracao: u32,
+    /// Número sequencial do arquivo (NSA).
+    #[fw(pos = "158..163", numeric)]
+    pub sequencial_arquivo: u32,
+    #[fw(pos = "164..166", numeric)]
+    pub versao_layout: u16,
+    #[fw(pos = "167..171", numeric)]
+    pub densidade: u32,
+    #[fw(pos = "172..191", alpha)]
+    pub reservado_banco: String,
+    #[fw(pos = "192..211", alpha)]
+    pub reservado_empresa: String,
+    #[fw(pos = "212..240", filler)]
+    pub uso_febraban_3: (),
+}
+
+/// Header de lote de cobrança (registro tipo `1`).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct HeaderLote {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    /// `R` = remessa, `T` = retorno.
+    #[fw(pos = "9..9", alpha)]
+    pub operacao: String,
+    /// `01` = cobrança.
+    #[fw(pos = "10..11", numeric)]
+    pub servico: u8,
+    #[fw(pos = "12..13", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "14..16", numeric)]
+    pub versao_layout: u16,
+    #[fw(pos = "17..17", filler)]
+    pub uso_febraban_2: (),
+    #[fw(pos = "18..18", numeric)]
+    pub tipo_inscricao: u8,
+    #[fw(pos = "19..33", numeric)]
+    pub numero_inscricao: u64,
+    #[fw(pos = "34..53", alpha)]
+    pub convenio: String,
+    #[fw(pos = "54..58", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "59..59", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "60..71", numeric)]
+    pub conta: u64,
+    #[fw(pos = "72..72", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "73..73", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "74..103", alpha)]
+    pub nome_empresa: String,
+    #[fw(pos = "104..143", alpha)]
+    pub mensagem_1: String,
+    #[fw(pos = "144..183", alpha)]
+    pub mensagem_2: String,
+    #[fw(pos = "184..191", numeric)]
+    pub numero_remessa_retorno: u32,
+    #[fw(pos = "192..199", numeric)]
+    pub data_gravacao: u32,
+    #[fw(pos = "200..207", numeric)]
+    pub data_credito: u32,
+    #[fw(pos = "208..240", filler)]
+    pub uso_febraban_3: (),
+}
+
+/// Segmento T do retorno: identificação do título, valor nominal e tarifas.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoT {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    /// Número sequencial do registro no lote.
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", filler)]
+    pub uso_febraban: (),
+    /// Código de movimento do retorno (ex: 06 = liquidação).
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_movimento: u8,
+    #[fw(pos = "18..22", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "23..23", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "24..35", numeric)]
+    pub conta: u64,
+    #[fw(pos = "36..36", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "37..37", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "38..57", alpha)]
+    pub nosso_numero: String,
+    #[fw(pos = "58..58", numeric)]
+    pub carteira: u8,
+    #[fw(pos = "59..73", alpha)]
+    pub numero_documento: String,
+    #[fw(pos = "74..81", numeric)]
+    pub vencimento: u32,
+    /// Valor nominal do título, em centavos.
+    #[fw(pos = "82..96", numeric)]
+    pub valor_titulo: i64,
+    #[fw(pos = "97..99", numeric)]
+    pub banco_cobrador: u16,
+    #[fw(pos = "100..104", numeric)]
+    pub agencia_cobradora: u32,
+    #[fw(pos = "105..105", alpha)]
+    pub agencia_cobradora_dv: String,
+    /// Identificação do título na empresa (uso da empresa).
+    #[fw(pos = "106..130", alpha)]
+    pub uso_empresa: String,
+    #[fw(pos = "131..132", numeric)]
+    pub codigo_moeda: u8,
+    #[fw(pos = "133..133", numeric)]
+    pub pagador_tipo_inscricao: u8,
+    #[fw(pos = "134..148", numeric)]
+    pub pagador_inscricao: u64,
+    #[fw(pos = "149..188", alpha)]
+    pub pagador_nome: String,
+    #[fw(pos = "189..198", numeric)]
+    pub contrato: u64,
+    /// Valor da tarifa/custas, em centavos.
+    #[fw(pos = "199..213", numeric)]
+    pub valor_tarifa: i64,
+    /// Até 5 códigos de motivo da ocorrência, de 2 posições.
+    #[fw(pos = "214..223", alpha)]
+    pub motivos: String,
+    #[fw(pos = "224..240", filler)]
+    pub uso_febraban_2: (),
+}
+
+/// Segmento U do retorno: valores efetivos do pagamento e datas de ocorrência/crédito.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoU {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_movimento: u8,
+    /// Juros, multa e encargos, em centavos (assim como os demais valores).
+    #[fw(pos = "18..32", numeric)]
+    pub valor_encargos: i64,
+    #[fw(pos = "33..47", numeric)]
+    pub valor_desconto: i64,
+    #[fw(pos = "48..62", numeric)]
+    pub valor_abatimento: i64,
+    #[fw(pos = "63..77", numeric)]
+    pub valor_iof: i64,
+    #[fw(pos = "78..92", numeric)]
+    pub valor_pago: i64,
+    #[fw(pos = "93..107", numeric)]
+    pub valor_liquido: i64,
+    #[fw(pos = "108..122", numeric)]
+    pub valor_outras_despesas: i64,
+    #[fw(pos = "123..137", numeric)]
+    pub valor_outros_creditos: i64,
+    #[fw(pos = "138..145", numeric)]
+    pub data_ocorrencia: u32,
+    #[fw(pos = "146..153", numeric)]
+    pub data_credito: u32,
+    #[fw(pos = "154..157", alpha)]
+    pub ocorrencia_pagador: String,
+    #[fw(pos = "158..165", numeric)]
+    pub data_ocorrencia_pagador: u32,
+    #[fw(pos = "166..180", numeric)]
+    pub valor_ocorrencia_pagador: i64,
+    #[fw(pos = "181..210", alpha)]
+    pub complemento_ocorrencia_pagador: String,
+    #[fw(pos = "211..213", numeric)]
+    pub banco_correspondente: u16,
+    #[fw(pos = "214..233", alpha)]
+    pub nosso_numero_correspondente: String,
+    #[fw(pos = "234..240", filler)]
+    pub uso_febraban_2: (),
+}
+
+/// Trailer de lote de cobrança (registro tipo `5`).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct TrailerLote {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..17", filler)]
+    pub uso_febraban: (),
+    /// Registros do lote, incluindo header e trailer.
+    #[fw(pos = "18..23", numeric)]
+    pub quantidade_registros: u32,
+    #[fw(pos = "24..29", numeric)]
+    pub quantidade_simples: u32,
+    /// Valor total da cobrança simples, em centavos (assim como os demais totais).
+    #[fw(pos = "30..46", numeric)]
+    pub valor_simples: i64,
+    #[fw(pos = "47..52", numeric)]
+    pub quantidade_vinculada: u32,
+    #[fw(pos = "53..69", numeric)]
+    pub valor_vinculada: i64,
+    #[fw(pos = "70..75", numeric)]
+    pub quantidade_caucionada: u32,
+    #[fw(pos = "76..92", numeric)]
+    pub valor_caucionada: i64,
+    #[fw(pos = "93..98", numeric)]
+    pub quantidade_descontada: u32,
+    #[fw(pos = "99..115", numeric)]
+    pub valor_descontada: i64,
+    #[fw(pos = "116..123", alpha)]
+    pub aviso_lancamento: String,
+    #[fw(pos = "124..240", filler)]
+    pub uso_febraban_2: (),
+}
+
+/// Trailer de arquivo (registro tipo `9`).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct TrailerArquivo {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..17", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "18..23", numeric)]
+    pub quantidade_lotes: u32,
+    /// Registros do arquivo, incluindo headers e trailers.
+    #[fw(pos = "24..29", numeric)]
+    pub quantidade_registros: u32,
+    #[fw(pos = "30..35", numeric)]
+    pub quantidade_contas: u32,
+    #[fw(pos = "36..240", filler)]
+    pub uso_febraban_2: (),
+}
+
+/// Um título do retorno: o par de segmentos T e U.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TituloRetorno {
+    pub t: SegmentoT,
+    pub u: SegmentoU,
+}
+
+impl TituloRetorno {
+    /// Monta o título a partir de um grupo de [`Pairing::RETORNO_COBRANCA`](crate::segments::Pairing::RETORNO_COBRANCA).
+    pub fn from_group(group: &SegmentGroup) -> Result<Self> {
+        Ok(Self { t: group.require(b'T')?, u: group.require(b'U')? })
+    }
+
+    /// Dados do título para o modelo de eventos ([`RetornoEvent`]).
+    pub fn movimento(&self) -> MovimentoRetorno {
+        MovimentoRetorno {
+            codigo_movimento: self.t.codigo_movimento,
+            nosso_numero: self.t.nosso_numero.clone(),
+            valor_pago: self.u.valor_pago,
+            valor_tarifa: self.t.valor_tarifa,
+            motivos: self.t.motivos.clone(),
+            seu_numero: self.t.numero_documento.clone(),
+            data_ocorrencia: self.u.data_ocorrencia,
+            data_credito: self.u.data_credito,
+        }
+    }
+}
+
+/// Lote de cobrança do retorno.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoteCobranca {
+    pub header: HeaderLote,
+    pub titulos: Vec<TituloRetorno>,
+    pub trailer: TrailerLote,
+}
+
+/// Retorno de cobrança completo, já conferido contra os trailers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetornoCobranca {
+    pub header: HeaderArquivo,
+    pub lotes: Vec<LoteCobranca>,
+    pub trailer: TrailerArquivo,
+}
+
+impl RetornoCobranca {
+    /// Títulos de todos os lotes, na ordem do arquivo.
+    pub fn titulos(&self) -> impl Iterator<Item = &TituloRetorno> {
+        self.lotes.iter().flat_map(|lote| &lote.titulos)
+    }
+
+    /// Eventos de negócio de todos os títulos (liquidações, baixas, rejeições, tarifas...).
+    pub fn events(&self) -> Vec<RetornoEvent> {
+        self.titulos().flat_map(|titulo| RetornoEvent::from_movimento(&titulo.movimento())).collect()
+    }
+}
+
+/// Registro do retorno identificado por [`Estrutura`], na ordem do arquivo.
+enum Lido {
+    HeaderArquivo(HeaderArquivo),
+    HeaderLote(HeaderLote),
+    Titulo(Box<TituloRetorno>),
+    TrailerLote(TrailerLote),
+    TrailerArquivo(TrailerArquivo),
+    /// Segmento T aguardando o U, ou detalhe ignorado (ex: segmento Y).
+    Nada,
+}
+
+/// Lote aberto durante a leitura.
+#[derive(Default)]
+struct LoteAberto {
+    /// Segmento T aguardando o U do mesmo título.
+    pendente: Option<SegmentoT>,
+    registros: u32,
+    /// Quantidade e valor nominal dos títulos por carteira (1 = simples, 2 = vinculada,
+    /// 3 = caucionada, 4 = descontada).
+    totais: [(i64, i64); 4],
+}
+
+/// Conferência da estrutura do retorno registro a registro, usada pela leitura completa
+/// ([`parse_retorno_cobranca`]) e pela leitura em fluxo ([`RetornoReader`]).
+#[derive(Default)]
+struct Estrutura {
+    header: bool,
+    /// Banco do header, para a tabela de ocorrências.
+    banco: Option<Bank>,
+    lote: Option<LoteAberto>,
+    lotes: u32,
+    trailer: bool,
+}
+
+impl Estrutura {
+    fn feed(&mut self, line_number: u64, buf: &[u8], options: &ParseOptions) -> Result<Lido> {
+        let parse_error = |e: FixedWidthError| e.at_line_with(line_number, buf, &[], options.redaction);
+        let structure = |message: &str| FixedWidthError::Structure { line_number, message: message.to_string() };
+        if self.trailer {
+            return Err(structure("registro após o trailer de arquivo"));
+        }
+        let lido = match (buf.get(7), &mut self.lote) {
+            (Some(b'0'), None) if !self.header => {
+                self.header = true;
+                let header = HeaderArquivo::parse_bytes(buf, options).map_err(parse_error)?;
+                self.banco = Bank::from_codigo(header.banco);
+                Lido::HeaderArquivo(header)
+            }
+            (_, _) if !self.header => return Err(structure("arquivo sem header de arquivo")),
+            (Some(b'1'), None) => {
+                let header = HeaderLote::parse_bytes(buf, options).map_err(parse_error)?;
+                self.lote = Some(LoteAberto { registros: 1, ..Default::default() });
+                Lido::HeaderLote(header)
+            }
+            (Some(b'3'), Some(aberto)) => {
+                aberto.registros += 1;
+                match (buf.get(13), aberto.pendente.take()) {
+                    (Some(b'T'), None) => {
+                        let t = SegmentoT::parse_bytes(buf, options).map_err(parse_error)?;
+                        // Código fora da tabela do banco: o título segue, com aviso
+                        if let Ocorrencia::Outra(codigo) = ocorrencias::ocorrencia(t.codigo_movimento, CnabFormat::Cnab240, self.banco)
+                            && let Some(field) = SegmentoT::field_by_name("codigo_movimento")
+                        {
+                            options.warn(field, FixedWidthError::UnknownCode { field: field.name, code: format!("{codigo:02}") });
+                        }
+                        aberto.pendente = Some(t);
+                        Lido::Nada
+                    }
+                    (Some(b'U'), Some(t)) => {
+                        let u = SegmentoU::parse_bytes(buf, options).map_err(parse_error)?;
+                        if let Some((quantidade, valor)) = aberto.totais.get_mut(usize::from(t.carteira).wrapping_sub(1)) {
+                            *quantidade += 1;
+                            *valor += t.valor_titulo;
+                        }
+                        Lido::Titulo(Box::new(TituloRetorno { t, u }))
+                    }
+                    (Some(b'U'), None) => return Err(structure("segmento U sem o segmento T")),
+                    (_, Some(_)) => return Err(structure("segmento T sem o segmento U")),
+                    (_, None) => Lido::Nada,
+                }
+            }
+            (Some(b'5'), Some(aberto)) => {
+                if aberto.pendente.is_some() {
+                    return Err(structure("segmento T sem o segmento U"));
+                }
+                let trailer = TrailerLote::parse_bytes(buf, options).map_err(parse_error)?;
+                let mut check = TotalsCheck::new("TrailerLote").check(
+                    "quantidade_registros",
+                    trailer.quantidade_registros.into(),
+                    (aberto.registros + 1).into(),
+                );
+                let informados = [
+                    ("quantidade_simples", "valor_simples", trailer.quantidade_simples, trailer.valor_simples),
+                    ("quantidade_vinculada", "valor_vinculada", trailer.quantidade_vinculada, trailer.valor_vinculada),
+                    ("quantidade_caucionada", "valor_caucionada", trailer.quantidade_caucionada, trailer.valor_caucionada),
+                    ("quantidade_descontada", "valor_descontada", trailer.quantidade_descontada, trailer.valor_descontada),
+                ];
+                if informados.iter().any(|&(_, _, quantidade, valor)| quantidade != 0 || valor != 0) {
+                    for ((campo_quantidade, campo_valor, quantidade, valor), (titulos, total)) in
+                        informados.into_iter().zip(aberto.totais)
+                    {
+                        check = check.check(campo_quantidade, quantidade.into(), titulos).check(campo_valor, valor, total);
+                    }
+                }
+                check.finish(line_number)?;
+                self.lote = None;
+                self.lotes += 1;
+                Lido::TrailerLote(trailer)
+            }
+            (Some(b'9'), None) => {
+                let trailer = TrailerArquivo::parse_bytes(buf, options).map_err(parse_error)?;
+                TotalsCheck::new("TrailerArquivo")
+                    .check("quantidade_lotes", trailer.quantidade_lotes.into(), self.lotes.into())
+                    .check("quantidade_registros", trailer.quantidade_registros.into(), line_number as i64)
+                    .finish(line_number)?;
+                self.trailer = true;
+                Lido::TrailerArquivo(trailer)
+            }
+            (Some(b'3' | b'5'), None) => return Err(structure("registro de detalhe fora de um lote")),
+            (Some(b'0' | b'1' | b'9'), Some(_)) => return Err(structure("lote sem trailer de lote")),
+            _ => return Err(structure("tipo de registro desconhecido")),
+        };
+        Ok(lido)
+    }
+
+    /// Confere o fim do arquivo, depois do último registro (`last`).
+    fn finish(&self, last: u64) -> Result<()> {
+        let structure = |message: &str| FixedWidthError::Structure { line_number: last, message: message.to_string() };
+        match (self.header, self.trailer) {
+            (true, true) => Ok(()),
+            (false, _) => Err(structure("arquivo vazio")),
+            (true, false) => Err(structure("arquivo sem trailer de arquivo")),
+        }
+    }
+}
+
+/// Lê um retorno de cobrança CNAB 240 completo.
+///
+/// Padrões: registros de 240 posições (quebras de linha entre eles são opcionais) em
+/// Latin-1. Segmentos de detalhe que não sejam T ou U (ex: Y) são contados mas
+/// ignorados. Falhas de estrutura (registro fora de ordem, T sem U) resultam em
+/// `FixedWidthError::Structure`, e totais dos trailers que não batem com o arquivo em
+/// `FixedWidthError::Integrity`; erros de campo vêm com o número do registro.
+///
+/// A quantidade de registros é sempre conferida. As quantidades e valores por carteira
+/// do trailer de lote só são conferidos quando o banco os informa (algum diferente de
+/// zero), pois vários bancos deixam esses totais zerados no retorno.
+pub fn parse_retorno_cobranca<R: BufRead>(reader: R) -> Result<RetornoCobranca> {
+    parse_retorno_cobranca_with(reader, &ParseOptions::default())
+}
+
+/// Como [`parse_retorno_cobranca`], com opções de parse. Com `options.warnings`, códigos
+/// de movimento fora da tabela de ocorrências do banco são registrados como aviso.
+pub fn parse_retorno_cobranca_with<R: BufRead>(reader: R, options: &ParseOptions) -> Result<RetornoCobranca> {
+    let mut reader = RecordReader::new(reader, Framing::FixedLength(240));
+    let mut buf = Vec::with_capacity(240);
+    let mut estrutura = Estrutura::default();
+
+    let mut header = None;
+    let mut lotes = Vec::new();
+    let mut lote = None;
+    let mut trailer = None;
+    while reader.read_record(&mut buf)? {
+        match estrutura.feed(reader.records_read(), &buf, options)? {
+            Lido::HeaderArquivo(parsed) => header = Some(parsed),
+            Lido::HeaderLote(header) => lote = Some((header, Vec::new())),
+            Lido::Titulo(titulo) => lote.as_mut().expect("lote aberto").1.push(*titulo),
+            Lido::TrailerLote(trailer) => {
+                let (header, titulos) = lote.take().expect("lote aberto");
+                lotes.push(LoteCobranca { header, titulos, trailer });
+            }
+            Lido::TrailerArquivo(parsed) => trailer = Some(parsed),
+            Lido::Nada => {}
+        }
+    }
+    estrutura.finish(reader.records_read())?;
+    let (Some(header), Some(trailer)) = (header, trailer) else { unreachable!("conferido por Estrutura::finish") };
+    Ok(RetornoCobranca { header, lotes, trailer })
+}
+
+/// Leitura em fluxo de um retorno de cobrança CNAB 240, título a título.
+///
+/// Em vez de montar o arquivo inteiro como [`parse_retorno_cobranca`], junta os
+/// segmentos T e U de cada título e entrega os eventos de negócio ([`RetornoEvent`]) à
+/// medida que o arquivo é lido, com memória constante:
+///
+/// ```no_run
+/// use cnab_fixedwidth::cnab240::RetornoReader;
+/// use cnab_fixedwidth::retorno::RetornoEvent;
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// # fn baixar(_: &str, _: i64, _: u32) -> std::io::Result<()> { Ok(()) }
+/// # fn alertar(_: &str, _: &[String]) -> std::io::Result<()> { Ok(()) }
+/// let mut retorno = RetornoReader::new(BufReader::new(File::open("retorno.ret")?));
+/// for event in &mut retorno {
+///     match event? {
+///         RetornoEvent::TituloLiquidado { nosso_numero, valor_pago, data_credito } => baixar(&nosso_numero, valor_pago, data_credito)?,
+///         RetornoEvent::EntradaRejeitada { nosso_numero, motivos } => alertar(&nosso_numero, &motivos)?,
+///         _ => {}
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// A estrutura e os totais dos trailers são conferidos como em [`parse_retorno_cobranca`],
+/// mas só quando o trailer é lido: um erro no fim do lote ou do arquivo chega depois dos
+/// eventos dos títulos anteriores, então aplicações que precisam tratar o arquivo como um
+/// todo devem acumular os eventos até o fim da leitura. Depois de um erro, a leitura
+/// termina.
+pub struct RetornoReader<R: BufRead> {
+    reader: RecordReader<R>,
+    buf: Vec<u8>,
+    options: ParseOptions,
+    estrutura: Estrutura,
+    header: Option<HeaderArquivo>,
+    events: VecDeque<RetornoEvent>,
+    done: bool,
+}
+
+impl<R: BufRead> RetornoReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: RecordReader::new(reader, Framing::FixedLength(240)),
+            buf: Vec::with_capacity(240),
+            options: ParseOptions::default(),
+            estrutura: Estrutura::default(),
+            header: None,
+            events: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Header do arquivo, depois do primeiro registro lido.
+    pub fn header(&self) -> Option<&HeaderArquivo> {
+        self.header.as_ref()
+    }
+
+    /// Próximo título (segmentos T e U), ou `None` no fim do arquivo.
+    pub fn next_titulo(&mut self) -> Result<Option<TituloRetorno>> {
+        while !self.done {
+            let lido = self.read();
+            if lido.is_err() {
+                self.done = true;
+            }
+            if let Some(titulo) = lido? {
+                return Ok(Some(titulo));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Lê um registro; `Ok(None)` para registros que não completam um título.
+    fn read(&mut self) -> Result<Option<TituloRetorno>> {
+        if !self.reader.read_record(&mut self.buf)? {
+            self.done = true;
+            self.estrutura.finish(self.reader.records_read())?;
+            return Ok(None);
+        }
+        match self.estrutura.feed(self.reader.records_read(), &self.buf, &self.options)? {
+            Lido::HeaderArquivo(header) => self.header = Some(header),
+            Lido::Titulo(titulo) => return Ok(Some(*titulo)),
+            _ => {}
+        }
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Iterator for RetornoReader<R> {
+    type Item = Result<RetornoEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.events.is_empty() {
+            match self.next_titulo() {
+                Ok(Some(titulo)) => self.events.extend(RetornoEvent::from_movimento(&titulo.movimento())),
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.events.pop_front().map(Ok)
+    }
+}
+
+/// Registro de detalhe (tipo `3`) escrito por [`FileWriter`].
+pub trait Detalhe: FixedWidthWrite {
+    /// Valor somado no trailer de lote (ex: valor do pagamento no segmento A). `None` em
+    /// segmentos complementares, que só contam como registro.
+    fn valor_lote(&self) -> Option<i64> {
+        None
+    }
+}
+
+/// Totais de um lote apurados por [`FileWriter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TotaisLote {
+    pub banco: u16,
+    pub lote: u16,
+    /// Registros do lote, incluindo header e trailer.
+    pub quantidade_registros: u32,
+    /// Detalhes com valor ([`Detalhe::valor_lote`]), ex: títulos ou pagamentos.
+    pub quantidade_lancamentos: u32,
+    /// Soma dos valores dos detalhes, em centavos.
+    pub valor_total: i64,
+}
+
+/// Trailer de lote (registro tipo `5`) montado a partir dos totais do lote.
+pub trait TrailerLote240: FixedWidthWrite {
+    fn from_totais(totais: &TotaisLote) -> Self;
+}
+
+impl Detalhe for SegmentoT {
+    fn valor_lote(&self) -> Option<i64> {
+        Some(self.valor_titulo)
+    }
+}
+
+impl Detalhe for SegmentoU {}
+
+impl TrailerLote240 for TrailerLote {
+    /// Na remessa de cobrança o trailer de lote só traz a quantidade de registros; os
+    /// totais por carteira são informados pelo banco no retorno.
+    fn from_totais(totais: &TotaisLote) -> Self {
+        TrailerLote {
+            banco: totais.banco,
+            lote: totais.lote,
+            tipo_registro: 5,
+            quantidade_registros: totais.quantidade_registros,
+            ..Default::default()
+        }
+    }
+}
+
+const BANCO: FieldSpec = FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric);
+const LOTE: FieldSpec = FieldSpec::new("lote", FieldPos::new_const(4, 7), FieldKind::Numeric);
+const TIPO_REGISTRO: FieldSpec = FieldSpec::new("tipo_registro", FieldPos::new_const(8, 8), FieldKind::Numeric);
+const SEQUENCIAL: FieldSpec = FieldSpec::new("sequencial", FieldPos::new_const(9, 13), FieldKind::Numeric);
+
+/// Escreve um arquivo CNAB 240 registro a registro, calculando a numeração e os trailers.
+///
+/// O chamador informa o header de arquivo, abre cada lote com o seu header e acrescenta
+/// os detalhes; o escritor preenche em cada registro o banco do header de arquivo, o
+/// número do lote, o tipo de registro e o sequencial no lote, e escreve os trailers de
+/// lote e de arquivo com as quantidades e somatórias do que foi escrito:
+///
+/// ```no_run
+/// use cnab_fixedwidth::cnab240::{Detalhe, FileWriter, HeaderArquivo, HeaderLote, TrailerLote};
+/// use std::fs::File;
+///
+/// # fn exemplo(header: HeaderArquivo, header_lote: HeaderLote, titulos: Vec<(impl Detalhe, impl Detalhe)>) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut writer = FileWriter::new(File::create("remessa.rem")?, header)?;
+/// writer.begin_lote(&header_lote)?;
+/// for (p, q) in titulos {
+///     writer.append(&p)?;
+///     writer.append(&q)?;
+/// }
+/// writer.end_lote::<TrailerLote>()?;
+/// let file = writer.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Registros terminam em `\r\n`. Detalhes fora de um lote, ou um arquivo encerrado com
+/// lote aberto, resultam em `FixedWidthError::Structure`.
+pub struct FileWriter<W: Write> {
+    out: W,
+    options: WriteOptions,
+    banco: u16,
+    lotes: u16,
+    registros: u32,
+    lote: Option<TotaisLote>,
+}
+
+impl<W: Write> FileWriter<W> {
+    /// Começa o arquivo escrevendo o header, em Latin-1.
+    pub fn new(out: W, header: HeaderArquivo) -> Result<Self> {
+        Self::with_options(out, header, WriteOptions::default())
+    }
+
+    /// Como [`new`](Self::new), com as opções de escrita de todos os registros.
+    pub fn with_options(out: W, header: HeaderArquivo, options: WriteOptions) -> Result<Self> {
+        let header = HeaderArquivo { lote: 0, tipo_registro: 0, ..header };
+        let mut writer = Self { out, options, banco: header.banco, lotes: 0, registros: 0, lote: None };
+        let mut line = header.write_bytes(&writer.options)?;
+        writer.fill_check_digits(&mut line, 53);
+        writer.write_line(line)?;
+        Ok(writer)
+    }
+
+    /// Abre o próximo lote, escrevendo o seu header (ex: [`HeaderLote`]).
+    pub fn begin_lote(&mut self, header: &impl FixedWidthWrite) -> Result<()> {
+        if self.lote.is_some() {
+            return Err(self.structure("lote sem trailer de lote"));
+        }
+        self.lotes += 1;
+        let totais = TotaisLote { banco: self.banco, lote: self.lotes, quantidade_registros: 1, ..Default::default() };
+        let mut line = header.write_bytes(&self.options)?;
+        self.number(&mut line, 1, None)?;
+        // No header de lote de cobrança (operação R) a agência começa uma posição depois
+        let agencia = if line.get(8) == Some(&b'R') { 54 } else { 53 };
+        self.fill_check_digits(&mut line, agencia);
+        self.write_line(line)?;
+        self.lote = Some(totais);
+        Ok(())
+    }
+
+    /// Acrescenta um detalhe ao lote aberto.
+    pub fn append(&mut self, detalhe: &impl Detalhe) -> Result<()> {
+        let Some(mut totais) = self.lote else {
+            return Err(self.structure("registro de detalhe fora de um lote"));
+        };
+        totais.quantidade_registros += 1;
+        if let Some(valor) = detalhe.valor_lote() {
+            totais.quantidade_lancamentos += 1;
+            totais.valor_total += valor;
+        }
+        let mut line = detalhe.write_bytes(&self.options)?;
+        // O sequencial no lote não conta o header de lote
+        self.number(&mut line, 3, Some(totais.quantidade_registros - 1))?;
+        if line.get(13) == Some(&b'P') {
+            self.fill_check_digits(&mut line, 18);
+        }
+        self.write_line(line)?;
+        self.lote = Some(totais);
+        Ok(())
+    }
+
+    /// Fecha o lote aberto com o trailer montado a partir dos totais, e o devolve.
+    pub fn end_lote<T: TrailerLote240>(&mut self) -> Result<T> {
+        let Some(mut totais) = self.lote.take() else {
+            return Err(self.structure("trailer de lote sem lote aberto"));
+        };
+        totais.quantidade_registros += 1;
+        let trailer = T::from_totais(&totais);
+        let mut line = trailer.write_bytes(&self.options)?;
+        self.number(&mut line, 5, None)?;
+        self.write_line(line)?;
+        Ok(trailer)
+    }
+
+    /// Escreve o trailer de arquivo e devolve o destino.
+    pub fn finish(mut self) -> Result<W> {
+        if self.lote.is_some() {
+            return Err(self.structure("lote sem trailer de lote"));
+        }
+        let trailer = TrailerArquivo {
+            banco: self.banco,
+            lote: 9999,
+            tipo_registro: 9,
+            quantidade_lotes: self.lotes.into(),
+            quantidade_registros: self.registros + 1,
+            ..Default::default()
+        };
+        let line = trailer.write_bytes(&self.options)?;
+        self.write_line(line)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Registros escritos até agora.
+    pub fn records_written(&self) -> u32 {
+        self.registros
+    }
+
+    /// Preenche banco, lote, tipo de registro e, nos detalhes, o sequencial.
+    fn number(&self, line: &mut [u8], tipo_registro: u8, sequencial: Option<u32>) -> Result<()> {
+        let numeric = |n: u32| Value::Numeric(n.into());
+        writer::write_field(line, &BANCO, &numeric(self.banco.into()), &self.options)?;
+        writer::write_field(line, &LOTE, &numeric(self.lotes.into()), &self.options)?;
+        writer::write_field(line, &TIPO_REGISTRO, &numeric(tipo_registro.into()), &self.options)?;
+        if let Some(sequencial) = sequencial {
+            writer::write_field(line, &SEQUENCIAL, &numeric(sequencial), &self.options)?;
+        }
+        Ok(())
+    }
+
+    /// Com `WriteOptions::check_digits`, preenche os DVs de agência e conta em branco; a
+    /// agência (5 dígitos) começa na posição `agencia`, seguida do DV, da conta (12) e do
+    /// DV da conta. Linhas sem números nessas posições ficam como estão.
+    fn fill_check_digits(&self, line: &mut [u8], agencia: usize) {
+        let Some(banco) = self.options.check_digits else { return };
+        let number = |range: std::ops::Range<usize>| {
+            std::str::from_utf8(line.get(range)?).ok()?.parse::<u64>().ok()
+        };
+        let (Some(numero_agencia), Some(conta)) = (number(agencia - 1..agencia + 4), number(agencia + 5..agencia + 17))
+        else {
+            return;
+        };
+        let (agencia_dv, conta_dv) = dv::agencia_conta(banco, numero_agencia as u32, conta);
+        // O Itaú informa o DAC da agência/conta, na posição seguinte à do DV da conta
+        let conta_dv_index = if banco == Bank::Itau { agencia + 18 } else { agencia + 17 };
+        for (index, dv) in [(agencia + 4, agencia_dv), (conta_dv_index, conta_dv)] {
+            if let Some(dv) = dv
+                && line[index] == b' '
+            {
+                line[index] = dv as u8;
+            }
+        }
+    }
+
+    fn write_line(&mut self, line: Vec<u8>) -> Result<()> {
+        self.registros += 1;
+        self.out.write_all(&line)?;
+        self.out.write_all(b"\r\n")?;
+        Ok(())
+    }
+
+    fn structure(&self, message: &str) -> FixedWidthError {
+        FixedWidthError::Structure { line_number: u64::from(self.registros) + 1, message: message.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::record;
+
+    fn arquivo(registros: &str) -> Vec<String> {
+        arquivo_com_totais(registros, "")
+    }
+
+    /// Arquivo com um título; `totais` preenche as posições 24 em diante do trailer de lote.
+    fn arquivo_com_totais(registros: &str, totais: &str) -> Vec<String> {
+        vec![
+            record(240, &[(1, "34100000"), (143, "216102026")]),
+            record(240, &[(1, "34100011T01")]),
+            record(240, &[(1, "3410001300001T 06"), (38, "12345               "), (58, "1"), (82, "000000000010000"), (199, "000000000000250")]),
+            record(240, &[(1, "3410001300002U 06"), (78, "000000000010050")]),
+            record(240, &[(1, "34100015"), (18, "000004"), (24, totais)]),
+            record(240, &[(1, "34199999"), (18, "000001"), (24, registros)]),
+        ]
+    }
+
+    #[test]
+    fn parses_a_whole_retorno_into_titles() {
+        let file = arquivo("000006").join("\r\n");
+        let retorno = parse_retorno_cobranca(file.as_bytes()).unwrap();
+        assert_eq!(retorno.header.data_geracao, 16102026);
+        assert_eq!(retorno.lotes.len(), 1);
+        let titulo = retorno.titulos().next().unwrap();
+        assert_eq!((titulo.t.nosso_numero.as_str(), titulo.u.valor_pago), ("12345", 10_050));
+        assert_eq!(
+            retorno.events(),
+            [
+                RetornoEvent::TituloLiquidado { nosso_numero: "12345".into(), valor_pago: 10_050, data_credito: 0 },
+                RetornoEvent::TarifaCobrada { nosso_numero: "12345".into(), valor: 250 },
+            ]
+        );
+
+        // Sem quebras de linha, mas com a quantidade de registros errada no trailer
+        let err = parse_retorno_cobranca(arquivo("000007").concat().as_bytes()).unwrap_err();
+        assert!(matches!(err, FixedWidthError::Integrity { line_number: 6, .. }), "{err}");
+        assert_eq!(err.line_number(), Some(6));
+
+        // Segmento U removido: o T fica sem par
+        let mut lines = arquivo("000005");
+        lines.remove(3);
+        let err = parse_retorno_cobranca(lines.join("\n").as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "linha 4: estrutura do arquivo inválida: segmento T sem o segmento U");
+
+        // Código de movimento fora da tabela: o título é lido e o código vira aviso
+        let mut lines = arquivo("000006");
+        lines[2].replace_range(15..17, "99");
+        let options = ParseOptions { warnings: Some(crate::Warnings::new()), ..Default::default() };
+        let retorno = parse_retorno_cobranca_with(lines.join("\n").as_bytes(), &options).unwrap();
+        assert_eq!(retorno.titulos().count(), 1);
+        let warnings = options.warnings.unwrap().take();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "campo codigo_movimento [16..17] Numeric: campo 'codigo_movimento' com código desconhecido: '99'");
+    }
+
+    #[test]
+    fn retorno_reader_streams_events_per_title() {
+        let file = arquivo("000006").join("\n");
+        let mut reader = RetornoReader::new(file.as_bytes());
+        let events: Vec<_> = reader.by_ref().collect::<Result<_>>().unwrap();
+        assert_eq!(events, parse_retorno_cobranca(file.as_bytes()).unwrap().events());
+        assert_eq!(reader.header().map(|h| h.data_geracao), Some(16102026));
+
+        // Os eventos do título chegam antes do erro do trailer de arquivo, e a leitura para nele
+        let file = arquivo("000009").join("\n");
+        let results: Vec<_> = RetornoReader::new(file.as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[2], Err(FixedWidthError::Integrity { line_number: 6, .. })));
+    }
+
+    #[test]
+    fn file_writer_numbers_records_and_builds_trailers() {
+        let header = HeaderArquivo { banco: 341, codigo_remessa_retorno: 2, data_geracao: 16102026, ..Default::default() };
+        let mut writer = FileWriter::new(Vec::new(), header).unwrap();
+        assert!(matches!(writer.append(&SegmentoU::default()), Err(FixedWidthError::Structure { line_number: 2, .. })));
+
+        writer.begin_lote(&HeaderLote { operacao: "T".into(), servico: 1, ..Default::default() }).unwrap();
+        for (nosso_numero, valor) in [("1", 10_000), ("2", 2_550)] {
+            let t = SegmentoT { segmento: "T".into(), nosso_numero: nosso_numero.into(), carteira: 1, valor_titulo: valor, ..Default::default() };
+            writer.append(&t).unwrap();
+            writer.append(&SegmentoU { segmento: "U".into(), ..Default::default() }).unwrap();
+        }
+        let trailer: TrailerLote = writer.end_lote().unwrap();
+        assert_eq!(trailer.quantidade_registros, 6);
+        let file = writer.finish().unwrap();
+
+        let lines: Vec<_> = file.split(|&b| b == b'\n').filter(|l| !l.is_empty()).map(|l| &l[..17]).collect();
+        let lines: Vec<_> = lines.iter().map(|l| std::str::from_utf8(l).unwrap()).collect();
+        assert_eq!(
+            lines,
+            [
+                "34100000         ",
+                "34100011T01  000 ",
+                "3410001300001T 00",
+                "3410001300002U 00",
+                "3410001300003T 00",
+                "3410001300004U 00",
+                "34100015         ",
+                "34199999         ",
+            ]
+        );
+        // O arquivo escrito passa na conferência da leitura
+        let retorno = parse_retorno_cobranca(&file[..]).unwrap();
+        assert_eq!((retorno.trailer.quantidade_lotes, retorno.trailer.quantidade_registros), (1, 8));
+        assert_eq!(retorno.titulos().map(|t| t.t.valor_titulo).sum::<i64>(), 12_550);
+    }
+
+    #[test]
+    fn checks_title_totals_when_the_bank_informs_them() {
+        let totais = |quantidade: &str, valor: &str| {
+            let lines = arquivo_com_totais("000006", &format!("{quantidade}{valor}"));
+            parse_retorno_cobranca(lines.join("\n").as_bytes())
+        };
+        assert!(totais("000001", "00000000000010000").is_ok());
+
+        let err = totais("000002", "00000000000010001").unwrap_err();
+        let FixedWidthError::Integrity { line_number, errors } = &err else { panic!("{err}") };
+        assert_eq!(*line_number, 5);
+        let campos: Vec<_> = errors.iter().map(|e| (e.field, e.declared, e.actual)).collect();
+        assert_eq!(campos, [("quantidade_simples", 2, 1), ("valor_simples", 10_001, 10_000)]);
+        assert_eq!(
+            err.to_string(),
+            "linha 5: totais do trailer não conferem: TrailerLote.quantidade_simples informa 2, lido 1; \
+             TrailerLote.valor_simples informa 10001, lido 10000"
+        );
+    }
+}
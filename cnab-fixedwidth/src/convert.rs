@@ -0,0 +1,548 @@
+//! # Conversão de Arquivos
+//!
+//! Conversões de um arquivo CNAB inteiro para formatos de integração,
+//! em uma única passada e sem carregar o arquivo em memória.
+
+use crate::json;
+use crate::reader::{Framing, RecordReader};
+use crate::registry::{LayoutRegistry, RecordLayout};
+use crate::{parse_line_bytes_with, DynRecord, FieldKind, FieldSpec, FixedWidthError, ParseOptions, Result, Value};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Converte um arquivo CNAB para JSON delimitado por linhas (NDJSON),
+/// usando as opções padrão (`Encoding::Latin1`).
+///
+/// Veja [`to_ndjson_with`].
+pub fn to_ndjson<R: BufRead, W: Write>(
+    reader: R,
+    writer: W,
+    layouts: &LayoutRegistry,
+) -> Result<u64> {
+    to_ndjson_with(reader, writer, layouts, &ParseOptions::default())
+}
+
+/// Converte um arquivo CNAB para NDJSON, um objeto por registro.
+///
+/// Cada linha do arquivo é identificada pelo `LayoutRegistry` e vira um objeto no formato:
+///
+/// ```text
+/// {"record":"header_arquivo","line":1,"fields":{"codigo_banco":341,"valor":12.34}}
+/// ```
+///
+/// Os campos saem na ordem do layout, e `record` é o layout que interpretou a linha
+/// (incluindo os alternativos de [`RecordLayout::or_fallback`](crate::RecordLayout::or_fallback)).
+/// Linhas vazias são ignoradas; linhas sem layout correspondente geram
+/// `FixedWidthError::UnknownRecord`. Erros de parse saem com o número da linha e o campo
+/// (`FixedWidthError::Field`).
+///
+/// # Retorno
+/// Quantidade de registros escritos.
+pub fn to_ndjson_with<R: BufRead, W: Write>(
+    reader: R,
+    mut writer: W,
+    layouts: &LayoutRegistry,
+    options: &ParseOptions,
+) -> Result<u64> {
+    let mut out = String::new();
+    let written = for_each_record(reader, Framing::Lines, layouts, options, |line_number, layout, parsed| {
+        out.clear();
+        out.push_str("{\"record\":");
+        json::write_str(&mut out, layout.name);
+        out.push_str(",\"line\":");
+        out.push_str(&line_number.to_string());
+        out.push_str(",\"fields\":");
+        write_fields(&mut out, parsed, 0);
+        out.push_str("}\n");
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    })?;
+
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Chaves extras dos objetos de [`to_json_lines_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonLinesOptions {
+    /// Chave com o nome do layout que interpretou a linha. Padrão: `"_record"`.
+    pub record_key: Option<&'static str>,
+    /// Chave com o número da linha no arquivo. Padrão: nenhuma.
+    pub line_key: Option<&'static str>,
+    /// Divisão do arquivo em registros. Padrão: uma linha por registro; arquivos blocados
+    /// usam `Framing::FixedLength` (veja [`crate::detect::Detected::framing`]).
+    pub framing: Framing,
+}
+
+impl Default for JsonLinesOptions {
+    fn default() -> Self {
+        Self { record_key: Some("_record"), line_key: None, framing: Framing::Lines }
+    }
+}
+
+/// Converte um arquivo CNAB para JSON Lines com objetos planos, usando as opções padrão.
+///
+/// Veja [`to_json_lines_with`].
+pub fn to_json_lines<R: BufRead, W: Write>(reader: R, writer: W, layouts: &LayoutRegistry) -> Result<u64> {
+    to_json_lines_with(reader, writer, layouts, &ParseOptions::default(), &JsonLinesOptions::default())
+}
+
+/// Converte um arquivo CNAB para JSON Lines, um objeto plano por registro.
+///
+/// Diferente de [`to_ndjson_with`], os campos ficam no primeiro nível do objeto, como
+/// esperam as ferramentas de ingestão (uma coluna por campo), e o tipo de registro vai
+/// em uma chave própria:
+///
+/// ```text
+/// {"_record":"detalhe","nome":"JOÃO","valor":0.05}
+/// ```
+///
+/// As chaves de `json` vêm antes dos campos; escolha nomes que não colidam com os dos
+/// layouts. Linhas vazias, registros desconhecidos e erros seguem [`to_ndjson_with`].
+///
+/// # Retorno
+/// Quantidade de registros escritos.
+pub fn to_json_lines_with<R: BufRead, W: Write>(
+    reader: R,
+    mut writer: W,
+    layouts: &LayoutRegistry,
+    options: &ParseOptions,
+    json: &JsonLinesOptions,
+) -> Result<u64> {
+    let mut out = String::new();
+    let written = for_each_record(reader, json.framing, layouts, options, |line_number, layout, parsed| {
+        out.clear();
+        let mut keys = 0;
+        if let Some(key) = json.record_key {
+            out.push('{');
+            json::write_str(&mut out, key);
+            out.push(':');
+            json::write_str(&mut out, layout.name);
+            keys += 1;
+        }
+        if let Some(key) = json.line_key {
+            out.push(if keys == 0 { '{' } else { ',' });
+            json::write_str(&mut out, key);
+            out.push(':');
+            out.push_str(&line_number.to_string());
+            keys += 1;
+        }
+        write_fields(&mut out, parsed, keys);
+        out.push('\n');
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    })?;
+
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Lê o arquivo registro a registro, identificando cada linha pelo `LayoutRegistry`.
+///
+/// Linhas vazias são ignoradas; devolve a quantidade de registros entregues a `f`.
+fn for_each_record<R: BufRead>(
+    reader: R,
+    framing: Framing,
+    layouts: &LayoutRegistry,
+    options: &ParseOptions,
+    mut f: impl FnMut(u64, &RecordLayout, &DynRecord) -> Result<()>,
+) -> Result<u64> {
+    let mut records = RecordReader::new(reader, framing);
+    let mut line = Vec::new();
+    let mut read = 0u64;
+
+    while records.read_record(&mut line)? {
+        let line_number = records.records_read();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (layout, parsed) = match layouts.resolve_parse(&line, options) {
+            None => return Err(FixedWidthError::UnknownRecord { line_number }),
+            Some(Ok(resolved)) => resolved,
+            Some(Err(e)) => {
+                let fields = layouts.resolve(&line).map_or(&[][..], |layout| &layout.fields);
+                return Err(e.at_line_with(line_number, &line, fields, options.redaction));
+            }
+        };
+        f(line_number, layout, &parsed)?;
+        read += 1;
+    }
+    Ok(read)
+}
+
+/// Fecha um objeto JSON com os campos do registro. Com `keys` chaves já escritas, o
+/// objeto já está aberto e os campos continuam após uma vírgula.
+fn write_fields(out: &mut String, record: &DynRecord, keys: usize) {
+    if keys == 0 {
+        out.push('{');
+    }
+    for (i, (name, value)) in record.iter().enumerate() {
+        if keys > 0 || i > 0 {
+            out.push(',');
+        }
+        json::write_str(out, name);
+        out.push(':');
+        json::write_value(out, value);
+    }
+    out.push('}');
+}
+
+/// Como as posições originais dos campos aparecem no cabeçalho do CSV.
+///
+/// Auditores conferem a exportação contra o arquivo bruto; com as posições no
+/// cabeçalho, cada coluna aponta para o trecho do registro de onde veio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvPositions {
+    /// Só os nomes dos campos.
+    #[default]
+    None,
+    /// Nomes com as posições como sufixo: `valor_titulo[78..92]`.
+    Suffix,
+    /// Uma segunda linha de cabeçalho com as posições: `78..92`.
+    Row,
+}
+
+/// Opções da exportação CSV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Separador de colunas. Padrão: `,`.
+    pub delimiter: char,
+    /// Posições dos campos no cabeçalho. Padrão: nenhuma.
+    pub positions: CsvPositions,
+    /// Divisão do arquivo em registros, como em [`JsonLinesOptions::framing`].
+    pub framing: Framing,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self { delimiter: ',', positions: CsvPositions::None, framing: Framing::Lines }
+    }
+}
+
+/// Converte um arquivo com um único tipo de registro para CSV, com os campos de `fields`.
+///
+/// A primeira linha traz os nomes dos campos (fillers ficam de fora), seguida das
+/// posições conforme `csv.positions`. Decimais saem com exatamente `scale` casas
+/// (`12.34`), campos opcionais em branco como células vazias e textos com o separador,
+/// aspas ou quebras de linha entre aspas. Linhas vazias são ignoradas; erros de parse
+/// saem com o número da linha e o campo.
+///
+/// # Retorno
+/// Quantidade de registros escritos.
+pub fn write_csv<R: BufRead, W: Write>(
+    reader: R,
+    mut writer: W,
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+    csv: &CsvOptions,
+) -> Result<u64> {
+    let mut records = RecordReader::new(reader, csv.framing);
+    let mut line = Vec::new();
+    let mut written = 0u64;
+
+    writer.write_all(csv_header(fields, csv).as_bytes())?;
+    while records.read_record(&mut line)? {
+        let line_number = records.records_read();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = parse_line_bytes_with(&line, fields, options)
+            .map_err(|e| e.at_line_with(line_number, &line, fields, options.redaction))?;
+        writer.write_all(csv_row(&parsed, csv).as_bytes())?;
+        written += 1;
+    }
+
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Saída de um tipo de registro em [`to_csv_with`].
+#[derive(Debug)]
+pub struct CsvFile<W> {
+    /// Nome do layout (tipo de registro).
+    pub record: &'static str,
+    /// Destino do CSV, devolvido depois do `flush`.
+    pub writer: W,
+    /// Registros escritos.
+    pub records: u64,
+}
+
+/// Converte um arquivo com vários tipos de registro para um CSV por tipo, em `dir`.
+///
+/// Cada tipo vai para `<nome do layout>.csv` (ex: `header_arquivo.csv`,
+/// `segmento_p.csv`), criado quando o primeiro registro do tipo aparece. Veja
+/// [`to_csv_with`].
+///
+/// # Retorno
+/// Caminho e quantidade de registros de cada arquivo, na ordem em que os tipos apareceram.
+pub fn to_csv<R: BufRead>(
+    reader: R,
+    dir: impl AsRef<Path>,
+    layouts: &LayoutRegistry,
+    options: &ParseOptions,
+    csv: &CsvOptions,
+) -> Result<Vec<(PathBuf, u64)>> {
+    let path = |record: &str| dir.as_ref().join(format!("{record}.csv"));
+    let files = to_csv_with(reader, layouts, options, csv, |layout| {
+        Ok(std::io::BufWriter::new(std::fs::File::create(path(layout.name))?))
+    })?;
+    Ok(files.into_iter().map(|file| (path(file.record), file.records)).collect())
+}
+
+/// Converte um arquivo com vários tipos de registro para um CSV por tipo, com os
+/// destinos abertos por `open` (ex: buffers em memória para uma resposta HTTP).
+///
+/// Cada layout do `LayoutRegistry` que interpreta alguma linha ganha um destino, com o
+/// cabeçalho dos seus campos (como em [`write_csv`]); alternativos de
+/// [`RecordLayout::or_fallback`] com nome próprio ganham o seu. Linhas vazias,
+/// registros desconhecidos e erros seguem [`to_ndjson_with`].
+pub fn to_csv_with<R: BufRead, W: Write>(
+    reader: R,
+    layouts: &LayoutRegistry,
+    options: &ParseOptions,
+    csv: &CsvOptions,
+    mut open: impl FnMut(&RecordLayout) -> std::io::Result<W>,
+) -> Result<Vec<CsvFile<W>>> {
+    let mut files: Vec<CsvFile<W>> = Vec::new();
+    for_each_record(reader, csv.framing, layouts, options, |_, layout, parsed| {
+        let index = match files.iter().position(|file| file.record == layout.name) {
+            Some(index) => index,
+            None => {
+                let mut writer = open(layout)?;
+                writer.write_all(csv_header(&layout.fields, csv).as_bytes())?;
+                files.push(CsvFile { record: layout.name, writer, records: 0 });
+                files.len() - 1
+            }
+        };
+        let file = &mut files[index];
+        file.writer.write_all(csv_row(parsed, csv).as_bytes())?;
+        file.records += 1;
+        Ok(())
+    })?;
+
+    for file in &mut files {
+        file.writer.flush()?;
+    }
+    Ok(files)
+}
+
+/// Cabeçalho do CSV (uma ou duas linhas, com a quebra de linha).
+fn csv_header(fields: &[FieldSpec], csv: &CsvOptions) -> String {
+    let fields: Vec<_> = fields.iter().filter(|field| !matches!(field.kind, FieldKind::Filler)).collect();
+    let position = |field: &FieldSpec| format!("{}..{}", field.pos.start(), field.pos.end());
+    let names: Vec<_> = fields
+        .iter()
+        .map(|field| match csv.positions {
+            CsvPositions::Suffix => format!("{}[{}]", field.name, position(field)),
+            CsvPositions::None | CsvPositions::Row => field.name.to_string(),
+        })
+        .collect();
+    let mut out = csv_line(names.iter().map(String::as_str), csv.delimiter);
+    if csv.positions == CsvPositions::Row {
+        let positions: Vec<_> = fields.iter().map(|field| position(field)).collect();
+        out.push_str(&csv_line(positions.iter().map(String::as_str), csv.delimiter));
+    }
+    out
+}
+
+/// Linha do CSV com os valores do registro, na ordem do layout.
+fn csv_row(record: &DynRecord, csv: &CsvOptions) -> String {
+    let cells: Vec<_> = record
+        .iter()
+        .map(|(_, value)| {
+            let mut cell = String::new();
+            match value {
+                Value::Alpha(text) => cell.push_str(text),
+                Value::Numeric(n) => cell.push_str(&n.to_string()),
+                Value::Decimal { raw, scale } => json::write_decimal(&mut cell, *raw, *scale),
+                Value::Null => {}
+            }
+            cell
+        })
+        .collect();
+    csv_line(cells.iter().map(String::as_str), csv.delimiter)
+}
+
+/// Junta as células com o separador, pondo entre aspas as que precisam.
+fn csv_line<'a>(cells: impl Iterator<Item = &'a str>, delimiter: char) -> String {
+    let mut out = String::new();
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.push(delimiter);
+        }
+        if cell.contains([delimiter, '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&cell.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(cell);
+        }
+    }
+    out.push_str("\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::RecordLayout;
+    use crate::{FieldKind, FieldPos, FieldSpec};
+
+    #[test]
+    fn converts_each_record_type() {
+        let mut layouts = LayoutRegistry::new();
+        layouts
+            .register(
+                RecordLayout::new(
+                    "header",
+                    vec![FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric)],
+                )
+                .when(FieldPos::new_const(4, 4), "0"),
+            )
+            .register(
+                RecordLayout::new(
+                    "detalhe",
+                    vec![
+                        FieldSpec::new("nome", FieldPos::new_const(5, 10), FieldKind::Alpha),
+                        FieldSpec::new("valor", FieldPos::new_const(11, 15), FieldKind::Decimal { scale: 2 }),
+                    ],
+                )
+                .when(FieldPos::new_const(4, 4), "3"),
+            );
+
+        let input = b"3410\r\n3413JO\xC3\x83O 00005\r\n" as &[u8];
+        let mut output = Vec::new();
+        let options = ParseOptions::with_encoding(crate::Encoding::Utf8);
+        let written = to_ndjson_with(input, &mut output, &layouts, &options).unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"record\":\"header\",\"line\":1,\"fields\":{\"banco\":341}}\n\
+             {\"record\":\"detalhe\",\"line\":2,\"fields\":{\"nome\":\"JOÃO\",\"valor\":0.05}}\n"
+        );
+
+        let unknown = b"3419\n" as &[u8];
+        assert!(matches!(
+            to_ndjson(unknown, Vec::new(), &layouts),
+            Err(FixedWidthError::UnknownRecord { line_number: 1 })
+        ));
+
+        let invalid = b"3410\n3413MARIA 0X005\n" as &[u8];
+        let err = to_ndjson(invalid, Vec::new(), &layouts).unwrap_err();
+        assert!(matches!(
+            &err,
+            FixedWidthError::Field { field: "valor", line_number: 2, snippet, .. } if snippet == "0X005"
+        ));
+        assert!(matches!(err.root(), FixedWidthError::InvalidNumeric { .. }));
+        assert_eq!(
+            err.to_string(),
+            "linha 2, campo valor [11..15] Decimal(2), trecho '0X005': campo 'valor' contém caracteres inválidos para numérico: '0X005'"
+        );
+    }
+
+    #[test]
+    fn json_lines_flatten_fields_next_to_the_record_tag() {
+        let mut layouts = LayoutRegistry::new();
+        layouts
+            .register(
+                RecordLayout::new("header", vec![FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric)])
+                    .when(FieldPos::new_const(4, 4), "0"),
+            )
+            .register(
+                RecordLayout::new(
+                    "detalhe",
+                    vec![
+                        FieldSpec::new("nome", FieldPos::new_const(5, 10), FieldKind::Alpha),
+                        FieldSpec::new("valor", FieldPos::new_const(11, 15), FieldKind::Decimal { scale: 2 }).optional(),
+                    ],
+                )
+                .when(FieldPos::new_const(4, 4), "3"),
+            );
+
+        let input = b"3410\n\n3413ANA        \n" as &[u8];
+        let mut output = Vec::new();
+        assert_eq!(to_json_lines(input, &mut output, &layouts).unwrap(), 2);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"_record\":\"header\",\"banco\":341}\n{\"_record\":\"detalhe\",\"nome\":\"ANA\",\"valor\":null}\n"
+        );
+
+        let mut output = Vec::new();
+        let json = JsonLinesOptions { record_key: None, line_key: Some("linha"), ..Default::default() };
+        to_json_lines_with(input, &mut output, &layouts, &ParseOptions::default(), &json).unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("{\"linha\":1,\"banco\":341}\n{\"linha\":3,"));
+
+        let mut output = Vec::new();
+        let json = JsonLinesOptions { record_key: None, line_key: None, ..Default::default() };
+        to_json_lines_with(&b"3410\n"[..], &mut output, &layouts, &ParseOptions::default(), &json).unwrap();
+        assert_eq!(output, b"{\"banco\":341}\n");
+
+        // Arquivo blocado, sem quebras de linha
+        let mut output = Vec::new();
+        let json = JsonLinesOptions { framing: Framing::FixedLength(4), ..Default::default() };
+        assert_eq!(to_json_lines_with(&b"34103410"[..], &mut output, &layouts, &ParseOptions::default(), &json).unwrap(), 2);
+        assert_eq!(output, b"{\"_record\":\"header\",\"banco\":341}\n{\"_record\":\"header\",\"banco\":341}\n");
+    }
+
+    #[test]
+    fn csv_export_writes_one_file_per_record_type() {
+        let mut layouts = LayoutRegistry::new();
+        layouts
+            .register(
+                RecordLayout::new("header", vec![FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric)])
+                    .when(FieldPos::new_const(4, 4), "0"),
+            )
+            .register(
+                RecordLayout::new(
+                    "segmento_p",
+                    vec![
+                        FieldSpec::new("nome", FieldPos::new_const(5, 10), FieldKind::Alpha),
+                        FieldSpec::new("valor", FieldPos::new_const(11, 15), FieldKind::Decimal { scale: 2 }),
+                    ],
+                )
+                .when(FieldPos::new_const(4, 4), "3"),
+            );
+        let input = b"3410\n3413ANA   01050\n\n3413BIA   00007\n" as &[u8];
+
+        let files = to_csv_with(input, &layouts, &ParseOptions::default(), &CsvOptions::default(), |_| Ok(Vec::new())).unwrap();
+        let files: Vec<_> = files.into_iter().map(|f| (f.record, f.records, String::from_utf8(f.writer).unwrap())).collect();
+        assert_eq!(
+            files,
+            [
+                ("header", 1, "banco\r\n341\r\n".to_string()),
+                ("segmento_p", 2, "nome,valor\r\nANA,10.50\r\nBIA,0.07\r\n".to_string()),
+            ]
+        );
+
+        let dir = std::env::temp_dir().join(format!("cnab-csv-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let written = to_csv(input, &dir, &layouts, &ParseOptions::default(), &CsvOptions::default()).unwrap();
+        assert_eq!(written, [(dir.join("header.csv"), 1), (dir.join("segmento_p.csv"), 2)]);
+        assert!(std::fs::read_to_string(dir.join("segmento_p.csv")).unwrap().ends_with("BIA,0.07\r\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csv_headers_carry_original_positions() {
+        let fields = [
+            FieldSpec::new("nome", FieldPos::new_const(1, 10), FieldKind::Alpha),
+            FieldSpec::new("brancos", FieldPos::new_const(11, 12), FieldKind::Filler),
+            FieldSpec::new("valor_titulo", FieldPos::new_const(13, 17), FieldKind::Decimal { scale: 2 }),
+        ];
+        let input = b"SILVA, ANA  01050\n\nJOAO        00007\n" as &[u8];
+        let export = |positions| {
+            let mut output = Vec::new();
+            let csv = CsvOptions { positions, ..CsvOptions::default() };
+            let written = write_csv(input, &mut output, &fields, &ParseOptions::default(), &csv).unwrap();
+            assert_eq!(written, 2);
+            String::from_utf8(output).unwrap()
+        };
+
+        let rows = "\"SILVA, ANA\",10.50\r\nJOAO,0.07\r\n";
+        assert_eq!(export(CsvPositions::None), format!("nome,valor_titulo\r\n{rows}"));
+        assert_eq!(export(CsvPositions::Suffix), format!("nome[1..10],valor_titulo[13..17]\r\n{rows}"));
+        assert_eq!(export(CsvPositions::Row), format!("nome,valor_titulo\r\n1..10,13..17\r\n{rows}"));
+    }
+}
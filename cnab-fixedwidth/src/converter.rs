@@ -0,0 +1,120 @@
+//! # Conversores para Layouts Dinâmicos
+//!
+//! Layouts carregados de configuração não têm tipos Rust para os campos. O
+//! [`ConverterRegistry`] associa funções de conversão a **nomes de campo** ou a **tags**
+//! (declaradas no layout, ex: `convert: data_ddmmaaaa`), para que o parser dinâmico
+//! produza valores tipados (datas, durações, enums) sem a macro derive.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{ConverterRegistry, Layout, ParseOptions, Value};
+//! use std::time::Duration;
+//!
+//! # fn exemplo(layout: &Layout, line: &[u8], options: &ParseOptions) -> cnab_fixedwidth::Result<()> {
+//! let mut converters = ConverterRegistry::new();
+//! converters.register_tag("dias", |v: &Value| {
+//!     let dias = v.as_i64().ok_or("esperado numérico")?;
+//!     Ok(Duration::from_secs(dias as u64 * 86_400))
+//! });
+//!
+//! let record = layout.parse_bytes_with_converters(line, &options, &converters)?;
+//! let prazo: Option<&Duration> = record.get_as("prazo");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Value;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+/// Valor convertido, de tipo conhecido apenas por quem registrou o conversor.
+pub type Converted = Arc<dyn Any + Send + Sync>;
+
+/// Função de conversão registrada.
+type ConverterFn = Arc<dyn Fn(&Value) -> Result<Converted, String> + Send + Sync>;
+
+/// Conversores indexados por nome de campo ou por tag.
+#[derive(Clone, Default)]
+pub struct ConverterRegistry {
+    by_field: HashMap<String, ConverterFn>,
+    by_tag: HashMap<String, ConverterFn>,
+}
+
+impl fmt::Debug for ConverterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConverterRegistry")
+            .field("fields", &self.by_field.keys().collect::<Vec<_>>())
+            .field("tags", &self.by_tag.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+static GLOBAL: OnceLock<ConverterRegistry> = OnceLock::new();
+
+impl ConverterRegistry {
+    /// Cria um registro vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra um conversor aplicado a qualquer campo com este nome.
+    pub fn register_field<T, F>(&mut self, field: &str, convert: F) -> &mut Self
+    where
+        T: Any + Send + Sync,
+        F: Fn(&Value) -> Result<T, String> + Send + Sync + 'static,
+    {
+        self.by_field.insert(field.to_string(), erase(convert));
+        self
+    }
+
+    /// Registra um conversor referenciado pela tag declarada no layout.
+    pub fn register_tag<T, F>(&mut self, tag: &str, convert: F) -> &mut Self
+    where
+        T: Any + Send + Sync,
+        F: Fn(&Value) -> Result<T, String> + Send + Sync + 'static,
+    {
+        self.by_tag.insert(tag.to_string(), erase(convert));
+        self
+    }
+
+    /// Instala o registro global, usado por `Layout::parse`/`parse_bytes`.
+    ///
+    /// Só pode ser chamado uma vez; devolve o registro de volta se já houver um instalado.
+    pub fn install_global(self) -> Result<(), Self> {
+        GLOBAL.set(self)
+    }
+
+    /// Registro global, se instalado.
+    pub fn global() -> Option<&'static ConverterRegistry> {
+        GLOBAL.get()
+    }
+
+    /// Converte o valor de um campo: o conversor do nome do campo tem precedência
+    /// sobre o da tag. Retorna `None` se nenhum se aplica.
+    pub(crate) fn convert(
+        &self,
+        field: &str,
+        tag: Option<&str>,
+        value: &Value,
+    ) -> Option<Result<Converted, String>> {
+        let converter = self
+            .by_field
+            .get(field)
+            .or_else(|| tag.and_then(|tag| self.by_tag.get(tag)))?;
+        Some(converter(value))
+    }
+
+    /// Indica se existe conversor para a tag.
+    pub(crate) fn has_tag(&self, tag: &str) -> bool {
+        self.by_tag.contains_key(tag)
+    }
+}
+
+fn erase<T, F>(convert: F) -> ConverterFn
+where
+    T: Any + Send + Sync,
+    F: Fn(&Value) -> Result<T, String> + Send + Sync + 'static,
+{
+    Arc::new(move |value| convert(value).map(|v| Arc::new(v) as Converted))
+}
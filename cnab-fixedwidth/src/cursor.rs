@@ -0,0 +1,118 @@
+//! # Leitura Campo a Campo
+//!
+//! [`FieldCursor`] percorre os campos de um registro um de cada vez, na ordem do layout,
+//! sem montar o [`DynRecord`](crate::DynRecord) inteiro. Útil em roteadores que só
+//! precisam dos discriminadores (tipo de registro, segmento) para decidir o que fazer
+//! com a linha, ou para processar registros longos com memória constante:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{FieldCursor, Layout};
+//!
+//! # fn exemplo(line: &[u8], layout: &Layout) -> cnab_fixedwidth::Result<()> {
+//! let mut cursor = FieldCursor::new(line, layout.fields());
+//! let tipo = cursor.next().transpose()?.map(|token| token.value);
+//! // ... os demais campos não são lidos
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{parse_field, slice_field, trim_record, FieldKind, FieldSpec, ParseOptions, Result, Value};
+
+/// Campo lido pelo cursor: especificação (nome e posição), bytes brutos e valor.
+#[derive(Debug, Clone)]
+pub struct FieldToken<'a> {
+    /// Especificação do campo no layout.
+    pub spec: &'a FieldSpec,
+    /// Bytes do campo na linha, sem decodificação.
+    pub raw: &'a [u8],
+    /// Valor convertido conforme o tipo do campo.
+    pub value: Value,
+}
+
+/// Iterador sobre os campos de uma linha; fillers são pulados, como em `parse_line`.
+///
+/// Um campo com erro é devolvido como `Err` e o cursor segue para o próximo.
+#[derive(Debug, Clone)]
+pub struct FieldCursor<'a> {
+    line: &'a [u8],
+    fields: &'a [FieldSpec],
+    options: ParseOptions,
+    next: usize,
+}
+
+impl<'a> FieldCursor<'a> {
+    /// Cursor sobre `line` com os campos de `fields` (ex: `layout.fields()`), em Latin-1.
+    pub fn new(line: &'a [u8], fields: &'a [FieldSpec]) -> Self {
+        Self::with_options(line, fields, ParseOptions::default())
+    }
+
+    /// Cursor com opções de parse (codificação, redação, `saturate`...).
+    pub fn with_options(line: &'a [u8], fields: &'a [FieldSpec], options: ParseOptions) -> Self {
+        Self { line: trim_record(line, &options), fields, options, next: 0 }
+    }
+
+    /// Campos ainda não lidos (incluindo fillers).
+    pub fn remaining(&self) -> &'a [FieldSpec] {
+        &self.fields[self.next..]
+    }
+
+    /// Avança até o campo `name` e o lê, pulando (sem converter) os anteriores.
+    ///
+    /// Retorna `None` se o campo não existir entre os restantes.
+    pub fn seek(&mut self, name: &str) -> Option<Result<FieldToken<'a>>> {
+        let offset = self.remaining().iter().position(|field| field.name == name)?;
+        self.next += offset;
+        self.next()
+    }
+}
+
+impl<'a> Iterator for FieldCursor<'a> {
+    type Item = Result<FieldToken<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.remaining().iter().position(|field| !matches!(field.kind, FieldKind::Filler))?;
+        let spec = &self.fields[self.next + offset];
+        self.next += offset + 1;
+        let token = slice_field(self.line, spec, &self.options).and_then(|raw| {
+            let value = parse_field(self.line, spec, &self.options)?;
+            Ok(FieldToken { spec, raw, value })
+        });
+        Some(token)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining().len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldPos, FixedWidthError};
+
+    #[test]
+    fn yields_fields_one_at_a_time() {
+        let fields = [
+            FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("uso_febraban", FieldPos::new_const(4, 4), FieldKind::Filler),
+            FieldSpec::new("segmento", FieldPos::new_const(5, 5), FieldKind::Alpha),
+            FieldSpec::new("valor", FieldPos::new_const(6, 10), FieldKind::Decimal { scale: 2 }),
+            FieldSpec::new("nome", FieldPos::new_const(11, 20), FieldKind::Alpha),
+        ];
+        let line = b"341 T00150XX\r\n";
+
+        let mut cursor = FieldCursor::new(line, &fields);
+        let banco = cursor.next().unwrap().unwrap();
+        assert_eq!((banco.spec.name, banco.raw, banco.value), ("banco", &b"341"[..], Value::Numeric(341)));
+        assert_eq!(cursor.next().unwrap().unwrap().value, Value::Alpha("T".to_string()));
+        // Parada antecipada: o restante da linha não é lido
+        assert_eq!(cursor.remaining().len(), 2);
+
+        let mut cursor = FieldCursor::new(line, &fields);
+        let valor = cursor.seek("valor").unwrap().unwrap();
+        assert_eq!(valor.value, Value::Decimal { raw: 150, scale: 2 });
+        assert!(matches!(cursor.next(), Some(Err(FixedWidthError::LineTooShort { len: 12, needed: 20 }))));
+        assert!(cursor.next().is_none());
+        assert!(cursor.seek("banco").is_none());
+    }
+}
@@ -0,0 +1,128 @@
+//! # Depuração de Layouts
+//!
+//! Quando as posições de uma struct estão deslocadas de uma casa, o erro de parse só diz
+//! qual campo falhou. [`annotate`] mostra a linha com uma régua e, campo a campo, a
+//! posição, o trecho bruto e o valor lido, e [`annotate_error`] marca com `^` as
+//! posições do campo que falhou:
+//!
+//! ```text
+//!          1         2
+//! 12345678901234567890
+//! 341  ANA  000105A006
+//!           ^^^^^^^
+//! campo 'valor' contém caracteres inválidos para numérico: '000105A'
+//! ```
+//!
+//! As posições são de bytes, como no CNAB: cada byte ocupa uma coluna (lido como
+//! Latin-1) e caracteres de controle aparecem como `·`.
+
+use crate::{parse_field, FieldKind, FieldSpec, FixedWidthError, ParseOptions};
+use std::fmt::Write;
+
+/// Régua, linha e o detalhamento de cada campo de `spec`, com as opções padrão.
+pub fn annotate(line: &str, spec: &[FieldSpec]) -> String {
+    annotate_bytes(line.as_bytes(), spec, &ParseOptions::default())
+}
+
+/// Como [`annotate`], sobre os bytes da linha e com opções de parse.
+///
+/// Cada campo sai em uma linha com a posição, o nome, o trecho bruto entre colchetes e o
+/// valor lido (textos entre aspas), ou o erro do campo. Fillers só mostram o trecho.
+pub fn annotate_bytes(line: &[u8], spec: &[FieldSpec], options: &ParseOptions) -> String {
+    let line = crate::trim_line_ending(line);
+    let mut out = ruler(line);
+    let name_width = spec.iter().map(|field| field.name.len()).max().unwrap_or(0);
+    for field in spec {
+        let value = match field.kind {
+            FieldKind::Filler => String::new(),
+            _ => match parse_field(line, field, options) {
+                Ok(value) => value.to_string(),
+                Err(e) => format!("ERRO: {e}"),
+            },
+        };
+        let pos = format!("{}..{}", field.pos.start(), field.pos.end());
+        let raw = render(&line[(field.pos.start() - 1).min(line.len())..field.pos.end().min(line.len())]);
+        let row = format!("{pos:>9}  {:<name_width$}  [{raw}]  {value}", field.name);
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Régua e linha com `^` sob as posições do campo de `spec` que causou `error`, seguidas
+/// da mensagem do erro.
+///
+/// Erros sem campo (ex: tamanho da linha) só trazem a mensagem. Em `LineTooShort`, as
+/// marcas vão até o fim do primeiro campo que não coube, além do fim da linha.
+pub fn annotate_error(line: &str, spec: &[FieldSpec], error: &FixedWidthError) -> String {
+    let line = crate::trim_line_ending(line.as_bytes());
+    let mut out = ruler(line);
+    if let Some(field) = error.field_in(spec) {
+        let start = field.pos.start() - 1;
+        let _ = writeln!(out, "{}{}", " ".repeat(start), "^".repeat(field.pos.width()));
+    }
+    let _ = writeln!(out, "{error}");
+    out
+}
+
+/// Dezenas, unidades e a linha, uma coluna por byte.
+fn ruler(line: &[u8]) -> String {
+    let mut tens = String::new();
+    for end in (10..=line.len()).step_by(10) {
+        let label = (end / 10).to_string();
+        let _ = write!(tens, "{label:>width$}", width = end - tens.len());
+    }
+    let units: String = (1..=line.len()).map(|pos| char::from(b'0' + (pos % 10) as u8)).collect();
+    format!("{tens}\n{units}\n{}\n", render(line))
+}
+
+/// Bytes como texto Latin-1, com caracteres de controle trocados por `·`.
+fn render(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| if b.is_ascii_control() { '·' } else { char::from(b) }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixedWidth, FixedWidthParse, FixedWidthSpec};
+
+    #[derive(FixedWidth, Debug)]
+    #[fw(width = 20)]
+    #[allow(dead_code)]
+    struct Detalhe {
+        #[fw(pos = "1..3", numeric)]
+        banco: u16,
+        #[fw(pos = "4..5", filler)]
+        brancos: (),
+        #[fw(pos = "6..10", alpha)]
+        nome: String,
+        #[fw(pos = "11..17", decimal = 2)]
+        valor: f64,
+        #[fw(pos = "18..20", numeric)]
+        ocorrencia: u8,
+    }
+
+    #[test]
+    fn annotates_fields_and_marks_the_failed_one() {
+        assert_eq!(
+            annotate("341  ANA  0001050006\r\n", Detalhe::spec()),
+            "         1         2\n\
+             12345678901234567890\n\
+             341  ANA  0001050006\n\
+             \x20    1..3  banco       [341]  341\n\
+             \x20    4..5  brancos     [  ]\n\
+             \x20   6..10  nome        [ANA  ]  'ANA'\n\
+             \x20  11..17  valor       [0001050]  10.50\n\
+             \x20  18..20  ocorrencia  [006]  6\n"
+        );
+
+        let line = "341  ANA  000105A006";
+        let error = Detalhe::parse(line).unwrap_err();
+        let annotated = annotate_error(line, Detalhe::spec(), &error);
+        assert!(annotated.starts_with("         1         2\n12345678901234567890\n341  ANA  000105A006\n          ^^^^^^^\n"), "{annotated}");
+        assert!(annotated.ends_with(&format!("{error}\n")));
+
+        let error = Detalhe::parse("341  ANA  00").unwrap_err();
+        assert!(annotate_error("341  ANA  00", Detalhe::spec(), &error).contains("\n          ^^^^^^^\n"));
+    }
+}
@@ -0,0 +1,154 @@
+//! # Detecção de Formato
+//!
+//! Identifica o formato (CNAB 240, 400 ou 150) e o banco de um arquivo pelo primeiro
+//! registro, para escolher os layouts sem perguntar ao usuário:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{cnab240, detect, CnabFormat};
+//!
+//! let mut file = std::io::BufReader::new(std::fs::File::open("retorno.ret")?);
+//! let detected = detect::peek(&mut file)?.ok_or("formato não reconhecido")?;
+//! if detected.format == CnabFormat::Cnab240 {
+//!     let retorno = cnab240::parse_retorno_cobranca(file)?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{CnabFormat, Framing, Result};
+use std::io::BufRead;
+
+/// Formato e banco identificados.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Detected {
+    pub format: CnabFormat,
+    /// Código do banco na compensação.
+    pub bank_code: u16,
+    /// Arquivo blocado: registros em sequência, sem quebras de linha (veja [`peek`]).
+    pub blocked: bool,
+}
+
+impl Detected {
+    /// Framing para ler o arquivo: uma linha por registro ou, em arquivos blocados,
+    /// registros do tamanho do formato.
+    ///
+    /// Com quebras de linha, o framing é `Lines` mesmo que o formato tenha tamanho fixo:
+    /// uma linha com brancos finais cortados falha no parse em vez de deslocar todos os
+    /// registros seguintes.
+    pub fn framing(&self) -> Framing {
+        if self.blocked { Framing::FixedLength(self.format.record_width()) } else { Framing::Lines }
+    }
+}
+
+/// Identifica o formato pelo tamanho do primeiro registro e lê o código do banco:
+/// posições 1..3 no CNAB 240, 77..79 no CNAB 400 e 43..45 no FEBRABAN 150.
+///
+/// A quebra de linha final é ignorada. `None` se o tamanho não é de nenhum formato ou o
+/// código do banco não é numérico.
+pub fn sniff(first_line: &str) -> Option<Detected> {
+    sniff_bytes(first_line.as_bytes())
+}
+
+/// Como [`sniff`], sobre os bytes do registro (ex: Latin-1).
+pub fn sniff_bytes(first_line: &[u8]) -> Option<Detected> {
+    let line = crate::trim_line_ending(first_line);
+    let format = CnabFormat::from_width(line.len())?;
+    let bank_pos = match format {
+        CnabFormat::Cnab240 => 1..4,
+        CnabFormat::Cnab400 => 77..80,
+        CnabFormat::Cnab150 => 43..46,
+    };
+    let bank = &line[bank_pos.start - 1..bank_pos.end - 1];
+    let bank_code = bank
+        .iter()
+        .try_fold(0u16, |acc, &b| b.is_ascii_digit().then(|| acc * 10 + u16::from(b - b'0')))?;
+    Some(Detected { format, bank_code, blocked: false })
+}
+
+/// Identifica o arquivo pela primeira linha já carregada no buffer do leitor, sem
+/// consumi-la: o leitor segue pronto para o parser do formato detectado.
+///
+/// A primeira linha precisa caber no buffer (os 8 KiB padrão do `BufReader` bastam).
+/// Sem quebra de linha no buffer, o arquivo é tratado como blocado (registros de tamanho
+/// fixo em sequência, como nas transferências de mainframe): o formato é o tamanho em que
+/// o início do segundo registro confere com o header. Leia-o com [`Detected::framing`].
+pub fn peek<R: BufRead>(reader: &mut R) -> Result<Option<Detected>> {
+    let buffered = reader.fill_buf()?;
+    match buffered.iter().position(|&b| b == b'\n') {
+        Some(end) => Ok(sniff_bytes(&buffered[..end])),
+        None => Ok(sniff_blocked(buffered)),
+    }
+}
+
+/// Formato de um arquivo blocado pelo header e pelo início do registro seguinte: no
+/// CNAB 240, o mesmo banco; no CNAB 400, um tipo de registro numérico após o header `0`;
+/// no FEBRABAN 150, um código de registro (letra) após o header `A`.
+fn sniff_blocked(buffered: &[u8]) -> Option<Detected> {
+    [CnabFormat::Cnab400, CnabFormat::Cnab240, CnabFormat::Cnab150].into_iter().find_map(|format| {
+        let width = format.record_width();
+        let header = buffered.get(..width)?;
+        let next = &buffered[width..];
+        let follows = match format {
+            CnabFormat::Cnab240 => header[7] == b'0' && (next.is_empty() || next.starts_with(&header[..3])),
+            CnabFormat::Cnab400 => header[0] == b'0' && next.first().is_none_or(|b| matches!(b, b'1'..=b'9')),
+            CnabFormat::Cnab150 => header[0] == b'A' && next.first().is_none_or(u8::is_ascii_uppercase),
+        };
+        let detected = sniff_bytes(header).filter(|_| follows)?;
+        Some(Detected { blocked: true, ..detected })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_format_and_bank_from_the_first_record() {
+        let header_240 = format!("341{}\r\n", "0".repeat(237));
+        assert_eq!(sniff(&header_240), Some(Detected { format: CnabFormat::Cnab240, bank_code: 341, blocked: false }));
+
+        let header_400 = format!("{:<76}237{:<321}", "01REMESSA01COBRANCA", "BRADESCO");
+        assert_eq!(sniff(&header_400), Some(Detected { format: CnabFormat::Cnab400, bank_code: 237, blocked: false }));
+
+        let header_150 = format!("{:<42}104{:<105}", "A1", "CAIXA");
+        assert_eq!(sniff(&header_150).map(|d| d.bank_code), Some(104));
+
+        assert_eq!(sniff("0341"), None);
+        assert_eq!(sniff(&format!("ABC{}", "0".repeat(237))), None);
+
+        // O leitor não consome a linha inspecionada
+        let file = format!("{header_240}3410001300001T{}\n", " ".repeat(226));
+        let mut reader = std::io::BufReader::new(file.as_bytes());
+        assert_eq!(peek(&mut reader).unwrap().map(|d| d.format), Some(CnabFormat::Cnab240));
+        let mut first = String::new();
+        reader.read_line(&mut first).unwrap();
+        assert_eq!(first, header_240);
+    }
+
+    #[test]
+    fn detects_blocked_files_without_line_breaks() {
+        let header_240 = format!("3410000{:<233}", "0");
+        let lote_240 = format!("3410001{:<233}", "1");
+        let blocked_240 = format!("{header_240}{lote_240}");
+        let detected = peek(&mut blocked_240.as_bytes()).unwrap().unwrap();
+        assert_eq!(detected, Detected { format: CnabFormat::Cnab240, bank_code: 341, blocked: true });
+        assert_eq!(detected.framing(), Framing::FixedLength(240));
+
+        let header_400 = format!("{:<76}237{:<321}", "02RETORNO01COBRANCA", "BRADESCO");
+        let file = format!("{header_400}1{:<399}9{:<399}", "", "");
+        assert_eq!(peek(&mut file.as_bytes()).unwrap().map(|d| d.format), Some(CnabFormat::Cnab400));
+
+        let header_150 = format!("{:<42}104{:<105}", "A1", "CAIXA");
+        let file = format!("{header_150}E{:<149}", "");
+        assert_eq!(peek(&mut file.as_bytes()).unwrap().map(|d| d.format), Some(CnabFormat::Cnab150));
+
+        // Um único registro, sem quebra de linha
+        assert_eq!(peek(&mut header_400.as_bytes()).unwrap().map(|d| d.bank_code), Some(237));
+        assert_eq!(peek(&mut &b"0341"[..]).unwrap(), None);
+
+        let mut records = crate::RecordReader::new(blocked_240.as_bytes(), detected.framing());
+        let mut buf = Vec::new();
+        assert!(records.read_record(&mut buf).unwrap() && records.read_record(&mut buf).unwrap());
+        assert_eq!(buf, lote_240.as_bytes());
+        assert!(!records.read_record(&mut buf).unwrap());
+    }
+}
@@ -0,0 +1,251 @@
+//! # Comparação de Layouts e Registros
+//!
+//! Bancos revisam os layouts todo ano. [`spec_diff`] compara duas versões de um
+//! registro (ou dois layouts) campo a campo, pelo nome, para medir o impacto da
+//! migração antes de mexer no código:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{diff, FixedWidth, FixedWidthSpec};
+//!
+//! # #[derive(FixedWidth)]
+//! # struct SegmentoPv9 {
+//! #     #[fw(pos = "38..57", alpha)]
+//! #     nosso_numero: String,
+//! #     #[fw(pos = "228..228", numeric)]
+//! #     codigo_moeda: u8,
+//! # }
+//! # #[derive(FixedWidth)]
+//! # struct SegmentoPv10 {
+//! #     #[fw(pos = "38..59", alpha)]
+//! #     nosso_numero: String,
+//! # }
+//! for change in diff::spec_diff(SegmentoPv9::spec(), SegmentoPv10::spec()) {
+//!     println!("{change}");
+//! }
+//! // nosso_numero: redimensionado de 38..57 para 38..59
+//! // codigo_moeda: removido (228..228, Numeric)
+//! ```
+//!
+//! [`diff_records`] compara dois registros com o mesmo layout, para descobrir por que o
+//! banco rejeitou uma remessa "igual" a outra que foi aceita:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{diff, FixedWidth, FixedWidthSpec};
+//!
+//! # #[derive(FixedWidth)]
+//! # struct SegmentoP {
+//! #     #[fw(pos = "86..100", decimal = 2)]
+//! #     valor_titulo: f64,
+//! # }
+//! # let (aceita, rejeitada) = (String::new(), String::new());
+//! for diff in diff::diff_records(&aceita, &rejeitada, SegmentoP::spec()) {
+//!     println!("{diff}");
+//! }
+//! // valor_titulo [86..100]: 10.50 -> 10.50 ('000000000001050' -> '1050           ')
+//! ```
+
+use crate::{parse_field, FieldKind, FieldPos, FieldSpec, ParseOptions, Value};
+
+/// Diferença de um campo entre duas versões de um layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecChange {
+    /// Campo só na nova versão.
+    Added { name: &'static str, pos: FieldPos, kind: FieldKind },
+    /// Campo só na versão anterior.
+    Removed { name: &'static str, pos: FieldPos, kind: FieldKind },
+    /// Mesma largura em outra posição.
+    Moved { name: &'static str, from: FieldPos, to: FieldPos },
+    /// Largura diferente (a posição inicial pode ter mudado também).
+    Resized { name: &'static str, from: FieldPos, to: FieldPos },
+    /// Tipo ou escala diferente.
+    Retyped { name: &'static str, from: FieldKind, to: FieldKind },
+}
+
+impl SpecChange {
+    /// Nome do campo.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Added { name, .. }
+            | Self::Removed { name, .. }
+            | Self::Moved { name, .. }
+            | Self::Resized { name, .. }
+            | Self::Retyped { name, .. } => name,
+        }
+    }
+}
+
+impl std::fmt::Display for SpecChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let range = |pos: &FieldPos| format!("{}..{}", pos.start(), pos.end());
+        match self {
+            Self::Added { name, pos, kind } => write!(f, "{name}: incluído ({}, {kind})", range(pos)),
+            Self::Removed { name, pos, kind } => write!(f, "{name}: removido ({}, {kind})", range(pos)),
+            Self::Moved { name, from, to } => write!(f, "{name}: movido de {} para {}", range(from), range(to)),
+            Self::Resized { name, from, to } => {
+                write!(f, "{name}: redimensionado de {} para {}", range(from), range(to))
+            }
+            Self::Retyped { name, from, to } => write!(f, "{name}: tipo alterado de {from} para {to}"),
+        }
+    }
+}
+
+/// Compara os campos de `a` (versão anterior) com os de `b` (nova versão), pelo nome.
+///
+/// Um campo pode ter mais de uma mudança (ex: `Moved` e `Retyped`). As mudanças saem na
+/// ordem dos campos de `a`, seguidas dos campos incluídos, na ordem de `b`. Campos
+/// repetidos com o mesmo nome (ex: fillers) são pareados na ordem em que aparecem.
+pub fn spec_diff(a: &[FieldSpec], b: &[FieldSpec]) -> Vec<SpecChange> {
+    let mut paired = vec![false; b.len()];
+    let mut changes = Vec::new();
+    for old in a {
+        let Some(index) = (0..b.len()).find(|&i| !paired[i] && b[i].name == old.name) else {
+            changes.push(SpecChange::Removed { name: old.name, pos: old.pos, kind: old.kind });
+            continue;
+        };
+        paired[index] = true;
+        let new = &b[index];
+        if old.pos.width() != new.pos.width() {
+            changes.push(SpecChange::Resized { name: old.name, from: old.pos, to: new.pos });
+        } else if old.pos != new.pos {
+            changes.push(SpecChange::Moved { name: old.name, from: old.pos, to: new.pos });
+        }
+        if old.kind != new.kind {
+            changes.push(SpecChange::Retyped { name: old.name, from: old.kind, to: new.kind });
+        }
+    }
+    changes.extend(
+        b.iter()
+            .zip(paired)
+            .filter(|(_, paired)| !paired)
+            .map(|(new, _)| SpecChange::Added { name: new.name, pos: new.pos, kind: new.kind }),
+    );
+    changes
+}
+
+/// Campo com conteúdo diferente em dois registros.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    pub name: &'static str,
+    pub pos: FieldPos,
+    /// Trecho do primeiro registro (Latin-1).
+    pub old_raw: String,
+    /// Trecho do segundo registro (Latin-1).
+    pub new_raw: String,
+    /// Valor lido do primeiro registro; `None` em fillers ou se o trecho não é válido.
+    pub old: Option<Value>,
+    /// Valor lido do segundo registro; `None` em fillers ou se o trecho não é válido.
+    pub new: Option<Value>,
+}
+
+impl std::fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |value: &Option<Value>| value.as_ref().map_or_else(|| "-".to_string(), Value::to_string);
+        write!(
+            f,
+            "{} [{}..{}]: {} -> {} ('{}' -> '{}')",
+            self.name,
+            self.pos.start(),
+            self.pos.end(),
+            value(&self.old),
+            value(&self.new),
+            self.old_raw,
+            self.new_raw
+        )
+    }
+}
+
+/// Campos de `spec` com conteúdo diferente nas duas linhas, com as opções padrão.
+pub fn diff_records(line_a: &str, line_b: &str, spec: &[FieldSpec]) -> Vec<FieldDiff> {
+    diff_records_bytes(line_a.as_bytes(), line_b.as_bytes(), spec, &ParseOptions::default())
+}
+
+/// Como [`diff_records`], sobre os bytes das linhas e com opções de parse.
+///
+/// A comparação é pelos bytes de cada campo, fillers incluídos: um campo com o mesmo
+/// valor mas preenchimento diferente (`'0001050'` e `'1050   '`) também aparece, pois é o
+/// tipo de diferença que faz um banco rejeitar o arquivo. Campos que não cabem numa das
+/// linhas contam como trecho vazio. Bytes fora dos campos de `spec` não são comparados
+/// (para a comparação byte a byte, veja [`homologation::compare`](crate::homologation::compare)).
+pub fn diff_records_bytes(line_a: &[u8], line_b: &[u8], spec: &[FieldSpec], options: &ParseOptions) -> Vec<FieldDiff> {
+    let (line_a, line_b) = (crate::trim_line_ending(line_a), crate::trim_line_ending(line_b));
+    let slice = |line: &[u8], pos: FieldPos| line[(pos.start() - 1).min(line.len())..pos.end().min(line.len())].to_vec();
+    let value = |line: &[u8], field: &FieldSpec| match field.kind {
+        FieldKind::Filler => None,
+        _ => parse_field(line, field, options).ok(),
+    };
+    spec.iter()
+        .filter(|field| slice(line_a, field.pos) != slice(line_b, field.pos))
+        .map(|field| FieldDiff {
+            name: field.name,
+            pos: field.pos,
+            old_raw: slice(line_a, field.pos).iter().map(|&b| char::from(b)).collect(),
+            new_raw: slice(line_b, field.pos).iter().map(|&b| char::from(b)).collect(),
+            old: value(line_a, field),
+            new: value(line_b, field),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &'static str, start: usize, end: usize, kind: FieldKind) -> FieldSpec {
+        FieldSpec::new(name, FieldPos::new_const(start, end), kind)
+    }
+
+    #[test]
+    fn reports_moved_resized_added_removed_and_retyped_fields() {
+        let v9 = [
+            field("banco", 1, 3, FieldKind::Numeric),
+            field("nosso_numero", 4, 13, FieldKind::Alpha),
+            field("valor", 14, 26, FieldKind::Decimal { scale: 2 }),
+            field("moeda", 27, 28, FieldKind::Numeric),
+            field("brancos", 29, 40, FieldKind::Filler),
+        ];
+        let v10 = [
+            field("banco", 1, 3, FieldKind::Numeric),
+            field("nosso_numero", 4, 15, FieldKind::Alpha),
+            field("valor", 16, 28, FieldKind::Numeric),
+            field("pix", 29, 30, FieldKind::Alpha),
+            field("brancos", 31, 40, FieldKind::Filler),
+        ];
+        let changes = spec_diff(&v9, &v10);
+        assert_eq!(
+            changes,
+            [
+                SpecChange::Resized { name: "nosso_numero", from: FieldPos::new_const(4, 13), to: FieldPos::new_const(4, 15) },
+                SpecChange::Moved { name: "valor", from: FieldPos::new_const(14, 26), to: FieldPos::new_const(16, 28) },
+                SpecChange::Retyped { name: "valor", from: FieldKind::Decimal { scale: 2 }, to: FieldKind::Numeric },
+                SpecChange::Removed { name: "moeda", pos: FieldPos::new_const(27, 28), kind: FieldKind::Numeric },
+                SpecChange::Resized { name: "brancos", from: FieldPos::new_const(29, 40), to: FieldPos::new_const(31, 40) },
+                SpecChange::Added { name: "pix", pos: FieldPos::new_const(29, 30), kind: FieldKind::Alpha },
+            ]
+        );
+        assert_eq!(changes[2].to_string(), "valor: tipo alterado de Decimal(2) para Numeric");
+        assert_eq!(changes[3].to_string(), "moeda: removido (27..28, Numeric)");
+        assert!(spec_diff(&v10, &v10).is_empty());
+    }
+
+    #[test]
+    fn lists_fields_whose_bytes_differ() {
+        let spec = [
+            field("banco", 1, 3, FieldKind::Numeric),
+            field("nome", 4, 8, FieldKind::Alpha),
+            field("valor", 9, 15, FieldKind::Decimal { scale: 2 }),
+            field("brancos", 16, 20, FieldKind::Filler),
+        ];
+        let diffs = diff_records("341ANA  0001050     \r\n", "341ANA  1050   00000", &spec);
+        assert_eq!(diffs.iter().map(|d| d.name).collect::<Vec<_>>(), ["valor", "brancos"]);
+        // Mesmo valor, preenchimento diferente
+        assert_eq!(diffs[0].old, Some(Value::Decimal { raw: 1050, scale: 2 }));
+        assert_eq!(diffs[0].old, diffs[0].new);
+        assert_eq!(diffs[0].to_string(), "valor [9..15]: 10.50 -> 10.50 ('0001050' -> '1050   ')");
+        assert_eq!(diff_records("341ANA  00010X0", "341ANA  0001050", &spec)[0].old, None);
+        assert_eq!(diffs[1].old_raw, "     ");
+
+        assert!(diff_records("341ANA  0001050", "341ANA  0001050", &spec).is_empty());
+        let short = diff_records("341ANA", "341ANA  0001050", &spec);
+        assert_eq!((short[0].old_raw.as_str(), short[1].old_raw.as_str()), ("ANA", ""));
+    }
+}
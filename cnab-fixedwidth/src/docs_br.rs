@@ -0,0 +1,244 @@
+//! # CPF e CNPJ (feature `docs-br`)
+//!
+//! Números de inscrição aparecem em quase todo segmento: da empresa nos headers, do
+//! pagador, do sacador, do favorecido. Este módulo confere os dígitos verificadores,
+//! normaliza o texto digitado (sem pontuação, com zeros à esquerda na largura do campo
+//! CNAB) e formata com a máscara usual.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::docs_br::{self, Documento};
+//!
+//! docs_br::validate_cnpj("11.222.333/0001-81")?;
+//! assert_eq!(docs_br::normalize("529.982.247-25", 14)?, "00052998224725");
+//! assert_eq!(docs_br::format(Documento::Cpf, "52998224725")?, "529.982.247-25");
+//! # Ok::<(), docs_br::DocError>(())
+//! ```
+//!
+//! Em structs derivadas, `#[fw(validate = "cpf_cnpj")]` confere o campo no parse com
+//! [`check_field`].
+
+use crate::nosso_numero::CheckDigit;
+use thiserror::Error;
+
+/// Erros de um número de inscrição.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DocError {
+    /// Caracteres que não são dígitos nem pontuação, ou quantidade de dígitos errada.
+    #[error("'{value}' não é um {documento} no formato esperado")]
+    InvalidFormat { documento: Documento, value: String },
+
+    /// Os dígitos verificadores não conferem (ou todos os dígitos são iguais).
+    #[error("{documento} '{value}' com dígitos verificadores inválidos")]
+    CheckDigits { documento: Documento, value: String },
+}
+
+/// Tipo do número de inscrição (o `tipo_inscricao` dos registros: 1 = CPF, 2 = CNPJ).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Documento {
+    Cpf,
+    Cnpj,
+}
+
+impl Documento {
+    /// Tipo pelo código de tipo de inscrição do CNAB.
+    pub fn from_tipo_inscricao(tipo: u8) -> Option<Self> {
+        match tipo {
+            1 => Some(Documento::Cpf),
+            2 => Some(Documento::Cnpj),
+            _ => None,
+        }
+    }
+
+    /// Quantidade de dígitos, com os DVs.
+    pub const fn width(self) -> usize {
+        match self {
+            Documento::Cpf => 11,
+            Documento::Cnpj => 14,
+        }
+    }
+}
+
+impl std::fmt::Display for Documento {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Documento::Cpf => "CPF",
+            Documento::Cnpj => "CNPJ",
+        })
+    }
+}
+
+/// Dígitos do texto, sem pontos, barras, hífens e espaços; `None` se sobrar outro caractere.
+pub fn digits(text: &str) -> Option<String> {
+    let digits: String = text.chars().filter(|c| !matches!(c, '.' | '/' | '-' | ' ')).collect();
+    digits.bytes().all(|b| b.is_ascii_digit()).then_some(digits)
+}
+
+/// Confere os DVs de um CPF, com ou sem pontuação e zeros à esquerda.
+pub fn validate_cpf(text: &str) -> Result<(), DocError> {
+    validate(Documento::Cpf, text)
+}
+
+/// Confere os DVs de um CNPJ, com ou sem pontuação e zeros à esquerda.
+pub fn validate_cnpj(text: &str) -> Result<(), DocError> {
+    validate(Documento::Cnpj, text)
+}
+
+/// Confere os DVs de um CPF ou CNPJ.
+pub fn validate(documento: Documento, text: &str) -> Result<(), DocError> {
+    let digits = fit(documento, text)?;
+    let (base, dvs) = digits.split_at(documento.width() - 2);
+    // Com todos os dígitos iguais os DVs conferem, mas o número não existe
+    let repeated = digits.bytes().all(|b| b == digits.as_bytes()[0]);
+    if repeated || check_digits(documento, base) != dvs {
+        return Err(DocError::CheckDigits { documento, value: text.to_string() });
+    }
+    Ok(())
+}
+
+/// Confere um número que pode ser CPF ou CNPJ (ex: campos sem o tipo de inscrição ao
+/// lado): vale se for um dos dois. O erro é o do CPF para até 11 dígitos significativos.
+pub fn validate_cpf_cnpj(text: &str) -> Result<(), DocError> {
+    let cpf_sized = digits(text).is_some_and(|d| significant(&d).len() <= Documento::Cpf.width());
+    match validate_cpf(text) {
+        Ok(()) => Ok(()),
+        Err(cpf) => validate_cnpj(text).map_err(|cnpj| if cpf_sized { cpf } else { cnpj }),
+    }
+}
+
+/// Sem pontuação e com zeros à esquerda até `width` posições (ex: 14 ou 15 no CNAB).
+pub fn normalize(text: &str, width: usize) -> Result<String, DocError> {
+    let documento = if width < Documento::Cnpj.width() { Documento::Cpf } else { Documento::Cnpj };
+    let digits = digits(text).filter(|d| significant(d).len() <= width);
+    let digits = digits.ok_or_else(|| DocError::InvalidFormat { documento, value: text.to_string() })?;
+    Ok(format!("{:0>width$}", significant(&digits)))
+}
+
+/// Formata com a máscara (`000.000.000-00` ou `00.000.000/0000-00`), sem conferir os DVs.
+pub fn format(documento: Documento, text: &str) -> Result<String, DocError> {
+    let d = fit(documento, text)?;
+    Ok(match documento {
+        Documento::Cpf => format!("{}.{}.{}-{}", &d[..3], &d[3..6], &d[6..9], &d[9..]),
+        Documento::Cnpj => format!("{}.{}.{}/{}-{}", &d[..2], &d[2..5], &d[5..8], &d[8..12], &d[12..]),
+    })
+}
+
+/// Valor de campo usado como número de inscrição, para `#[fw(validate = "cpf_cnpj")]`.
+pub trait Inscricao {
+    /// Texto do número; `None` quando não informado (branco ou zero).
+    fn inscricao(&self) -> Option<String>;
+}
+
+impl Inscricao for str {
+    fn inscricao(&self) -> Option<String> {
+        let text = self.trim();
+        (!significant(text).is_empty()).then(|| text.to_string())
+    }
+}
+
+impl Inscricao for String {
+    fn inscricao(&self) -> Option<String> {
+        self.as_str().inscricao()
+    }
+}
+
+impl<T: Inscricao + ?Sized> Inscricao for &T {
+    fn inscricao(&self) -> Option<String> {
+        (**self).inscricao()
+    }
+}
+
+impl<T: Inscricao> Inscricao for Option<T> {
+    fn inscricao(&self) -> Option<String> {
+        self.as_ref().and_then(Inscricao::inscricao)
+    }
+}
+
+macro_rules! impl_inscricao_int {
+    ($($ty:ty),*) => {$(
+        impl Inscricao for $ty {
+            fn inscricao(&self) -> Option<String> {
+                (*self != 0).then(|| self.to_string())
+            }
+        }
+    )*};
+}
+
+impl_inscricao_int!(u32, u64, u128);
+
+/// Confere um campo com CPF ou CNPJ; inscrição não informada passa (combine com um
+/// campo obrigatório quando ela não puder faltar).
+pub fn check_field<T: Inscricao + ?Sized>(field: &'static str, value: &T) -> crate::Result<()> {
+    match value.inscricao() {
+        Some(text) => validate_cpf_cnpj(&text)
+            .map_err(|_| crate::validation::failure(field, &text, "não é um CPF ou CNPJ válido")),
+        None => Ok(()),
+    }
+}
+
+/// Dígitos sem os zeros à esquerda.
+fn significant(digits: &str) -> &str {
+    digits.trim_start_matches('0')
+}
+
+/// Dígitos do documento com a quantidade exata, completando os zeros à esquerda.
+fn fit(documento: Documento, text: &str) -> Result<String, DocError> {
+    let len = documento.width();
+    digits(text)
+        .filter(|d| significant(d).len() <= len)
+        .map(|d| format!("{:0>len$}", significant(&d)))
+        .ok_or_else(|| DocError::InvalidFormat { documento, value: text.to_string() })
+}
+
+/// Os dois DVs da base (9 dígitos do CPF, 12 do CNPJ), em módulo 11.
+pub(crate) fn check_digits(documento: Documento, base: &str) -> String {
+    // CPF: pesos 2 a 10 (e 11, no segundo DV) sem repetir; CNPJ: pesos 2 a 9 em ciclo
+    let algorithm = |max_weight| CheckDigit::Mod11 { max_weight, on_ten: '0', on_eleven: '0' };
+    let (first, second) = match documento {
+        Documento::Cpf => (algorithm(10), algorithm(11)),
+        Documento::Cnpj => (algorithm(9), algorithm(9)),
+    };
+    let dv1 = first.compute(base).unwrap_or('0');
+    let dv2 = second.compute(&format!("{base}{dv1}")).unwrap_or('0');
+    format!("{dv1}{dv2}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_normalizes_and_formats_inscricoes() {
+        assert!(validate_cpf("529.982.247-25").is_ok());
+        assert!(validate_cnpj("11.222.333/0001-81").is_ok());
+        assert!(validate_cpf_cnpj("00052998224725").is_ok());
+        assert!(validate_cpf_cnpj("011222333000181").is_ok());
+        assert_eq!(
+            validate_cpf("529.982.247-24"),
+            Err(DocError::CheckDigits { documento: Documento::Cpf, value: "529.982.247-24".into() })
+        );
+        assert!(matches!(validate_cpf("111.111.111-11"), Err(DocError::CheckDigits { .. })));
+        assert!(matches!(validate_cnpj("11.222.333/0001-8A"), Err(DocError::InvalidFormat { .. })));
+
+        assert_eq!(normalize("529.982.247-25", 14).unwrap(), "00052998224725");
+        assert_eq!(normalize("11.222.333/0001-81", 15).unwrap(), "011222333000181");
+        assert!(normalize("123456789012345", 14).is_err());
+        assert_eq!(format(Documento::Cpf, "00052998224725").unwrap(), "529.982.247-25");
+        assert_eq!(format(Documento::Cnpj, "11222333000181").unwrap(), "11.222.333/0001-81");
+        assert_eq!(Documento::from_tipo_inscricao(2), Some(Documento::Cnpj));
+
+        assert!(check_field("inscricao", &52_998_224_725_u64).is_ok());
+        assert!(check_field("inscricao", &0_u64).is_ok());
+        let err = check_field("inscricao", "52998224724").unwrap_err();
+        assert_eq!(err.to_string(), "campo 'inscricao' com valor '52998224724' inválido: não é um CPF ou CNPJ válido");
+
+        #[derive(crate::FixedWidth, Debug)]
+        struct Pagador {
+            #[fw(pos = "1..15", numeric, validate = "cpf_cnpj")]
+            inscricao: u64,
+        }
+        use crate::FixedWidthParse;
+        assert_eq!(Pagador::parse("011222333000181").unwrap().inscricao, 11_222_333_000_181);
+        assert!(Pagador::parse("000000000000000").is_ok());
+        assert!(matches!(Pagador::parse("011222333000182"), Err(crate::FixedWidthError::Validation(e)) if e.field == "inscricao"));
+    }
+}
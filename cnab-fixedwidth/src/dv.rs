@@ -0,0 +1,141 @@
+//! # Dígitos Verificadores
+//!
+//! Algoritmos de módulo 10 e 11 usados pelos bancos nos DVs de agência, conta e nosso
+//! número. Cada banco escolhe o módulo, os pesos e o que vira o DV quando o resto dá 10
+//! ou 11; [`algorithm`] devolve a [`CheckDigit`] de cada combinação e [`compute`] e
+//! [`validate`] a aplicam aos dígitos.
+//!
+//! | Banco | Agência | Conta | Nosso número |
+//! |---|---|---|---|
+//! | Itaú | — | módulo 10 sobre agência (4) + conta (5) | módulo 10 sobre agência + conta + carteira + número |
+//! | Bradesco | módulo 11, resto 1 vira `P` | módulo 11 (base 7), resto 1 vira `P` | módulo 11 (base 7) sobre carteira + número, resto 1 vira `P` |
+//! | Banco do Brasil | módulo 11, resto 1 vira `X` | módulo 11, resto 1 vira `X` | módulo 11 sobre convênio (4 ou 6) + número, resto 1 vira `X` |
+//! | Caixa | — | módulo 11 sobre agência (4) + operação (3) + conta (8) | módulo 11 sobre modalidade + número |
+//! | Santander | — | — | módulo 11 |
+//! | Sicredi | — | — | módulo 11 sobre cooperativa + posto + beneficiário + número |
+//!
+//! ```no_run
+//! use cnab_fixedwidth::banks::Bank;
+//! use cnab_fixedwidth::dv::{self, Campo};
+//!
+//! assert_eq!(dv::compute(Bank::Itau, Campo::Conta, "005712345")?, '7');
+//! dv::validate(Bank::BancoDoBrasil, Campo::Agencia, "0001", '9')?;
+//! # Ok::<(), dv::DvError>(())
+//! ```
+//!
+//! Com [`WriteOptions::with_check_digits`](crate::writer::WriteOptions::with_check_digits),
+//! o `cnab240::FileWriter` preenche os DVs de agência e conta deixados em branco.
+
+use crate::banks::Bank;
+use crate::nosso_numero::CheckDigit;
+use thiserror::Error;
+
+/// Campo a que o DV pertence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Campo {
+    Agencia,
+    Conta,
+    NossoNumero,
+}
+
+/// Erros no cálculo ou na conferência de um DV.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DvError {
+    /// O banco não usa DV nesse campo.
+    #[error("{banco:?} não usa dígito verificador em {campo:?}")]
+    NotUsed { banco: Bank, campo: Campo },
+
+    /// Os dígitos informados têm outros caracteres.
+    #[error("'{digits}' não é uma sequência de dígitos")]
+    InvalidDigits { digits: String },
+
+    /// O DV informado não confere.
+    #[error("'{digits}': dígito verificador {found}, esperado {expected}")]
+    Mismatch { digits: String, expected: char, found: char },
+}
+
+/// Algoritmo do DV do campo no banco (`CheckDigit::None` quando o banco não usa DV nele).
+pub fn algorithm(banco: Bank, campo: Campo) -> CheckDigit {
+    let mod11 = |max_weight, on_ten| CheckDigit::Mod11 { max_weight, on_ten, on_eleven: '0' };
+    match (banco, campo) {
+        (Bank::Itau, Campo::Agencia) => CheckDigit::None,
+        (Bank::Itau, _) => CheckDigit::Mod10,
+        (Bank::Bradesco, Campo::Agencia) => mod11(9, 'P'),
+        (Bank::Bradesco, _) => mod11(7, 'P'),
+        (Bank::BancoDoBrasil, _) => mod11(9, 'X'),
+        (Bank::Caixa, Campo::Agencia) => CheckDigit::None,
+        (Bank::Caixa, _) => mod11(9, '0'),
+        (Bank::Santander | Bank::Sicredi, Campo::NossoNumero) => mod11(9, '0'),
+        (Bank::Santander | Bank::Sicredi, _) => CheckDigit::None,
+    }
+}
+
+/// Calcula o DV de `digits`, já compostos como o banco pede (veja a tabela do módulo).
+pub fn compute(banco: Bank, campo: Campo, digits: &str) -> Result<char, DvError> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(DvError::InvalidDigits { digits: digits.to_string() });
+    }
+    algorithm(banco, campo).compute(digits).ok_or(DvError::NotUsed { banco, campo })
+}
+
+/// Confere o DV informado (maiúsculas e minúsculas são equivalentes).
+pub fn validate(banco: Bank, campo: Campo, digits: &str, dv: char) -> Result<(), DvError> {
+    let expected = compute(banco, campo, digits)?;
+    if !expected.eq_ignore_ascii_case(&dv) {
+        return Err(DvError::Mismatch { digits: digits.to_string(), expected, found: dv });
+    }
+    Ok(())
+}
+
+/// DVs de agência e conta a partir dos números, com a composição do banco (`None` onde o
+/// banco não usa DV).
+///
+/// No Itaú o DAC da conta usa a agência de 4 dígitos e a conta de 5; na Caixa, a conta
+/// informada leva a operação à frente (ex: `001_00012345`).
+pub fn agencia_conta(banco: Bank, agencia: u32, conta: u64) -> (Option<char>, Option<char>) {
+    let digits_conta = match banco {
+        Bank::Itau => format!("{:04}{:05}", agencia % 10_000, conta % 100_000),
+        Bank::Caixa => format!("{:04}{:011}", agencia % 10_000, conta % 100_000_000_000),
+        _ => conta.to_string(),
+    };
+    let agencia_dv = compute(banco, Campo::Agencia, &format!("{:04}", agencia % 10_000)).ok();
+    (agencia_dv, compute(banco, Campo::Conta, &digits_conta).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnab240::{FileWriter, HeaderArquivo, HeaderLote, TrailerLote};
+    use crate::writer::WriteOptions;
+
+    #[test]
+    fn computes_and_validates_bank_check_digits() {
+        assert_eq!(compute(Bank::Itau, Campo::Conta, "005712345"), Ok('7'));
+        assert_eq!(compute(Bank::BancoDoBrasil, Campo::Agencia, "0001"), Ok('9'));
+        assert_eq!(compute(Bank::Bradesco, Campo::NossoNumero, "0900000000002"), Ok('P'));
+        assert_eq!(compute(Bank::Caixa, Campo::Conta, "0001001000123456"), Ok('2'));
+        assert_eq!(compute(Bank::Santander, Campo::Agencia, "1234"), Err(DvError::NotUsed { banco: Bank::Santander, campo: Campo::Agencia }));
+        assert!(matches!(compute(Bank::Itau, Campo::Conta, "12a4"), Err(DvError::InvalidDigits { .. })));
+
+        assert!(validate(Bank::Bradesco, Campo::NossoNumero, "1900000000002", '8').is_ok());
+        assert!(validate(Bank::BancoDoBrasil, Campo::Conta, "6", 'x').is_ok());
+        assert_eq!(
+            validate(Bank::Itau, Campo::Conta, "005712345", '3').unwrap_err().to_string(),
+            "'005712345': dígito verificador 3, esperado 7"
+        );
+        assert_eq!(agencia_conta(Bank::Itau, 57, 12_345), (None, Some('7')));
+        assert_eq!(agencia_conta(Bank::BancoDoBrasil, 1, 6), (Some('9'), Some('X')));
+
+        // O FileWriter preenche só os DVs em branco
+        let header = HeaderArquivo { banco: 1, agencia: 1, conta: 6, ..Default::default() };
+        let options = WriteOptions::default().with_check_digits(Bank::BancoDoBrasil);
+        let mut writer = FileWriter::with_options(Vec::new(), header, options).unwrap();
+        let lote = HeaderLote { operacao: "R".into(), agencia: 1, agencia_dv: "5".into(), conta: 6, ..Default::default() };
+        writer.begin_lote(&lote).unwrap();
+        writer.end_lote::<TrailerLote>().unwrap();
+        let file = writer.finish().unwrap();
+        let lines: Vec<_> = file.split(|&b| b == b'\n').collect();
+        assert_eq!((lines[0][57], lines[0][70]), (b'9', b'X'));
+        assert_eq!((lines[1][58], lines[1][71]), (b'5', b'X'));
+    }
+}
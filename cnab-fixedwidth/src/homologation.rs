@@ -0,0 +1,257 @@
+//! # Homologação
+//!
+//! Na homologação de um convênio, o banco envia um arquivo de exemplo e confere a
+//! remessa gerada posição a posição. [`compare`] faz essa conferência antes do envio:
+//! compara os dois arquivos registro a registro, ignorando os campos que variam por
+//! natureza (datas de geração, sequenciais, horários) e produz o checklist que os bancos
+//! pedem no onboarding.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::homologation::{self, ToleranceRules};
+//! use cnab_fixedwidth::FieldPos;
+//!
+//! # let (gerado, exemplo) = (Vec::new(), Vec::new());
+//! let rules = ToleranceRules::new()
+//!     .ignore("data_geracao", FieldPos::new(144, 151)?)
+//!     .ignore_in(FieldPos::new(8, 8)?, "0", "sequencial_arquivo", FieldPos::new(158, 163)?);
+//! let report = homologation::compare(&gerado, &exemplo, &rules);
+//! println!("{}", report.checklist());
+//! assert!(report.is_approved());
+//! # Ok::<(), cnab_fixedwidth::LayoutError>(())
+//! ```
+
+use crate::FieldPos;
+use std::fmt::Write as _;
+
+/// Campo que pode diferir entre a remessa gerada e o exemplo do banco.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tolerance {
+    /// Nome do campo, usado no checklist.
+    pub name: String,
+    /// Posições ignoradas.
+    pub pos: FieldPos,
+    /// Restringe a regra aos registros com `tag` em `tag_pos` (ex: só o header de arquivo).
+    pub record: Option<(FieldPos, String)>,
+}
+
+impl Tolerance {
+    /// Verifica se a regra vale para o registro.
+    fn applies_to(&self, record: &[u8]) -> bool {
+        match &self.record {
+            None => true,
+            Some((tag_pos, tag)) => record.get(tag_pos.as_range()) == Some(tag.as_bytes()),
+        }
+    }
+}
+
+/// Conjunto de campos com variação permitida.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToleranceRules {
+    rules: Vec<Tolerance>,
+}
+
+impl ToleranceRules {
+    /// Nenhuma variação permitida: os arquivos devem ser idênticos.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignora `pos` em todos os registros (ex: sequencial do registro).
+    pub fn ignore(mut self, name: impl Into<String>, pos: FieldPos) -> Self {
+        self.rules.push(Tolerance { name: name.into(), pos, record: None });
+        self
+    }
+
+    /// Ignora `pos` apenas nos registros com `tag` em `tag_pos` (ex: data de geração
+    /// do header de arquivo, tipo `0` na posição 8 do CNAB 240).
+    pub fn ignore_in(
+        mut self,
+        tag_pos: FieldPos,
+        tag: impl Into<String>,
+        name: impl Into<String>,
+        pos: FieldPos,
+    ) -> Self {
+        self.rules.push(Tolerance { name: name.into(), pos, record: Some((tag_pos, tag.into())) });
+        self
+    }
+
+    /// Regras declaradas, na ordem.
+    pub fn rules(&self) -> &[Tolerance] {
+        &self.rules
+    }
+}
+
+/// Divergência entre um registro gerado e o do exemplo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// Quantidade de registros diferente.
+    RecordCount { expected: usize, found: usize },
+    /// Registro com tamanho diferente do exemplo.
+    RecordLength { line: usize, expected: usize, found: usize },
+    /// Trecho com conteúdo diferente (posições contíguas fora das tolerâncias).
+    Content { line: usize, pos: FieldPos, expected: String, found: String },
+}
+
+/// Resultado da comparação.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HomologationReport {
+    /// Registros comparados (o menor dos dois arquivos).
+    pub records: usize,
+    /// Divergências, na ordem do arquivo.
+    pub findings: Vec<Finding>,
+    /// Quantas vezes cada tolerância foi usada (na ordem de `ToleranceRules::rules`),
+    /// contando só os registros em que o campo de fato diferia.
+    pub tolerated: Vec<(String, usize)>,
+}
+
+impl HomologationReport {
+    /// A remessa confere com o exemplo, fora os campos tolerados.
+    pub fn is_approved(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Checklist legível, um item por linha (`[OK]`, `[FALHA]` ou `[TOLERADO]`).
+    pub fn checklist(&self) -> String {
+        let mut out = String::new();
+        let count = self.findings.iter().find(|f| matches!(f, Finding::RecordCount { .. }));
+        match count {
+            Some(Finding::RecordCount { expected, found }) => {
+                let _ = writeln!(out, "[FALHA] Quantidade de registros: esperado {expected}, gerado {found}");
+            }
+            _ => {
+                let _ = writeln!(out, "[OK] Quantidade de registros: {}", self.records);
+            }
+        }
+        if !self.findings.iter().any(|f| matches!(f, Finding::RecordLength { .. })) {
+            let _ = writeln!(out, "[OK] Tamanho dos registros");
+        }
+        if !self.findings.iter().any(|f| matches!(f, Finding::Content { .. })) {
+            let _ = writeln!(out, "[OK] Conteúdo dos registros");
+        }
+        for finding in &self.findings {
+            match finding {
+                Finding::RecordCount { .. } => {}
+                Finding::RecordLength { line, expected, found } => {
+                    let _ = writeln!(out, "[FALHA] Registro {line}: tamanho {found}, esperado {expected}");
+                }
+                Finding::Content { line, pos, expected, found } => {
+                    let _ = writeln!(
+                        out,
+                        "[FALHA] Registro {line}, posições {}..{}: esperado '{expected}', gerado '{found}'",
+                        pos.start(),
+                        pos.end()
+                    );
+                }
+            }
+        }
+        for (name, count) in self.tolerated.iter().filter(|(_, count)| *count > 0) {
+            let _ = writeln!(out, "[TOLERADO] {name}: diferente em {count} registro(s)");
+        }
+        out
+    }
+}
+
+/// Compara a remessa gerada com o arquivo de exemplo do banco, registro a registro
+/// (uma linha por registro, `\r\n` ou `\n`).
+///
+/// Bytes diferentes fora das tolerâncias são agrupados em trechos contíguos; os
+/// textos do checklist são lidos como Latin-1.
+pub fn compare(generated: &[u8], sample: &[u8], rules: &ToleranceRules) -> HomologationReport {
+    let generated = records(generated);
+    let sample = records(sample);
+    let mut report = HomologationReport {
+        records: generated.len().min(sample.len()),
+        findings: Vec::new(),
+        tolerated: rules.rules.iter().map(|rule| (rule.name.clone(), 0)).collect(),
+    };
+    if generated.len() != sample.len() {
+        report.findings.push(Finding::RecordCount { expected: sample.len(), found: generated.len() });
+    }
+
+    for (index, (found, expected)) in generated.iter().zip(&sample).enumerate() {
+        let line = index + 1;
+        if found.len() != expected.len() {
+            report.findings.push(Finding::RecordLength { line, expected: expected.len(), found: found.len() });
+            continue;
+        }
+        let active: Vec<_> = rules.rules.iter().enumerate().filter(|(_, rule)| rule.applies_to(expected)).collect();
+        let mut tolerated = vec![false; rules.rules.len()];
+        let mut start = None;
+        // Uma posição além do fim fecha o último trecho divergente
+        for i in 0..=found.len() {
+            let differs = i < found.len() && found[i] != expected[i] && {
+                let rule = active.iter().find(|(_, rule)| rule.pos.as_range().contains(&i));
+                if let Some((rule_index, _)) = rule {
+                    tolerated[*rule_index] = true;
+                }
+                rule.is_none()
+            };
+            match (differs, start) {
+                (true, None) => start = Some(i),
+                (false, Some(first)) => {
+                    start = None;
+                    report.findings.push(Finding::Content {
+                        line,
+                        pos: FieldPos::new_const(first + 1, i),
+                        expected: latin1(&expected[first..i]),
+                        found: latin1(&found[first..i]),
+                    });
+                }
+                _ => {}
+            }
+        }
+        for (count, used) in report.tolerated.iter_mut().zip(tolerated) {
+            count.1 += usize::from(used);
+        }
+    }
+    report
+}
+
+/// Registros de um arquivo em linhas, sem o fim de linha (e sem a linha vazia final).
+fn records(file: &[u8]) -> Vec<&[u8]> {
+    let mut records: Vec<_> = file.split(|&b| b == b'\n').map(crate::trim_line_ending).collect();
+    if records.last().is_some_and(|last| last.is_empty()) {
+        records.pop();
+    }
+    records
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_ignoring_tolerated_fields() {
+        let sample = b"0 BANCO 20240101 000001\r\n1 TITULO 0000010000 000002\r\n9 000003\r\n";
+        let generated = b"0 BANCO 20261016 000001\n1 TITULO 0000012000 000002\n9 000003\n";
+        let header = FieldPos::new_const(1, 1);
+        let rules = ToleranceRules::new()
+            .ignore_in(header, "0", "data_geracao", FieldPos::new_const(9, 16))
+            .ignore("sequencial", FieldPos::new_const(18, 23));
+
+        let report = compare(generated, sample, &rules);
+        assert!(!report.is_approved());
+        assert_eq!(
+            report.findings,
+            vec![Finding::Content {
+                line: 2,
+                pos: FieldPos::new_const(16, 16),
+                expected: "0".to_string(),
+                found: "2".to_string(),
+            }]
+        );
+        assert_eq!(report.tolerated, vec![("data_geracao".to_string(), 1), ("sequencial".to_string(), 0)]);
+
+        let checklist = report.checklist();
+        assert!(checklist.contains("[OK] Quantidade de registros: 3"));
+        assert!(checklist.contains("[FALHA] Registro 2, posições 16..16: esperado '0', gerado '2'"));
+        assert!(checklist.contains("[TOLERADO] data_geracao: diferente em 1 registro(s)"));
+
+        let report = compare(&sample[..sample.len() - 10], sample, &rules);
+        assert_eq!(report.findings, vec![Finding::RecordCount { expected: 3, found: 2 }]);
+    }
+}
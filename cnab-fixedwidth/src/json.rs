@@ -0,0 +1,116 @@
+//! Serialização JSON mínima dos valores parseados, sem dependências externas.
+//!
+//! Decimais são escritos a partir do inteiro bruto + escala, sem passar por `f64`,
+//! para que `Decimal { raw: 1234, scale: 2 }` vire exatamente `12.34`.
+
+use crate::{DynRecord, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Saída JSON canônica, estável entre execuções, para testes de snapshot (golden files).
+///
+/// - Chaves em ordem alfabética (independente da ordem do `HashMap`);
+/// - Decimais escritos com exatamente `scale` casas, sem passar por `f64`;
+/// - Sem espaços entre os tokens.
+pub trait CanonicalJson {
+    /// Gera o JSON canônico do registro.
+    fn to_canonical_json(&self) -> String;
+}
+
+impl CanonicalJson for HashMap<&'static str, Value> {
+    fn to_canonical_json(&self) -> String {
+        write_sorted(self.iter().map(|(name, value)| (*name, value)))
+    }
+}
+
+impl CanonicalJson for DynRecord {
+    fn to_canonical_json(&self) -> String {
+        write_sorted(self.iter())
+    }
+}
+
+/// Escreve o objeto com as chaves em ordem alfabética.
+fn write_sorted<'a>(entries: impl Iterator<Item = (&'static str, &'a Value)>) -> String {
+    let mut entries: Vec<_> = entries.collect();
+    entries.sort_unstable_by_key(|(name, _)| *name);
+
+    let mut out = String::from("{");
+    for (i, (name, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_str(&mut out, name);
+        out.push(':');
+        write_value(&mut out, value);
+    }
+    out.push('}');
+    out
+}
+
+/// Escreve uma string JSON (com aspas e escapes).
+pub(crate) fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Escreve um `Value` como valor JSON.
+pub(crate) fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Alpha(s) => write_str(out, s),
+        Value::Numeric(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Decimal { raw, scale } => write_decimal(out, *raw, *scale),
+        Value::Null => out.push_str("null"),
+    }
+}
+
+/// Escreve um decimal implícito com exatamente `scale` casas.
+pub(crate) fn write_decimal(out: &mut String, raw: i64, scale: u8) {
+    if scale == 0 {
+        let _ = write!(out, "{raw}");
+        return;
+    }
+    let sign = if raw < 0 { "-" } else { "" };
+    let digits = raw.unsigned_abs().to_string();
+    let scale = scale as usize;
+    let padded = format!("{digits:0>width$}", width = scale + 1);
+    let (int, frac) = padded.split_at(padded.len() - scale);
+    let _ = write!(out, "{sign}{int}.{frac}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_json_is_sorted_and_exact() {
+        let record: HashMap<&'static str, Value> = [
+            ("valor", Value::Decimal { raw: 1050, scale: 2 }),
+            ("banco", Value::Numeric(341)),
+            ("juros", Value::Decimal { raw: -7, scale: 3 }),
+            ("nome", Value::Alpha("JOSÉ \"ZÉ\"".to_string())),
+            ("desconto", Value::Null),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            record.to_canonical_json(),
+            r#"{"banco":341,"desconto":null,"juros":-0.007,"nome":"JOSÉ \"ZÉ\"","valor":10.50}"#
+        );
+    }
+}
@@ -0,0 +1,521 @@
+//! # Layouts Dinâmicos
+//!
+//! Nem todo layout é conhecido em tempo de compilação: bancos e versões de layout
+//! podem vir de configuração. Este módulo monta a lista de `FieldSpec` em tempo de
+//! execução, com as mesmas validações que a macro derive faz em compilação
+//! (posições válidas, sobreposição e tamanho do registro), e devolve [`DynRecord`]s
+//! com getters tipados.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{FieldKind, LayoutBuilder};
+//!
+//! # let line = "";
+//! let layout = LayoutBuilder::new()
+//!     .width(240)
+//!     .field("codigo_banco", 1, 3, FieldKind::Numeric)
+//!     .field("nome_empresa", 73, 102, FieldKind::Alpha)
+//!     .build()?;
+//!
+//! let record = layout.parse(line)?;
+//! let banco = record.get_i64("codigo_banco");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Com as features `yaml`, `toml` ou `json`, layouts também podem ser carregados de
+//! arquivos de definição mantidos como dados: `from_yaml`, `from_toml` e `from_json`.
+
+#[cfg(any(feature = "yaml", feature = "toml", feature = "json"))]
+mod schema;
+
+#[cfg(feature = "json")]
+pub use schema::from_json;
+#[cfg(feature = "toml")]
+pub use schema::from_toml;
+#[cfg(feature = "yaml")]
+pub use schema::from_yaml;
+
+use crate::converter::{Converted, ConverterRegistry};
+use crate::writer::{self, WriteOptions};
+use crate::{parse_line_bytes_with, FieldKind, FieldPos, FieldSpec, FixedWidthError, ParseOptions, Result, Sign, Value};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+use thiserror::Error;
+
+/// Maior escala aceita em campos decimais: o limite de `rust_decimal::Decimal`.
+pub const MAX_SCALE: u8 = 28;
+
+/// Erros na definição de um layout.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum LayoutError {
+    /// Posição inválida em `FieldPos::new`: `start` deve ser >= 1 e `end` >= `start`.
+    #[error("posição inválida {start}..{end} (start deve ser >= 1 e end >= start)")]
+    InvalidRange { start: usize, end: usize },
+
+    /// Posição inválida: `start` deve ser >= 1 e `end` >= `start`.
+    #[error("campo '{field}': posição inválida {start}..{end} (start deve ser >= 1 e end >= start)")]
+    InvalidPos { field: String, start: usize, end: usize },
+
+    /// Dois campos ocupam as mesmas posições.
+    #[error("campos '{first}' e '{second}' se sobrepõem nas posições {start}..{end}")]
+    Overlap { first: String, second: String, start: usize, end: usize },
+
+    /// O campo passa do tamanho declarado para o registro.
+    #[error("campo '{field}' termina na posição {end}, mas o registro tem {width} posições")]
+    ExceedsWidth { field: String, end: usize, width: usize },
+
+    /// Dois campos com o mesmo nome.
+    #[error("campo '{field}' declarado mais de uma vez")]
+    DuplicateField { field: String },
+
+    /// Escala decimal acima de [`MAX_SCALE`].
+    #[error("campo '{field}': escala {scale} acima do máximo de {MAX_SCALE} casas")]
+    InvalidScale { field: String, scale: u8 },
+
+    /// Arquivo de definição de layout malformado (YAML/TOML/JSON).
+    #[error("definição de layout inválida: {message}")]
+    Schema { message: String },
+}
+
+/// Interna o nome de um campo definido em tempo de execução.
+///
+/// `FieldSpec::name` é `&'static str`; nomes vindos de configuração são copiados
+/// uma única vez para uma tabela global e vivem até o fim do processo. Como layouts
+/// são carregados na inicialização, a memória usada é limitada ao conjunto de nomes distintos.
+pub(crate) fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = names.get(name) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(name.to_owned().into_boxed_str());
+    names.insert(leaked);
+    leaked
+}
+
+/// Construtor de layouts em tempo de execução.
+///
+/// Erros de posição são guardados e reportados em [`build`](LayoutBuilder::build),
+/// para manter a API encadeável.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutBuilder {
+    fields: Vec<FieldSpec>,
+    width: Option<usize>,
+    converters: Vec<(&'static str, String)>,
+    error: Option<LayoutError>,
+}
+
+impl LayoutBuilder {
+    /// Cria um construtor vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declara o tamanho do registro (ex: 240). Campos além dele são rejeitados em `build`.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Adiciona um campo com posição CNAB (1-based, inclusiva).
+    pub fn field(self, name: &str, start: usize, end: usize, kind: FieldKind) -> Self {
+        self.add(name, start, end, kind, false)
+    }
+
+    /// Adiciona um campo opcional (branco vira `Value::Null`).
+    pub fn optional_field(self, name: &str, start: usize, end: usize, kind: FieldKind) -> Self {
+        self.add(name, start, end, kind, true)
+    }
+
+    fn add(mut self, name: &str, start: usize, end: usize, kind: FieldKind, optional: bool) -> Self {
+        match FieldPos::new(start, end) {
+            Ok(pos) => {
+                let spec = FieldSpec::new(intern(name), pos, kind);
+                self.spec(if optional { spec.optional() } else { spec })
+            }
+            Err(_) => {
+                let error = LayoutError::InvalidPos { field: name.to_string(), start, end };
+                self.error.get_or_insert(error);
+                self
+            }
+        }
+    }
+
+    /// Associa ao campo a tag de um conversor do [`ConverterRegistry`] (ex: "data_ddmmaaaa").
+    pub fn convert(mut self, field: &str, tag: &str) -> Self {
+        self.converters.push((intern(field), tag.to_string()));
+        self
+    }
+
+    /// Adiciona uma especificação já montada.
+    pub fn spec(mut self, spec: FieldSpec) -> Self {
+        self.fields.push(spec);
+        self
+    }
+
+    /// Valida e constrói o layout.
+    pub fn build(self) -> std::result::Result<Layout, LayoutError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        validate(&self.fields, self.width)?;
+        if let Some((field, _)) = self.converters.iter().find(|(f, _)| !self.fields.iter().any(|s| s.name == *f)) {
+            return Err(LayoutError::Schema { message: format!("conversor para campo inexistente '{field}'") });
+        }
+        Ok(Layout { fields: self.fields, width: self.width, converters: self.converters })
+    }
+}
+
+/// Valida nomes duplicados, sobreposição e tamanho do registro.
+/// (Posições já são válidas por construção de `FieldPos`.)
+fn validate(fields: &[FieldSpec], width: Option<usize>) -> std::result::Result<(), LayoutError> {
+    for (i, field) in fields.iter().enumerate() {
+        let (start, end) = (field.pos.start(), field.pos.end());
+        if let FieldKind::Decimal { scale } | FieldKind::Zoned { scale } | FieldKind::Packed { scale } = field.kind
+            && scale > MAX_SCALE
+        {
+            return Err(LayoutError::InvalidScale { field: field.name.to_string(), scale });
+        }
+        if let Some(width) = width
+            && end > width
+        {
+            return Err(LayoutError::ExceedsWidth { field: field.name.to_string(), end, width });
+        }
+
+        for other in &fields[..i] {
+            if other.name == field.name {
+                return Err(LayoutError::DuplicateField { field: field.name.to_string() });
+            }
+            let overlap_start = start.max(other.pos.start());
+            let overlap_end = end.min(other.pos.end());
+            if overlap_start <= overlap_end {
+                return Err(LayoutError::Overlap {
+                    first: other.name.to_string(),
+                    second: field.name.to_string(),
+                    start: overlap_start,
+                    end: overlap_end,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Layout validado, montado em tempo de execução.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    fields: Vec<FieldSpec>,
+    width: Option<usize>,
+    converters: Vec<(&'static str, String)>,
+}
+
+impl Layout {
+    /// Campos do layout, na ordem de declaração.
+    pub fn fields(&self) -> &[FieldSpec] {
+        &self.fields
+    }
+
+    /// Tags de conversão por campo (`LayoutBuilder::convert`), como `(campo, tag)`.
+    pub fn converters(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.converters.iter().map(|(field, tag)| (*field, tag.as_str()))
+    }
+
+    /// Tamanho declarado do registro, se houver.
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+
+    /// Consome o layout devolvendo os campos (ex: para um `RecordLayout`).
+    pub fn into_fields(self) -> Vec<FieldSpec> {
+        self.fields
+    }
+
+    /// Faz o parse de uma linha já decodificada (UTF-8).
+    pub fn parse(&self, line: &str) -> Result<DynRecord> {
+        self.parse_bytes(line.as_bytes(), &ParseOptions::with_encoding(crate::Encoding::Utf8))
+    }
+
+    /// Faz o parse de uma linha em bytes brutos.
+    ///
+    /// Aplica os conversores do registro global ([`ConverterRegistry::install_global`]), se houver.
+    pub fn parse_bytes(&self, line: &[u8], options: &ParseOptions) -> Result<DynRecord> {
+        match ConverterRegistry::global() {
+            Some(converters) => self.parse_bytes_with_converters(line, options, converters),
+            None => self.parse_values(line, options),
+        }
+    }
+
+    /// Faz o parse aplicando os conversores informados aos campos com conversor
+    /// (por nome de campo ou pela tag associada no layout).
+    ///
+    /// Campos opcionais em branco (`Value::Null`) não são convertidos.
+    pub fn parse_bytes_with_converters(
+        &self,
+        line: &[u8],
+        options: &ParseOptions,
+        converters: &ConverterRegistry,
+    ) -> Result<DynRecord> {
+        let mut record = self.parse_values(line, options)?;
+        for (name, value) in &record.values {
+            if value.is_null() {
+                continue;
+            }
+            let tag = self.converters.iter().find(|(f, _)| f == name).map(|(_, t)| t.as_str());
+            if let Some(tag) = tag
+                && !converters.has_tag(tag)
+                && converters.convert(name, None, value).is_none()
+            {
+                return Err(FixedWidthError::Conversion {
+                    field: name,
+                    message: format!("conversor '{tag}' não registrado"),
+                });
+            }
+            if let Some(converted) = converters.convert(name, tag, value) {
+                let converted = converted.map_err(|message| FixedWidthError::Conversion { field: name, message })?;
+                record.converted.push((name, converted));
+            }
+        }
+        Ok(record)
+    }
+
+    fn parse_values(&self, line: &[u8], options: &ParseOptions) -> Result<DynRecord> {
+        parse_line_bytes_with(line, &self.fields, options)
+    }
+
+    /// Escreve um registro com os valores de `record`, buscados pelo nome do campo.
+    ///
+    /// Campos ausentes ficam em branco, assim como fillers e posições não mapeadas.
+    pub fn to_bytes(&self, record: &DynRecord, options: &WriteOptions) -> Result<Vec<u8>> {
+        let values: Vec<_> = self.fields.iter().map(|f| record.get(f.name).cloned().unwrap_or(Value::Null)).collect();
+        writer::write_line(&self.fields, &values, self.record_width(), options)
+    }
+
+    /// Faixas do registro não cobertas por nenhum campo (veja [`crate::spec_gaps`]).
+    pub fn spec_gaps(&self) -> Vec<FieldPos> {
+        crate::spec_gaps(self.mapped(), self.record_width())
+    }
+
+    /// Posições declaradas: campos e indicadores de sinal.
+    fn mapped(&self) -> impl Iterator<Item = FieldPos> + '_ {
+        self.fields.iter().flat_map(|f| match f.sign {
+            Sign::Indicator { pos, .. } => vec![f.pos, pos],
+            _ => vec![f.pos],
+        })
+    }
+
+    /// Tamanho declarado ou, sem ele, a última posição usada.
+    fn record_width(&self) -> usize {
+        self.width.unwrap_or_else(|| self.mapped().map(|pos| pos.end()).max().unwrap_or(0))
+    }
+}
+
+/// Registro parseado a partir de uma lista de campos, com os valores na ordem do layout.
+///
+/// É o retorno de [`parse_line`](crate::parse_line) e demais funções de parse sem struct:
+/// a iteração segue sempre a ordem dos campos, para exportações e testes de snapshot
+/// estáveis. Use [`into_map`](Self::into_map) quando precisar de um `HashMap`.
+///
+/// A igualdade compara apenas os valores parseados, não os convertidos.
+#[derive(Clone)]
+pub struct DynRecord {
+    values: Vec<(&'static str, Value)>,
+    converted: Vec<(&'static str, Converted)>,
+}
+
+impl std::fmt::Debug for DynRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynRecord")
+            .field("values", &self.values)
+            .field("converted", &self.converted.iter().map(|(n, _)| n).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl PartialEq for DynRecord {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl DynRecord {
+    /// Registro com os valores informados, na ordem em que aparecem.
+    pub(crate) fn from_values(values: Vec<(&'static str, Value)>) -> Self {
+        Self { values, converted: Vec::new() }
+    }
+
+    /// Converte em um `HashMap` (sem ordem definida de iteração).
+    pub fn into_map(self) -> HashMap<&'static str, Value> {
+        self.values.into_iter().collect()
+    }
+
+    /// Valor convertido de um campo (veja [`ConverterRegistry`]), se o tipo for `T`.
+    pub fn get_as<T: std::any::Any>(&self, name: &str) -> Option<&T> {
+        self.converted
+            .iter()
+            .find(|(n, _)| *n == name)
+            .and_then(|(_, v)| v.downcast_ref::<T>())
+    }
+
+    /// Valor bruto de um campo.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|(n, _)| *n == name).map(|(_, v)| v)
+    }
+
+    /// Valor de um campo `Numeric`.
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get(name)?.as_i64()
+    }
+
+    /// Valor de um campo `Alpha`.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name)?.as_str()
+    }
+
+    /// Valor de um campo `Decimal` convertido para `f64`.
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get(name)?.as_f64()
+    }
+
+    /// Valor de um campo `Decimal`, sem perdas.
+    #[cfg(feature = "rust_decimal")]
+    pub fn get_decimal(&self, name: &str) -> Option<rust_decimal::Decimal> {
+        self.get(name)?.as_decimal()
+    }
+
+    /// Itera sobre `(nome, valor)` na ordem do layout.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Value)> {
+        self.values.iter().map(|(n, v)| (*n, v))
+    }
+
+    /// Quantidade de campos com valor.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Indica se o registro não tem campos.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Serializa os valores como um objeto JSON, na ordem do layout.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{");
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            crate::json::write_str(&mut out, name);
+            out.push(':');
+            crate::json::write_value(&mut out, value);
+        }
+        out.push('}');
+        out
+    }
+}
+
+impl std::ops::Index<&str> for DynRecord {
+    type Output = Value;
+
+    /// Valor de um campo. Entra em pânico se o campo não existir, como `HashMap`.
+    fn index(&self, name: &str) -> &Value {
+        self.get(name).unwrap_or_else(|| panic!("campo '{name}' não existe no registro"))
+    }
+}
+
+impl FromIterator<(&'static str, Value)> for DynRecord {
+    fn from_iter<I: IntoIterator<Item = (&'static str, Value)>>(iter: I) -> Self {
+        Self::from_values(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for DynRecord {
+    type Item = (&'static str, Value);
+    type IntoIter = std::vec::IntoIter<(&'static str, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_validates_and_parses() {
+        let layout = LayoutBuilder::new()
+            .width(15)
+            .field("banco", 1, 3, FieldKind::Numeric)
+            .field("nome", 4, 10, FieldKind::Alpha)
+            .optional_field("valor", 11, 15, FieldKind::Decimal { scale: 2 })
+            .build()
+            .unwrap();
+
+        let record = layout.parse("341ACME   01050").unwrap();
+        assert_eq!(record.get_i64("banco"), Some(341));
+        assert_eq!(record.get_str("nome"), Some("ACME"));
+        assert_eq!(record.get_f64("valor"), Some(10.5));
+        assert_eq!(record.get_i64("nome"), None);
+        assert_eq!(record.iter().map(|(n, _)| n).collect::<Vec<_>>(), ["banco", "nome", "valor"]);
+
+        let overlap = LayoutBuilder::new()
+            .field("a", 1, 5, FieldKind::Alpha)
+            .field("b", 5, 8, FieldKind::Alpha)
+            .build();
+        assert_eq!(
+            overlap.unwrap_err(),
+            LayoutError::Overlap { first: "a".into(), second: "b".into(), start: 5, end: 5 }
+        );
+
+        let zero = LayoutBuilder::new().field("a", 0, 5, FieldKind::Alpha).build();
+        assert!(matches!(zero, Err(LayoutError::InvalidPos { .. })));
+
+        let too_wide = LayoutBuilder::new().width(10).field("a", 5, 11, FieldKind::Alpha).build();
+        assert!(matches!(too_wide, Err(LayoutError::ExceedsWidth { end: 11, width: 10, .. })));
+
+        let scale = LayoutBuilder::new().field("a", 1, 5, FieldKind::Zoned { scale: 29 }).build();
+        assert!(matches!(scale, Err(LayoutError::InvalidScale { scale: 29, .. })));
+    }
+
+    #[test]
+    fn converters_by_tag_and_field_name() {
+        use std::time::Duration;
+
+        let layout = LayoutBuilder::new()
+            .field("vencimento", 1, 8, FieldKind::Numeric)
+            .field("prazo", 9, 11, FieldKind::Numeric)
+            .optional_field("desconto_ate", 12, 19, FieldKind::Numeric)
+            .convert("vencimento", "data_ddmmaaaa")
+            .convert("desconto_ate", "data_ddmmaaaa")
+            .build()
+            .unwrap();
+
+        let mut converters = ConverterRegistry::new();
+        converters
+            .register_tag("data_ddmmaaaa", |v: &Value| {
+                let n = v.as_i64().ok_or("esperado numérico")?;
+                let (dia, mes, ano) = (n / 1_000_000, n / 10_000 % 100, n % 10_000);
+                if !(1..=31).contains(&dia) || !(1..=12).contains(&mes) {
+                    return Err(format!("data inválida: {n:08}"));
+                }
+                Ok((ano as u16, mes as u8, dia as u8))
+            })
+            .register_field("prazo", |v: &Value| {
+                let dias = v.as_i64().ok_or("esperado numérico")?;
+                Ok(Duration::from_secs(dias as u64 * 86_400))
+            });
+
+        let options = ParseOptions::default();
+        let record = layout
+            .parse_bytes_with_converters(b"31122024030        ", &options, &converters)
+            .unwrap();
+        assert_eq!(record.get_as::<(u16, u8, u8)>("vencimento"), Some(&(2024, 12, 31)));
+        assert_eq!(record.get_as::<Duration>("prazo"), Some(&Duration::from_secs(30 * 86_400)));
+        assert_eq!(record.get_as::<(u16, u8, u8)>("desconto_ate"), None);
+        assert_eq!(record.get_i64("vencimento"), Some(31122024));
+
+        let invalid = layout.parse_bytes_with_converters(b"32122024030        ", &options, &converters);
+        assert!(matches!(invalid, Err(FixedWidthError::Conversion { field: "vencimento", .. })));
+    }
+}
@@ -0,0 +1,178 @@
+//! Arquivos de definição de layout (YAML, TOML ou JSON).
+//!
+//! Formato (YAML):
+//!
+//! ```yaml
+//! width: 240
+//! fields:
+//!   - { name: codigo_banco, pos: "1..3", kind: numeric }
+//!   - { name: nome_empresa, pos: "73..102", kind: alpha }
+//!   - { name: valor, pos: "120..134", kind: decimal, scale: 2 }
+//!   - { name: desconto, pos: "135..149", kind: decimal, scale: 2, optional: true }
+//!   - { name: vencimento, pos: "78..85", kind: numeric, convert: data_ddmmaaaa }
+//!   - { name: uso_febraban, pos: "230..*", kind: filler }
+//! ```
+//!
+//! `convert` referencia uma tag registrada no [`ConverterRegistry`](crate::converter::ConverterRegistry).
+//!
+//! As posições usam a mesma sintaxe da macro derive (1-based, inclusiva; `*` = fim do registro).
+
+use super::{Layout, LayoutBuilder, LayoutError};
+use crate::FieldKind;
+use serde::Deserialize;
+
+/// Definição de layout como lida do arquivo.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutDef {
+    width: Option<usize>,
+    fields: Vec<FieldDef>,
+}
+
+/// Definição de um campo como lida do arquivo.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldDef {
+    name: String,
+    pos: String,
+    kind: KindDef,
+    scale: Option<u8>,
+    #[serde(default)]
+    optional: bool,
+    /// Tag de um conversor do `ConverterRegistry` (ex: "data_ddmmaaaa").
+    convert: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum KindDef {
+    Alpha,
+    Numeric,
+    Decimal,
+    Zoned,
+    Packed,
+    Filler,
+}
+
+/// Converte a definição lida em um layout validado.
+fn build(def: LayoutDef) -> Result<Layout, LayoutError> {
+    let mut builder = LayoutBuilder::new();
+    if let Some(width) = def.width {
+        builder = builder.width(width);
+    }
+
+    for field in def.fields {
+        let (start, end) = parse_pos(&field.name, &field.pos, def.width)?;
+        let kind = match (field.kind, field.scale) {
+            (KindDef::Decimal, Some(scale)) => FieldKind::Decimal { scale },
+            (KindDef::Decimal, None) => return Err(schema_error(&field.name, "decimal exige scale")),
+            (KindDef::Zoned, Some(scale)) => FieldKind::Zoned { scale },
+            (KindDef::Zoned, None) => return Err(schema_error(&field.name, "zoned exige scale")),
+            (KindDef::Packed, scale) => FieldKind::Packed { scale: scale.unwrap_or(0) },
+            (_, Some(_)) => return Err(schema_error(&field.name, "scale só se aplica a decimal, zoned e packed")),
+            (KindDef::Alpha, None) => FieldKind::Alpha,
+            (KindDef::Numeric, None) => FieldKind::Numeric,
+            (KindDef::Filler, None) => FieldKind::Filler,
+        };
+        builder = if field.optional {
+            builder.optional_field(&field.name, start, end, kind)
+        } else {
+            builder.field(&field.name, start, end, kind)
+        };
+        if let Some(tag) = &field.convert {
+            builder = builder.convert(&field.name, tag);
+        }
+    }
+
+    builder.build()
+}
+
+/// Interpreta "start..end" (ou "start..*", resolvido contra `width`).
+fn parse_pos(field: &str, pos: &str, width: Option<usize>) -> Result<(usize, usize), LayoutError> {
+    let invalid = || schema_error(field, &format!("pos '{pos}' deve estar no formato start..end"));
+    let (start, end) = pos.split_once("..").ok_or_else(invalid)?;
+    let start = start.trim().parse().map_err(|_| invalid())?;
+    let end = match end.trim() {
+        "*" => width.ok_or_else(|| schema_error(field, "pos aberto (start..*) exige width no layout"))?,
+        end => end.parse().map_err(|_| invalid())?,
+    };
+    Ok((start, end))
+}
+
+fn schema_error(field: &str, message: &str) -> LayoutError {
+    LayoutError::Schema { message: format!("campo '{field}': {message}") }
+}
+
+/// Carrega um layout de um documento YAML.
+#[cfg(feature = "yaml")]
+pub fn from_yaml(source: &str) -> Result<Layout, LayoutError> {
+    let def = serde_yaml::from_str(source).map_err(|e| LayoutError::Schema { message: e.to_string() })?;
+    build(def)
+}
+
+/// Carrega um layout de um documento TOML (campos em `[[fields]]`).
+#[cfg(feature = "toml")]
+pub fn from_toml(source: &str) -> Result<Layout, LayoutError> {
+    let def = toml::from_str(source).map_err(|e| LayoutError::Schema { message: e.to_string() })?;
+    build(def)
+}
+
+/// Carrega um layout de um documento JSON.
+#[cfg(feature = "json")]
+pub fn from_json(source: &str) -> Result<Layout, LayoutError> {
+    let def = serde_json::from_str(source).map_err(|e| LayoutError::Schema { message: e.to_string() })?;
+    build(def)
+}
+
+#[cfg(all(test, feature = "yaml", feature = "toml"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_and_toml_describe_the_same_layout() {
+        let yaml = r#"
+width: 20
+fields:
+  - { name: banco, pos: "1..3", kind: numeric }
+  - { name: nome, pos: "4..10", kind: alpha }
+  - { name: valor, pos: "11..15", kind: decimal, scale: 2, optional: true }
+  - { name: uso_febraban, pos: "16..*", kind: filler }
+"#;
+        let toml = r#"
+width = 20
+
+[[fields]]
+name = "banco"
+pos = "1..3"
+kind = "numeric"
+
+[[fields]]
+name = "nome"
+pos = "4..10"
+kind = "alpha"
+
+[[fields]]
+name = "valor"
+pos = "11..15"
+kind = "decimal"
+scale = 2
+optional = true
+
+[[fields]]
+name = "uso_febraban"
+pos = "16..*"
+kind = "filler"
+"#;
+        let line = "341ACME        FEBRA";
+        for layout in [from_yaml(yaml).unwrap(), from_toml(toml).unwrap()] {
+            assert_eq!(layout.fields().len(), 4);
+            assert_eq!(layout.fields()[3].pos.end(), 20);
+            let record = layout.parse(line).unwrap();
+            assert_eq!(record.get_i64("banco"), Some(341));
+            assert!(record.get("valor").unwrap().is_null());
+        }
+
+        let bad = from_yaml("fields:\n  - { name: a, pos: \"1..3\", kind: decimal }\n");
+        assert!(matches!(bad, Err(LayoutError::Schema { .. })));
+    }
+}
@@ -0,0 +1,19 @@
+//! # Layouts prontos
+//!
+//! Structs tipadas dos layouts padrão, para não redigitar o manual em cada projeto. Cada
+//! layout fica atrás da sua feature:
+//! - `layouts-240`: cobrança (`cobranca240`), pagamentos (`pagamento240`) e extrato
+//!   (`extrato240`) FEBRABAN 240.
+//! - `layouts-400`: cobrança CNAB 400, remessa e retorno (`cobranca400`).
+//! - `layouts-150`: débito automático FEBRABAN 150 (`debito150`).
+
+#[cfg(feature = "layouts-240")]
+pub mod cobranca240;
+#[cfg(feature = "layouts-400")]
+pub mod cobranca400;
+#[cfg(feature = "layouts-150")]
+pub mod debito150;
+#[cfg(feature = "layouts-240")]
+pub mod extrato240;
+#[cfg(feature = "layouts-240")]
+pub mod pagamento240;
@@ -0,0 +1,366 @@
+//! # FEBRABAN 240 — Cobrança
+//!
+//! Todos os registros do layout de cobrança FEBRABAN 240 (v10): headers e trailers de
+//! arquivo e de lote, os segmentos da remessa (P, Q e R) e os do retorno (T e U). Os
+//! registros comuns aos dois sentidos são os de [`crate::cnab240`].
+//!
+//! ```no_run
+//! use cnab_fixedwidth::layouts::cobranca240::SegmentoP;
+//! use cnab_fixedwidth::FixedWidthWrite;
+//!
+//! let p = SegmentoP {
+//!     banco: 341,
+//!     lote: 1,
+//!     tipo_registro: 3,
+//!     sequencial: 1,
+//!     segmento: "P".into(),
+//!     nosso_numero: "12345".into(),
+//!     valor_titulo: 10_000,
+//!     ..Default::default()
+//! };
+//! let line = p.to_bytes()?;
+//! # Ok::<(), cnab_fixedwidth::FixedWidthError>(())
+//! ```
+//!
+//! Assim como no retorno, valores monetários são centavos e datas `DDMMAAAA`; campos
+//! com mais de uma interpretação (valor ou percentual) seguem o código que os precede.
+
+use crate::cnab240::Detalhe;
+use crate::registry::{LayoutRegistry, RecordLayout};
+use crate::segments::SegmentGroup;
+use crate::{FieldPos, FixedWidth, Result};
+
+pub use crate::cnab240::{HeaderArquivo, HeaderLote, SegmentoT, SegmentoU, TrailerArquivo, TrailerLote};
+
+/// Segmento P da remessa: dados do título (obrigatório).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoP {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", filler)]
+    pub uso_febraban: (),
+    /// Código de movimento da remessa (ex: 01 = entrada de títulos, 02 = pedido de baixa).
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_movimento: u8,
+    #[fw(pos = "18..22", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "23..23", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "24..35", numeric)]
+    pub conta: u64,
+    #[fw(pos = "36..36", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "37..37", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "38..57", alpha)]
+    pub nosso_numero: String,
+    #[fw(pos = "58..58", numeric)]
+    pub carteira: u8,
+    /// 1 = com cadastramento (registrada), 2 = sem cadastramento.
+    #[fw(pos = "59..59", numeric)]
+    pub forma_cadastramento: u8,
+    /// 1 = tradicional, 2 = escritural.
+    #[fw(pos = "60..60", alpha)]
+    pub tipo_documento: String,
+    /// 1 = banco emite, 2 = cliente emite.
+    #[fw(pos = "61..61", numeric)]
+    pub emissao_boleto: u8,
+    #[fw(pos = "62..62", alpha)]
+    pub distribuicao_boleto: String,
+    /// Seu número.
+    #[fw(pos = "63..77", alpha)]
+    pub numero_documento: String,
+    #[fw(pos = "78..85", numeric)]
+    pub vencimento: u32,
+    /// Valor nominal do título, em centavos.
+    #[fw(pos = "86..100", numeric)]
+    pub valor_titulo: i64,
+    #[fw(pos = "101..105", numeric)]
+    pub agencia_cobradora: u32,
+    #[fw(pos = "106..106", alpha)]
+    pub agencia_cobradora_dv: String,
+    /// Espécie do título (ex: 02 = duplicata mercantil, 04 = duplicata de serviço).
+    #[fw(pos = "107..108", numeric)]
+    pub especie: u8,
+    /// `A` = aceite, `N` = não aceite.
+    #[fw(pos = "109..109", alpha)]
+    pub aceite: String,
+    #[fw(pos = "110..117", numeric)]
+    pub data_emissao: u32,
+    /// 1 = valor por dia, 2 = taxa mensal, 3 = isento.
+    #[fw(pos = "118..118", numeric)]
+    pub codigo_juros: u8,
+    #[fw(pos = "119..126", numeric)]
+    pub data_juros: u32,
+    #[fw(pos = "127..141", numeric)]
+    pub juros_mora: i64,
+    #[fw(pos = "142..142", numeric)]
+    pub codigo_desconto_1: u8,
+    #[fw(pos = "143..150", numeric)]
+    pub data_desconto_1: u32,
+    #[fw(pos = "151..165", numeric)]
+    pub desconto_1: i64,
+    #[fw(pos = "166..180", numeric)]
+    pub valor_iof: i64,
+    #[fw(pos = "181..195", numeric)]
+    pub valor_abatimento: i64,
+    /// Identificação do título na empresa, devolvida no retorno.
+    #[fw(pos = "196..220", alpha)]
+    pub uso_empresa: String,
+    /// 1 = protestar dias corridos, 2 = dias úteis, 3 = não protestar.
+    #[fw(pos = "221..221", numeric)]
+    pub codigo_protesto: u8,
+    #[fw(pos = "222..223", numeric)]
+    pub prazo_protesto: u8,
+    #[fw(pos = "224..224", numeric)]
+    pub codigo_baixa: u8,
+    #[fw(pos = "225..227", alpha)]
+    pub prazo_baixa: String,
+    /// 09 = real.
+    #[fw(pos = "228..229", numeric)]
+    pub codigo_moeda: u8,
+    #[fw(pos = "230..239", numeric)]
+    pub contrato: u64,
+    #[fw(pos = "240..240", filler)]
+    pub uso_livre: (),
+}
+
+/// Segmento Q da remessa: pagador e sacador/avalista (obrigatório).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoQ {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_movimento: u8,
+    /// 1 = CPF, 2 = CNPJ.
+    #[fw(pos = "18..18", numeric)]
+    pub pagador_tipo_inscricao: u8,
+    #[fw(pos = "19..33", numeric)]
+    pub pagador_inscricao: u64,
+    #[fw(pos = "34..73", alpha)]
+    pub pagador_nome: String,
+    #[fw(pos = "74..113", alpha)]
+    pub pagador_endereco: String,
+    #[fw(pos = "114..128", alpha)]
+    pub pagador_bairro: String,
+    #[fw(pos = "129..133", numeric)]
+    pub pagador_cep: u32,
+    #[fw(pos = "134..136", numeric)]
+    pub pagador_cep_sufixo: u16,
+    #[fw(pos = "137..151", alpha)]
+    pub pagador_cidade: String,
+    #[fw(pos = "152..153", alpha)]
+    pub pagador_uf: String,
+    #[fw(pos = "154..154", numeric)]
+    pub sacador_tipo_inscricao: u8,
+    #[fw(pos = "155..169", numeric)]
+    pub sacador_inscricao: u64,
+    #[fw(pos = "170..209", alpha)]
+    pub sacador_nome: String,
+    #[fw(pos = "210..212", numeric)]
+    pub banco_correspondente: u16,
+    #[fw(pos = "213..232", alpha)]
+    pub nosso_numero_correspondente: String,
+    #[fw(pos = "233..240", filler)]
+    pub uso_febraban_2: (),
+}
+
+/// Segmento R da remessa: descontos 2 e 3, multa e mensagens (opcional).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoR {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_movimento: u8,
+    #[fw(pos = "18..18", numeric)]
+    pub codigo_desconto_2: u8,
+    #[fw(pos = "19..26", numeric)]
+    pub data_desconto_2: u32,
+    #[fw(pos = "27..41", numeric)]
+    pub desconto_2: i64,
+    #[fw(pos = "42..42", numeric)]
+    pub codigo_desconto_3: u8,
+    #[fw(pos = "43..50", numeric)]
+    pub data_desconto_3: u32,
+    #[fw(pos = "51..65", numeric)]
+    pub desconto_3: i64,
+    /// 1 = valor fixo, 2 = percentual.
+    #[fw(pos = "66..66", numeric)]
+    pub codigo_multa: u8,
+    #[fw(pos = "67..74", numeric)]
+    pub data_multa: u32,
+    #[fw(pos = "75..89", numeric)]
+    pub multa: i64,
+    #[fw(pos = "90..99", alpha)]
+    pub informacao_pagador: String,
+    #[fw(pos = "100..139", alpha)]
+    pub mensagem_3: String,
+    #[fw(pos = "140..179", alpha)]
+    pub mensagem_4: String,
+    #[fw(pos = "180..199", filler)]
+    pub uso_febraban_2: (),
+    #[fw(pos = "200..207", numeric)]
+    pub codigo_ocorrencia_pagador: u32,
+    #[fw(pos = "208..210", numeric)]
+    pub banco_debito: u16,
+    #[fw(pos = "211..215", numeric)]
+    pub agencia_debito: u32,
+    #[fw(pos = "216..216", alpha)]
+    pub agencia_debito_dv: String,
+    #[fw(pos = "217..228", numeric)]
+    pub conta_debito: u64,
+    #[fw(pos = "229..229", alpha)]
+    pub conta_debito_dv: String,
+    #[fw(pos = "230..230", alpha)]
+    pub agencia_conta_debito_dv: String,
+    /// 1 = emite aviso de débito automático, 2 = não emite.
+    #[fw(pos = "231..231", numeric)]
+    pub aviso_debito_automatico: u8,
+    #[fw(pos = "232..240", filler)]
+    pub uso_febraban_3: (),
+}
+
+/// Um título da remessa: os segmentos P e Q e, se houver, o R.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TituloRemessa {
+    pub p: SegmentoP,
+    pub q: SegmentoQ,
+    pub r: Option<SegmentoR>,
+}
+
+impl TituloRemessa {
+    /// Monta o título a partir de um grupo de [`Pairing::REMESSA_COBRANCA`](crate::segments::Pairing::REMESSA_COBRANCA).
+    pub fn from_group(group: &SegmentGroup) -> Result<Self> {
+        Ok(Self { p: group.require(b'P')?, q: group.require(b'Q')?, r: group.parse(b'R')? })
+    }
+}
+
+impl From<&TituloRemessa> for crate::reconcile::TituloRemessa {
+    fn from(titulo: &TituloRemessa) -> Self {
+        Self {
+            nosso_numero: titulo.p.nosso_numero.trim().to_string(),
+            seu_numero: titulo.p.numero_documento.trim().to_string(),
+            valor: titulo.p.valor_titulo,
+            vencimento: titulo.p.vencimento,
+        }
+    }
+}
+
+impl Detalhe for SegmentoP {
+    fn valor_lote(&self) -> Option<i64> {
+        Some(self.valor_titulo)
+    }
+}
+
+impl Detalhe for SegmentoQ {}
+impl Detalhe for SegmentoR {}
+
+/// Layouts de todos os registros da cobrança, da remessa e do retorno, identificados
+/// pelo tipo de registro (posição 8) e, nos detalhes, pelo segmento (posição 14).
+pub fn registry() -> LayoutRegistry {
+    const TIPO: FieldPos = FieldPos::new_const(8, 8);
+    const SEGMENTO: FieldPos = FieldPos::new_const(14, 14);
+    let mut registry = LayoutRegistry::new();
+    registry
+        .register(RecordLayout::of::<HeaderArquivo>("header_arquivo").when(TIPO, "0"))
+        .register(RecordLayout::of::<HeaderLote>("header_lote").when(TIPO, "1"))
+        .register(RecordLayout::of::<SegmentoP>("segmento_p").when(TIPO, "3").when(SEGMENTO, "P"))
+        .register(RecordLayout::of::<SegmentoQ>("segmento_q").when(TIPO, "3").when(SEGMENTO, "Q"))
+        .register(RecordLayout::of::<SegmentoR>("segmento_r").when(TIPO, "3").when(SEGMENTO, "R"))
+        .register(RecordLayout::of::<SegmentoT>("segmento_t").when(TIPO, "3").when(SEGMENTO, "T"))
+        .register(RecordLayout::of::<SegmentoU>("segmento_u").when(TIPO, "3").when(SEGMENTO, "U"))
+        .register(RecordLayout::of::<TrailerLote>("trailer_lote").when(TIPO, "5"))
+        .register(RecordLayout::of::<TrailerArquivo>("trailer_arquivo").when(TIPO, "9"));
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixedWidthParse, FixedWidthWrite};
+
+    #[test]
+    fn remessa_segments_cover_the_record_and_round_trip() {
+        assert!(SegmentoP::spec_gaps().is_empty(), "{:?}", SegmentoP::spec_gaps());
+        assert!(SegmentoQ::spec_gaps().is_empty(), "{:?}", SegmentoQ::spec_gaps());
+        assert!(SegmentoR::spec_gaps().is_empty(), "{:?}", SegmentoR::spec_gaps());
+        assert!(HeaderArquivo::spec_gaps().is_empty() && TrailerArquivo::spec_gaps().is_empty());
+
+        let p = SegmentoP {
+            banco: 341,
+            lote: 1,
+            tipo_registro: 3,
+            sequencial: 1,
+            segmento: "P".into(),
+            codigo_movimento: 1,
+            nosso_numero: "12345".into(),
+            vencimento: 16102026,
+            valor_titulo: 10_000,
+            codigo_moeda: 9,
+            ..Default::default()
+        };
+        let line = p.to_line().unwrap();
+        assert_eq!(line.len(), 240);
+        assert_eq!(&line[..17], "3410001300001P 01");
+        assert_eq!(&line[37..57], "12345               ");
+        assert_eq!(&line[77..100], "16102026000000000010000");
+        assert_eq!(SegmentoP::parse(&line).unwrap(), p);
+
+        let q = SegmentoQ { pagador_nome: "JOSE DA SILVA".into(), pagador_uf: "SP".into(), ..Default::default() };
+        let line = q.to_line().unwrap();
+        assert_eq!((&line[33..46], &line[151..153]), ("JOSE DA SILVA", "SP"));
+        assert_eq!(SegmentoQ::parse(&line).unwrap(), q);
+
+        let mut writer = crate::cnab240::FileWriter::new(Vec::new(), HeaderArquivo { banco: 341, ..Default::default() }).unwrap();
+        writer.begin_lote(&HeaderLote { operacao: "R".into(), servico: 1, ..Default::default() }).unwrap();
+        writer.append(&SegmentoP { sequencial: 9, ..p }).unwrap();
+        writer.append(&SegmentoQ { segmento: "Q".into(), ..q }).unwrap();
+        let trailer: TrailerLote = writer.end_lote().unwrap();
+        assert_eq!(trailer.quantidade_registros, 4);
+        let file = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let segmentos: Vec<_> = file.lines().map(|line| &line[..14]).collect();
+        assert_eq!(segmentos, ["34100000      ", "34100011R01  0", "3410001300001P", "3410001300002Q", "34100015      ", "34199999      "]);
+        let registry = registry();
+        let names: Vec<_> = file.lines().map(|line| registry.resolve(line.as_bytes()).unwrap().name).collect();
+        assert_eq!(names, ["header_arquivo", "header_lote", "segmento_p", "segmento_q", "trailer_lote", "trailer_arquivo"]);
+
+        let groups = crate::segments::SegmentGroups::new(file.as_bytes(), crate::segments::Pairing::REMESSA_COBRANCA);
+        let titulos: Vec<_> = groups.map(|group| TituloRemessa::from_group(&group?)).collect::<Result<_>>().unwrap();
+        assert_eq!((titulos.len(), titulos[0].p.nosso_numero.as_str(), titulos[0].r.is_none()), (1, "12345", true));
+        assert_eq!(crate::reconcile::TituloRemessa::from(&titulos[0]).valor, 10_000);
+    }
+}
@@ -0,0 +1,541 @@
+//! # CNAB 400 — Cobrança
+//!
+//! Registros do layout clássico de 400 posições, ainda o padrão da cobrança de pequenos
+//! clientes em Itaú, Bradesco e Banco do Brasil: header, detalhe tipo 1 da remessa e do
+//! retorno, detalhe tipo 7 do retorno (convênios de 7 dígitos do Banco do Brasil) e
+//! trailers.
+//!
+//! Cada banco ocupa algumas faixas com dados próprios (composição do nosso número,
+//! agência/conta, mensagens). As structs mapeiam os campos que os bancos compartilham e
+//! expõem essas faixas como texto (`reservado_banco*`), para o layout do banco ler depois.
+//!
+//! Valores monetários são centavos e datas `DDMMAA`, como no arquivo.
+//!
+//! [`RemessaWriter`] escreve a remessa preenchendo o sequencial de cada registro e o
+//! trailer.
+
+use crate::reader::{Framing, RecordReader};
+use crate::registry::{LayoutRegistry, RecordLayout};
+use crate::writer::{self, FixedWidthWrite, WriteOptions};
+use crate::{
+    FieldKind, FieldPos, FieldSpec, FixedWidth, FixedWidthError, ParseOptions, Result, TotalsCheck, Value,
+};
+use std::io::{BufRead, Write};
+
+/// Header de arquivo (registro tipo `0`), igual na remessa e no retorno.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 400)]
+#[fw_record(write)]
+pub struct HeaderArquivo {
+    #[fw(pos = "1..1", numeric)]
+    pub tipo_registro: u8,
+    /// 1 = remessa, 2 = retorno.
+    #[fw(pos = "2..2", numeric)]
+    pub operacao: u8,
+    /// `REMESSA` ou `RETORNO`.
+    #[fw(pos = "3..9", alpha)]
+    pub literal_operacao: String,
+    /// `01` = cobrança.
+    #[fw(pos = "10..11", numeric)]
+    pub servico: u8,
+    #[fw(pos = "12..26", alpha)]
+    pub literal_servico: String,
+    /// Código da empresa no banco (ou agência e conta, conforme o banco).
+    #[fw(pos = "27..46", alpha)]
+    pub codigo_empresa: String,
+    #[fw(pos = "47..76", alpha)]
+    pub nome_empresa: String,
+    #[fw(pos = "77..79", numeric)]
+    pub banco: u16,
+    #[fw(pos = "80..94", alpha)]
+    pub nome_banco: String,
+    #[fw(pos = "95..100", numeric)]
+    pub data_gravacao: u32,
+    /// Uso do banco (ex: sequencial da remessa no Bradesco, densidade no Itaú).
+    #[fw(pos = "101..394", alpha)]
+    pub reservado_banco: String,
+    #[fw(pos = "395..400", numeric)]
+    pub sequencial: u32,
+}
+
+/// Detalhe da remessa (registro tipo `1`): um título.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 400)]
+#[fw_record(write)]
+pub struct RemessaTipo1 {
+    #[fw(pos = "1..1", numeric)]
+    pub tipo_registro: u8,
+    /// Identificação da empresa (inscrição, agência e conta), conforme o banco.
+    #[fw(pos = "2..37", alpha)]
+    pub reservado_banco_1: String,
+    /// Número de controle do participante, devolvido no retorno.
+    #[fw(pos = "38..62", alpha)]
+    pub uso_empresa: String,
+    /// Nosso número e carteira, conforme o banco.
+    #[fw(pos = "63..108", alpha)]
+    pub reservado_banco_2: String,
+    /// Código de ocorrência da remessa (ex: 01 = remessa, 02 = pedido de baixa).
+    #[fw(pos = "109..110", numeric)]
+    pub codigo_ocorrencia: u8,
+    /// Seu número.
+    #[fw(pos = "111..120", alpha)]
+    pub numero_documento: String,
+    #[fw(pos = "121..126", numeric)]
+    pub vencimento: u32,
+    /// Valor nominal do título, em centavos.
+    #[fw(pos = "127..139", numeric)]
+    pub valor_titulo: i64,
+    #[fw(pos = "140..142", numeric)]
+    pub banco_cobrador: u16,
+    #[fw(pos = "143..147", numeric)]
+    pub agencia_cobradora: u32,
+    #[fw(pos = "148..149", numeric)]
+    pub especie: u8,
+    /// `A` = aceite, `N` = não aceite.
+    #[fw(pos = "150..150", alpha)]
+    pub aceite: String,
+    #[fw(pos = "151..156", numeric)]
+    pub data_emissao: u32,
+    #[fw(pos = "157..158", numeric)]
+    pub instrucao_1: u8,
+    #[fw(pos = "159..160", numeric)]
+    pub instrucao_2: u8,
+    /// Juros de mora por dia de atraso.
+    #[fw(pos = "161..173", numeric)]
+    pub juros_mora: i64,
+    #[fw(pos = "174..179", numeric)]
+    pub data_desconto: u32,
+    #[fw(pos = "180..192", numeric)]
+    pub valor_desconto: i64,
+    #[fw(pos = "193..205", numeric)]
+    pub valor_iof: i64,
+    #[fw(pos = "206..218", numeric)]
+    pub valor_abatimento: i64,
+    /// 01 = CPF, 02 = CNPJ.
+    #[fw(pos = "219..220", numeric)]
+    pub pagador_tipo_inscricao: u8,
+    #[fw(pos = "221..234", numeric)]
+    pub pagador_inscricao: u64,
+    #[fw(pos = "235..274", alpha)]
+    pub pagador_nome: String,
+    #[fw(pos = "275..314", alpha)]
+    pub pagador_endereco: String,
+    /// Bairro (Itaú) ou primeira mensagem (Bradesco).
+    #[fw(pos = "315..326", alpha)]
+    pub reservado_banco_3: String,
+    #[fw(pos = "327..334", numeric)]
+    pub pagador_cep: u32,
+    /// Cidade, UF, sacador/avalista ou segunda mensagem, conforme o banco.
+    #[fw(pos = "335..394", alpha)]
+    pub reservado_banco_4: String,
+    #[fw(pos = "395..400", numeric)]
+    pub sequencial: u32,
+}
+
+/// Detalhe do retorno (registro tipo `1`): uma ocorrência de um título.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 400)]
+#[fw_record(write)]
+pub struct RetornoTipo1 {
+    #[fw(pos = "1..1", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "2..3", numeric)]
+    pub empresa_tipo_inscricao: u8,
+    #[fw(pos = "4..17", numeric)]
+    pub empresa_inscricao: u64,
+    /// Agência e conta da empresa, conforme o banco.
+    #[fw(pos = "18..37", alpha)]
+    pub reservado_banco_1: String,
+    #[fw(pos = "38..62", alpha)]
+    pub uso_empresa: String,
+    /// Nosso número e carteira, conforme o banco.
+    #[fw(pos = "63..108", alpha)]
+    pub reservado_banco_2: String,
+    /// Código de ocorrência do retorno (ex: 06 = liquidação).
+    #[fw(pos = "109..110", numeric)]
+    pub codigo_ocorrencia: u8,
+    #[fw(pos = "111..116", numeric)]
+    pub data_ocorrencia: u32,
+    #[fw(pos = "117..126", alpha)]
+    pub numero_documento: String,
+    #[fw(pos = "127..146", alpha)]
+    pub reservado_banco_3: String,
+    #[fw(pos = "147..152", numeric)]
+    pub vencimento: u32,
+    #[fw(pos = "153..165", numeric)]
+    pub valor_titulo: i64,
+    #[fw(pos = "166..168", numeric)]
+    pub banco_cobrador: u16,
+    #[fw(pos = "169..173", numeric)]
+    pub agencia_cobradora: u32,
+    #[fw(pos = "174..175", alpha)]
+    pub especie: String,
+    /// Tarifa de cobrança, em centavos.
+    #[fw(pos = "176..188", numeric)]
+    pub valor_tarifa: i64,
+    /// Outras despesas e juros de operação (Bradesco) ou brancos (Itaú).
+    #[fw(pos = "189..214", alpha)]
+    pub reservado_banco_4: String,
+    #[fw(pos = "215..227", numeric)]
+    pub valor_iof: i64,
+    #[fw(pos = "228..240", numeric)]
+    pub valor_abatimento: i64,
+    #[fw(pos = "241..253", numeric)]
+    pub valor_desconto: i64,
+    /// Valor pago (creditado), em centavos.
+    #[fw(pos = "254..266", numeric)]
+    pub valor_pago: i64,
+    #[fw(pos = "267..279", numeric)]
+    pub juros_mora: i64,
+    #[fw(pos = "280..292", numeric)]
+    pub outros_creditos: i64,
+    #[fw(pos = "293..295", alpha)]
+    pub reservado_banco_5: String,
+    #[fw(pos = "296..301", numeric)]
+    pub data_credito: u32,
+    /// Motivos da ocorrência, nome do pagador etc., conforme o banco.
+    #[fw(pos = "302..394", alpha)]
+    pub reservado_banco_6: String,
+    #[fw(pos = "395..400", numeric)]
+    pub sequencial: u32,
+}
+
+/// Detalhe do retorno tipo `7` do Banco do Brasil (convênios de 7 dígitos).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 400)]
+#[fw_record(write)]
+pub struct RetornoTipo7 {
+    #[fw(pos = "1..1", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "2..3", numeric)]
+    pub empresa_tipo_inscricao: u8,
+    #[fw(pos = "4..17", numeric)]
+    pub empresa_inscricao: u64,
+    #[fw(pos = "18..21", numeric)]
+    pub agencia: u16,
+    #[fw(pos = "22..22", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "23..30", numeric)]
+    pub conta: u32,
+    #[fw(pos = "31..31", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "32..38", numeric)]
+    pub convenio: u32,
+    #[fw(pos = "39..63", alpha)]
+    pub uso_empresa: String,
+    /// Convênio (7 dígitos) seguido do sequencial do título (10 dígitos).
+    #[fw(pos = "64..80", alpha)]
+    pub nosso_numero: String,
+    #[fw(pos = "81..81", numeric)]
+    pub tipo_cobranca: u8,
+    #[fw(pos = "82..82", numeric)]
+    pub tipo_cobranca_especifico: u8,
+    #[fw(pos = "83..86", numeric)]
+    pub dias_calculo: u16,
+    #[fw(pos = "87..88", numeric)]
+    pub natureza_recebimento: u8,
+    #[fw(pos = "89..91", alpha)]
+    pub prefixo_titulo: String,
+    #[fw(pos = "92..94", numeric)]
+    pub variacao_carteira: u16,
+    #[fw(pos = "95..95", numeric)]
+    pub conta_caucao: u8,
+    #[fw(pos = "96..100", numeric)]
+    pub taxa_desconto: u32,
+    #[fw(pos = "101..105", numeric)]
+    pub taxa_iof: u32,
+    #[fw(pos = "106..106", filler)]
+    pub brancos: (),
+    #[fw(pos = "107..108", numeric)]
+    pub carteira: u8,
+    /// Comando (código de ocorrência) do retorno (ex: 06 = liquidação).
+    #[fw(pos = "109..110", numeric)]
+    pub codigo_ocorrencia: u8,
+    #[fw(pos = "111..116", numeric)]
+    pub data_liquidacao: u32,
+    #[fw(pos = "117..126", alpha)]
+    pub numero_documento: String,
+    #[fw(pos = "127..146", filler)]
+    pub brancos_2: (),
+    #[fw(pos = "147..152", numeric)]
+    pub vencimento: u32,
+    #[fw(pos = "153..165", numeric)]
+    pub valor_titulo: i64,
+    #[fw(pos = "166..168", numeric)]
+    pub banco_recebedor: u16,
+    #[fw(pos = "169..172", numeric)]
+    pub agencia_recebedora: u16,
+    #[fw(pos = "173..173", alpha)]
+    pub agencia_recebedora_dv: String,
+    #[fw(pos = "174..175", numeric)]
+    pub especie: u8,
+    #[fw(pos = "176..181", numeric)]
+    pub data_credito: u32,
+    #[fw(pos = "182..188", numeric)]
+    pub valor_tarifa: i64,
+    #[fw(pos = "189..201", numeric)]
+    pub outras_despesas: i64,
+    #[fw(pos = "202..214", numeric)]
+    pub juros_desconto: i64,
+    #[fw(pos = "215..227", numeric)]
+    pub iof_desconto: i64,
+    #[fw(pos = "228..240", numeric)]
+    pub valor_abatimento: i64,
+    #[fw(pos = "241..253", numeric)]
+    pub valor_desconto: i64,
+    /// Valor recebido, em centavos.
+    #[fw(pos = "254..266", numeric)]
+    pub valor_pago: i64,
+    #[fw(pos = "267..279", numeric)]
+    pub juros_mora: i64,
+    #[fw(pos = "280..292", numeric)]
+    pub outros_recebimentos: i64,
+    #[fw(pos = "293..305", numeric)]
+    pub abatimento_nao_aproveitado: i64,
+    #[fw(pos = "306..318", numeric)]
+    pub valor_lancamento: i64,
+    /// 0 = sem lançamento, 1 = débito, 2 = crédito.
+    #[fw(pos = "319..319", numeric)]
+    pub indicativo_debito_credito: u8,
+    #[fw(pos = "320..320", numeric)]
+    pub indicador_valor: u8,
+    #[fw(pos = "321..332", numeric)]
+    pub valor_ajuste: i64,
+    /// Canal de pagamento, autorização de liquidação parcial etc.
+    #[fw(pos = "333..394", alpha)]
+    pub reservado_banco: String,
+    #[fw(pos = "395..400", numeric)]
+    pub sequencial: u32,
+}
+
+/// Trailer da remessa (registro tipo `9`): só o sequencial.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 400)]
+#[fw_record(write)]
+pub struct TrailerRemessa {
+    #[fw(pos = "1..1", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "2..394", filler)]
+    pub brancos: (),
+    #[fw(pos = "395..400", numeric)]
+    pub sequencial: u32,
+}
+
+/// Trailer do retorno (registro tipo `9`), com os totais da cobrança simples.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 400)]
+#[fw_record(write)]
+pub struct TrailerRetorno {
+    #[fw(pos = "1..1", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "2..2", numeric)]
+    pub operacao: u8,
+    #[fw(pos = "3..4", numeric)]
+    pub servico: u8,
+    #[fw(pos = "5..7", numeric)]
+    pub banco: u16,
+    #[fw(pos = "8..17", alpha)]
+    pub reservado_banco_1: String,
+    #[fw(pos = "18..25", numeric)]
+    pub quantidade_titulos: u32,
+    /// Valor total dos títulos em cobrança simples, em centavos.
+    #[fw(pos = "26..39", numeric)]
+    pub valor_total: i64,
+    /// Totais das outras carteiras e avisos bancários, conforme o banco.
+    #[fw(pos = "40..394", alpha)]
+    pub reservado_banco_2: String,
+    #[fw(pos = "395..400", numeric)]
+    pub sequencial: u32,
+}
+
+const SEQUENCIAL: FieldSpec = FieldSpec::new("sequencial", FieldPos::new_const(395, 400), FieldKind::Numeric);
+
+/// Escreve uma remessa CNAB 400, numerando os registros.
+///
+/// Cada registro recebe nas posições 395 a 400 o número sequencial do registro no
+/// arquivo (o header é o 1), e [`finish`](Self::finish) escreve o [`TrailerRemessa`].
+/// Registros terminam em `\r\n`.
+pub struct RemessaWriter<W: Write> {
+    out: W,
+    options: WriteOptions,
+    registros: u32,
+}
+
+impl<W: Write> RemessaWriter<W> {
+    /// Começa a remessa escrevendo o header, em Latin-1.
+    pub fn new(out: W, header: &HeaderArquivo) -> Result<Self> {
+        Self::with_options(out, header, WriteOptions::default())
+    }
+
+    /// Como [`new`](Self::new), com as opções de escrita de todos os registros.
+    pub fn with_options(out: W, header: &HeaderArquivo, options: WriteOptions) -> Result<Self> {
+        let mut writer = Self { out, options, registros: 0 };
+        writer.append(header)?;
+        Ok(writer)
+    }
+
+    /// Acrescenta um registro (ex: [`RemessaTipo1`], ou um registro de mensagem do banco).
+    pub fn append(&mut self, record: &impl FixedWidthWrite) -> Result<()> {
+        self.registros += 1;
+        let mut line = record.write_bytes(&self.options)?;
+        writer::write_field(&mut line, &SEQUENCIAL, &Value::Numeric(self.registros.into()), &self.options)?;
+        self.out.write_all(&line)?;
+        self.out.write_all(b"\r\n")?;
+        Ok(())
+    }
+
+    /// Escreve o trailer e devolve o destino.
+    pub fn finish(mut self) -> Result<W> {
+        self.append(&TrailerRemessa { tipo_registro: 9, ..Default::default() })?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Registros escritos até agora.
+    pub fn records_written(&self) -> u32 {
+        self.registros
+    }
+}
+
+const TIPO_REGISTRO: FieldPos = FieldPos::new_const(1, 1);
+
+/// Layouts dos registros da remessa, identificados pelo tipo de registro (posição 1).
+pub fn remessa_registry() -> LayoutRegistry {
+    let mut registry = LayoutRegistry::new();
+    registry
+        .register(RecordLayout::of::<HeaderArquivo>("header_arquivo").when(TIPO_REGISTRO, "0"))
+        .register(RecordLayout::of::<RemessaTipo1>("remessa_tipo1").when(TIPO_REGISTRO, "1"))
+        .register(RecordLayout::of::<TrailerRemessa>("trailer_remessa").when(TIPO_REGISTRO, "9"));
+    registry
+}
+
+/// Layouts dos registros do retorno, identificados pelo tipo de registro (posição 1).
+pub fn retorno_registry() -> LayoutRegistry {
+    let mut registry = LayoutRegistry::new();
+    registry
+        .register(RecordLayout::of::<HeaderArquivo>("header_arquivo").when(TIPO_REGISTRO, "0"))
+        .register(RecordLayout::of::<RetornoTipo1>("retorno_tipo1").when(TIPO_REGISTRO, "1"))
+        .register(RecordLayout::of::<RetornoTipo7>("retorno_tipo7").when(TIPO_REGISTRO, "7"))
+        .register(RecordLayout::of::<TrailerRetorno>("trailer_retorno").when(TIPO_REGISTRO, "9"));
+    registry
+}
+
+/// Confere a estrutura de uma remessa ou retorno CNAB 400.
+///
+/// O arquivo deve começar com o header (tipo `0`) e terminar com o trailer (tipo `9`),
+/// e o sequencial de cada registro (posições 395 a 400) deve ser a sua posição no
+/// arquivo. Falhas de estrutura resultam em `FixedWidthError::Structure` e sequenciais
+/// fora de ordem em `FixedWidthError::Integrity`. Os totais do trailer do retorno não são
+/// conferidos, pois cada banco soma neles carteiras diferentes.
+///
+/// # Retorno
+/// Quantidade de registros lidos.
+pub fn validate<R: BufRead>(reader: R) -> Result<u64> {
+    let options = ParseOptions::default();
+    let mut reader = RecordReader::new(reader, Framing::FixedLength(400));
+    let mut buf = Vec::with_capacity(400);
+    let structure = |line_number: u64, message: &str| FixedWidthError::Structure {
+        line_number,
+        message: message.to_string(),
+    };
+
+    let mut trailer = false;
+    while reader.read_record(&mut buf)? {
+        let line_number = reader.records_read();
+        if trailer {
+            return Err(structure(line_number, "registro após o trailer"));
+        }
+        match buf.first() {
+            Some(b'0') if line_number == 1 => {}
+            _ if line_number == 1 => return Err(structure(line_number, "arquivo sem header")),
+            Some(b'0') => return Err(structure(line_number, "header repetido")),
+            Some(b'9') => trailer = true,
+            _ => {}
+        }
+        let sequencial = crate::parse_field(&buf, &SEQUENCIAL, &options)
+            .map_err(|e| e.at_line_with(line_number, &buf, &[SEQUENCIAL], options.redaction))?;
+        let Value::Numeric(sequencial) = sequencial else { unreachable!("campo numérico") };
+        TotalsCheck::new("Registro").check("sequencial", sequencial, line_number as i64).finish(line_number)?;
+    }
+
+    let last = reader.records_read();
+    match (last, trailer) {
+        (0, _) => Err(structure(last, "arquivo vazio")),
+        (_, false) => Err(structure(last, "arquivo sem trailer")),
+        (_, true) => Ok(last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedWidthParse;
+
+    #[test]
+    fn cnab400_records_cover_400_positions() {
+        let gaps = [
+            HeaderArquivo::spec_gaps(),
+            RemessaTipo1::spec_gaps(),
+            RetornoTipo1::spec_gaps(),
+            RetornoTipo7::spec_gaps(),
+            TrailerRemessa::spec_gaps(),
+            TrailerRetorno::spec_gaps(),
+        ];
+        assert!(gaps.iter().all(Vec::is_empty), "{gaps:?}");
+
+        let mut line = vec![b'0'; 400];
+        line[0] = b'7';
+        line[63..80].copy_from_slice(b"12345670000000042");
+        line[108..116].copy_from_slice(b"06161026");
+        line[253..266].copy_from_slice(b"0000000010050");
+        line[332..394].fill(b' ');
+        line[394..].copy_from_slice(b"000002");
+        let tipo7 = RetornoTipo7::parse(std::str::from_utf8(&line).unwrap()).unwrap();
+        assert_eq!(tipo7.nosso_numero, "12345670000000042");
+        assert_eq!((tipo7.codigo_ocorrencia, tipo7.data_liquidacao, tipo7.valor_pago), (6, 161026, 10_050));
+        assert_eq!(tipo7.sequencial, 2);
+
+        let titulo = RemessaTipo1 {
+            tipo_registro: 1,
+            codigo_ocorrencia: 1,
+            numero_documento: "NF123".into(),
+            vencimento: 161026,
+            valor_titulo: 10_000,
+            aceite: "N".into(),
+            pagador_nome: "JOSE DA SILVA".into(),
+            sequencial: 2,
+            ..Default::default()
+        };
+        let line = titulo.to_line().unwrap();
+        assert_eq!(line.len(), 400);
+        assert_eq!(&line[108..139], "01NF123     1610260000000010000");
+        assert_eq!(&line[394..], "000002");
+        assert_eq!(RemessaTipo1::parse(&line).unwrap(), titulo);
+    }
+
+    #[test]
+    fn remessa_writer_numbers_every_record() {
+        let header = HeaderArquivo { operacao: 1, literal_operacao: "REMESSA".into(), ..Default::default() };
+        let mut writer = RemessaWriter::new(Vec::new(), &header).unwrap();
+        for numero_documento in ["NF1", "NF2"] {
+            let titulo = RemessaTipo1 { tipo_registro: 1, numero_documento: numero_documento.into(), ..Default::default() };
+            writer.append(&titulo).unwrap();
+        }
+        let file = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<_> = file.lines().collect();
+        let sequenciais: Vec<_> = lines.iter().map(|line| &line[394..]).collect();
+        assert_eq!(sequenciais, ["000001", "000002", "000003", "000004"]);
+        assert!(lines[3].starts_with("9 "));
+        assert_eq!(RemessaTipo1::parse(lines[2]).unwrap().numero_documento, "NF2");
+
+        assert_eq!(validate(file.as_bytes()).unwrap(), 4);
+        let registry = remessa_registry();
+        let names: Vec<_> = lines.iter().map(|line| registry.resolve(line.as_bytes()).unwrap().name).collect();
+        assert_eq!(names, ["header_arquivo", "remessa_tipo1", "remessa_tipo1", "trailer_remessa"]);
+
+        let sem_titulo = format!("{}\r\n{}", lines[0], lines[3]);
+        assert!(matches!(validate(sem_titulo.as_bytes()), Err(FixedWidthError::Integrity { line_number: 2, .. })));
+        let sem_trailer = lines[..3].join("\r\n");
+        assert!(matches!(validate(sem_trailer.as_bytes()), Err(FixedWidthError::Structure { line_number: 3, .. })));
+    }
+}
@@ -0,0 +1,494 @@
+//! # FEBRABAN 150 — Débito Automático
+//!
+//! Layout de 150 posições trocado entre bancos e empresas conveniadas (concessionárias,
+//! condomínios, escolas) para débito automático em conta: registros `A` (header), `B`
+//! a `X` (detalhes) e `Z` (trailer), versão 05 do manual.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::layouts::debito150::{self, Registro};
+//!
+//! let file = std::io::BufReader::new(std::fs::File::open("DEB.RET")?);
+//! let arquivo = debito150::parse_arquivo(file)?;
+//! for debito in arquivo.registros.iter().filter_map(|r| match r {
+//!     Registro::F(f) => Some(f),
+//!     _ => None,
+//! }) {
+//!     println!("{} {}", debito.cliente_empresa, debito.codigo_retorno);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Valores monetários são centavos e datas `AAAAMMDD`, como no arquivo.
+
+use crate::reader::{Framing, RecordReader};
+use crate::registry::{LayoutRegistry, RecordLayout};
+use crate::{FieldPos, FixedWidth, FixedWidthError, FixedWidthParse, ParseOptions, Result, TotalsCheck};
+use std::io::BufRead;
+
+/// Registro A: header do arquivo.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroA {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    /// 1 = remessa (empresa para o banco), 2 = retorno (banco para a empresa).
+    #[fw(pos = "2..2", numeric)]
+    pub codigo_remessa: u8,
+    #[fw(pos = "3..22", alpha)]
+    pub convenio: String,
+    #[fw(pos = "23..42", alpha)]
+    pub nome_empresa: String,
+    #[fw(pos = "43..45", numeric)]
+    pub banco: u16,
+    #[fw(pos = "46..65", alpha)]
+    pub nome_banco: String,
+    #[fw(pos = "66..73", numeric)]
+    pub data_geracao: u32,
+    /// Número sequencial do arquivo (NSA).
+    #[fw(pos = "74..79", numeric)]
+    pub sequencial_arquivo: u32,
+    #[fw(pos = "80..81", numeric)]
+    pub versao_layout: u8,
+    /// `DEBITO AUTOMATICO`.
+    #[fw(pos = "82..98", alpha)]
+    pub servico: String,
+    #[fw(pos = "99..150", filler)]
+    pub reservado: (),
+}
+
+/// Registro B: cadastramento (ou exclusão) de débito automático feito no banco.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroB {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    /// Identificação do cliente na empresa.
+    #[fw(pos = "2..26", alpha)]
+    pub cliente_empresa: String,
+    #[fw(pos = "27..30", alpha)]
+    pub agencia: String,
+    /// Identificação do cliente no banco (conta).
+    #[fw(pos = "31..44", alpha)]
+    pub cliente_banco: String,
+    /// Data da opção ou da exclusão.
+    #[fw(pos = "45..52", numeric)]
+    pub data_opcao: u32,
+    #[fw(pos = "53..149", filler)]
+    pub reservado: (),
+    /// 1 = exclusão, 2 = inclusão.
+    #[fw(pos = "150..150", numeric)]
+    pub codigo_movimento: u8,
+}
+
+/// Registro C: ocorrências no cadastramento, enviadas pela empresa.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroC {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..26", alpha)]
+    pub cliente_empresa: String,
+    #[fw(pos = "27..30", alpha)]
+    pub agencia: String,
+    #[fw(pos = "31..44", alpha)]
+    pub cliente_banco: String,
+    #[fw(pos = "45..84", alpha)]
+    pub ocorrencia_1: String,
+    #[fw(pos = "85..124", alpha)]
+    pub ocorrencia_2: String,
+    #[fw(pos = "125..149", filler)]
+    pub reservado: (),
+    #[fw(pos = "150..150", numeric)]
+    pub codigo_movimento: u8,
+}
+
+/// Registro D: alteração da identificação do cliente na empresa.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroD {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..26", alpha)]
+    pub cliente_empresa_anterior: String,
+    #[fw(pos = "27..30", alpha)]
+    pub agencia: String,
+    #[fw(pos = "31..44", alpha)]
+    pub cliente_banco: String,
+    #[fw(pos = "45..69", alpha)]
+    pub cliente_empresa: String,
+    #[fw(pos = "70..129", alpha)]
+    pub ocorrencia: String,
+    #[fw(pos = "130..149", filler)]
+    pub reservado: (),
+    /// 0 = alteração, 1 = exclusão.
+    #[fw(pos = "150..150", numeric)]
+    pub codigo_movimento: u8,
+}
+
+/// Registro E: débito a ser feito em conta, enviado pela empresa.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroE {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..26", alpha)]
+    pub cliente_empresa: String,
+    #[fw(pos = "27..30", alpha)]
+    pub agencia: String,
+    #[fw(pos = "31..44", alpha)]
+    pub cliente_banco: String,
+    #[fw(pos = "45..52", numeric)]
+    pub vencimento: u32,
+    /// Valor do débito, em centavos.
+    #[fw(pos = "53..67", numeric)]
+    pub valor: i64,
+    /// `03` = real.
+    #[fw(pos = "68..69", alpha)]
+    pub codigo_moeda: String,
+    #[fw(pos = "70..129", alpha)]
+    pub uso_empresa: String,
+    /// 1 = CNPJ, 2 = CPF.
+    #[fw(pos = "130..130", numeric)]
+    pub tipo_identificacao: u8,
+    #[fw(pos = "131..145", numeric)]
+    pub identificacao: u64,
+    #[fw(pos = "146..149", filler)]
+    pub reservado: (),
+    /// 0 = débito normal, 1 = cancelamento.
+    #[fw(pos = "150..150", numeric)]
+    pub codigo_movimento: u8,
+}
+
+/// Registro F: resultado do débito, devolvido pelo banco.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroF {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..26", alpha)]
+    pub cliente_empresa: String,
+    #[fw(pos = "27..30", alpha)]
+    pub agencia: String,
+    #[fw(pos = "31..44", alpha)]
+    pub cliente_banco: String,
+    /// Data do débito (ou do vencimento, se não debitado).
+    #[fw(pos = "45..52", numeric)]
+    pub data_debito: u32,
+    /// Valor debitado, em centavos.
+    #[fw(pos = "53..67", numeric)]
+    pub valor: i64,
+    /// `00` = débito efetuado; os demais códigos indicam o motivo da recusa.
+    #[fw(pos = "68..69", alpha)]
+    pub codigo_retorno: String,
+    #[fw(pos = "70..129", alpha)]
+    pub uso_empresa: String,
+    #[fw(pos = "130..130", numeric)]
+    pub tipo_identificacao: u8,
+    #[fw(pos = "131..145", numeric)]
+    pub identificacao: u64,
+    #[fw(pos = "146..149", filler)]
+    pub reservado: (),
+    #[fw(pos = "150..150", numeric)]
+    pub codigo_movimento: u8,
+}
+
+/// Registro H: ocorrência na alteração da opção, devolvida pelo banco.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroH {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..26", alpha)]
+    pub cliente_empresa_anterior: String,
+    #[fw(pos = "27..30", alpha)]
+    pub agencia: String,
+    #[fw(pos = "31..44", alpha)]
+    pub cliente_banco: String,
+    #[fw(pos = "45..69", alpha)]
+    pub cliente_empresa: String,
+    #[fw(pos = "70..127", alpha)]
+    pub ocorrencia: String,
+    #[fw(pos = "128..149", filler)]
+    pub reservado: (),
+    #[fw(pos = "150..150", numeric)]
+    pub codigo_movimento: u8,
+}
+
+/// Registro J: confirmação do processamento de um arquivo de remessa.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroJ {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    /// NSA do arquivo processado.
+    #[fw(pos = "2..7", numeric)]
+    pub sequencial_arquivo: u32,
+    #[fw(pos = "8..15", numeric)]
+    pub data_geracao: u32,
+    #[fw(pos = "16..21", numeric)]
+    pub quantidade_registros: u32,
+    #[fw(pos = "22..38", numeric)]
+    pub valor_total: i64,
+    #[fw(pos = "39..46", numeric)]
+    pub data_processamento: u32,
+    #[fw(pos = "47..150", filler)]
+    pub reservado: (),
+}
+
+/// Registro K: lançamento específico do convênio, com layout combinado entre as partes.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroK {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..26", alpha)]
+    pub cliente_empresa: String,
+    #[fw(pos = "27..30", alpha)]
+    pub agencia: String,
+    #[fw(pos = "31..44", alpha)]
+    pub cliente_banco: String,
+    /// Conteúdo definido pelo convênio.
+    #[fw(pos = "45..149", alpha)]
+    pub dados: String,
+    #[fw(pos = "150..150", numeric)]
+    pub codigo_movimento: u8,
+}
+
+/// Registro T: totais de um arquivo de débitos já processado.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroT {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..7", numeric)]
+    pub quantidade_registros: u32,
+    #[fw(pos = "8..24", numeric)]
+    pub valor_total: i64,
+    #[fw(pos = "25..150", filler)]
+    pub reservado: (),
+}
+
+/// Registro X: relação das agências do banco.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroX {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    #[fw(pos = "2..5", alpha)]
+    pub agencia: String,
+    #[fw(pos = "6..35", alpha)]
+    pub nome: String,
+    #[fw(pos = "36..65", alpha)]
+    pub endereco: String,
+    #[fw(pos = "66..70", alpha)]
+    pub numero: String,
+    #[fw(pos = "71..75", numeric)]
+    pub cep: u32,
+    #[fw(pos = "76..78", numeric)]
+    pub cep_sufixo: u16,
+    #[fw(pos = "79..98", alpha)]
+    pub cidade: String,
+    #[fw(pos = "99..100", alpha)]
+    pub uf: String,
+    /// `A` = ativa, `B` = encerrada.
+    #[fw(pos = "101..101", alpha)]
+    pub situacao: String,
+    #[fw(pos = "102..150", filler)]
+    pub reservado: (),
+}
+
+/// Registro Z: trailer do arquivo.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 150)]
+#[fw_record(write)]
+pub struct RegistroZ {
+    #[fw(pos = "1..1", alpha)]
+    pub codigo_registro: String,
+    /// Registros do arquivo, incluindo header e trailer.
+    #[fw(pos = "2..7", numeric)]
+    pub quantidade_registros: u32,
+    /// Soma dos valores dos registros E ou F, em centavos.
+    #[fw(pos = "8..24", numeric)]
+    pub valor_total: i64,
+    #[fw(pos = "25..150", filler)]
+    pub reservado: (),
+}
+
+/// Qualquer registro do layout, pelo código na posição 1.
+#[derive(Debug, Clone, PartialEq, FixedWidth)]
+#[fw(tag_pos = "1..1")]
+pub enum Registro {
+    #[fw(tag = "A")]
+    A(RegistroA),
+    #[fw(tag = "B")]
+    B(RegistroB),
+    #[fw(tag = "C")]
+    C(RegistroC),
+    #[fw(tag = "D")]
+    D(RegistroD),
+    #[fw(tag = "E")]
+    E(RegistroE),
+    #[fw(tag = "F")]
+    F(RegistroF),
+    #[fw(tag = "H")]
+    H(RegistroH),
+    #[fw(tag = "J")]
+    J(RegistroJ),
+    #[fw(tag = "K")]
+    K(RegistroK),
+    #[fw(tag = "T")]
+    T(RegistroT),
+    #[fw(tag = "X")]
+    X(RegistroX),
+    #[fw(tag = "Z")]
+    Z(RegistroZ),
+}
+
+/// Arquivo de débito automático completo, já conferido contra o trailer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArquivoDebito {
+    pub header: RegistroA,
+    /// Registros entre o header e o trailer, na ordem do arquivo.
+    pub registros: Vec<Registro>,
+    pub trailer: RegistroZ,
+}
+
+/// Layouts de todos os registros, identificados pelo código na posição 1.
+pub fn registry() -> LayoutRegistry {
+    const CODIGO: FieldPos = FieldPos::new_const(1, 1);
+    let mut registry = LayoutRegistry::new();
+    registry
+        .register(RecordLayout::of::<RegistroA>("registro_a").when(CODIGO, "A"))
+        .register(RecordLayout::of::<RegistroB>("registro_b").when(CODIGO, "B"))
+        .register(RecordLayout::of::<RegistroC>("registro_c").when(CODIGO, "C"))
+        .register(RecordLayout::of::<RegistroD>("registro_d").when(CODIGO, "D"))
+        .register(RecordLayout::of::<RegistroE>("registro_e").when(CODIGO, "E"))
+        .register(RecordLayout::of::<RegistroF>("registro_f").when(CODIGO, "F"))
+        .register(RecordLayout::of::<RegistroH>("registro_h").when(CODIGO, "H"))
+        .register(RecordLayout::of::<RegistroJ>("registro_j").when(CODIGO, "J"))
+        .register(RecordLayout::of::<RegistroK>("registro_k").when(CODIGO, "K"))
+        .register(RecordLayout::of::<RegistroT>("registro_t").when(CODIGO, "T"))
+        .register(RecordLayout::of::<RegistroX>("registro_x").when(CODIGO, "X"))
+        .register(RecordLayout::of::<RegistroZ>("registro_z").when(CODIGO, "Z"));
+    registry
+}
+
+/// Lê um arquivo FEBRABAN 150 completo.
+///
+/// Padrões: registros de 150 posições (quebras de linha entre eles são opcionais) em
+/// Latin-1. O arquivo deve começar com um registro A e terminar com um Z, cuja quantidade
+/// de registros e soma dos valores (registros E e F) devem bater com o arquivo; caso
+/// contrário, o erro é `FixedWidthError::Integrity`. Falhas de estrutura resultam em
+/// `FixedWidthError::Structure` e erros de campo vêm com o número do registro.
+pub fn parse_arquivo<R: BufRead>(reader: R) -> Result<ArquivoDebito> {
+    let options = ParseOptions::default();
+    let mut reader = RecordReader::new(reader, Framing::FixedLength(150));
+    let mut buf = Vec::with_capacity(150);
+    let structure = |line_number: u64, message: String| FixedWidthError::Structure { line_number, message };
+
+    let mut header = None;
+    let mut registros = Vec::new();
+    let mut trailer = None;
+    let mut valor_total = 0i64;
+    while reader.read_record(&mut buf)? {
+        let line_number = reader.records_read();
+        let registro = Registro::parse_bytes(&buf, &options)
+            .map_err(|e| e.at_line_with(line_number, &buf, &[], options.redaction))?;
+        if trailer.is_some() {
+            return Err(structure(line_number, "registro após o registro Z".to_string()));
+        }
+        match (registro, &header) {
+            (Registro::A(a), None) => header = Some(a),
+            (_, None) => return Err(structure(line_number, "arquivo sem registro A".to_string())),
+            (Registro::A(_), Some(_)) => return Err(structure(line_number, "registro A repetido".to_string())),
+            (Registro::Z(z), Some(_)) => {
+                TotalsCheck::new("RegistroZ")
+                    .check("quantidade_registros", z.quantidade_registros.into(), line_number as i64)
+                    .check("valor_total", z.valor_total, valor_total)
+                    .finish(line_number)?;
+                trailer = Some(z);
+            }
+            (registro, Some(_)) => {
+                if let Registro::E(RegistroE { valor, .. }) | Registro::F(RegistroF { valor, .. }) = &registro {
+                    valor_total += valor;
+                }
+                registros.push(registro);
+            }
+        }
+    }
+
+    let last = reader.records_read();
+    match (header, trailer) {
+        (Some(header), Some(trailer)) => Ok(ArquivoDebito { header, registros, trailer }),
+        (None, _) => Err(structure(last, "arquivo vazio".to_string())),
+        (Some(_), None) => Err(structure(last, "arquivo sem registro Z".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedWidthWrite;
+
+    #[test]
+    fn parses_a_debito_automatico_retorno() {
+        let gaps = [RegistroA::spec_gaps(), RegistroE::spec_gaps(), RegistroF::spec_gaps(), RegistroX::spec_gaps()];
+        assert!(gaps.iter().all(Vec::is_empty), "{gaps:?}");
+
+        let header = RegistroA {
+            codigo_registro: "A".into(),
+            codigo_remessa: 2,
+            convenio: "1234".into(),
+            banco: 1,
+            data_geracao: 20261016,
+            sequencial_arquivo: 7,
+            versao_layout: 5,
+            servico: "DEBITO AUTOMATICO".into(),
+            ..Default::default()
+        };
+        let debito = |cliente: &str, valor, codigo_retorno: &str| RegistroF {
+            codigo_registro: "F".into(),
+            cliente_empresa: cliente.into(),
+            data_debito: 20261015,
+            valor,
+            codigo_retorno: codigo_retorno.into(),
+            ..Default::default()
+        };
+        let trailer = |valor_total| RegistroZ {
+            codigo_registro: "Z".into(),
+            quantidade_registros: 4,
+            valor_total,
+            ..Default::default()
+        };
+        let arquivo = |valor_total| {
+            [
+                header.to_bytes().unwrap(),
+                debito("UC 0042", 15_990, "00").to_bytes().unwrap(),
+                debito("UC 0077", 8_050, "01").to_bytes().unwrap(),
+                trailer(valor_total).to_bytes().unwrap(),
+            ]
+            .join(&b"\r\n"[..])
+        };
+
+        let parsed = parse_arquivo(&arquivo(24_040)[..]).unwrap();
+        assert_eq!((parsed.header.sequencial_arquivo, parsed.registros.len()), (7, 2));
+        assert!(matches!(&parsed.registros[1], Registro::F(f) if f.cliente_empresa == "UC 0077" && f.codigo_retorno == "01"));
+
+        let err = parse_arquivo(&arquivo(15_990)[..]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "linha 4: totais do trailer não conferem: RegistroZ.valor_total informa 15990, lido 24040"
+        );
+    }
+}
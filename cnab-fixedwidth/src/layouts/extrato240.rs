@@ -0,0 +1,380 @@
+//! # FEBRABAN 240 — Extrato para Conciliação Bancária
+//!
+//! Extrato eletrônico CNAB 240 (serviço `04`): um lote por conta, com o saldo inicial no
+//! header de lote, um segmento E por lançamento e os saldos finais e totais no trailer
+//! de lote. [`parse_extrato`] lê o arquivo inteiro e devolve um [`Extrato`] com valores
+//! já com sinal (débitos negativos), a partir do indicador D/C de cada valor.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::layouts::extrato240;
+//!
+//! let file = std::io::BufReader::new(std::fs::File::open("extrato.ret")?);
+//! for conta in extrato240::parse_extrato(file)?.contas {
+//!     let movimento: i64 = conta.lancamentos.iter().map(|l| l.valor).sum();
+//!     println!("{} {} -> {}", conta.conta, conta.saldo_inicial, conta.saldo_inicial + movimento);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Header e trailer de arquivo são os de [`crate::cnab240`]. Valores são centavos e datas
+//! `DDMMAAAA`.
+
+use crate::cnab240::{HeaderArquivo, TrailerArquivo};
+use crate::reader::{Framing, RecordReader};
+use crate::{FixedWidth, FixedWidthError, FixedWidthParse, ParseOptions, Result, TotalsCheck};
+use std::io::BufRead;
+
+/// Header de lote do extrato (registro tipo `1`), com o saldo inicial da conta.
+#[derive(Debug, Clone, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+pub struct HeaderLoteExtrato {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    /// `E` = extrato.
+    #[fw(pos = "9..9", alpha)]
+    pub operacao: String,
+    /// `04` = extrato para conciliação.
+    #[fw(pos = "10..11", numeric)]
+    pub servico: u8,
+    #[fw(pos = "12..13", numeric)]
+    pub forma_lancamento: u8,
+    #[fw(pos = "14..16", numeric)]
+    pub versao_layout: u16,
+    #[fw(pos = "17..17", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "18..18", numeric)]
+    pub tipo_inscricao: u8,
+    #[fw(pos = "19..32", numeric)]
+    pub numero_inscricao: u64,
+    #[fw(pos = "33..52", alpha)]
+    pub convenio: String,
+    #[fw(pos = "53..57", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "58..58", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "59..70", numeric)]
+    pub conta: u64,
+    #[fw(pos = "71..71", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "72..72", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "73..102", alpha)]
+    pub nome_empresa: String,
+    #[fw(pos = "103..142", filler)]
+    pub uso_febraban_2: (),
+    #[fw(pos = "143..150", numeric)]
+    pub data_saldo_inicial: u32,
+    /// Saldo inicial, negativo quando devedor.
+    #[fw(pos = "151..168", numeric, sign_pos = "169..169", credit = "C")]
+    pub saldo_inicial: i64,
+    /// `D` = devedor, `C` = credor.
+    #[fw(pos = "169..169", alpha)]
+    pub situacao_saldo_inicial: String,
+    /// `P` = parcial, `F` = final, `I` = intradia.
+    #[fw(pos = "170..170", alpha)]
+    pub posicao_saldo_inicial: String,
+    #[fw(pos = "171..173", alpha)]
+    pub moeda: String,
+    #[fw(pos = "174..178", numeric)]
+    pub sequencia_extrato: u32,
+    #[fw(pos = "179..240", filler)]
+    pub uso_febraban_3: (),
+}
+
+/// Segmento E: um lançamento na conta.
+#[derive(Debug, Clone, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+pub struct SegmentoE {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..17", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "18..18", numeric)]
+    pub tipo_inscricao: u8,
+    #[fw(pos = "19..32", numeric)]
+    pub numero_inscricao: u64,
+    #[fw(pos = "33..52", alpha)]
+    pub convenio: String,
+    #[fw(pos = "53..57", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "58..58", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "59..70", numeric)]
+    pub conta: u64,
+    #[fw(pos = "71..71", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "72..72", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "73..102", alpha)]
+    pub nome_empresa: String,
+    #[fw(pos = "103..108", filler)]
+    pub uso_febraban_2: (),
+    /// Natureza do lançamento (ex: `DPV` = disponível, `SCR` = a compensar).
+    #[fw(pos = "109..111", alpha)]
+    pub natureza: String,
+    #[fw(pos = "112..113", numeric)]
+    pub tipo_complemento: u8,
+    #[fw(pos = "114..133", alpha)]
+    pub complemento: String,
+    /// `S` = isento de CPMF, `N` = não isento.
+    #[fw(pos = "134..134", alpha)]
+    pub isencao_cpmf: String,
+    #[fw(pos = "135..142", numeric)]
+    pub data_contabil: u32,
+    #[fw(pos = "143..150", numeric)]
+    pub data_lancamento: u32,
+    /// Valor do lançamento, negativo nos débitos.
+    #[fw(pos = "151..168", numeric, sign_pos = "169..169", credit = "C")]
+    pub valor: i64,
+    /// `D` = débito, `C` = crédito.
+    #[fw(pos = "169..169", alpha)]
+    pub tipo_lancamento: String,
+    /// Categoria do lançamento (ex: 101 = cheques, 201 = depósitos).
+    #[fw(pos = "170..172", numeric)]
+    pub categoria: u16,
+    #[fw(pos = "173..176", alpha)]
+    pub codigo_historico: String,
+    #[fw(pos = "177..201", alpha)]
+    pub historico: String,
+    #[fw(pos = "202..240", alpha)]
+    pub documento: String,
+}
+
+/// Trailer de lote do extrato (registro tipo `5`), com o saldo final e os totais.
+#[derive(Debug, Clone, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+pub struct TrailerLoteExtrato {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..17", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "18..18", numeric)]
+    pub tipo_inscricao: u8,
+    #[fw(pos = "19..32", numeric)]
+    pub numero_inscricao: u64,
+    #[fw(pos = "33..52", alpha)]
+    pub convenio: String,
+    #[fw(pos = "53..57", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "58..58", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "59..70", numeric)]
+    pub conta: u64,
+    #[fw(pos = "71..71", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "72..72", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "73..88", filler)]
+    pub uso_febraban_2: (),
+    #[fw(pos = "89..106", numeric)]
+    pub vinculado_dia_anterior: i64,
+    #[fw(pos = "107..124", numeric)]
+    pub limite: i64,
+    #[fw(pos = "125..142", numeric)]
+    pub saldo_bloqueado: i64,
+    #[fw(pos = "143..150", numeric)]
+    pub data_saldo_final: u32,
+    /// Saldo final, negativo quando devedor.
+    #[fw(pos = "151..168", numeric, sign_pos = "169..169", credit = "C")]
+    pub saldo_final: i64,
+    #[fw(pos = "169..169", alpha)]
+    pub situacao_saldo_final: String,
+    #[fw(pos = "170..170", alpha)]
+    pub posicao_saldo_final: String,
+    /// Registros do lote, incluindo header e trailer.
+    #[fw(pos = "171..176", numeric)]
+    pub quantidade_registros: u32,
+    /// Soma dos débitos do lote (sem sinal).
+    #[fw(pos = "177..194", numeric)]
+    pub total_debitos: i64,
+    /// Soma dos créditos do lote.
+    #[fw(pos = "195..212", numeric)]
+    pub total_creditos: i64,
+    #[fw(pos = "213..240", filler)]
+    pub uso_febraban_3: (),
+}
+
+/// Um lançamento do extrato.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lancamento {
+    pub data_lancamento: u32,
+    pub data_contabil: u32,
+    /// Valor com sinal: negativo nos débitos, positivo nos créditos.
+    pub valor: i64,
+    pub natureza: String,
+    pub categoria: u16,
+    pub codigo_historico: String,
+    pub historico: String,
+    pub documento: String,
+}
+
+impl From<SegmentoE> for Lancamento {
+    fn from(e: SegmentoE) -> Self {
+        Self {
+            data_lancamento: e.data_lancamento,
+            data_contabil: e.data_contabil,
+            valor: e.valor,
+            natureza: e.natureza,
+            categoria: e.categoria,
+            codigo_historico: e.codigo_historico,
+            historico: e.historico,
+            documento: e.documento,
+        }
+    }
+}
+
+/// Extrato de uma conta (um lote do arquivo).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtratoConta {
+    pub agencia: u32,
+    pub conta: u64,
+    pub conta_dv: String,
+    pub data_saldo_inicial: u32,
+    /// Saldo inicial com sinal (negativo quando devedor).
+    pub saldo_inicial: i64,
+    pub lancamentos: Vec<Lancamento>,
+    pub data_saldo_final: u32,
+    /// Saldo final com sinal (negativo quando devedor).
+    pub saldo_final: i64,
+}
+
+/// Extrato completo: uma entrada por conta, na ordem do arquivo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extrato {
+    pub header: HeaderArquivo,
+    pub contas: Vec<ExtratoConta>,
+    pub trailer: TrailerArquivo,
+}
+
+/// Lê um extrato eletrônico CNAB 240 completo.
+///
+/// Padrões: registros de 240 posições (quebras de linha entre eles são opcionais) em
+/// Latin-1. A quantidade de registros e as somas de débitos e créditos de cada lote são
+/// conferidas contra o trailer de lote (divergências resultam em
+/// `FixedWidthError::Integrity`); falhas de estrutura resultam em
+/// `FixedWidthError::Structure` e erros de campo vêm com o número do registro.
+pub fn parse_extrato<R: BufRead>(reader: R) -> Result<Extrato> {
+    let options = ParseOptions::default();
+    let mut reader = RecordReader::new(reader, Framing::FixedLength(240));
+    let mut buf = Vec::with_capacity(240);
+    let structure = |line_number: u64, message: &str| FixedWidthError::Structure {
+        line_number,
+        message: message.to_string(),
+    };
+
+    let mut header = None;
+    let mut contas = Vec::new();
+    // Header do lote aberto, lançamentos e registros lidos
+    let mut lote: Option<(HeaderLoteExtrato, Vec<Lancamento>, u32)> = None;
+    let mut trailer = None;
+    while reader.read_record(&mut buf)? {
+        let line_number = reader.records_read();
+        let parse_error = |e: FixedWidthError| e.at_line_with(line_number, &buf, &[], options.redaction);
+        if trailer.is_some() {
+            return Err(structure(line_number, "registro após o trailer de arquivo"));
+        }
+        match (buf.get(7), &mut lote) {
+            (Some(b'0'), None) if header.is_none() => {
+                header = Some(HeaderArquivo::parse_bytes(&buf, &options).map_err(parse_error)?);
+            }
+            (_, _) if header.is_none() => return Err(structure(line_number, "arquivo sem header de arquivo")),
+            (Some(b'1'), None) => {
+                let header = HeaderLoteExtrato::parse_bytes(&buf, &options).map_err(parse_error)?;
+                lote = Some((header, Vec::new(), 1));
+            }
+            (Some(b'3'), Some((_, lancamentos, registros))) => {
+                *registros += 1;
+                if buf.get(13) == Some(&b'E') {
+                    lancamentos.push(SegmentoE::parse_bytes(&buf, &options).map_err(parse_error)?.into());
+                }
+            }
+            (Some(b'5'), Some((_, lancamentos, registros))) => {
+                let trailer = TrailerLoteExtrato::parse_bytes(&buf, &options).map_err(parse_error)?;
+                let registros = *registros + 1;
+                let debitos: i64 = lancamentos.iter().filter(|l| l.valor < 0).map(|l| -l.valor).sum();
+                let creditos: i64 = lancamentos.iter().filter(|l| l.valor > 0).map(|l| l.valor).sum();
+                TotalsCheck::new("TrailerLoteExtrato")
+                    .check("quantidade_registros", trailer.quantidade_registros.into(), registros.into())
+                    .check("total_debitos", trailer.total_debitos, debitos)
+                    .check("total_creditos", trailer.total_creditos, creditos)
+                    .finish(line_number)?;
+                let (header, lancamentos, _) = lote.take().expect("lote aberto");
+                contas.push(ExtratoConta {
+                    agencia: header.agencia,
+                    conta: header.conta,
+                    conta_dv: header.conta_dv,
+                    data_saldo_inicial: header.data_saldo_inicial,
+                    saldo_inicial: header.saldo_inicial,
+                    lancamentos,
+                    data_saldo_final: trailer.data_saldo_final,
+                    saldo_final: trailer.saldo_final,
+                });
+            }
+            (Some(b'9'), None) => trailer = Some(TrailerArquivo::parse_bytes(&buf, &options).map_err(parse_error)?),
+            (Some(b'3' | b'5'), None) => return Err(structure(line_number, "registro de detalhe fora de um lote")),
+            (Some(b'0' | b'1' | b'9'), Some(_)) => return Err(structure(line_number, "lote sem trailer de lote")),
+            _ => return Err(structure(line_number, "tipo de registro desconhecido")),
+        }
+    }
+
+    let last = reader.records_read();
+    match (header, trailer) {
+        (Some(header), Some(trailer)) => Ok(Extrato { header, contas, trailer }),
+        (None, _) => Err(structure(last, "arquivo vazio")),
+        (Some(_), None) => Err(structure(last, "arquivo sem trailer de arquivo")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::record;
+
+    #[test]
+    fn statement_amounts_follow_the_debit_credit_indicator() {
+        let gaps = [HeaderLoteExtrato::spec_gaps(), SegmentoE::spec_gaps(), TrailerLoteExtrato::spec_gaps()];
+        assert!(gaps.iter().all(Vec::is_empty), "{gaps:?}");
+
+        let lancamento = |seq: &str, valor: &str, dc: &str| {
+            record(240, &[(1, "00100013"), (9, seq), (14, "E"), (143, "15102026"), (151, valor), (169, dc), (177, "PIX RECEBIDO             ")])
+        };
+        let arquivo = |total_creditos: &str| {
+            [
+                record(240, &[(1, "00100000")]),
+                record(240, &[(1, "00100011E04"), (59, "000000012345"), (143, "14102026"), (151, "000000000000100000"), (169, "D")]),
+                lancamento("00001", "000000000000025000", "C"),
+                lancamento("00002", "000000000000005050", "D"),
+                record(240, &[(1, "00100015"), (151, "000000000000080050"), (169, "D"), (171, "000004"), (177, "000000000000005050"), (195, total_creditos)]),
+                record(240, &[(1, "00199999")]),
+            ]
+            .join("\n")
+        };
+
+        let extrato = parse_extrato(arquivo("000000000000025000").as_bytes()).unwrap();
+        let conta = &extrato.contas[0];
+        assert_eq!((conta.conta, conta.saldo_inicial, conta.saldo_final), (12345, -100_000, -80_050));
+        let valores: Vec<_> = conta.lancamentos.iter().map(|l| l.valor).collect();
+        assert_eq!(valores, [25_000, -5_050]);
+        assert_eq!(conta.lancamentos[0].historico, "PIX RECEBIDO");
+        assert_eq!(conta.saldo_inicial + valores.iter().sum::<i64>(), conta.saldo_final);
+
+        let err = parse_extrato(arquivo("000000000000025001").as_bytes()).unwrap_err();
+        assert!(matches!(&err, FixedWidthError::Integrity { line_number: 5, errors } if errors[0].field == "total_creditos"), "{err}");
+    }
+}
@@ -0,0 +1,601 @@
+//! # FEBRABAN 240 — Pagamentos
+//!
+//! Registros do CNAB 240 de pagamento a fornecedores, salários e tributos (v10): header
+//! e trailer de lote de pagamento e os segmentos de cada forma de lançamento:
+//! - A, B e C: crédito em conta, DOC/TED e PIX, com dados complementares do favorecido;
+//! - J e J-52: liquidação de boletos (e QR Code PIX), com pagador e beneficiário;
+//! - N: tributos sem código de barras; O: contas e tributos com código de barras;
+//! - W: informações complementares do tributo.
+//!
+//! Header e trailer de arquivo são os de [`crate::cnab240`]. Valores monetários são
+//! centavos, quantidades de moeda têm 5 casas implícitas e datas são `DDMMAAAA`.
+
+use crate::cnab240::{Detalhe, TotaisLote, TrailerLote240};
+use crate::FixedWidth;
+
+pub use crate::cnab240::{HeaderArquivo, TrailerArquivo};
+
+/// Header de lote de pagamento (registro tipo `1`).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct HeaderLotePagamento {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    /// `C` = lançamento a crédito.
+    #[fw(pos = "9..9", alpha)]
+    pub operacao: String,
+    /// Tipo de serviço (ex: 20 = pagamento a fornecedor, 30 = salários, 22 = tributos).
+    #[fw(pos = "10..11", numeric)]
+    pub servico: u8,
+    /// Forma de lançamento (ex: 01 = crédito em conta, 41 = TED, 31 = títulos de outros bancos).
+    #[fw(pos = "12..13", numeric)]
+    pub forma_lancamento: u8,
+    #[fw(pos = "14..16", numeric)]
+    pub versao_layout: u16,
+    #[fw(pos = "17..17", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "18..18", numeric)]
+    pub tipo_inscricao: u8,
+    #[fw(pos = "19..32", numeric)]
+    pub numero_inscricao: u64,
+    #[fw(pos = "33..52", alpha)]
+    pub convenio: String,
+    #[fw(pos = "53..57", numeric)]
+    pub agencia: u32,
+    #[fw(pos = "58..58", alpha)]
+    pub agencia_dv: String,
+    #[fw(pos = "59..70", numeric)]
+    pub conta: u64,
+    #[fw(pos = "71..71", alpha)]
+    pub conta_dv: String,
+    #[fw(pos = "72..72", alpha)]
+    pub agencia_conta_dv: String,
+    #[fw(pos = "73..102", alpha)]
+    pub nome_empresa: String,
+    #[fw(pos = "103..142", alpha)]
+    pub mensagem: String,
+    #[fw(pos = "143..172", alpha)]
+    pub logradouro: String,
+    #[fw(pos = "173..177", numeric)]
+    pub numero: u32,
+    #[fw(pos = "178..192", alpha)]
+    pub complemento: String,
+    #[fw(pos = "193..212", alpha)]
+    pub cidade: String,
+    #[fw(pos = "213..217", numeric)]
+    pub cep: u32,
+    #[fw(pos = "218..220", alpha)]
+    pub cep_complemento: String,
+    #[fw(pos = "221..222", alpha)]
+    pub uf: String,
+    /// Indicativo da forma de pagamento do serviço.
+    #[fw(pos = "223..224", numeric)]
+    pub forma_pagamento: u8,
+    #[fw(pos = "225..230", filler)]
+    pub uso_febraban_2: (),
+    /// Códigos de ocorrência do retorno, até 5 de 2 posições.
+    #[fw(pos = "231..240", alpha)]
+    pub ocorrencias: String,
+}
+
+/// Segmento A: crédito em conta, DOC/TED ou PIX para o favorecido.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoA {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    /// 0 = inclusão, 5 = alteração, 9 = exclusão.
+    #[fw(pos = "15..15", numeric)]
+    pub tipo_movimento: u8,
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_instrucao: u8,
+    /// Câmara centralizadora (ex: 018 = TED, 700 = DOC, 009 = PIX).
+    #[fw(pos = "18..20", numeric)]
+    pub camara: u16,
+    #[fw(pos = "21..23", numeric)]
+    pub favorecido_banco: u16,
+    #[fw(pos = "24..28", numeric)]
+    pub favorecido_agencia: u32,
+    #[fw(pos = "29..29", alpha)]
+    pub favorecido_agencia_dv: String,
+    #[fw(pos = "30..41", numeric)]
+    pub favorecido_conta: u64,
+    #[fw(pos = "42..42", alpha)]
+    pub favorecido_conta_dv: String,
+    #[fw(pos = "43..43", alpha)]
+    pub favorecido_agencia_conta_dv: String,
+    #[fw(pos = "44..73", alpha)]
+    pub favorecido_nome: String,
+    /// Número atribuído pela empresa ao pagamento.
+    #[fw(pos = "74..93", alpha)]
+    pub seu_numero: String,
+    #[fw(pos = "94..101", numeric)]
+    pub data_pagamento: u32,
+    /// `BRL`.
+    #[fw(pos = "102..104", alpha)]
+    pub tipo_moeda: String,
+    /// Quantidade de moeda, com 5 casas implícitas.
+    #[fw(pos = "105..119", numeric)]
+    pub quantidade_moeda: i64,
+    /// Valor do pagamento, em centavos.
+    #[fw(pos = "120..134", numeric)]
+    pub valor_pagamento: i64,
+    /// Número atribuído pelo banco, devolvido no retorno.
+    #[fw(pos = "135..154", alpha)]
+    pub nosso_numero: String,
+    #[fw(pos = "155..162", numeric)]
+    pub data_real: u32,
+    #[fw(pos = "163..177", numeric)]
+    pub valor_real: i64,
+    #[fw(pos = "178..217", alpha)]
+    pub informacoes: String,
+    #[fw(pos = "218..219", alpha)]
+    pub finalidade_doc: String,
+    #[fw(pos = "220..224", alpha)]
+    pub finalidade_ted: String,
+    #[fw(pos = "225..226", alpha)]
+    pub finalidade_complementar: String,
+    #[fw(pos = "227..229", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "230..230", numeric)]
+    pub aviso_favorecido: u8,
+    #[fw(pos = "231..240", alpha)]
+    pub ocorrencias: String,
+}
+
+/// Segmento B: endereço e documento do favorecido, complemento do segmento A.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoB {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..17", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "18..18", numeric)]
+    pub favorecido_tipo_inscricao: u8,
+    #[fw(pos = "19..32", numeric)]
+    pub favorecido_inscricao: u64,
+    #[fw(pos = "33..62", alpha)]
+    pub logradouro: String,
+    #[fw(pos = "63..67", numeric)]
+    pub numero: u32,
+    #[fw(pos = "68..82", alpha)]
+    pub complemento: String,
+    #[fw(pos = "83..97", alpha)]
+    pub bairro: String,
+    #[fw(pos = "98..117", alpha)]
+    pub cidade: String,
+    #[fw(pos = "118..122", numeric)]
+    pub cep: u32,
+    #[fw(pos = "123..125", alpha)]
+    pub cep_complemento: String,
+    #[fw(pos = "126..127", alpha)]
+    pub uf: String,
+    #[fw(pos = "128..135", numeric)]
+    pub vencimento: u32,
+    #[fw(pos = "136..150", numeric)]
+    pub valor_documento: i64,
+    #[fw(pos = "151..165", numeric)]
+    pub valor_abatimento: i64,
+    #[fw(pos = "166..180", numeric)]
+    pub valor_desconto: i64,
+    #[fw(pos = "181..195", numeric)]
+    pub valor_mora: i64,
+    #[fw(pos = "196..210", numeric)]
+    pub valor_multa: i64,
+    #[fw(pos = "211..225", alpha)]
+    pub documento_favorecido: String,
+    #[fw(pos = "226..226", numeric)]
+    pub aviso_favorecido: u8,
+    #[fw(pos = "227..232", numeric)]
+    pub codigo_ug_siape: u32,
+    /// ISPB do banco do favorecido (obrigatório em TED e PIX).
+    #[fw(pos = "233..240", numeric)]
+    pub ispb: u32,
+}
+
+/// Segmento C: deduções, acréscimos e conta substituta, complemento do segmento A.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoC {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..17", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "18..32", numeric)]
+    pub valor_ir: i64,
+    #[fw(pos = "33..47", numeric)]
+    pub valor_iss: i64,
+    #[fw(pos = "48..62", numeric)]
+    pub valor_iof: i64,
+    #[fw(pos = "63..77", numeric)]
+    pub outras_deducoes: i64,
+    #[fw(pos = "78..92", numeric)]
+    pub outros_acrescimos: i64,
+    #[fw(pos = "93..97", numeric)]
+    pub agencia_substituta: u32,
+    #[fw(pos = "98..98", alpha)]
+    pub agencia_substituta_dv: String,
+    #[fw(pos = "99..110", numeric)]
+    pub conta_substituta: u64,
+    #[fw(pos = "111..111", alpha)]
+    pub conta_substituta_dv: String,
+    #[fw(pos = "112..112", alpha)]
+    pub agencia_conta_substituta_dv: String,
+    #[fw(pos = "113..127", numeric)]
+    pub valor_inss: i64,
+    #[fw(pos = "128..240", filler)]
+    pub uso_febraban_2: (),
+}
+
+/// Segmento J: liquidação de título (boleto) pelo código de barras.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoJ {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", numeric)]
+    pub tipo_movimento: u8,
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_instrucao: u8,
+    /// Código de barras do boleto (44 dígitos, não a linha digitável).
+    #[fw(pos = "18..61", alpha)]
+    pub codigo_barras: String,
+    #[fw(pos = "62..91", alpha)]
+    pub nome_beneficiario: String,
+    #[fw(pos = "92..99", numeric)]
+    pub vencimento: u32,
+    #[fw(pos = "100..114", numeric)]
+    pub valor_titulo: i64,
+    #[fw(pos = "115..129", numeric)]
+    pub valor_desconto_abatimento: i64,
+    #[fw(pos = "130..144", numeric)]
+    pub valor_mora_multa: i64,
+    #[fw(pos = "145..152", numeric)]
+    pub data_pagamento: u32,
+    #[fw(pos = "153..167", numeric)]
+    pub valor_pagamento: i64,
+    #[fw(pos = "168..182", numeric)]
+    pub quantidade_moeda: i64,
+    #[fw(pos = "183..202", alpha)]
+    pub seu_numero: String,
+    #[fw(pos = "203..222", alpha)]
+    pub nosso_numero: String,
+    #[fw(pos = "223..224", numeric)]
+    pub codigo_moeda: u8,
+    #[fw(pos = "225..230", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "231..240", alpha)]
+    pub ocorrencias: String,
+}
+
+/// Segmento J-52: pagador, beneficiário e sacador do título (e dados do QR Code PIX),
+/// complemento do segmento J.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoJ52 {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_movimento: u8,
+    /// `52`.
+    #[fw(pos = "18..19", numeric)]
+    pub registro_opcional: u8,
+    #[fw(pos = "20..20", numeric)]
+    pub pagador_tipo_inscricao: u8,
+    #[fw(pos = "21..35", numeric)]
+    pub pagador_inscricao: u64,
+    #[fw(pos = "36..75", alpha)]
+    pub pagador_nome: String,
+    #[fw(pos = "76..76", numeric)]
+    pub beneficiario_tipo_inscricao: u8,
+    #[fw(pos = "77..91", numeric)]
+    pub beneficiario_inscricao: u64,
+    #[fw(pos = "92..131", alpha)]
+    pub beneficiario_nome: String,
+    #[fw(pos = "132..132", numeric)]
+    pub sacador_tipo_inscricao: u8,
+    #[fw(pos = "133..147", numeric)]
+    pub sacador_inscricao: u64,
+    #[fw(pos = "148..187", alpha)]
+    pub sacador_nome: String,
+    /// Chave ou URL do QR Code PIX (J-52 PIX); brancos nos boletos.
+    #[fw(pos = "188..240", alpha)]
+    pub pix: String,
+}
+
+/// Segmento N: pagamento de tributo sem código de barras (DARF, GPS, GARE...).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoN {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", numeric)]
+    pub tipo_movimento: u8,
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_instrucao: u8,
+    #[fw(pos = "18..37", alpha)]
+    pub seu_numero: String,
+    #[fw(pos = "38..57", alpha)]
+    pub nosso_numero: String,
+    #[fw(pos = "58..87", alpha)]
+    pub nome_contribuinte: String,
+    #[fw(pos = "88..95", numeric)]
+    pub data_pagamento: u32,
+    #[fw(pos = "96..110", numeric)]
+    pub valor_pagamento: i64,
+    /// Campos do tributo, com layout próprio para cada um (código da receita, competência...).
+    #[fw(pos = "111..230", alpha)]
+    pub informacoes_tributo: String,
+    #[fw(pos = "231..240", alpha)]
+    pub ocorrencias: String,
+}
+
+/// Segmento O: pagamento de conta de concessionária ou tributo com código de barras.
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoO {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    #[fw(pos = "15..15", numeric)]
+    pub tipo_movimento: u8,
+    #[fw(pos = "16..17", numeric)]
+    pub codigo_instrucao: u8,
+    #[fw(pos = "18..61", alpha)]
+    pub codigo_barras: String,
+    #[fw(pos = "62..91", alpha)]
+    pub nome_concessionaria: String,
+    #[fw(pos = "92..99", numeric)]
+    pub vencimento: u32,
+    #[fw(pos = "100..107", numeric)]
+    pub data_pagamento: u32,
+    #[fw(pos = "108..122", numeric)]
+    pub valor_pagamento: i64,
+    #[fw(pos = "123..142", alpha)]
+    pub seu_numero: String,
+    #[fw(pos = "143..162", alpha)]
+    pub nosso_numero: String,
+    #[fw(pos = "163..230", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "231..240", alpha)]
+    pub ocorrencias: String,
+}
+
+/// Segmento W: informações complementares do pagamento de tributo (ex: FGTS).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct SegmentoW {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..13", numeric)]
+    pub sequencial: u32,
+    #[fw(pos = "14..14", alpha)]
+    pub segmento: String,
+    /// Número do registro complementar (1 ou 2).
+    #[fw(pos = "15..15", numeric)]
+    pub complementar: u8,
+    /// Uso das informações 1 e 2 (ex: 1 = impressas no comprovante).
+    #[fw(pos = "16..16", numeric)]
+    pub uso_informacoes: u8,
+    #[fw(pos = "17..56", alpha)]
+    pub informacao_1: String,
+    #[fw(pos = "57..96", alpha)]
+    pub informacao_2: String,
+    #[fw(pos = "97..146", alpha)]
+    pub informacoes_tributo: String,
+    #[fw(pos = "147..230", filler)]
+    pub uso_febraban: (),
+    #[fw(pos = "231..240", alpha)]
+    pub ocorrencias: String,
+}
+
+/// Trailer de lote de pagamento (registro tipo `5`).
+#[derive(Debug, Clone, Default, PartialEq, FixedWidth)]
+#[fw(width = 240)]
+#[fw_record(write)]
+pub struct TrailerLotePagamento {
+    #[fw(pos = "1..3", numeric)]
+    pub banco: u16,
+    #[fw(pos = "4..7", numeric)]
+    pub lote: u16,
+    #[fw(pos = "8..8", numeric)]
+    pub tipo_registro: u8,
+    #[fw(pos = "9..17", filler)]
+    pub uso_febraban: (),
+    /// Registros do lote, incluindo header e trailer.
+    #[fw(pos = "18..23", numeric)]
+    pub quantidade_registros: u32,
+    /// Soma dos valores dos pagamentos, em centavos.
+    #[fw(pos = "24..41", numeric)]
+    pub valor_total: i64,
+    /// Soma das quantidades de moeda, com 5 casas implícitas.
+    #[fw(pos = "42..59", numeric)]
+    pub quantidade_moeda: i64,
+    #[fw(pos = "60..65", numeric)]
+    pub aviso_debito: u32,
+    #[fw(pos = "66..230", filler)]
+    pub uso_febraban_2: (),
+    #[fw(pos = "231..240", alpha)]
+    pub ocorrencias: String,
+}
+
+impl Detalhe for SegmentoA {
+    fn valor_lote(&self) -> Option<i64> {
+        Some(self.valor_pagamento)
+    }
+}
+
+impl Detalhe for SegmentoJ {
+    fn valor_lote(&self) -> Option<i64> {
+        Some(self.valor_pagamento)
+    }
+}
+
+impl Detalhe for SegmentoN {
+    fn valor_lote(&self) -> Option<i64> {
+        Some(self.valor_pagamento)
+    }
+}
+
+impl Detalhe for SegmentoO {
+    fn valor_lote(&self) -> Option<i64> {
+        Some(self.valor_pagamento)
+    }
+}
+
+impl Detalhe for SegmentoB {}
+impl Detalhe for SegmentoC {}
+impl Detalhe for SegmentoJ52 {}
+impl Detalhe for SegmentoW {}
+
+impl TrailerLote240 for TrailerLotePagamento {
+    fn from_totais(totais: &TotaisLote) -> Self {
+        TrailerLotePagamento {
+            banco: totais.banco,
+            lote: totais.lote,
+            tipo_registro: 5,
+            quantidade_registros: totais.quantidade_registros,
+            valor_total: totais.valor_total,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixedWidthParse, FixedWidthWrite};
+
+    #[test]
+    fn pagamento_segments_cover_the_record() {
+        let gaps = [
+            HeaderLotePagamento::spec_gaps(),
+            SegmentoA::spec_gaps(),
+            SegmentoB::spec_gaps(),
+            SegmentoC::spec_gaps(),
+            SegmentoJ::spec_gaps(),
+            SegmentoJ52::spec_gaps(),
+            SegmentoN::spec_gaps(),
+            SegmentoO::spec_gaps(),
+            SegmentoW::spec_gaps(),
+            TrailerLotePagamento::spec_gaps(),
+        ];
+        assert!(gaps.iter().all(Vec::is_empty), "{gaps:?}");
+
+        let ted = SegmentoA {
+            banco: 1,
+            lote: 1,
+            tipo_registro: 3,
+            sequencial: 1,
+            segmento: "A".into(),
+            camara: 18,
+            favorecido_banco: 341,
+            favorecido_nome: "FORNECEDOR LTDA".into(),
+            data_pagamento: 16102026,
+            tipo_moeda: "BRL".into(),
+            valor_pagamento: 152_000,
+            ..Default::default()
+        };
+        let line = ted.to_line().unwrap();
+        assert_eq!(&line[..23], "0010001300001A000018341");
+        assert_eq!(&line[93..134], "16102026BRL000000000000000000000000152000");
+        assert_eq!(SegmentoA::parse(&line).unwrap(), ted);
+
+        let boleto = SegmentoJ {
+            segmento: "J".into(),
+            codigo_barras: "34191790010104351004791020150008291070026000".into(),
+            valor_pagamento: 26_000,
+            ..Default::default()
+        };
+        let line = boleto.to_line().unwrap();
+        assert_eq!(&line[17..61], boleto.codigo_barras);
+        assert_eq!(SegmentoJ::parse(&line).unwrap(), boleto);
+
+        // Lote escrito pelo FileWriter: o trailer soma os pagamentos, não os complementos
+        let mut writer = crate::cnab240::FileWriter::new(Vec::new(), HeaderArquivo { banco: 1, ..Default::default() }).unwrap();
+        writer.begin_lote(&HeaderLotePagamento { operacao: "C".into(), ..Default::default() }).unwrap();
+        writer.append(&ted).unwrap();
+        writer.append(&SegmentoB { segmento: "B".into(), ..Default::default() }).unwrap();
+        writer.append(&boleto).unwrap();
+        let trailer: TrailerLotePagamento = writer.end_lote().unwrap();
+        assert_eq!((trailer.quantidade_registros, trailer.valor_total), (5, 178_000));
+        let file = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<_> = file.lines().collect();
+        assert_eq!((&lines[5][..23], &lines[6][..29]), ("00100015         000005", "00199999         000001000007"));
+    }
+}
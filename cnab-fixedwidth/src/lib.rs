@@ -5,9 +5,71 @@
 //!
 //! O foco deste core é a **extração segura e tipada** dos dados, delegando validações
 //! de negócio (CPF, datas, lógica de banco) para a camada superior.
-pub use cnab_derive::FixedWidth;
+pub use cnab_derive::{FixedWidth, FixedWidthCode};
 
-use std::collections::HashMap;
+// Permite usar a macro derive dentro do próprio crate (o código gerado referencia `cnab_fixedwidth::`).
+extern crate self as cnab_fixedwidth;
+
+#[cfg(feature = "anonymize")]
+pub mod anonymize;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod banks;
+pub mod boleto;
+pub mod cnab240;
+pub mod convert;
+pub mod converter;
+pub mod cursor;
+pub mod debug;
+pub mod detect;
+pub mod diff;
+#[cfg(feature = "docs-br")]
+pub mod docs_br;
+pub mod dv;
+pub mod homologation;
+mod json;
+pub mod layout;
+pub mod layouts;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "translit")]
+pub mod normalize;
+pub mod nosso_numero;
+pub mod ocorrencias;
+pub mod output;
+#[cfg(feature = "polars")]
+pub mod polars;
+pub mod preview;
+pub mod query;
+pub mod reader;
+pub mod reconcile;
+#[cfg(feature = "remessa")]
+pub mod remessa;
+pub mod registry;
+pub mod retorno;
+pub mod scaffold;
+pub mod segments;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "sql")]
+pub mod sql;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+pub mod tools;
+pub mod validation;
+pub mod writer;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+pub use converter::ConverterRegistry;
+pub use cursor::FieldCursor;
+pub use json::CanonicalJson;
+pub use layout::{DynRecord, Layout, LayoutBuilder, LayoutError};
+pub use reader::{Framing, Lifecycle, RecordReader};
+pub use registry::{CnabFormat, FileLayoutRegistry, LayoutKey, LayoutRegistry, RecordLayout};
+pub use writer::{FixedWidthWrite, Overflow, RawRecord, WriteOptions, WritePolicy};
+
+use std::borrow::Cow;
 use std::ops::Range;
 use thiserror::Error;
 
@@ -16,30 +78,68 @@ use thiserror::Error;
 /// # Importante
 /// Manuais de banco utilizam indexação **baseada em 1** e **inclusiva**.
 /// Exemplo: "Posição 001 a 003" significa os 3 primeiros caracteres.
-#[derive(Debug, Clone, Copy)]
+///
+/// Só é construída por [`FieldPos::new`] (ou [`FieldPos::new_const`]), que garantem
+/// `start >= 1` e `end >= start`; por isso a conversão para `Range` nunca dá underflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct FieldPos {
     /// Posição inicial (1-based, inclusivo).
-    pub start: usize,
+    start: usize,
     /// Posição final (1-based, inclusivo).
-    pub end: usize,
+    end: usize,
 }
 
 impl FieldPos {
+    /// Cria uma posição validada (`start >= 1` e `end >= start`).
+    pub fn new(start: usize, end: usize) -> std::result::Result<Self, LayoutError> {
+        if start == 0 || end < start {
+            return Err(LayoutError::InvalidRange { start, end });
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Versão `const` de [`FieldPos::new`], para layouts em `const`/`static` (e para a macro derive).
+    ///
+    /// # Panics
+    /// Se a posição for inválida. Em contexto `const`, isso vira erro de compilação.
+    pub const fn new_const(start: usize, end: usize) -> Self {
+        assert!(start >= 1 && end >= start, "posição inválida: start deve ser >= 1 e end >= start");
+        Self { start, end }
+    }
+
+    /// Posição inicial (1-based, inclusivo).
+    pub const fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Posição final (1-based, inclusivo).
+    pub const fn end(&self) -> usize {
+        self.end
+    }
+
     /// Converte a posição CNAB (1-based inclusivo) para um Range Rust (0-based exclusivo).
     ///
     /// Exemplo: CNAB `1..3` (3 chars) torna-se Rust `0..3` (índices 0, 1, 2).
-    fn as_range(&self) -> Range<usize> {
+    pub const fn as_range(&self) -> Range<usize> {
         (self.start - 1)..self.end
     }
 
     /// Retorna a largura total do campo em caracteres.
-    pub fn width(&self) -> usize {
+    pub const fn width(&self) -> usize {
         self.end - self.start + 1
     }
 }
 
+/// Posições 1-based inclusivas, como nos manuais: `78..92`.
+impl std::fmt::Display for FieldPos {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
 /// Define o tipo de dado esperado no campo para conversão.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum FieldKind {
     /// Texto alfanumérico.
     /// Geralmente alinhado à esquerda e preenchido com espaços à direita.
@@ -57,12 +157,88 @@ pub enum FieldKind {
         /// Número de casas decimais a considerar.
         scale: u8
     },
+
+    /// Região sem dados (ex: "uso exclusivo FEBRABAN").
+    /// Só é validada quanto ao tamanho da linha; não gera valor no parse.
+    Filler,
+
+    /// Decimal zonado com sinal sobreposto ("overpunch") no último dígito,
+    /// comum em arquivos gerados em mainframe.
+    ///
+    /// O último caractere carrega dígito e sinal: `{`/`A`..`I` = +0..+9, `}`/`J`..`R` = -0..-9.
+    /// Exemplo: `"0000123J"` com `scale: 2` representa `-12.31`.
+    Zoned {
+        /// Número de casas decimais a considerar.
+        scale: u8
+    },
+
+    /// Decimal compactado (COMP-3): dois dígitos BCD por byte e o sinal no último nibble
+    /// (`C`/`A`/`E`/`F` = positivo, `D`/`B` = negativo). Os bytes são binários e não passam
+    /// pela codificação.
+    ///
+    /// Com `scale: 0` o valor é `Numeric`; caso contrário, `Decimal`. Em registros binários,
+    /// leia com `Framing::FixedLength` e `trim_line_ending: false`, pois um byte compactado
+    /// pode valer `0x0D`/`0x0A`.
+    Packed {
+        /// Número de casas decimais a considerar.
+        scale: u8
+    },
+}
+
+/// Identificador do tipo de um campo ou valor, sem os parâmetros (ex: escala).
+///
+/// Útil para camadas genéricas (validação, exportação) que precisam ramificar por tipo.
+/// É `#[non_exhaustive]`: matches devem ter um braço `_`, para não quebrar
+/// quando novos tipos forem adicionados.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[non_exhaustive]
+pub enum FieldKindTag {
+    Alpha,
+    Numeric,
+    Decimal,
+    Filler,
+    /// Decimal zonado. Seus valores são `Decimal`.
+    Zoned,
+    /// Decimal compactado (COMP-3). Seus valores são `Numeric` (escala 0) ou `Decimal`.
+    Packed,
+    /// Valor ausente (campo opcional em branco). Só aparece em `Value::kind`.
+    Null,
+}
+
+impl FieldKind {
+    /// Identificador do tipo, sem parâmetros.
+    pub fn tag(&self) -> FieldKindTag {
+        match self {
+            FieldKind::Alpha => FieldKindTag::Alpha,
+            FieldKind::Numeric => FieldKindTag::Numeric,
+            FieldKind::Decimal { .. } => FieldKindTag::Decimal,
+            FieldKind::Filler => FieldKindTag::Filler,
+            FieldKind::Zoned { .. } => FieldKindTag::Zoned,
+            FieldKind::Packed { .. } => FieldKindTag::Packed,
+        }
+    }
+}
+
+/// Nome do tipo com a escala, quando houver: `Alpha`, `Numeric`, `Decimal(2)`...
+impl std::fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldKind::Alpha => f.write_str("Alpha"),
+            FieldKind::Numeric => f.write_str("Numeric"),
+            FieldKind::Decimal { scale } => write!(f, "Decimal({scale})"),
+            FieldKind::Filler => f.write_str("Filler"),
+            FieldKind::Zoned { scale } => write!(f, "Zoned({scale})"),
+            FieldKind::Packed { scale } => write!(f, "Packed({scale})"),
+        }
+    }
 }
 
 /// Metadados que definem um campo no layout.
 ///
 /// Esta estrutura é geralmente construída automaticamente pela macro derive.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
 pub struct FieldSpec {
     /// Nome do campo (deve coincidir com o nome na struct alvo).
     /// Usamos `&'static str` para performance (zero alocação na definição).
@@ -73,6 +249,150 @@ pub struct FieldSpec {
 
     /// Tipo de dado para tratamento.
     pub kind: FieldKind,
+
+    /// Campo opcional: se estiver todo em branco, vira `Value::Null`
+    /// em vez de `0`/string vazia ("brancos quando não informado").
+    pub optional: bool,
+
+    /// Como o sinal de campos numéricos/decimais é informado.
+    pub sign: Sign,
+
+    /// Caractere de preenchimento (padrão: `'0'` em numéricos, espaço nos demais).
+    /// Ex: fillers que o banco exige zerados.
+    pub pad: Option<u8>,
+
+    /// Alinhamento do conteúdo (padrão: texto à esquerda, numéricos à direita).
+    /// Ex: nosso número alfanumérico alinhado à direita e preenchido com zeros.
+    pub align: Option<Align>,
+
+    /// Tratamento do texto na escrita (truncar, maiúsculas, acentos); sem política
+    /// própria, vale a de `WriteOptions`.
+    pub policy: Option<WritePolicy>,
+
+    /// Caracteres aceitos no conteúdo (ex: `"A-Z0-9 "`), no parse e na escrita.
+    /// Faixas são escritas como `A-Z`; um `-` no início ou no fim é literal.
+    pub charset: Option<&'static str>,
+}
+
+/// Lado em que o conteúdo de um campo fica; o preenchimento vai do lado oposto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Align {
+    /// Conteúdo à esquerda, preenchimento à direita (padrão de campos alfanuméricos).
+    Left,
+    /// Conteúdo à direita, preenchimento à esquerda (padrão de campos numéricos).
+    Right,
+}
+
+/// Convenção de sinal de um campo numérico ou decimal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum Sign {
+    /// Sem sinal: só dígitos (padrão CNAB).
+    #[default]
+    Unsigned,
+    /// Sinal no próprio campo: `-`/`+` no início ou no fim (ex: `-0001234`, `0001234-`).
+    Inline,
+    /// Indicador de débito/crédito em outra posição do registro: o valor é positivo
+    /// quando o indicador é igual a `credit` e negativo caso contrário. Na escrita, valores
+    /// negativos levam `debit`.
+    Indicator { pos: FieldPos, credit: &'static str, debit: &'static str },
+}
+
+impl FieldSpec {
+    /// Cria a especificação de um campo obrigatório.
+    pub const fn new(name: &'static str, pos: FieldPos, kind: FieldKind) -> Self {
+        Self { name, pos, kind, optional: false, sign: Sign::Unsigned, pad: None, align: None, policy: None, charset: None }
+    }
+
+    /// Marca o campo como opcional (branco vira `Value::Null`).
+    pub const fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// Aceita sinal (`-`/`+`) no início ou no fim do campo.
+    pub const fn signed(mut self) -> Self {
+        self.sign = Sign::Inline;
+        self
+    }
+
+    /// Usa o indicador D/C em `pos` como sinal do valor (positivo quando igual a `credit`).
+    /// Negativos são escritos com `"D"`.
+    pub const fn sign_indicator(self, pos: FieldPos, credit: &'static str) -> Self {
+        self.sign_indicator_with(pos, credit, "D")
+    }
+
+    /// Como [`sign_indicator`](Self::sign_indicator), com o indicador de débito escrito
+    /// em valores negativos (ex: `"-"` em layouts que usam `+`/`-`).
+    pub const fn sign_indicator_with(mut self, pos: FieldPos, credit: &'static str, debit: &'static str) -> Self {
+        self.sign = Sign::Indicator { pos, credit, debit };
+        self
+    }
+
+    /// Caractere de preenchimento (ex: `b'0'` para fillers zerados). Na escrita completa
+    /// o campo; no parse, é removido do lado oposto ao alinhamento.
+    pub const fn pad(mut self, pad: u8) -> Self {
+        self.pad = Some(pad);
+        self
+    }
+
+    /// Alinhamento do conteúdo, quando o banco foge da convenção do tipo.
+    pub const fn align(mut self, align: Align) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    /// Política de escrita do texto deste campo, no lugar da de `WriteOptions`.
+    pub const fn write_policy(mut self, policy: WritePolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Restringe o conteúdo aos caracteres de `charset` (ex: `"A-Z0-9 "`), como nos
+    /// manuais que limitam um campo a letras maiúsculas e dígitos.
+    pub const fn charset(mut self, charset: &'static str) -> Self {
+        self.charset = Some(charset);
+        self
+    }
+
+    /// Verifica o texto do campo contra `charset`; o caractere de preenchimento é
+    /// sempre aceito. O erro traz a posição (no registro) do primeiro caractere recusado.
+    pub(crate) fn check_charset(&self, text: &str, encoding: Encoding) -> Result<()> {
+        let Some(charset) = self.charset else {
+            return Ok(());
+        };
+        let pad = char::from(self.pad.unwrap_or(b' '));
+        for (index, (byte_offset, ch)) in text.char_indices().enumerate() {
+            if ch != pad && !charset_contains(charset, ch) {
+                // Posições contam bytes: em UTF-8 o deslocamento é o do byte, nas demais, o do caractere
+                let offset = if encoding == Encoding::Utf8 { byte_offset } else { index };
+                return Err(FixedWidthError::InvalidChar { field: self.name, ch, pos: self.pos.start() + offset });
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifica se um valor é compatível com este campo
+    /// (mesmo tipo e escala; `Null` só em campos opcionais).
+    pub fn matches(&self, value: &Value) -> bool {
+        match (self.kind, value) {
+            (_, Value::Null) => self.optional || matches!(self.kind, FieldKind::Filler),
+            (FieldKind::Packed { scale: 0 }, Value::Numeric(_)) => true,
+            (
+                FieldKind::Decimal { scale } | FieldKind::Zoned { scale } | FieldKind::Packed { scale },
+                Value::Decimal { scale: value_scale, .. },
+            ) => scale == *value_scale,
+            (kind, value) => kind.tag() == value.kind(),
+        }
+    }
+}
+
+/// Forma compacta para logs e mensagens de erro: `valor_titulo [78..92] Decimal(2)`.
+impl std::fmt::Display for FieldSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}] {}", self.name, self.pos, self.kind)
+    }
 }
 
 /// Representação intermediária de um valor parseado.
@@ -80,6 +400,7 @@ pub struct FieldSpec {
 /// O parser extrai a string bruta e converte para uma destas variantes
 /// antes de popular a struct final.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum Value {
     /// Valor textual (String owned).
     Alpha(String),
@@ -88,6 +409,72 @@ pub enum Value {
     /// Valor decimal representado como inteiro bruto + escala.
     /// Ex: 12.34 vira `Decimal { raw: 1234, scale: 2 }`.
     Decimal { raw: i64, scale: u8 },
+    /// Campo opcional não informado (todo em branco).
+    Null,
+}
+
+/// Valor parseado que empresta o texto da linha, quando possível.
+///
+/// Devolvido por [`parse_field_ref`]: o texto de um campo `Alpha` só é copiado se a
+/// decodificação exigir (ex: Latin-1 com acentos); quem só inspeciona o valor não aloca.
+/// [`ValueRef::into_owned`] converte para [`Value`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'a> {
+    /// Valor textual, emprestado da linha ou decodificado.
+    Alpha(Cow<'a, str>),
+    /// Valor inteiro (i64).
+    Numeric(i64),
+    /// Valor decimal representado como inteiro bruto + escala.
+    Decimal { raw: i64, scale: u8 },
+    /// Campo opcional não informado (todo em branco).
+    Null,
+}
+
+impl ValueRef<'_> {
+    /// Converte para [`Value`], copiando o texto se ele ainda for emprestado.
+    pub fn into_owned(self) -> Value {
+        match self {
+            ValueRef::Alpha(text) => Value::Alpha(text.into_owned()),
+            ValueRef::Numeric(n) => Value::Numeric(n),
+            ValueRef::Decimal { raw, scale } => Value::Decimal { raw, scale },
+            ValueRef::Null => Value::Null,
+        }
+    }
+
+    /// Texto de um campo Alpha.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ValueRef::Alpha(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Inteiro de um campo numérico.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ValueRef::Numeric(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Inteiro bruto e escala de um campo `Decimal`.
+    pub fn as_decimal_parts(&self) -> Option<(i64, u8)> {
+        match self {
+            ValueRef::Decimal { raw, scale } => Some((*raw, *scale)),
+            _ => None,
+        }
+    }
+
+    /// Indica se é um campo opcional não informado.
+    pub fn is_null(&self) -> bool {
+        matches!(self, ValueRef::Null)
+    }
+}
+
+impl From<ValueRef<'_>> for Value {
+    fn from(value: ValueRef<'_>) -> Self {
+        value.into_owned()
+    }
 }
 
 /// Erros possíveis durante o processo de parsing.
@@ -97,6 +484,10 @@ pub enum FixedWidthError {
     #[error("linha é menor que o necessário: len={len}, precisa de >= {needed}")]
     LineTooShort { len: usize, needed: usize },
 
+    /// A linha não tem exatamente o tamanho declarado do registro (`#[fw(width = N, strict)]`).
+    #[error("linha com tamanho {len}, esperado exatamente {expected}")]
+    WrongLength { len: usize, expected: usize },
+
     /// O campo foi definido como Numérico/Decimal, mas contém caracteres não numéricos.
     #[error("campo '{field}' contém caracteres inválidos para numérico: '{snippet}'")]
     InvalidNumeric {
@@ -104,182 +495,2571 @@ pub enum FixedWidthError {
         snippet: String,
     },
 
-    /// Erro genérico de UTF-8 (embora `&str` já garanta UTF-8 válido na entrada).
+    /// O valor numérico não cabe no tipo do campo na struct (ex: 999 em um `u8`).
+    #[error("campo '{field}' com valor {value} fora da faixa do tipo {target_type}")]
+    NumericOverflow {
+        field: &'static str,
+        value: i64,
+        target_type: &'static str,
+    },
+
+    /// A fatia do campo não é UTF-8 válido (ex: posição cortando um caractere multibyte).
     #[error("erro de UTF-8 na linha")]
     InvalidUtf8,
-}
 
-/// Resultado padrão utilizado pelo crate.
-pub type Result<T> = std::result::Result<T, FixedWidthError>;
+    /// O campo contém um byte fora da faixa ASCII com `Encoding::Ascii`.
+    #[error("campo '{field}' contém byte não ASCII: 0x{byte:02X}")]
+    InvalidAscii { field: &'static str, byte: u8 },
 
-/// Faz o parse de uma linha de texto bruta com base em uma lista de especificações de campos.
-///
-/// # Argumentos
-/// * `line` - A linha bruta do arquivo (pode conter `\r` ou `\n` no final).
-/// * `fields` - Lista de especificações (`FieldSpec`) gerada pela macro.
-///
-/// # Retorno
-/// Retorna um `HashMap` onde a chave é o nome do campo e o valor é o `Value` parseado.
-pub fn parse_line<'a>(
-    line: &'a str,
-    fields: &[FieldSpec],
-) -> Result<HashMap<&'static str, Value>> {
-    // Remove quebras de linha comuns em Windows (\r\n) e Unix (\n)
-    // para evitar que contem no tamanho da string ou sujem o último campo.
-    let line = line.trim_end_matches(&['\r', '\n'][..]);
-    let len = line.len();
+    /// Um conversor do `ConverterRegistry` rejeitou o valor do campo.
+    #[error("campo '{field}': falha na conversão: {message}")]
+    Conversion { field: &'static str, message: String },
 
-    // Pré-aloca o mapa para evitar realocações dinâmicas
-    let mut map = HashMap::with_capacity(fields.len());
+    /// O valor não cabe na largura do campo na escrita.
+    #[error("campo '{field}': valor '{value}' não cabe em {width} posições")]
+    ValueTooLong { field: &'static str, value: String, width: usize },
 
-    for field in fields {
-        // Validação de limites (Bounds check)
-        let needed = field.pos.end;
-        if len < needed {
-            return Err(FixedWidthError::LineTooShort { len, needed });
-        }
+    /// Valor negativo em um campo sem sinal, na escrita.
+    #[error("campo '{field}' sem sinal recebeu valor negativo {value}")]
+    NegativeUnsigned { field: &'static str, value: i64 },
 
-        // Fatia a string (Slice) usando a conversão segura de índices
-        let slice = &line[field.pos.as_range()];
+    /// O caractere não existe na codificação de escrita (ex: `€` em Latin-1).
+    #[error("campo '{field}' contém caractere '{ch}' sem representação na codificação")]
+    Unencodable { field: &'static str, ch: char },
 
-        let value = match field.kind {
-            FieldKind::Alpha => {
-                // Alpha: Remove espaços à direita (padrão CNAB)
-                Value::Alpha(slice.trim_end().to_string())
-            }
-            FieldKind::Numeric => {
-                // Numeric: Remove espaços em volta.
-                // Bancos as vezes mandam campos numéricos zerados como espaços em branco.
-                let s = slice.trim();
-                if s.is_empty() {
-                    Value::Numeric(0)
-                } else if !s.chars().all(|c| c.is_ascii_digit()) {
-                    return Err(FixedWidthError::InvalidNumeric {
-                        field: field.name,
-                        snippet: slice.to_string(),
-                    });
-                } else {
-                    let n = s.parse::<i64>().map_err(|_| FixedWidthError::InvalidNumeric {
-                        field: field.name,
-                        snippet: slice.to_string(),
-                    })?;
-                    Value::Numeric(n)
-                }
-            }
-            FieldKind::Decimal { scale } => {
-                // Decimal: Segue a mesma lógica do numérico, mas preserva a escala.
-                let s = slice.trim();
-                if s.is_empty() {
-                    Value::Decimal { raw: 0, scale }
-                } else if !s.chars().all(|c| c.is_ascii_digit()) {
-                    return Err(FixedWidthError::InvalidNumeric {
-                        field: field.name,
-                        snippet: slice.to_string(),
-                    });
-                } else {
-                    let n = s.parse::<i64>().map_err(|_| FixedWidthError::InvalidNumeric {
-                        field: field.name,
-                        snippet: slice.to_string(),
-                    })?;
-                    Value::Decimal { raw: n, scale }
-                }
-            }
-        };
+    /// Caractere fora do conjunto permitido para o campo (`charset`).
+    #[error("campo '{field}' contém caractere '{ch}' fora do conjunto permitido, na posição {pos}")]
+    InvalidChar { field: &'static str, ch: char, pos: usize },
 
-        map.insert(field.name, value);
-    }
+    /// Em UTF-8 com posições em bytes, o campo começa ou termina no meio de um caractere
+    /// multibyte (ex: um "ç" de um arquivo Latin-1 convertido para UTF-8).
+    #[error("campo '{field}': a posição {pos} cai no meio de um caractere multibyte")]
+    CharBoundary { field: &'static str, pos: usize },
 
-    Ok(map)
-}
+    /// Linha física de um registro quebrado (`Framing::Wrapped`) fora do tamanho esperado.
+    #[error("registro {record}: linha física {part} com {len} posições, esperado {expected}")]
+    WrappedLine { record: u64, part: usize, len: usize, expected: usize },
 
-/// Trait implementada automaticamente pela macro derive para expor as especificações dos campos.
-pub trait FixedWidthSpec {
-    fn spec() -> &'static [FieldSpec];
-}
+    /// O código lido não corresponde a nenhuma variante do enum do campo (`#[fw(code)]`).
+    #[error("campo '{field}' com código desconhecido: '{code}'")]
+    UnknownCode { field: &'static str, code: String },
 
-/// Trait principal implementada pela macro derive.
-/// Permite instanciar uma Struct a partir de uma linha de texto.
-pub trait FixedWidthParse: Sized {
-    fn parse(line: &str) -> Result<Self>;
+    /// O tipo de registro lido não corresponde a nenhuma variante do enum.
+    #[error("tipo de registro desconhecido: '{tag}'")]
+    UnknownRecordType { tag: String },
+
+    /// Nenhum layout registrado corresponde à linha.
+    #[error("linha {line_number}: nenhum layout corresponde ao registro")]
+    UnknownRecord { line_number: u64 },
+
+    /// Registro fora da estrutura esperada do arquivo (ex: segmento sem o par, quantidade
+    /// do trailer que não confere).
+    #[error("linha {line_number}: estrutura do arquivo inválida: {message}")]
+    Structure { line_number: u64, message: String },
+
+    /// Totais de um trailer (quantidade de registros, de títulos, soma dos valores) que não
+    /// conferem com os registros lidos.
+    #[error("linha {line_number}: totais do trailer não conferem: {}", join_integrity(errors))]
+    Integrity { line_number: u64, errors: Vec<IntegrityError> },
+
+    /// Valor que não passou em uma conferência declarada no campo (`#[fw(validate(...))]`).
+    #[error(transparent)]
+    Validation(ValidationError),
+
+    /// Leitura interrompida por um [`CancelToken`](reader::CancelToken).
+    #[error("leitura cancelada após {records} registros")]
+    Cancelled { records: u64 },
+
+    /// Erro de um campo ao processar um arquivo, com a localização exata no arquivo.
+    ///
+    /// Gerado por [`FixedWidthError::at_line`] (usado pelo leitor e pelas conversões de arquivo).
+    #[error("linha {line_number}, campo {field} [{pos}] {kind}, trecho '{snippet}': {source}")]
+    Field {
+        field: &'static str,
+        pos: FieldPos,
+        kind: FieldKind,
+        line_number: u64,
+        snippet: String,
+        source: Box<FixedWidthError>,
+    },
+
+    /// Erro de um registro que não se refere a um campo específico (ex: tamanho da linha).
+    #[error("linha {line_number}: {source}")]
+    Line {
+        line_number: u64,
+        source: Box<FixedWidthError>,
+    },
+
+    /// Erro informado por uma implementação de `Deserialize`/`Serialize` (módulo `serde`).
+    #[error("serde: {0}")]
+    Serde(String),
+
+    /// Falha ao montar ou gravar a saída de uma exportação (ex: Arrow, Parquet).
+    #[error("falha na exportação: {0}")]
+    Export(String),
+
+    /// Erro de leitura ou escrita ao processar um arquivo.
+    #[error("erro de E/S: {0}")]
+    Io(#[from] std::io::Error),
 }
 
-// --- Métodos Auxiliares para Value ---
+impl FixedWidthError {
+    /// Anexa o número da linha e, quando o erro vem de um campo de `fields`,
+    /// a posição e o trecho bruto do campo.
+    ///
+    /// Erros de E/S e erros que já têm contexto são devolvidos como estão.
+    /// O trecho segue a redação padrão global ([`Redaction::set_default`]).
+    pub fn at_line(self, line_number: u64, line: &[u8], fields: &[FieldSpec]) -> Self {
+        self.at_line_with(line_number, line, fields, Redaction::default_global())
+    }
 
-impl Value {
-    /// Tenta converter o valor interno para `f64`.
-    /// Útil para campos `Decimal`. Aplica a divisão pela potência de 10 conforme a escala.
-    pub fn as_f64(&self) -> Option<f64> {
-        match self {
-            Value::Decimal { raw, scale } => {
-                let factor = 10_i64.pow(*scale as u32) as f64;
-                Some(*raw as f64 / factor)
+    /// Como [`at_line`](Self::at_line), com a redação do trecho informada.
+    pub fn at_line_with(self, line_number: u64, line: &[u8], fields: &[FieldSpec], redaction: Redaction) -> Self {
+        if matches!(
+            self,
+            Self::Io(_)
+                | Self::Field { .. }
+                | Self::Line { .. }
+                | Self::UnknownRecord { .. }
+                | Self::Structure { .. }
+                | Self::Integrity { .. }
+                | Self::Cancelled { .. }
+        ) {
+            return self;
+        }
+        match self.field_in(fields) {
+            Some(field) => {
+                let start = (field.pos.start() - 1).min(line.len());
+                let end = field.pos.end().min(line.len());
+                Self::Field {
+                    field: field.name,
+                    pos: field.pos,
+                    kind: field.kind,
+                    line_number,
+                    // Latin-1: mostra qualquer byte sem falhar
+                    snippet: redaction.apply(&line[start..end].iter().map(|&b| b as char).collect::<String>()),
+                    source: Box::new(self),
+                }
             }
-            _ => None,
+            None => Self::Line { line_number, source: Box::new(self) },
         }
     }
 
-    /// Tenta converter o valor interno para `i64`.
-    pub fn as_i64(&self) -> Option<i64> {
+    /// Campo de `fields` de onde o erro veio, pelo nome do campo no erro (ou, em
+    /// `LineTooShort`, o primeiro campo que não coube na linha).
+    pub(crate) fn field_in<'f>(&self, fields: &'f [FieldSpec]) -> Option<&'f FieldSpec> {
         match self {
-            Value::Numeric(n) => Some(*n),
+            Self::InvalidNumeric { field, .. }
+            | Self::NumericOverflow { field, .. }
+            | Self::InvalidAscii { field, .. }
+            | Self::Conversion { field, .. }
+            | Self::UnknownCode { field, .. }
+            | Self::InvalidChar { field, .. }
+            | Self::CharBoundary { field, .. }
+            | Self::Validation(ValidationError { field, .. })
+            | Self::Field { field, .. } => fields.iter().find(|f| f.name == *field),
+            Self::LineTooShort { len, .. } => fields.iter().find(|f| f.pos.end() > *len),
+            Self::Line { source, .. } => source.field_in(fields),
             _ => None,
         }
     }
 
-    /// Tenta obter a referência da string interna (para campos Alpha).
-    pub fn as_str(&self) -> Option<&str> {
+    /// Ajusta posições relativas de um bloco aninhado para posições do registro.
+    pub fn shifted(self, offset: usize) -> Self {
         match self {
-            Value::Alpha(s) => Some(s),
+            Self::LineTooShort { len, needed } => Self::LineTooShort { len: len + offset, needed: needed + offset },
+            Self::InvalidChar { field, ch, pos } => Self::InvalidChar { field, ch, pos: pos + offset },
+            Self::CharBoundary { field, pos } => Self::CharBoundary { field, pos: pos + offset },
+            error => error,
+        }
+    }
+
+    /// Número da linha do arquivo onde o erro ocorreu, se conhecido.
+    pub fn line_number(&self) -> Option<u64> {
+        match self {
+            Self::Field { line_number, .. }
+            | Self::Line { line_number, .. }
+            | Self::UnknownRecord { line_number }
+            | Self::Structure { line_number, .. }
+            | Self::Integrity { line_number, .. } => Some(*line_number),
             _ => None,
         }
     }
+
+    /// Erro original, sem os envoltórios de contexto (`Field`/`Line`).
+    pub fn root(&self) -> &FixedWidthError {
+        match self {
+            Self::Field { source, .. } | Self::Line { source, .. } => source.root(),
+            _ => self,
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Total de um trailer que difere do apurado nos registros lidos.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{record}.{field} informa {declared}, lido {actual}")]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct IntegrityError {
+    /// Registro que traz o total (ex: `TrailerLote`).
+    pub record: &'static str,
+    /// Campo do total (ex: `quantidade_registros`).
+    pub field: &'static str,
+    /// Valor informado no trailer.
+    pub declared: i64,
+    /// Valor apurado nos registros.
+    pub actual: i64,
+}
 
-    #[test]
-    fn parse_cnab_like_header() {
-        // Linha fake com exatamente 240 caracteres para simular CNAB
-        let line = "34100000         2297460810001556256000036236       0625610000000362366 MARTINS RIBEIRO ADMINISTRADORABANCO TESTE                              10312202508440000000108501600";
+/// Valor lido que não passou em uma conferência do campo (`#[fw(validate(...))]`).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("campo '{field}' com valor '{value}' inválido: {rule}")]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct ValidationError {
+    pub field: &'static str,
+    /// Valor lido, como texto.
+    pub value: String,
+    /// Regra que o valor não cumpre (ex: `"maior que o máximo 999"`).
+    pub rule: String,
+}
 
-        // Definição manual de campos (o que a macro faria)
-        let fields = vec![
-            FieldSpec {
-                name: "codigo_banco",
-                pos: FieldPos { start: 1, end: 3 },
-                kind: FieldKind::Numeric,
-            },
-            FieldSpec {
-                name: "lote_servico",
-                pos: FieldPos { start: 4, end: 7 },
-                kind: FieldKind::Numeric,
-            },
-            FieldSpec {
-                name: "tipo_registro",
-                pos: FieldPos { start: 8, end: 8 },
-                kind: FieldKind::Numeric,
-            },
-            FieldSpec {
-                name: "nome_banco",
-                pos: FieldPos { start: 103, end: 113 },
-                kind: FieldKind::Alpha,
-            },
-        ];
+fn join_integrity(errors: &[IntegrityError]) -> String {
+    errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
 
-        let parsed = parse_line(&line, &fields).unwrap();
+/// Conferência dos totais de um trailer, acumulando todas as divergências.
+pub(crate) struct TotalsCheck {
+    record: &'static str,
+    errors: Vec<IntegrityError>,
+}
 
-        // Validações
-        assert_eq!(parsed["codigo_banco"], Value::Numeric(341));
-        assert_eq!(parsed["lote_servico"], Value::Numeric(0));
-        assert_eq!(parsed["tipo_registro"], Value::Numeric(0));
+impl TotalsCheck {
+    pub(crate) fn new(record: &'static str) -> Self {
+        Self { record, errors: Vec::new() }
+    }
 
-        if let Value::Alpha(nome) = &parsed["nome_banco"] {
-            // Verifica se o trim funcionou (removeu espaços à direita)
-            assert!(nome.starts_with("BANCO TESTE"));
+    pub(crate) fn check(mut self, field: &'static str, declared: i64, actual: i64) -> Self {
+        if declared != actual {
+            self.errors.push(IntegrityError { record: self.record, field, declared, actual });
+        }
+        self
+    }
+
+    /// `FixedWidthError::Integrity` com as divergências, se houver.
+    pub(crate) fn finish(self, line_number: u64) -> Result<()> {
+        if self.errors.is_empty() {
+            Ok(())
         } else {
-            panic!("nome_banco não é Alpha");
+            Err(FixedWidthError::Integrity { line_number, errors: self.errors })
+        }
+    }
+}
+
+/// Resultado padrão utilizado pelo crate.
+pub type Result<T> = std::result::Result<T, FixedWidthError>;
+
+/// Codificação de caracteres dos bytes da linha.
+///
+/// Por padrão as posições CNAB se referem a **bytes** (veja [`PositionUnit`]); a
+/// codificação só define como a fatia de cada campo é convertida para texto.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// ISO-8859-1. Cada byte é um caractere. Padrão, pois é o que os bancos enviam.
+    #[default]
+    Latin1,
+    /// Windows-1252. Igual ao Latin-1, exceto pela faixa 0x80..=0x9F (ex: `€`, `“`).
+    Windows1252,
+    /// UTF-8. Uma fatia que corta um caractere multibyte resulta em `InvalidUtf8`.
+    Utf8,
+    /// ASCII estrito. Bytes acima de 0x7F resultam em `InvalidAscii`.
+    Ascii,
+}
+
+/// Caracteres da faixa 0x80..=0x9F no Windows-1252.
+/// Posições não definidas pela codificação são mantidas como no Latin-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+impl Encoding {
+    /// Converte bytes (ex: uma linha inteira) para texto nesta codificação.
+    pub fn decode(self, bytes: &[u8]) -> Result<Cow<'_, str>> {
+        self.decode_field(bytes, "<linha>")
+    }
+
+    /// Converte o texto de um campo para bytes nesta codificação (usado na escrita).
+    pub(crate) fn encode_field(self, text: &str, field: &'static str) -> Result<Vec<u8>> {
+        if text.is_ascii() {
+            return Ok(text.as_bytes().to_vec());
+        }
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Latin1 | Encoding::Windows1252 | Encoding::Ascii => text
+                .chars()
+                .map(|ch| match (self, ch as u32) {
+                    (_, 0..=0x7F) => Ok(ch as u8),
+                    (Encoding::Windows1252, _) if let Some(i) = WINDOWS_1252_HIGH.iter().position(|&c| c == ch) => {
+                        Ok(0x80 + i as u8)
+                    }
+                    (Encoding::Latin1, 0x80..=0xFF) | (Encoding::Windows1252, 0xA0..=0xFF) => Ok(ch as u8),
+                    _ => Err(FixedWidthError::Unencodable { field, ch }),
+                })
+                .collect(),
+        }
+    }
+
+    /// Converte a fatia de um campo para texto.
+    ///
+    /// Fatias puramente ASCII (o caso comum) são emprestadas sem alocação.
+    fn decode_field<'a>(self, bytes: &'a [u8], field: &'static str) -> Result<Cow<'a, str>> {
+        if bytes.is_ascii() {
+            // ASCII é válido em todas as codificações suportadas.
+            return Ok(Cow::Borrowed(std::str::from_utf8(bytes).map_err(|_| FixedWidthError::InvalidUtf8)?));
+        }
+        match self {
+            Encoding::Latin1 => Ok(Cow::Owned(bytes.iter().map(|&b| b as char).collect())),
+            Encoding::Windows1252 => Ok(Cow::Owned(
+                bytes
+                    .iter()
+                    .map(|&b| match b {
+                        0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                        _ => b as char,
+                    })
+                    .collect(),
+            )),
+            Encoding::Utf8 => std::str::from_utf8(bytes)
+                .map(Cow::Borrowed)
+                .map_err(|_| FixedWidthError::InvalidUtf8),
+            Encoding::Ascii => {
+                let byte = bytes.iter().copied().find(|b| !b.is_ascii()).unwrap_or_default();
+                Err(FixedWidthError::InvalidAscii { field, byte })
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Como as posições dos campos são contadas na linha.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum PositionUnit {
+    /// Bytes: o padrão CNAB, para arquivos na codificação original do banco.
+    #[default]
+    Bytes,
+    /// Caracteres Unicode: para arquivos já convertidos para UTF-8, em que um "ç" ocupa
+    /// dois bytes mas uma única posição do layout. Mais lento, pois cada campo percorre
+    /// a linha desde o início.
+    Chars,
+}
+
+/// Opções que controlam o parsing de uma linha.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Codificação dos bytes da linha.
+    pub encoding: Encoding,
+
+    /// Remove `\r`/`\n` do final da linha antes do parse (padrão: `true`).
+    /// Desligue em registros de tamanho fixo, onde uma quebra no fim do registro é dado.
+    pub trim_line_ending: bool,
+
+    /// Substitui `\r`/`\n` dentro dos campos por espaços (padrão: `false`).
+    pub replace_newlines: bool,
+
+    /// Como o conteúdo dos campos aparece nas mensagens de erro
+    /// (padrão: o global, [`Redaction::set_default`]).
+    pub redaction: Redaction,
+
+    /// Satura em vez de falhar em numéricos inválidos ou fora da faixa (padrão: `false`).
+    ///
+    /// Para perfilar arquivos sujos: estouros viram o maior/menor valor do tipo, dígitos
+    /// inválidos viram `0`, e o erro original vai para `warnings`, se houver.
+    pub saturate: bool,
+
+    /// Onde registrar anomalias não fatais (ex: valores saturados).
+    pub warnings: Option<Warnings>,
+
+    /// Numéricos obrigatórios em branco são lidos como zero (padrão: `true`). Desligado,
+    /// resultam em `InvalidNumeric`; campos `Option` em branco continuam `None`.
+    pub blank_numeric_as_zero: bool,
+
+    /// Numéricos com espaços no meio (`"12 345"`) são lidos sem os espaços, com o
+    /// `InvalidNumeric` original registrado em `warnings` (padrão: `false`, erro).
+    pub numeric_internal_spaces: bool,
+
+    /// O que fazer com conteúdo além da largura declarada em `#[fw(width = N)]`
+    /// (padrão: ignorar).
+    pub trailing_data: TrailingData,
+
+    /// Unidade das posições dos campos (padrão: bytes, como no CNAB). Structs com
+    /// `#[fw(unit = "chars")]` sempre usam caracteres.
+    pub unit: PositionUnit,
+
+    /// Caminho rápido para arquivos gerados por sistema e sabidamente limpos (padrão: `false`).
+    ///
+    /// Campos numéricos sem sinal são lidos direto dos bytes, sem decodificação e sem
+    /// aparar espaços: `" 123"` passa a ser `InvalidNumeric` em vez de `123`. Structs
+    /// derivadas validam o tamanho da linha uma única vez, antes dos campos.
+    pub fast: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            encoding: Encoding::default(),
+            trim_line_ending: true,
+            replace_newlines: false,
+            redaction: Redaction::default_global(),
+            saturate: false,
+            warnings: None,
+            blank_numeric_as_zero: true,
+            numeric_internal_spaces: false,
+            trailing_data: TrailingData::Ignore,
+            unit: PositionUnit::Bytes,
+            fast: false,
+        }
+    }
+}
+
+/// Tratamento de conteúdo além da largura declarada do registro (ex: lixo após a
+/// posição 240). Espaços no fim da linha nunca contam.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingData {
+    /// Ignora o excesso.
+    #[default]
+    Ignore,
+    /// Registra um `WrongLength` em `warnings`, no campo `"sobra"`.
+    Warn,
+    /// Falha com `WrongLength`.
+    Reject,
+}
+
+/// Avisos registrados durante o parse: problemas que não interromperam o registro.
+///
+/// Clones compartilham a mesma lista, então as opções podem ser clonadas (ex: para blocos
+/// aninhados) sem perder avisos.
+#[derive(Debug, Clone, Default)]
+pub struct Warnings(std::sync::Arc<std::sync::Mutex<Vec<FieldError>>>);
+
+impl Warnings {
+    /// Cria uma lista vazia.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra um aviso.
+    pub fn push(&self, warning: FieldError) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push(warning);
+    }
+
+    /// Remove e devolve os avisos registrados até agora.
+    pub fn take(&self) -> Vec<FieldError> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Quantidade de avisos registrados.
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// `true` se não há avisos.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ParseOptions {
+    /// Registra o erro de um valor saturado como aviso, se houver onde registrar.
+    fn warn(&self, field: &FieldSpec, error: FixedWidthError) {
+        if let Some(warnings) = &self.warnings {
+            warnings.push(FieldError::new(field, self.redaction.redact_error(error)));
+        }
+    }
+}
+
+/// Redação do conteúdo dos campos nas mensagens de erro (ex: `InvalidNumeric`),
+/// para ambientes onde os registros carregam CPF/CNPJ e outros dados sensíveis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Redaction {
+    /// Trecho original (padrão).
+    #[default]
+    None,
+    /// Cada caractere não branco vira `*`, preservando o tamanho.
+    Mask,
+    /// Hash do trecho (`hash:<16 hex>`), para correlacionar erros sem expor o conteúdo.
+    /// Não é criptográfico: não protege campos com poucos valores possíveis contra força bruta.
+    Hash,
+}
+
+static DEFAULT_REDACTION: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(0);
+
+impl Redaction {
+    /// Define a redação padrão do processo, usada por `ParseOptions::default()`
+    /// e por [`FixedWidthError::at_line`].
+    pub fn set_default(redaction: Redaction) {
+        let code = match redaction {
+            Redaction::None => 0,
+            Redaction::Mask => 1,
+            Redaction::Hash => 2,
+        };
+        DEFAULT_REDACTION.store(code, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Redação padrão do processo.
+    pub fn default_global() -> Redaction {
+        match DEFAULT_REDACTION.load(std::sync::atomic::Ordering::Relaxed) {
+            1 => Redaction::Mask,
+            2 => Redaction::Hash,
+            _ => Redaction::None,
+        }
+    }
+
+    /// Aplica a redação a um trecho.
+    pub fn apply(self, snippet: &str) -> String {
+        match self {
+            Redaction::None => snippet.to_string(),
+            Redaction::Mask => snippet.chars().map(|c| if c == ' ' { c } else { '*' }).collect(),
+            Redaction::Hash => {
+                // FNV-1a: o mesmo trecho gera o mesmo hash em qualquer execução e versão do Rust
+                let hash = snippet
+                    .bytes()
+                    .fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3));
+                format!("hash:{hash:016x}")
+            }
+        }
+    }
+
+    /// Aplica a redação aos trechos carregados pelo erro.
+    fn redact_error(self, error: FixedWidthError) -> FixedWidthError {
+        match (self, error) {
+            (Redaction::None, error) => error,
+            (redaction, FixedWidthError::InvalidNumeric { field, snippet }) => {
+                FixedWidthError::InvalidNumeric { field, snippet: redaction.apply(&snippet) }
+            }
+            (redaction, FixedWidthError::UnknownCode { field, code }) => {
+                FixedWidthError::UnknownCode { field, code: redaction.apply(&code) }
+            }
+            (_, error) => error,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Opções padrão com a codificação informada.
+    pub fn with_encoding(encoding: Encoding) -> Self {
+        Self { encoding, ..Default::default() }
+    }
+
+    /// Opções estritas: numéricos em branco, espaços no meio de numéricos e conteúdo
+    /// além da largura declarada são erros.
+    pub fn strict() -> Self {
+        Self {
+            blank_numeric_as_zero: false,
+            numeric_internal_spaces: false,
+            trailing_data: TrailingData::Reject,
+            ..Default::default()
+        }
+    }
+
+    /// Opções tolerantes: numéricos em branco valem zero, espaços no meio de numéricos
+    /// são descartados e o conteúdo além da largura declarada é ignorado.
+    pub fn lenient() -> Self {
+        Self {
+            blank_numeric_as_zero: true,
+            numeric_internal_spaces: true,
+            trailing_data: TrailingData::Ignore,
+            ..Default::default()
+        }
+    }
+
+    /// Opções para um bloco aninhado (`#[fw(nested)]`): a linha já foi preparada
+    /// pelo registro externo, então o fim de linha não é removido de novo.
+    pub fn nested(&self) -> Self {
+        // O bloco recebe o restante da linha: o excesso só é conferido no registro externo
+        Self { trim_line_ending: false, trailing_data: TrailingData::Ignore, ..self.clone() }
+    }
+}
+
+/// Faz o parse de uma linha de texto bruta com base em uma lista de especificações de campos.
+///
+/// # Argumentos
+/// * `line` - A linha bruta do arquivo (pode conter `\r` ou `\n` no final).
+/// * `fields` - Lista de especificações (`FieldSpec`) gerada pela macro.
+///
+/// # Retorno
+/// Retorna um [`DynRecord`] com `(nome, Value)` na ordem de `fields` (fillers não entram).
+pub fn parse_line(
+    line: &str,
+    fields: &[FieldSpec],
+) -> Result<DynRecord> {
+    parse_line_bytes_with(line.as_bytes(), fields, &ParseOptions::with_encoding(Encoding::Utf8))
+}
+
+/// Como [`parse_line`], com as opções informadas (ex: [`ParseOptions::strict`]); a
+/// linha já está em UTF-8, então a codificação de `options` é ignorada.
+pub fn parse_line_with(
+    line: &str,
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+) -> Result<DynRecord> {
+    parse_line_bytes_with(line.as_bytes(), fields, &ParseOptions { encoding: Encoding::Utf8, ..options.clone() })
+}
+
+/// Faz o parse de uma linha em bytes brutos, sem transcodificar o arquivo antes.
+///
+/// Usa as opções padrão (`Encoding::Latin1`). Veja [`parse_line_bytes_with`].
+pub fn parse_line_bytes(
+    line: &[u8],
+    fields: &[FieldSpec],
+) -> Result<DynRecord> {
+    parse_line_bytes_with(line, fields, &ParseOptions::default())
+}
+
+/// Faz o parse de uma linha em bytes brutos usando as opções informadas.
+///
+/// As posições dos campos são contadas conforme `options.unit`: em bytes por padrão,
+/// como nos manuais bancários, ou em caracteres com [`PositionUnit::Chars`].
+pub fn parse_line_bytes_with(
+    line: &[u8],
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+) -> Result<DynRecord> {
+    let line = trim_record(line, options);
+
+    // Pré-aloca os valores para evitar realocações dinâmicas
+    let mut values = Vec::with_capacity(fields.len());
+
+    for field in fields {
+        let value = parse_field(line, field, options)?;
+        if !matches!(field.kind, FieldKind::Filler) {
+            values.push((field.name, value));
+        }
+    }
+
+    Ok(DynRecord::from_values(values))
+}
+
+/// Erro de um campo específico, coletado sem interromper o parse da linha.
+///
+/// Produzido por [`parse_line_collect`] e pelo `parse_lenient` gerado com `#[fw_record(lenient)]`.
+#[derive(Debug, Error)]
+#[error("campo {field} [{pos}]{}: {error}", .kind.map(|kind| format!(" {kind}")).unwrap_or_default())]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct FieldError {
+    /// Nome do campo.
+    pub field: &'static str,
+    /// Posição do campo no registro.
+    pub pos: FieldPos,
+    /// Tipo do campo (`None` em blocos aninhados, que não têm um tipo único).
+    pub kind: Option<FieldKind>,
+    /// Erro encontrado.
+    pub error: FixedWidthError,
+}
+
+impl FieldError {
+    /// Associa o erro à especificação do campo.
+    pub fn new(spec: &FieldSpec, error: FixedWidthError) -> Self {
+        Self { field: spec.name, pos: spec.pos, kind: Some(spec.kind), error }
+    }
+}
+
+/// Faz o parse de todos os campos da linha, sem parar no primeiro erro.
+///
+/// Usa `Encoding::Utf8`, como [`parse_line`]. Veja [`parse_line_bytes_collect_with`].
+pub fn parse_line_collect(
+    line: &str,
+    fields: &[FieldSpec],
+) -> (DynRecord, Vec<FieldError>) {
+    parse_line_bytes_collect_with(line.as_bytes(), fields, &ParseOptions::with_encoding(Encoding::Utf8))
+}
+
+/// Faz o parse de todos os campos da linha, coletando os erros de cada campo.
+///
+/// Campos com erro ficam fora do registro; os erros saem na ordem do layout.
+pub fn parse_line_bytes_collect_with(
+    line: &[u8],
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+) -> (DynRecord, Vec<FieldError>) {
+    let line = trim_record(line, options);
+    let mut values = Vec::with_capacity(fields.len());
+    let mut errors = Vec::new();
+
+    for field in fields {
+        match parse_field(line, field, options) {
+            Ok(value) => {
+                if !matches!(field.kind, FieldKind::Filler) {
+                    values.push((field.name, value));
+                }
+            }
+            Err(error) => errors.push(FieldError::new(field, error)),
+        }
+    }
+
+    (DynRecord::from_values(values), errors)
+}
+
+/// Prepara a linha para o parse conforme as opções.
+///
+/// Remove quebras de linha comuns em Windows (\r\n) e Unix (\n)
+/// para evitar que contem no tamanho da linha ou sujem o último campo.
+pub fn trim_record<'a>(line: &'a [u8], options: &ParseOptions) -> &'a [u8] {
+    if options.trim_line_ending { trim_line_ending(line) } else { line }
+}
+
+/// Confere o conteúdo após a posição `width`, conforme `options.trailing_data`.
+///
+/// Usado pela macro derive em structs com `#[fw(width = N)]`.
+pub fn check_trailing(line: &[u8], width: usize, options: &ParseOptions) -> Result<()> {
+    let len = record_len(line, options.unit);
+    if options.trailing_data == TrailingData::Ignore || len <= width {
+        return Ok(());
+    }
+    let blank = match options.unit {
+        PositionUnit::Bytes => line[width..].iter().all(|&b| b == b' '),
+        PositionUnit::Chars => String::from_utf8_lossy(line).chars().skip(width).all(|c| c == ' '),
+    };
+    let error = FixedWidthError::WrongLength { len, expected: width };
+    match options.trailing_data {
+        _ if blank => {}
+        TrailingData::Reject => return Err(error),
+        _ => {
+            if let Some(warnings) = &options.warnings {
+                warnings.push(FieldError { field: "sobra", pos: FieldPos::new_const(width + 1, len), kind: None, error });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lê o conteúdo da posição discriminadora (tipo de registro) de uma linha.
+///
+/// Usado pela macro derive em enums de registros.
+pub fn read_tag<'a>(line: &'a [u8], pos: FieldPos, options: &ParseOptions) -> Result<Cow<'a, str>> {
+    let tag = FieldSpec::new("<tipo de registro>", pos, FieldKind::Alpha);
+    options.encoding.decode_field(slice_field(trim_record(line, options), &tag, options)?, tag.name)
+}
+
+/// Remove `\r` e `\n` do final da linha.
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let end = line
+        .iter()
+        .rposition(|b| !matches!(b, b'\r' | b'\n'))
+        .map_or(0, |i| i + 1);
+    &line[..end]
+}
+
+/// Fatia os bytes de um campo conforme a unidade das posições e a codificação.
+///
+/// Em UTF-8 com posições em bytes, um campo que começa ou termina no meio de um
+/// caractere multibyte resulta em `CharBoundary`.
+pub(crate) fn slice_field<'a>(line: &'a [u8], field: &FieldSpec, options: &ParseOptions) -> Result<&'a [u8]> {
+    let range = byte_range(line, field.pos, options.unit)?;
+    if options.encoding == Encoding::Utf8 && options.unit == PositionUnit::Bytes {
+        check_char_boundary(line, field)?;
+    }
+    Ok(&line[range])
+}
+
+/// Faixa de bytes ocupada por `pos`, com as posições contadas em bytes ou em caracteres.
+fn byte_range(line: &[u8], pos: FieldPos, unit: PositionUnit) -> Result<Range<usize>> {
+    match unit {
+        PositionUnit::Bytes if line.len() < pos.end() => {
+            Err(FixedWidthError::LineTooShort { len: line.len(), needed: pos.end() })
+        }
+        PositionUnit::Bytes => Ok(pos.as_range()),
+        PositionUnit::Chars => {
+            // Início de cada caractere UTF-8 (bytes que não são de continuação), mais o fim da linha
+            let mut starts = line
+                .iter()
+                .enumerate()
+                .filter(|&(_, &b)| !is_utf8_continuation(b))
+                .map(|(i, _)| i)
+                .chain(std::iter::once(line.len()));
+            let start = starts.nth(pos.start() - 1);
+            match (start, starts.nth(pos.end() - pos.start())) {
+                (Some(start), Some(end)) => Ok(start..end),
+                _ => Err(FixedWidthError::LineTooShort { len: record_len(line, unit), needed: pos.end() }),
+            }
+        }
+    }
+}
+
+fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Verifica se o campo (posições em bytes) começa e termina em fronteiras de caractere UTF-8.
+fn check_char_boundary(line: &[u8], field: &FieldSpec) -> Result<()> {
+    let range = field.pos.as_range();
+    let pos = if line.get(range.start).is_some_and(|&b| is_utf8_continuation(b)) {
+        field.pos.start()
+    } else if line.get(range.end).is_some_and(|&b| is_utf8_continuation(b)) {
+        field.pos.end()
+    } else {
+        return Ok(());
+    };
+    Err(FixedWidthError::CharBoundary { field: field.name, pos })
+}
+
+/// Extrai e converte um único campo da linha (já preparada por [`trim_record`]).
+///
+/// É o caminho usado pelo código gerado pela macro derive, que converte cada campo
+/// direto para a struct, sem montar o [`DynRecord`] de [`parse_line_bytes_with`].
+/// Fillers só têm o tamanho validado e resultam em `Value::Null`.
+pub fn parse_field(line: &[u8], field: &FieldSpec, options: &ParseOptions) -> Result<Value> {
+    parse_field_ref(line, field, options).map(ValueRef::into_owned)
+}
+
+/// Como [`parse_field`], mas sem copiar o texto de campos `Alpha` que possam ser
+/// emprestados da linha (já sem o preenchimento).
+pub fn parse_field_ref<'a>(line: &'a [u8], field: &FieldSpec, options: &ParseOptions) -> Result<ValueRef<'a>> {
+    if let FieldKind::Filler = field.kind {
+        slice_field(line, field, options)?;
+        return Ok(ValueRef::Null);
+    }
+
+    // Compactado: bytes binários, sem decodificação de texto
+    if let FieldKind::Packed { scale } = field.kind {
+        let bytes = slice_field(line, field, options)?;
+        if field.optional && bytes.iter().all(|&b| b == b' ') {
+            return Ok(ValueRef::Null);
+        }
+        let raw = match parse_packed(bytes, field) {
+            Ok(raw) => raw,
+            Err(e) if options.saturate => {
+                options.warn(field, e);
+                saturated_packed(bytes)
+            }
+            Err(e) => return Err(options.redaction.redact_error(e)),
+        };
+        return Ok(if scale == 0 { ValueRef::Numeric(raw) } else { ValueRef::Decimal { raw, scale } });
+    }
+
+    // Caminho rápido (`options.fast`): dígitos puros direto dos bytes, sem decodificar nem
+    // aparar. Campos em branco, linhas curtas e conversões especiais seguem o caminho normal.
+    if options.fast
+        && !options.saturate
+        && !options.numeric_internal_spaces
+        && options.unit == PositionUnit::Bytes
+        && matches!(field.kind, FieldKind::Numeric | FieldKind::Decimal { .. })
+        && matches!(field.sign, Sign::Unsigned)
+        && field.pad.is_none()
+        && field.charset.is_none()
+        && let Some(bytes) = line.get(field.pos.as_range())
+        && !(field.optional && bytes.first() == Some(&b' '))
+    {
+        let raw = parse_digits_fast(bytes, field).map_err(|e| options.redaction.redact_error(e))?;
+        return Ok(match field.kind {
+            FieldKind::Decimal { scale } => ValueRef::Decimal { raw, scale },
+            _ => ValueRef::Numeric(raw),
+        });
+    }
+
+    let slice = field_text(line, field, options)?;
+
+    if field.optional && slice.trim().is_empty() {
+        return Ok(ValueRef::Null);
+    }
+    field.check_charset(&slice, options.encoding)?;
+
+    let numeric = matches!(field.kind, FieldKind::Numeric | FieldKind::Decimal { .. } | FieldKind::Zoned { .. });
+    if numeric && !options.blank_numeric_as_zero && slice.trim().is_empty() {
+        let error = FixedWidthError::InvalidNumeric { field: field.name, snippet: slice.into_owned() };
+        return Err(options.redaction.redact_error(error));
+    }
+
+    let value = match parse_value(line, &slice, field, options.unit) {
+        // Espaços no meio do número: lido sem eles, com aviso
+        Err(e @ FixedWidthError::InvalidNumeric { .. })
+            if options.numeric_internal_spaces
+                && matches!(field.kind, FieldKind::Numeric | FieldKind::Decimal { .. })
+                && slice.trim().contains(' ') =>
+        {
+            let compact = Cow::Owned(slice.replace(' ', ""));
+            match parse_value(line, &compact, field, options.unit) {
+                Ok(value) => {
+                    options.warn(field, e);
+                    Ok(value)
+                }
+                Err(_) => Err(e),
+            }
+        }
+        result => result,
+    };
+    match value {
+        Ok(value) => Ok(value),
+        Err(e @ FixedWidthError::InvalidNumeric { .. }) if options.saturate => {
+            let raw = saturated_digits(&slice);
+            options.warn(field, e);
+            Ok(match field.kind {
+                FieldKind::Decimal { scale } | FieldKind::Zoned { scale } => ValueRef::Decimal { raw, scale },
+                _ => ValueRef::Numeric(raw),
+            })
+        }
+        Err(e) => Err(options.redaction.redact_error(e)),
+    }
+}
+
+/// Verifica se `ch` pertence ao conjunto `charset` (ex: `"A-Z0-9 .-"`): faixas `a-z` e
+/// caracteres avulsos; um `-` sem caractere depois é literal.
+pub fn charset_contains(charset: &str, ch: char) -> bool {
+    let mut chars = charset.chars();
+    while let Some(first) = chars.next() {
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('-')
+            && let Some(last) = lookahead.next()
+        {
+            if (first..=last).contains(&ch) {
+                return true;
+            }
+            chars = lookahead;
+        } else if first == ch {
+            return true;
+        }
+    }
+    false
+}
+
+/// Valor saturado de um numérico inválido: dígitos que estouram `i64` viram o
+/// maior (ou, com `-`, o menor) `i64`; qualquer outro conteúdo vira 0.
+fn saturated_digits(slice: &str) -> i64 {
+    let s = slice.trim();
+    let (negative, digits) = match s.strip_prefix('-').or_else(|| s.strip_suffix('-')) {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').or_else(|| s.strip_suffix('+')).unwrap_or(s)),
+    };
+    match (!digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()), negative) {
+        (true, false) => i64::MAX,
+        (true, true) => i64::MIN,
+        (false, _) => 0,
+    }
+}
+
+/// Valor saturado de um compactado inválido: dígitos e sinal válidos que estouram `i64`
+/// viram o maior (ou, com sinal negativo, o menor) `i64`; nibbles inválidos viram 0.
+fn saturated_packed(bytes: &[u8]) -> i64 {
+    let Some((&last, head)) = bytes.split_last() else { return 0 };
+    let digits_ok = head.iter().flat_map(|&b| [b >> 4, b & 0x0F]).chain([last >> 4]).all(|d| d <= 9);
+    match (digits_ok, last & 0x0F) {
+        (true, 0x0C | 0x0A | 0x0E | 0x0F) => i64::MAX,
+        (true, 0x0D | 0x0B) => i64::MIN,
+        _ => 0,
+    }
+}
+
+/// Conversão de `i64` para o tipo do campo; com `saturate`, valores fora da faixa
+/// viram o limite do tipo (e `error` é registrado como aviso). Usada pela macro derive.
+pub fn saturating_try_from<T: TryFrom<i64>>(
+    value: i64,
+    field: &FieldSpec,
+    options: &ParseOptions,
+    error: FixedWidthError,
+) -> Result<T> {
+    if let Ok(converted) = T::try_from(value) {
+        return Ok(converted);
+    }
+    if !options.saturate {
+        return Err(error);
+    }
+    // Limites dos inteiros primitivos, do mais largo ao mais estreito:
+    // o primeiro que couber no tipo é o seu limite
+    let limits: &[i64] = if value > 0 {
+        &[i64::MAX, u32::MAX as i64, i32::MAX as i64, u16::MAX as i64, i16::MAX as i64, u8::MAX as i64, i8::MAX as i64, 0]
+    } else {
+        &[i64::MIN, i32::MIN as i64, i16::MIN as i64, i8::MIN as i64, 0]
+    };
+    let Some(clamped) = limits.iter().find_map(|&limit| T::try_from(limit).ok()) else {
+        return Err(error);
+    };
+    options.warn(field, error);
+    Ok(clamped)
+}
+
+/// Decodifica um decimal compactado (COMP-3): nibbles BCD e o sinal no último nibble.
+fn parse_packed(bytes: &[u8], field: &FieldSpec) -> Result<i64> {
+    let invalid = || FixedWidthError::InvalidNumeric {
+        field: field.name,
+        snippet: bytes.iter().map(|b| format!("{b:02X}")).collect::<Vec<_>>().join(" "),
+    };
+    let Some((&last, head)) = bytes.split_last() else { return Ok(0) };
+
+    let mut value: i64 = 0;
+    let digits = head.iter().flat_map(|&b| [b >> 4, b & 0x0F]).chain([last >> 4]);
+    for digit in digits {
+        if digit > 9 {
+            return Err(invalid());
+        }
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add(i64::from(digit)))
+            .ok_or_else(invalid)?;
+    }
+    match last & 0x0F {
+        0x0C | 0x0A | 0x0E | 0x0F => Ok(value),
+        0x0D | 0x0B => Ok(-value),
+        _ => Err(invalid()),
+    }
+}
+
+/// Converte o texto de um campo conforme o tipo.
+fn parse_value<'a>(line: &[u8], slice: &Cow<'a, str>, field: &FieldSpec, unit: PositionUnit) -> Result<ValueRef<'a>> {
+    let value = match field.kind {
+        FieldKind::Alpha => {
+            // Alpha: Remove espaços à direita (padrão CNAB), ou o preenchimento declarado.
+            // Texto emprestado da linha continua emprestado.
+            ValueRef::Alpha(match slice {
+                Cow::Borrowed(slice) => Cow::Borrowed(strip_alpha_padding(slice, field)),
+                Cow::Owned(slice) => Cow::Owned(strip_alpha_padding(slice, field).to_string()),
+            })
+        }
+        FieldKind::Numeric => {
+            // Numeric: Remove espaços em volta.
+            // Bancos as vezes mandam campos numéricos zerados como espaços em branco.
+            ValueRef::Numeric(parse_signed(line, strip_numeric_padding(slice, field), field, unit)?)
+        }
+        FieldKind::Decimal { scale } => {
+            // Decimal: Segue a mesma lógica do numérico, mas preserva a escala.
+            ValueRef::Decimal { raw: parse_signed(line, strip_numeric_padding(slice, field), field, unit)?, scale }
+        }
+        FieldKind::Zoned { scale } => ValueRef::Decimal { raw: parse_zoned(slice, field)?, scale },
+        // Tratado em `parse_field_ref`, antes da decodificação
+        FieldKind::Packed { .. } => unreachable!("campo compactado não passa pela decodificação de texto"),
+        FieldKind::Filler => ValueRef::Null,
+    };
+
+    Ok(value)
+}
+
+/// Remove o preenchimento de um campo alfanumérico: à direita por padrão, à esquerda
+/// com `Align::Right` (ex: `"0000012345"` com `pad = '0'` vira `"12345"`).
+fn strip_alpha_padding<'a>(slice: &'a str, field: &FieldSpec) -> &'a str {
+    let pad = field.pad.unwrap_or(b' ') as char;
+    match field.align {
+        Some(Align::Right) => slice.trim_start_matches(pad).trim_end(),
+        _ => slice.trim_end_matches(pad).trim_end(),
+    }
+}
+
+/// Remove um preenchimento não convencional (nem zero, nem espaço) de um campo numérico.
+fn strip_numeric_padding<'a>(slice: &'a str, field: &FieldSpec) -> &'a str {
+    match field.pad {
+        Some(pad) if pad != b'0' && pad != b' ' => match field.align {
+            Some(Align::Left) => slice.trim_end_matches(pad as char),
+            _ => slice.trim_start_matches(pad as char),
+        },
+        _ => slice,
+    }
+}
+
+/// Fatia e decodifica o texto bruto de um campo, sem aparar espaços.
+///
+/// Usada pelos campos com `#[fw(parse_with = "...")]`, que recebem o texto exatamente
+/// como está no registro.
+pub fn field_text<'a>(line: &'a [u8], field: &FieldSpec, options: &ParseOptions) -> Result<Cow<'a, str>> {
+    let slice = options.encoding.decode_field(slice_field(line, field, options)?, field.name)?;
+
+    // Quebras de linha perdidas em campos de texto livre viram espaços
+    if options.replace_newlines && slice.contains(['\r', '\n']) {
+        return Ok(Cow::Owned(slice.replace(['\r', '\n'], " ")));
+    }
+    Ok(slice)
+}
+
+/// Fatia um campo alfanumérico de uma linha UTF-8 sem copiar, já sem o preenchimento.
+///
+/// Usada pelo código gerado para campos `&'a str` em [`FixedWidthParseRef`], com as
+/// posições na unidade de `options`. Com posições em bytes, um campo que corta um
+/// caractere multibyte ao meio resulta em `CharBoundary`.
+pub fn field_str<'a>(line: &'a str, field: &FieldSpec, options: &ParseOptions) -> Result<&'a str> {
+    let range = byte_range(line.as_bytes(), field.pos, options.unit)?;
+    if options.unit == PositionUnit::Bytes {
+        check_char_boundary(line.as_bytes(), field)?;
+    }
+    let slice = line.get(range).ok_or(FixedWidthError::InvalidUtf8)?;
+    field.check_charset(slice, Encoding::Utf8)?;
+    Ok(strip_alpha_padding(slice, field))
+}
+
+/// Converte uma fatia numérica aplicando a convenção de sinal do campo.
+fn parse_signed(line: &[u8], slice: &str, field: &FieldSpec, unit: PositionUnit) -> Result<i64> {
+    match field.sign {
+        Sign::Unsigned => parse_digits(slice, field),
+        Sign::Inline => {
+            let s = slice.trim();
+            let (negative, digits) = if let Some(rest) = s.strip_prefix('-').or_else(|| s.strip_suffix('-')) {
+                (true, rest)
+            } else {
+                (false, s.strip_prefix('+').or_else(|| s.strip_suffix('+')).unwrap_or(s))
+            };
+            // Espaços entre o sinal e os dígitos não são aceitos
+            if digits.starts_with(' ') || digits.ends_with(' ') {
+                return Err(FixedWidthError::InvalidNumeric { field: field.name, snippet: slice.to_string() });
+            }
+            let value = parse_digits(digits, field).map_err(|_| FixedWidthError::InvalidNumeric {
+                field: field.name,
+                snippet: slice.to_string(),
+            })?;
+            Ok(if negative { -value } else { value })
+        }
+        Sign::Indicator { pos, credit, .. } => {
+            let value = parse_digits(slice, field)?;
+            let indicator = &line[byte_range(line, pos, unit)?];
+            let credit_flag = indicator.trim_ascii() == credit.as_bytes();
+            Ok(if credit_flag { value } else { -value })
+        }
+    }
+}
+
+/// Converte um decimal zonado, decodificando o sinal sobreposto no último caractere.
+fn parse_zoned(slice: &str, field: &FieldSpec) -> Result<i64> {
+    let s = slice.trim();
+    let invalid = || FixedWidthError::InvalidNumeric { field: field.name, snippet: slice.to_string() };
+    let Some(last) = s.chars().last() else { return Ok(0) };
+    let (digit, negative) = match last {
+        '0'..='9' => (last as u8 - b'0', false),
+        '{' => (0, false),
+        'A'..='I' => (last as u8 - b'A' + 1, false),
+        '}' => (0, true),
+        'J'..='R' => (last as u8 - b'J' + 1, true),
+        _ => return Err(invalid()),
+    };
+    let head = &s[..s.len() - last.len_utf8()];
+    let value = if head.is_empty() { 0 } else { parse_digits(head, field).map_err(|_| invalid())? };
+    let value = value
+        .checked_mul(10)
+        .and_then(|v| v.checked_add(i64::from(digit)))
+        .ok_or_else(invalid)?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Converte uma fatia numérica (só dígitos, com espaços em volta) para `i64`.
+/// Fatias vazias ou só com espaços valem 0.
+fn parse_digits(slice: &str, field: &FieldSpec) -> Result<i64> {
+    let s = slice.trim();
+    if s.is_empty() {
+        return Ok(0);
+    }
+    let invalid = || FixedWidthError::InvalidNumeric {
+        field: field.name,
+        snippet: slice.to_string(),
+    };
+    if !s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    s.parse::<i64>().map_err(|_| invalid())
+}
+
+/// Converte dígitos ASCII sem espaços nem sinal: qualquer outro byte é `InvalidNumeric`.
+fn parse_digits_fast(bytes: &[u8], field: &FieldSpec) -> Result<i64> {
+    bytes
+        .iter()
+        .try_fold(0i64, |value, &b| {
+            let digit = b.wrapping_sub(b'0');
+            if digit > 9 {
+                return None;
+            }
+            value.checked_mul(10)?.checked_add(i64::from(digit))
+        })
+        .ok_or_else(|| FixedWidthError::InvalidNumeric {
+            field: field.name,
+            snippet: bytes.iter().map(|&b| b as char).collect(),
+        })
+}
+
+/// Conversão de um decimal implícito (inteiro bruto + escala) para o tipo do campo.
+///
+/// Usada pela macro derive em campos `decimal = N`. Implementada para `f64` e, com a
+/// feature `rust_decimal`, para `rust_decimal::Decimal` (conversão sem perdas até 28 casas).
+pub trait FromDecimal: Sized {
+    /// Constrói o valor a partir de `raw / 10^scale`.
+    fn from_decimal(raw: i64, scale: u8) -> Self;
+}
+
+impl FromDecimal for f64 {
+    fn from_decimal(raw: i64, scale: u8) -> Self {
+        // Acima de 10^18 a potência não cabe em `i64`
+        let divisor = 10_i64.checked_pow(scale as u32).map_or_else(|| 10_f64.powi(scale as i32), |d| d as f64);
+        raw as f64 / divisor
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl FromDecimal for rust_decimal::Decimal {
+    fn from_decimal(raw: i64, scale: u8) -> Self {
+        use rust_decimal::Decimal;
+        // `Decimal` guarda no máximo 28 casas; escalas maiores são arredondadas para 28
+        Decimal::try_new(raw, scale as u32).unwrap_or_else(|_| {
+            10_i64
+                .checked_pow(scale as u32 - Decimal::MAX_SCALE)
+                .map_or(Decimal::ZERO, |shift| Decimal::new(raw, Decimal::MAX_SCALE) / Decimal::from(shift))
+        })
+    }
+}
+
+/// Tabela de códigos de um campo (ex: tipo de inscrição 1 = CPF, 2 = CNPJ) mapeada
+/// para um enum.
+///
+/// Usada pela macro derive em campos `#[fw(code)]`; normalmente implementada com
+/// `#[derive(FixedWidthCode)]` e `#[code("1")]` em cada variante:
+///
+/// ```no_run
+/// use cnab_fixedwidth::FixedWidthCode;
+///
+/// #[derive(FixedWidthCode)]
+/// enum TipoInscricao {
+///     #[code("1")]
+///     Cpf,
+///     #[code("2")]
+///     Cnpj,
+/// }
+/// ```
+pub trait FixedWidthCode: Sized {
+    /// Variante do código lido do campo (já sem o preenchimento), ou `None` se desconhecido.
+    fn from_code(code: &str) -> Option<Self>;
+
+    /// Código escrito no campo.
+    fn code(&self) -> &'static str;
+}
+
+/// Extensão do layout de uma struct derivada: a maior posição usada (ou `width`).
+///
+/// Implementada pela macro derive; usada para validar, em tempo de compilação, blocos
+/// aninhados (`#[fw(nested, offset = N)]`) contra os demais campos do registro.
+pub trait RecordSpan {
+    /// Última posição (1-based, inclusiva) ocupada pelo layout.
+    const END: usize;
+
+    /// Posições declaradas no layout (campos, fillers, indicadores de sinal e blocos
+    /// aninhados), usadas por [`spec_gaps`].
+    fn mapped() -> Vec<FieldPos>;
+}
+
+/// Faixas de `1..=width` não cobertas por nenhuma das posições `mapped`.
+///
+/// Útil para verificar se um layout cobre todas as posições do registro
+/// (ex: as 240 do CNAB 240); regiões reservadas devem ser declaradas como `filler`.
+pub fn spec_gaps(mapped: impl IntoIterator<Item = FieldPos>, width: usize) -> Vec<FieldPos> {
+    let mut mapped: Vec<_> = mapped.into_iter().collect();
+    mapped.sort_by_key(|pos| pos.start());
+    let mut gaps = Vec::new();
+    let mut next = 1;
+    for pos in mapped {
+        if pos.start() > next && next <= width {
+            gaps.push(FieldPos::new_const(next, (pos.start() - 1).min(width)));
+        }
+        next = next.max(pos.end() + 1);
+    }
+    if next <= width {
+        gaps.push(FieldPos::new_const(next, width));
+    }
+    gaps
+}
+
+/// `max` em contexto `const`, usado pelo código gerado.
+pub const fn const_max(a: usize, b: usize) -> usize {
+    if a > b { a } else { b }
+}
+
+/// Quantidade de dígitos decimais de `max` (ex: 10 para `u32::MAX`), usada pelo código
+/// gerado para verificar, em compilação, se o tipo inteiro comporta a largura do campo.
+pub const fn max_digits(max: u128) -> usize {
+    let mut digits = 1;
+    let mut rest = max / 10;
+    while rest > 0 {
+        digits += 1;
+        rest /= 10;
+    }
+    digits
+}
+
+/// Parte da linha a partir do deslocamento de um bloco aninhado (na unidade de `options`).
+pub fn nested_line<'a>(line: &'a [u8], offset: usize, options: &ParseOptions) -> Result<&'a [u8]> {
+    let start = match options.unit {
+        PositionUnit::Bytes => offset,
+        // Deslocamento em caracteres: byte onde começa o caractere `offset` (ou o fim da linha)
+        PositionUnit::Chars => line
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| !is_utf8_continuation(b))
+            .map(|(i, _)| i)
+            .chain(std::iter::once(line.len()))
+            .nth(offset)
+            .unwrap_or(usize::MAX),
+    };
+    line.get(start..)
+        .ok_or(FixedWidthError::LineTooShort { len: record_len(line, options.unit), needed: offset + 1 })
+}
+
+/// Tamanho do registro na unidade das posições (bytes ou caracteres).
+pub fn record_len(line: &[u8], unit: PositionUnit) -> usize {
+    match unit {
+        PositionUnit::Bytes => line.len(),
+        PositionUnit::Chars => line.iter().filter(|&&b| !is_utf8_continuation(b)).count(),
+    }
+}
+
+/// Trait implementada automaticamente pela macro derive para expor as especificações dos campos.
+///
+/// Permite que ferramentas (documentação, validadores, geradores) inspecionem o layout
+/// sem fazer o parse de uma linha. Blocos aninhados não aparecem em `spec()`: use a
+/// especificação da struct interna, deslocada pelo `offset` do bloco.
+pub trait FixedWidthSpec {
+    /// Campos declarados diretamente na struct, na ordem da declaração.
+    fn spec() -> &'static [FieldSpec];
+
+    /// Campo pelo nome (o nome do campo na struct).
+    fn field_by_name(name: &str) -> Option<&'static FieldSpec> {
+        Self::spec().iter().find(|field| field.name == name)
+    }
+
+    /// Tamanho do registro: `width` declarado ou a maior posição usada
+    /// (incluindo blocos aninhados e indicadores de sinal, nas structs derivadas).
+    fn total_width() -> usize {
+        Self::spec().iter().map(|field| field.pos.end()).max().unwrap_or(0)
+    }
+}
+
+/// Trait principal implementada pela macro derive.
+/// Permite instanciar uma Struct a partir de uma linha de texto.
+///
+/// Em enums de registros, cada variante embrulha o tipo de um registro e é escolhida
+/// pelo conteúdo de uma posição discriminadora:
+///
+/// ```no_run
+/// use cnab_fixedwidth::cnab240::{HeaderArquivo, TrailerArquivo};
+/// use cnab_fixedwidth::FixedWidth;
+///
+/// #[derive(FixedWidth)]
+/// #[fw(tag_pos = "8..8")]
+/// enum Registro {
+///     #[fw(tag = "0")]
+///     Header(HeaderArquivo),
+///     #[fw(tag = "9")]
+///     Trailer(TrailerArquivo),
+///     // Opcional: tipos desconhecidos viram (tipo, linha) em vez de erro
+///     #[fw(other)]
+///     Outro(char, String),
+/// }
+/// ```
+pub trait FixedWidthParse: Sized {
+    /// Faz o parse de uma linha já decodificada (UTF-8).
+    fn parse(line: &str) -> Result<Self> {
+        Self::parse_bytes(line.as_bytes(), &ParseOptions::with_encoding(Encoding::Utf8))
+    }
+
+    /// Faz o parse de uma linha em bytes brutos, na codificação definida em `options`.
+    fn parse_bytes(line: &[u8], options: &ParseOptions) -> Result<Self>;
+
+    /// Faz o parse de uma linha já decodificada (UTF-8) com as opções informadas
+    /// (ex: [`ParseOptions::strict`]); a codificação de `options` é ignorada.
+    fn parse_with(line: &str, options: &ParseOptions) -> Result<Self> {
+        Self::parse_bytes(line.as_bytes(), &ParseOptions { encoding: Encoding::Utf8, ..options.clone() })
+    }
+
+    /// Faz o parse coletando as anomalias não fatais (valores saturados, espaços no meio
+    /// de numéricos, conteúdo após a largura declarada) em vez de descartá-las.
+    ///
+    /// Os avisos vão para o relatório, não para o `warnings` de `options`. Espaços no meio
+    /// de numéricos são sempre tolerados; o excesso no fim da linha é avisado a menos que
+    /// `options.trailing_data` diga outra coisa.
+    fn parse_report(line: &[u8], options: &ParseOptions) -> Result<ParseReport<Self>> {
+        let warnings = Warnings::new();
+        let trailing_data = match options.trailing_data {
+            TrailingData::Ignore => TrailingData::Warn,
+            other => other,
+        };
+        let options = ParseOptions {
+            warnings: Some(warnings.clone()),
+            numeric_internal_spaces: true,
+            trailing_data,
+            ..options.clone()
+        };
+        let record = Self::parse_bytes(line, &options)?;
+        Ok(ParseReport { record, warnings: warnings.take() })
+    }
+}
+
+/// Registro lido por [`FixedWidthParse::parse_report`], com os avisos do parse.
+#[derive(Debug)]
+pub struct ParseReport<T> {
+    /// O registro, lido apesar dos avisos.
+    pub record: T,
+    /// Anomalias encontradas, com o campo e a posição de cada uma.
+    pub warnings: Vec<FieldError>,
+}
+
+impl<T> ParseReport<T> {
+    /// `true` se o registro foi lido sem avisos.
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Parse sem alocação: a struct guarda fatias (`&'a str`) da própria linha.
+///
+/// Implementada pela macro derive em structs com um parâmetro de tempo de vida; campos
+/// alfanuméricos do tipo `&'a str` (ou `Option<&'a str>`) apontam direto para a linha,
+/// os demais são convertidos como em [`FixedWidthParse`]. A linha deve estar em UTF-8.
+pub trait FixedWidthParseRef<'a>: Sized {
+    /// Faz o parse de uma linha, emprestando os campos de texto dela.
+    fn parse_ref(line: &'a str) -> Result<Self>;
+}
+
+// --- Métodos Auxiliares para Value ---
+
+impl Value {
+    /// Tenta converter o valor interno para `f64`.
+    /// Útil para campos `Decimal`. Aplica a divisão pela potência de 10 conforme a escala.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_decimal_parts().map(|(raw, scale)| f64::from_decimal(raw, scale))
+    }
+
+    /// Retorna o inteiro bruto e a escala de um campo `Decimal`, sem conversão.
+    pub fn as_decimal_parts(&self) -> Option<(i64, u8)> {
+        match self {
+            Value::Decimal { raw, scale } => Some((*raw, *scale)),
+            _ => None,
+        }
+    }
+
+    /// Converte um campo `Decimal` para `rust_decimal::Decimal`, sem perdas.
+    ///
+    /// Retorna `None` se a escala passar de 28 casas, o máximo de `rust_decimal::Decimal`.
+    #[cfg(feature = "rust_decimal")]
+    pub fn as_decimal(&self) -> Option<rust_decimal::Decimal> {
+        self.as_decimal_parts()
+            .and_then(|(raw, scale)| rust_decimal::Decimal::try_new(raw, scale as u32).ok())
+    }
+
+    /// Tenta converter o valor interno para `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Numeric(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Identificador do tipo do valor.
+    pub fn kind(&self) -> FieldKindTag {
+        match self {
+            Value::Alpha(_) => FieldKindTag::Alpha,
+            Value::Numeric(_) => FieldKindTag::Numeric,
+            Value::Decimal { .. } => FieldKindTag::Decimal,
+            Value::Null => FieldKindTag::Null,
+        }
+    }
+
+    /// Indica se é um campo opcional não informado.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Extrai a string interna (para campos Alpha), sem copiar.
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            Value::Alpha(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Tenta obter a referência da string interna (para campos Alpha).
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Alpha(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Valor legível em logs: texto entre aspas simples, decimais com ponto (`12.34`) e
+/// `nulo` para campos não informados.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Alpha(text) => write!(f, "'{text}'"),
+            Value::Numeric(n) => write!(f, "{n}"),
+            Value::Decimal { raw, scale } => {
+                let mut out = String::new();
+                json::write_decimal(&mut out, *raw, *scale);
+                f.write_str(&out)
+            }
+            Value::Null => f.write_str("nulo"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Registro zerado de `width` posições, com os trechos informados (posição 1-based, texto).
+    pub(crate) fn record(width: usize, parts: &[(usize, &str)]) -> String {
+        let mut line = vec![b'0'; width];
+        for (pos, text) in parts {
+            line[pos - 1..pos - 1 + text.len()].copy_from_slice(text.as_bytes());
+        }
+        String::from_utf8(line).unwrap()
+    }
+
+    /// Layout com testes de fronteira gerados pela macro (`#[fw_record(gen_tests)]`).
+    #[allow(dead_code)]
+    #[derive(Debug, FixedWidth)]
+    #[fw(width = 20)]
+    #[fw_record(gen_tests)]
+    struct LayoutComTestesGerados {
+        #[fw(pos = "1..3", numeric)]
+        banco: u16,
+        #[fw(pos = "4..8", numeric)]
+        parcela: Option<u32>,
+        #[fw(pos = "9..15", alpha)]
+        nome: String,
+        #[fw(pos = "16..*", decimal = 2)]
+        valor: f64,
+    }
+
+    /// `width` além do último campo, com escrita: testes de leitura e escrita gerados.
+    #[allow(dead_code)]
+    #[derive(Debug, FixedWidth)]
+    #[fw(width = 20)]
+    #[fw_record(gen_tests, write)]
+    struct LayoutMaiorQueOsCampos {
+        #[fw(pos = "1..3", numeric)]
+        banco: u16,
+        #[fw(pos = "4..10", alpha)]
+        nome: String,
+    }
+
+    #[test]
+    fn parse_cnab_like_header() {
+        // Linha fake com exatamente 240 caracteres para simular CNAB
+        let line = "34100000         2297460810001556256000036236       0625610000000362366 MARTINS RIBEIRO ADMINISTRADORABANCO TESTE                              10312202508440000000108501600";
+
+        // Definição manual de campos (o que a macro faria)
+        let fields = vec![
+            FieldSpec::new("codigo_banco", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("lote_servico", FieldPos::new_const(4, 7), FieldKind::Numeric),
+            FieldSpec::new("tipo_registro", FieldPos::new_const(8, 8), FieldKind::Numeric),
+            FieldSpec::new("nome_banco", FieldPos::new_const(103, 113), FieldKind::Alpha),
+        ];
+
+        let parsed = parse_line(line, &fields).unwrap();
+
+        // Validações
+        assert_eq!(parsed["codigo_banco"], Value::Numeric(341));
+        assert_eq!(parsed["lote_servico"], Value::Numeric(0));
+        assert_eq!(parsed["tipo_registro"], Value::Numeric(0));
+
+        if let Value::Alpha(nome) = &parsed["nome_banco"] {
+            // Verifica se o trim funcionou (removeu espaços à direita)
+            assert!(nome.starts_with("BANCO TESTE"));
+        } else {
+            panic!("nome_banco não é Alpha");
+        }
+    }
+
+    #[test]
+    fn parse_latin1_bytes() {
+        // "JOSÉ" em ISO-8859-1: o 'É' ocupa um único byte (0xC9)
+        let line = b"001JOS\xC9      ";
+        let fields = vec![
+            FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("nome", FieldPos::new_const(4, 13), FieldKind::Alpha),
+        ];
+
+        let parsed = parse_line_bytes(line, &fields).unwrap();
+        assert_eq!(parsed["banco"], Value::Numeric(1));
+        assert_eq!(parsed["nome"], Value::Alpha("JOSÉ".to_string()));
+
+        let ascii = ParseOptions::with_encoding(Encoding::Ascii);
+        assert!(matches!(
+            parse_line_bytes_with(line, &fields, &ascii),
+            Err(FixedWidthError::InvalidAscii { field: "nome", byte: 0xC9 })
+        ));
+    }
+
+    #[test]
+    fn derive_rejects_numeric_overflow() {
+        #[derive(Debug, FixedWidth)]
+        struct Registro {
+            #[fw(pos = "1..3", numeric)]
+            banco: u8,
+            #[fw(pos = "4..8", numeric)]
+            lote: u32,
+        }
+
+        let ok = Registro::parse("25500042").unwrap();
+        assert_eq!((ok.banco, ok.lote), (255, 42));
+
+        assert!(matches!(
+            Registro::parse("34100042"),
+            Err(FixedWidthError::NumericOverflow { field: "banco", value: 341, target_type: "u8" })
+        ));
+    }
+
+    #[test]
+    fn derive_resolves_open_ended_filler() {
+        #[derive(Debug, FixedWidth)]
+        #[fw(width = 12)]
+        struct Trailer {
+            #[fw(pos = "1..3", numeric)]
+            banco: u32,
+            #[fw(pos = "4..6", alpha)]
+            tipo: String,
+            #[fw(pos = "7..*", filler)]
+            _uso_febraban: (),
+        }
+
+        let trailer = Trailer::parse("341ABC      ").unwrap();
+        assert_eq!((trailer.banco, trailer.tipo.as_str()), (341, "ABC"));
+
+        // O filler vai até a posição 12, então a linha precisa alcançá-la
+        assert!(matches!(
+            Trailer::parse("341ABC"),
+            Err(FixedWidthError::LineTooShort { len: 6, needed: 12 })
+        ));
+    }
+
+    #[test]
+    fn derive_maps_blank_optionals_to_none() {
+        type Opcional<T> = Option<T>;
+
+        #[derive(Debug, FixedWidth)]
+        struct Detalhe {
+            #[fw(pos = "1..3", numeric)]
+            banco: u32,
+            #[fw(pos = "4..8", numeric)]
+            desconto: Option<u32>,
+            #[fw(pos = "9..12", alpha)]
+            complemento: Option<String>,
+            #[fw(pos = "13..17", decimal = 2)]
+            juros: Option<f64>,
+            #[fw(pos = "18..19", numeric, optional)]
+            parcela: Opcional<u8>,
+        }
+
+        let blank = Detalhe::parse("341                   ").unwrap();
+        assert_eq!(blank.banco, 341);
+        assert_eq!(blank.desconto, None);
+        assert_eq!(blank.complemento, None);
+        assert_eq!(blank.juros, None);
+        assert_eq!(blank.parcela, None);
+
+        let filled = Detalhe::parse("34100010AP 10015002").unwrap();
+        assert_eq!(filled.desconto, Some(10));
+        assert_eq!(filled.complemento.as_deref(), Some("AP 1"));
+        assert_eq!(filled.juros, Some(1.5));
+        assert_eq!(filled.parcela, Some(2));
+    }
+
+    #[test]
+    fn derive_business_key_drives_eq_and_hash() {
+        #[derive(Debug, FixedWidth)]
+        #[fw_record(key(fields = "nosso_numero, numero_documento"))]
+        struct Titulo {
+            #[fw(pos = "1..5", numeric)]
+            nosso_numero: u64,
+            #[fw(pos = "6..9", alpha)]
+            numero_documento: String,
+            #[fw(pos = "10..14", decimal = 2)]
+            valor: f64,
+        }
+
+        let a = Titulo::parse("00042DOC100100").unwrap();
+        let b = Titulo::parse("00042DOC100250").unwrap();
+        let c = Titulo::parse("00043DOC100100").unwrap();
+
+        assert_eq!(a.business_key(), (&42, &"DOC1".to_string()));
+        // Valores diferentes, mesma chave: mesmo título
+        assert_ne!(a.valor, b.valor);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let unicos: std::collections::HashSet<_> = [a, b, c].into_iter().collect();
+        assert_eq!(unicos.len(), 2);
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn derive_decimal_into_rust_decimal() {
+        use rust_decimal::Decimal;
+
+        #[derive(Debug, FixedWidth)]
+        struct Pagamento {
+            #[fw(pos = "1..15", decimal = 2)]
+            valor: Decimal,
+            #[fw(pos = "16..20", decimal = 5)]
+            taxa: Option<Decimal>,
+        }
+
+        let p = Pagamento::parse("000000000000011     ").unwrap();
+        assert_eq!(p.valor, Decimal::new(11, 2));
+        assert_eq!(p.valor.to_string(), "0.11");
+        assert_eq!(p.taxa, None);
+
+        // Acima de 28 casas o valor é arredondado, sem pânico
+        assert_eq!(Decimal::from_decimal(15, 29), Decimal::new(2, 28));
+        assert_eq!(Decimal::from_decimal(i64::MAX, 200), Decimal::ZERO);
+        assert_eq!(Value::Decimal { raw: 15, scale: 29 }.as_decimal(), None);
+        assert_eq!(f64::from_decimal(5, 20), 5e-20);
+    }
+
+    #[test]
+    fn derive_record_enum_dispatch_with_catch_all() {
+        #[derive(Debug, FixedWidth)]
+        struct Header {
+            #[fw(pos = "1..3", numeric)]
+            banco: u32,
+        }
+
+        #[derive(Debug, FixedWidth)]
+        struct Detalhe {
+            #[fw(pos = "5..8", alpha)]
+            nome: String,
+        }
+
+        #[derive(Debug, FixedWidth)]
+        #[fw(tag_pos = "4..4")]
+        enum Registro {
+            #[fw(tag = "0")]
+            Header(Header),
+            #[fw(tag = "3")]
+            #[fw(tag = "4")]
+            Detalhe(Detalhe),
+            #[fw(other)]
+            Outro(char, String),
+        }
+
+        #[derive(Debug, FixedWidth)]
+        #[fw(tag_pos = "4..4")]
+        enum Estrito {
+            #[fw(tag = "0")]
+            Header(Header),
+        }
+
+        assert!(matches!(Registro::parse("3410"), Ok(Registro::Header(Header { banco: 341 }))));
+        assert!(matches!(Registro::parse("3414ANA \r\n"), Ok(Registro::Detalhe(d)) if d.nome == "ANA"));
+        assert!(matches!(Registro::parse("3415XYZ\n"), Ok(Registro::Outro('5', raw)) if raw == "3415XYZ"));
+        assert!(matches!(Estrito::parse("3410"), Ok(Estrito::Header(h)) if h.banco == 341));
+        assert!(matches!(
+            Estrito::parse("3415"),
+            Err(FixedWidthError::UnknownRecordType { tag }) if tag == "5"
+        ));
+    }
+
+    #[test]
+    fn spec_matches_value_kinds() {
+        let pos = FieldPos::new_const(1, 5);
+        let valor = FieldSpec::new("valor", pos, FieldKind::Decimal { scale: 2 });
+        let nome = FieldSpec::new("nome", pos, FieldKind::Alpha).optional();
+
+        assert_eq!(Value::Numeric(1).kind(), FieldKindTag::Numeric);
+        assert_eq!(FieldKind::Decimal { scale: 2 }.tag(), FieldKindTag::Decimal);
+
+        assert!(valor.matches(&Value::Decimal { raw: 100, scale: 2 }));
+        assert!(!valor.matches(&Value::Decimal { raw: 100, scale: 3 }));
+        assert!(!valor.matches(&Value::Numeric(100)));
+        assert!(!valor.matches(&Value::Null));
+        assert!(nome.matches(&Value::Alpha("ANA".into())));
+        assert!(nome.matches(&Value::Null));
+    }
+
+    #[test]
+    fn field_pos_validates_on_construction() {
+        assert_eq!(FieldPos::new(1, 3).unwrap().as_range(), 0..3);
+        assert_eq!(FieldPos::new(0, 3), Err(LayoutError::InvalidRange { start: 0, end: 3 }));
+        assert_eq!(FieldPos::new(5, 4), Err(LayoutError::InvalidRange { start: 5, end: 4 }));
+
+        const POS: FieldPos = FieldPos::new_const(8, 8);
+        assert_eq!((POS.start(), POS.end(), POS.width()), (8, 8, 1));
+    }
+
+    #[test]
+    fn derive_strict_width_rejects_wrong_lengths() {
+        #[derive(Debug, FixedWidth)]
+        #[fw(width = 8, strict)]
+        struct Registro {
+            #[fw(pos = "1..3", numeric)]
+            banco: u32,
+            #[fw(pos = "4..*", filler)]
+            _resto: (),
+        }
+
+        assert_eq!(Registro::WIDTH, 8);
+        assert_eq!(Registro::parse("341     \r\n").unwrap().banco, 341);
+        assert!(matches!(
+            Registro::parse("341      LIXO"),
+            Err(FixedWidthError::WrongLength { len: 13, expected: 8 })
+        ));
+        assert!(matches!(
+            Registro::parse("341"),
+            Err(FixedWidthError::WrongLength { len: 3, expected: 8 })
+        ));
+    }
+
+    #[test]
+    fn collects_every_field_error() {
+        let fields = [
+            FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("nome", FieldPos::new_const(4, 8), FieldKind::Alpha),
+            FieldSpec::new("valor", FieldPos::new_const(9, 13), FieldKind::Decimal { scale: 2 }),
+            FieldSpec::new("agencia", FieldPos::new_const(14, 17), FieldKind::Numeric),
+        ];
+        let (values, errors) = parse_line_collect("3X1JOAO 0012A", &fields);
+        assert_eq!(values.len(), 1);
+        assert_eq!(values["nome"], Value::Alpha("JOAO".to_string()));
+        let failed: Vec<_> = errors.iter().map(|e| e.field).collect();
+        assert_eq!(failed, ["banco", "valor", "agencia"]);
+        assert!(matches!(errors[2].error, FixedWidthError::LineTooShort { len: 13, needed: 17 }));
+
+        #[derive(FixedWidth, Debug)]
+        #[fw_record(lenient)]
+        struct Detalhe {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "4..8", alpha)]
+            nome: String,
+            #[fw(pos = "9..13", decimal = 2)]
+            valor: Option<f64>,
+            #[fw(pos = "14..15", numeric)]
+            carteira: u8,
+        }
+
+        let (detalhe, errors) = Detalhe::parse_lenient("341JOAO 0X12A09");
+        assert_eq!((detalhe.banco, detalhe.nome.as_str(), detalhe.valor, detalhe.carteira), (341, "JOAO", None, 9));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "campo valor [9..13] Decimal(2): campo 'valor' contém caracteres inválidos para numérico: '0X12A'");
+    }
+
+    #[test]
+    fn specs_and_values_display_compactly_for_logs() {
+        let spec = FieldSpec::new("valor_titulo", FieldPos::new_const(78, 92), FieldKind::Decimal { scale: 2 });
+        assert_eq!(spec.to_string(), "valor_titulo [78..92] Decimal(2)");
+        assert_eq!(FieldKind::Packed { scale: 0 }.to_string(), "Packed(0)");
+
+        let values = [Value::Alpha("JOAO".into()), Value::Numeric(-7), Value::Decimal { raw: -1234, scale: 2 }, Value::Null];
+        let shown: Vec<_> = values.iter().map(Value::to_string).collect();
+        assert_eq!(shown, ["'JOAO'", "-7", "-12.34", "nulo"]);
+
+        let fields = [spec];
+        let error = FixedWidthError::InvalidNumeric { field: "valor_titulo", snippet: "X".into() };
+        assert!(error.at_line(3, &[b'0'; 100], &fields).to_string().starts_with("linha 3, campo valor_titulo [78..92] Decimal(2), trecho"));
+    }
+
+    #[test]
+    fn parse_with_delegates_to_user_function() {
+        #[derive(Debug, PartialEq)]
+        struct AgenciaConta {
+            agencia: u16,
+            conta: u32,
+            dv: char,
+        }
+
+        // Formato composto "AAAA/CCCCCC-D"
+        fn agencia_conta(text: &str) -> std::result::Result<AgenciaConta, String> {
+            let (agencia, resto) = text.split_once('/').ok_or("separador '/' ausente")?;
+            let (conta, dv) = resto.split_once('-').ok_or("separador '-' ausente")?;
+            Ok(AgenciaConta {
+                agencia: agencia.parse().map_err(|_| "agência inválida")?,
+                conta: conta.parse().map_err(|_| "conta inválida")?,
+                dv: dv.chars().next().ok_or("DV ausente")?,
+            })
+        }
+
+        #[derive(FixedWidth, Debug)]
+        struct Cedente {
+            #[fw(pos = "1..13", parse_with = "agencia_conta")]
+            conta: AgenciaConta,
+            #[fw(pos = "14..26", parse_with = "agencia_conta")]
+            conta_destino: Option<AgenciaConta>,
+        }
+
+        let cedente = Cedente::parse("1234/567890-X             ").unwrap();
+        assert_eq!(cedente.conta, AgenciaConta { agencia: 1234, conta: 567890, dv: 'X' });
+        assert_eq!(cedente.conta_destino, None);
+
+        assert!(matches!(
+            Cedente::parse("1234567890-X              "),
+            Err(FixedWidthError::Conversion { field: "conta", message }) if message == "separador '/' ausente"
+        ));
+    }
+
+    #[test]
+    fn signed_and_debit_credit_values() {
+        #[derive(FixedWidth, Debug)]
+        struct Lancamento {
+            #[fw(pos = "1..8", numeric, signed)]
+            ajuste: i32,
+            #[fw(pos = "9..16", decimal = 2, signed)]
+            juros: f64,
+            #[fw(pos = "17..24", decimal = 2, sign_pos = "25..25", credit = "C")]
+            valor: f64,
+            #[fw(pos = "25..25", alpha)]
+            natureza: String,
+        }
+
+        let lancamento = Lancamento::parse(concat!("-0000123", "00001234", "00010000", "D")).unwrap();
+        assert_eq!((lancamento.ajuste, lancamento.juros, lancamento.valor), (-123, 12.34, -100.0));
+        assert_eq!(lancamento.natureza, "D");
+
+        let credito = Lancamento::parse(concat!("    +123", "-0001234", "00010000", "C")).unwrap();
+        assert_eq!((credito.ajuste, credito.juros, credito.valor), (123, -12.34, 100.0));
+
+        assert!(matches!(
+            Lancamento::parse(concat!("-  00123", "00001234", "00010000", "D")),
+            Err(FixedWidthError::InvalidNumeric { field: "ajuste", .. })
+        ));
+        // Sem `signed`, o sinal continua inválido
+        let fields = [FieldSpec::new("valor", FieldPos::new_const(1, 4), FieldKind::Numeric)];
+        assert!(parse_line("-123", &fields).is_err());
+    }
+
+    #[test]
+    fn optional_decimal_keeps_blank_and_zero_apart() {
+        #[derive(FixedWidth, Debug)]
+        struct Encargos {
+            #[fw(pos = "1..5", decimal = 2)]
+            juros: Option<f64>,
+            #[fw(pos = "6..10", decimal = 2)]
+            desconto: Option<f64>,
+            #[fw(pos = "11..15", decimal = 2)]
+            multa: f64,
+        }
+
+        // Branco = "não se aplica"; zeros = valor zero informado
+        let encargos = Encargos::parse("     00000     ").unwrap();
+        assert_eq!((encargos.juros, encargos.desconto, encargos.multa), (None, Some(0.0), 0.0));
+
+        #[cfg(feature = "rust_decimal")]
+        {
+            #[derive(FixedWidth, Debug)]
+            struct EncargosExatos {
+                #[fw(pos = "1..5", decimal = 2)]
+                juros: Option<rust_decimal::Decimal>,
+                #[fw(pos = "6..10", decimal = 2)]
+                desconto: Option<rust_decimal::Decimal>,
+            }
+
+            let exatos = EncargosExatos::parse("     00000").unwrap();
+            assert_eq!(exatos.juros, None);
+            assert_eq!(exatos.desconto, Some(rust_decimal::Decimal::new(0, 2)));
+        }
+    }
+
+    #[test]
+    fn zoned_overpunch_decodes_sign() {
+        #[derive(FixedWidth, Debug)]
+        struct Saldo {
+            #[fw(pos = "1..8", zoned = 2)]
+            anterior: f64,
+            #[fw(pos = "9..16", zoned = 2)]
+            atual: f64,
+            #[fw(pos = "17..20", zoned = 0)]
+            dias: Option<f64>,
+        }
+
+        let saldo = Saldo::parse("0000123J0000500{    ").unwrap();
+        assert_eq!((saldo.anterior, saldo.atual, saldo.dias), (-12.31, 50.0, None));
+        let saldo = Saldo::parse("0000123A0000500}003}").unwrap();
+        assert_eq!((saldo.anterior, saldo.atual, saldo.dias), (12.31, -50.0, Some(-30.0)));
+
+        assert!(matches!(
+            Saldo::parse("0000123Z0000500{    "),
+            Err(FixedWidthError::InvalidNumeric { field: "anterior", .. })
+        ));
+    }
+
+    #[test]
+    fn redacts_snippets_in_errors() {
+        let fields = [FieldSpec::new("cpf", FieldPos::new_const(1, 11), FieldKind::Numeric)];
+        let line = b"123.456.789";
+        let message = |redaction| {
+            let options = ParseOptions { redaction, ..Default::default() };
+            parse_line_bytes_with(line, &fields, &options).unwrap_err().to_string()
+        };
+
+        assert!(message(Redaction::None).contains("'123.456.789'"));
+        assert!(message(Redaction::Mask).ends_with("'***********'"));
+        let hashed = message(Redaction::Hash);
+        assert!(!hashed.contains("456") && hashed.contains("hash:"));
+        assert_eq!(hashed, message(Redaction::Hash));
+        // Valor fixo (FNV-1a), que não muda entre versões do Rust
+        assert_eq!(Redaction::Hash.apply("a"), "hash:af63dc4c8601ec8c");
+
+        let err = parse_line_bytes_with(line, &fields, &ParseOptions { redaction: Redaction::Mask, ..Default::default() })
+            .unwrap_err()
+            .at_line_with(7, line, &fields, Redaction::Mask);
+        assert!(!err.to_string().contains("456"));
+    }
+
+    #[test]
+    fn packed_decimal_decodes_bcd_and_sign() {
+        #[derive(FixedWidth, Debug)]
+        struct Movimento {
+            #[fw(pos = "1..2", numeric)]
+            tipo: u8,
+            #[fw(pos = "3..6", packed = 2)]
+            valor: f64,
+            #[fw(pos = "7..9", packed = 0)]
+            quantidade: i32,
+        }
+
+        let options = ParseOptions { trim_line_ending: false, ..Default::default() };
+        let line = [b'0', b'1', 0x00, 0x12, 0x34, 0x5C, 0x00, 0x04, 0x2D];
+        let movimento = Movimento::parse_bytes(&line, &options).unwrap();
+        assert_eq!((movimento.tipo, movimento.valor, movimento.quantidade), (1, 123.45, -42));
+
+        // Um último byte 0x0D é dado compactado (dígito 0, sinal negativo), não quebra de linha
+        let line = [b'0', b'1', 0x00, 0x00, 0x00, 0x0C, 0x00, 0x01, 0x0D];
+        assert_eq!(Movimento::parse_bytes(&line, &options).unwrap().quantidade, -10);
+
+        let invalid = [b'0', b'1', 0x00, 0x1A, 0x34, 0x5C, 0x00, 0x00, 0x0C];
+        assert!(matches!(
+            Movimento::parse_bytes(&invalid, &options),
+            Err(FixedWidthError::InvalidNumeric { field: "valor", snippet }) if snippet == "00 1A 34 5C"
+        ));
+    }
+
+    #[test]
+    fn nested_blocks_reuse_layout_with_offset() {
+        // Bloco "controle" do CNAB 240: banco/lote/registro
+        #[derive(FixedWidth, Debug, PartialEq)]
+        struct Controle {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "4..7", numeric)]
+            lote: u16,
+            #[fw(pos = "8..8", numeric)]
+            registro: u8,
+        }
+
+        // Bloco "empresa": tipo e número de inscrição
+        #[derive(FixedWidth, Debug, PartialEq)]
+        struct Empresa {
+            #[fw(pos = "1..1", numeric)]
+            tipo_inscricao: u8,
+            #[fw(pos = "2..15", numeric)]
+            inscricao: u64,
+        }
+
+        #[derive(FixedWidth, Debug)]
+        #[fw(width = 30)]
+        struct HeaderLote {
+            #[fw(nested)]
+            controle: Controle,
+            #[fw(pos = "9..9", alpha)]
+            operacao: String,
+            #[fw(nested, offset = 9)]
+            empresa: Empresa,
+            #[fw(pos = "25..30", alpha)]
+            livre: String,
+        }
+
+        assert_eq!(<HeaderLote as RecordSpan>::END, 30);
+        assert_eq!(<Empresa as RecordSpan>::END, 15);
+
+        let header = HeaderLote::parse("34100011C212345678000199LIVRE ").unwrap();
+        assert_eq!(header.controle, Controle { banco: 341, lote: 1, registro: 1 });
+        assert_eq!(header.operacao, "C");
+        assert_eq!(header.empresa, Empresa { tipo_inscricao: 2, inscricao: 12345678000199 });
+        assert_eq!(header.livre, "LIVRE");
+
+        // Posições do erro são as do registro, não as do bloco
+        assert!(matches!(
+            HeaderLote::parse("34100011C2123"),
+            Err(FixedWidthError::LineTooShort { len: 13, needed: 24 })
+        ));
+    }
+
+    #[test]
+    fn repeated_groups_parse_into_vec_and_array() {
+        #[derive(FixedWidth, Debug, PartialEq, Default)]
+        struct Ocorrencia {
+            #[fw(pos = "1..2", numeric)]
+            codigo: u8,
+            #[fw(pos = "3..5", alpha)]
+            motivo: String,
+        }
+
+        #[derive(FixedWidth, Debug)]
+        #[fw(width = 20)]
+        #[fw_record(lenient)]
+        struct Segmento {
+            #[fw(pos = "1..1", alpha)]
+            tipo: String,
+            // Ocorrências de 5 posições, separadas por 1 posição livre
+            #[fw(occurs = 3, stride = 6, offset = 1)]
+            ocorrencias: Vec<Ocorrencia>,
+            #[fw(nested, occurs = 2, stride = 1, offset = 18)]
+            flags: [Flag; 2],
+        }
+
+        #[derive(FixedWidth, Debug, PartialEq, Default)]
+        struct Flag {
+            #[fw(pos = "1..1", alpha)]
+            valor: String,
+        }
+
+        assert_eq!(<Segmento as RecordSpan>::END, 20);
+
+        let segmento = Segmento::parse("T01AAA 02BBB 03CCCSN").unwrap();
+        assert_eq!(segmento.tipo, "T");
+        let codigos: Vec<_> = segmento.ocorrencias.iter().map(|o| (o.codigo, o.motivo.as_str())).collect();
+        assert_eq!(codigos, [(1, "AAA"), (2, "BBB"), (3, "CCC")]);
+        assert_eq!(segmento.flags.map(|f| f.valor), ["S".to_string(), "N".to_string()]);
+
+        // Erros da terceira ocorrência apontam posições do registro
+        assert!(matches!(
+            Segmento::parse("T01AAA 02BBB 0"),
+            Err(FixedWidthError::LineTooShort { len: 14, needed: 15 })
+        ));
+        let (_, errors) = Segmento::parse_lenient("T01AAA 02BBB XXCCCSN");
+        assert_eq!((errors[0].field, errors[0].pos.start(), errors[0].pos.end()), ("ocorrencias", 2, 18));
+    }
+
+    #[test]
+    fn spec_gaps_report_unmapped_positions() {
+        #[derive(FixedWidth, Debug)]
+        #[allow(dead_code)]
+        struct Controle {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "5..5", numeric)]
+            registro: u8,
+        }
+
+        #[derive(FixedWidth, Debug)]
+        #[allow(dead_code)]
+        #[fw(width = 20)]
+        struct Detalhe {
+            #[fw(nested)]
+            controle: Controle,
+            #[fw(pos = "6..10", filler)]
+            uso_febraban: (),
+            #[fw(pos = "13..16", decimal = 2, sign_pos = "17..17")]
+            valor: f64,
+        }
+
+        let gaps: Vec<_> = Detalhe::spec_gaps().iter().map(|p| (p.start(), p.end())).collect();
+        assert_eq!(gaps, [(4, 4), (11, 12), (18, 20)]);
+
+        let layout = LayoutBuilder::new()
+            .width(10)
+            .field("a", 1, 2, FieldKind::Numeric)
+            .field("b", 5, 10, FieldKind::Alpha)
+            .build()
+            .unwrap();
+        assert_eq!(layout.spec_gaps(), [FieldPos::new_const(3, 4)]);
+    }
+
+    #[test]
+    fn saturate_clamps_numerics_and_records_warnings() {
+        #[derive(FixedWidth, Debug)]
+        struct Perfil {
+            #[fw(pos = "1..3", numeric)]
+            quantidade: u8,
+            #[fw(pos = "4..6", numeric)]
+            agencia: u16,
+            #[fw(pos = "7..10", decimal = 2)]
+            valor: f64,
+        }
+
+        let line = "9991X312,4";
+        assert!(matches!(
+            Perfil::parse(line),
+            Err(FixedWidthError::NumericOverflow { field: "quantidade", value: 999, .. })
+        ));
+
+        let warnings = Warnings::new();
+        let options = ParseOptions {
+            encoding: Encoding::Utf8,
+            saturate: true,
+            warnings: Some(warnings.clone()),
+            ..Default::default()
+        };
+        let perfil = Perfil::parse_bytes(line.as_bytes(), &options).unwrap();
+        assert_eq!((perfil.quantidade, perfil.agencia, perfil.valor), (u8::MAX, 0, 0.0));
+
+        let warnings = warnings.take();
+        let fields: Vec<_> = warnings.iter().map(|w| w.field).collect();
+        assert_eq!(fields, ["quantidade", "agencia", "valor"]);
+        assert!(matches!(warnings[1].error, FixedWidthError::InvalidNumeric { .. }));
+        assert_eq!((warnings[2].pos.start(), warnings[2].pos.end()), (7, 10));
+
+        let fields = [FieldSpec::new("enorme", FieldPos::new_const(1, 20), FieldKind::Numeric).signed()];
+        let record = parse_line_bytes_with(b"-9999999999999999999", &fields, &options).unwrap();
+        assert_eq!(record["enorme"], Value::Numeric(i64::MIN));
+
+        // Compactado: estouro vira o limite pelo nibble de sinal; nibble inválido vira 0
+        let fields = [FieldSpec::new("compactado", FieldPos::new_const(1, 11), FieldKind::Packed { scale: 0 })];
+        let options = ParseOptions { trim_line_ending: false, saturate: true, ..Default::default() };
+        let mut line = [0x99; 11];
+        for (sign, expected) in [(0x9C, i64::MAX), (0x9D, i64::MIN), (0x97, 0)] {
+            line[10] = sign;
+            let record = parse_line_bytes_with(&line, &fields, &options).unwrap();
+            assert_eq!(record["compactado"], Value::Numeric(expected));
+        }
+    }
+
+    #[test]
+    fn parse_report_records_non_fatal_anomalies() {
+        #[derive(FixedWidth, Debug)]
+        #[fw(width = 10)]
+        struct Registro {
+            #[fw(pos = "1..6", numeric)]
+            valor: u32,
+            #[fw(pos = "7..10", alpha)]
+            nome: String,
+        }
+
+        let report = Registro::parse_report(b"12 345ABCD", &ParseOptions::default()).unwrap();
+        assert_eq!(report.record.valor, 12_345);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!((report.warnings[0].field, report.warnings[0].pos.start()), ("valor", 1));
+        assert!(matches!(report.warnings[0].error, FixedWidthError::InvalidNumeric { .. }));
+        // Sem onde avisar, continua sendo erro
+        assert!(matches!(Registro::parse("12 345ABCD"), Err(FixedWidthError::InvalidNumeric { .. })));
+
+        let report = Registro::parse_report(b"012345ABCD  LIXO  \r\n", &ParseOptions::default()).unwrap();
+        assert_eq!(report.record.nome, "ABCD");
+        assert_eq!(report.warnings[0].to_string(), "campo sobra [11..18]: linha com tamanho 18, esperado exatamente 10");
+        assert!(Registro::parse_report(b"012345ABCD    ", &ParseOptions::default()).unwrap().is_clean());
+        let ignore = ParseOptions { trailing_data: TrailingData::Ignore, ..Default::default() };
+        assert!(Registro::parse_bytes(b"012345ABCDLIXO", &ignore).is_ok());
+    }
+
+    #[test]
+    fn strict_and_lenient_options_control_numerics_and_trailing_data() {
+        #[derive(FixedWidth, Debug)]
+        #[fw(width = 10)]
+        struct Registro {
+            #[fw(pos = "1..6", numeric)]
+            valor: u32,
+            #[fw(pos = "7..10", numeric)]
+            quantidade: Option<u16>,
+        }
+
+        let strict = ParseOptions::strict();
+        assert!(matches!(Registro::parse_with("      0001", &strict), Err(FixedWidthError::InvalidNumeric { field: "valor", .. })));
+        assert!(matches!(Registro::parse_with("012345    LIXO", &strict), Err(FixedWidthError::WrongLength { len: 14, expected: 10 })));
+        assert_eq!(Registro::parse_with("012345    ", &strict).unwrap().quantidade, None);
+
+        let lenient = ParseOptions::lenient();
+        let registro = Registro::parse_with("12 345  1 LIXO", &lenient).unwrap();
+        assert_eq!((registro.valor, registro.quantidade), (12_345, Some(1)));
+        assert_eq!(Registro::parse_with("      0001", &lenient).unwrap().valor, 0);
+        assert!(matches!(Registro::parse("12 3450001"), Err(FixedWidthError::InvalidNumeric { .. })));
+
+        let fields = [FieldSpec::new("valor", FieldPos::new_const(1, 6), FieldKind::Numeric)];
+        assert!(parse_line_with("      ", &fields, &strict).is_err());
+        assert_eq!(parse_line_with("1 2 3 ", &fields, &lenient).unwrap()["valor"], Value::Numeric(123));
+        let keep_ending = ParseOptions { trim_line_ending: false, ..ParseOptions::strict() };
+        assert!(matches!(Registro::parse_with("0123450001\r\n", &keep_ending), Err(FixedWidthError::WrongLength { len: 12, .. })));
+    }
+
+    #[test]
+    fn integer_capacity_is_checked_at_compile_time() {
+        assert_eq!(max_digits(u8::MAX as u128), 3);
+        assert_eq!(max_digits(u32::MAX as u128), 10);
+        assert_eq!(max_digits(i64::MAX as u128), 19);
+        assert_eq!(max_digits(u64::MAX as u128), 20);
+
+        // Larguras no limite do tipo compilam; uma posição a mais seria erro de compilação
+        #[derive(FixedWidth, Debug)]
+        struct Limites {
+            #[fw(pos = "1..3", numeric)]
+            pequeno: u8,
+            #[fw(pos = "4..23", numeric)]
+            grande: Option<u64>,
+        }
+
+        let limites = Limites::parse("25500000000000000000001").unwrap();
+        assert_eq!((limites.pequeno, limites.grande), (255, Some(1)));
+    }
+
+    #[test]
+    fn code_fields_map_to_enums() {
+        #[derive(FixedWidthCode, Debug, Clone, Copy, PartialEq)]
+        enum TipoInscricao {
+            #[code("1")]
+            Cpf,
+            #[code("2")]
+            Cnpj,
+        }
+
+        #[derive(FixedWidth, Debug, PartialEq)]
+        #[fw_record(write)]
+        struct Sacado {
+            #[fw(pos = "1..1", code)]
+            tipo_inscricao: TipoInscricao,
+            #[fw(pos = "2..3", alpha, code)]
+            tipo_avalista: Option<TipoInscricao>,
+        }
+
+        let sacado = Sacado::parse("2  ").unwrap();
+        assert_eq!(sacado, Sacado { tipo_inscricao: TipoInscricao::Cnpj, tipo_avalista: None });
+        assert_eq!(Sacado::parse("11 ").unwrap().tipo_avalista, Some(TipoInscricao::Cpf));
+        assert_eq!(sacado.to_line().unwrap(), "2  ");
+
+        let error = Sacado::parse("3  ").unwrap_err();
+        assert!(matches!(&error, FixedWidthError::UnknownCode { field: "tipo_inscricao", code } if code == "3"));
+        let fields = [FieldSpec::new("tipo_inscricao", FieldPos::new_const(1, 1), FieldKind::Alpha)];
+        assert!(matches!(error.at_line(7, b"3  ", &fields), FixedWidthError::Field { line_number: 7, .. }));
+    }
+
+    #[test]
+    fn bool_fields_compare_with_truthy_value() {
+        #[derive(FixedWidth, Debug, PartialEq)]
+        #[fw_record(write)]
+        struct Titulo {
+            #[fw(pos = "1..1", bool, truthy = "A", falsy = "N")]
+            aceite: bool,
+            #[fw(pos = "2..2", bool, truthy = "1")]
+            dda: bool,
+            #[fw(pos = "3..3", bool)]
+            protesto: Option<bool>,
+        }
+
+        let titulo = Titulo::parse("N1 ").unwrap();
+        assert_eq!(titulo, Titulo { aceite: false, dda: true, protesto: None });
+        assert_eq!(Titulo::parse("A0S").unwrap(), Titulo { aceite: true, dda: false, protesto: Some(true) });
+        assert!(matches!(Titulo::parse("X1 "), Err(FixedWidthError::UnknownCode { field: "aceite", .. })));
+
+        assert_eq!(titulo.to_line().unwrap(), "N1 ");
+        assert_eq!(Titulo { aceite: true, dda: false, protesto: Some(false) }.to_line().unwrap(), "A  ");
+    }
+
+    #[test]
+    fn charset_restricts_field_content() {
+        #[derive(FixedWidth, Debug)]
+        #[fw_record(write)]
+        struct Sacado {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "4..13", alpha, charset = "A-Z0-9 ")]
+            nome: String,
+        }
+
+        assert_eq!(Sacado::parse("341JOSE 2    ").unwrap().nome, "JOSE 2");
+        assert!(matches!(
+            Sacado::parse("341JOSé     "),
+            Err(FixedWidthError::InvalidChar { field: "nome", ch: 'é', pos: 7 })
+        ));
+        let latin1 = Sacado::parse_bytes(b"341JO\xc9 SILVA ", &ParseOptions::default());
+        assert!(matches!(latin1, Err(FixedWidthError::InvalidChar { ch: 'É', pos: 6, .. })));
+
+        // Em bloco aninhado, a posição é a do registro
+        #[derive(FixedWidth, Debug)]
+        struct Detalhe {
+            #[fw(pos = "1..5", numeric)]
+            sequencial: u32,
+            #[fw(nested, offset = 5)]
+            sacado: Sacado,
+        }
+        let detalhe = Detalhe::parse("00001341JOSE      ").unwrap();
+        assert_eq!((detalhe.sequencial, detalhe.sacado.nome.as_str()), (1, "JOSE"));
+        assert!(matches!(
+            Detalhe::parse("00001341JOSé     "),
+            Err(FixedWidthError::InvalidChar { field: "nome", ch: 'é', pos: 12 })
+        ));
+
+        let sacado = Sacado { banco: 341, nome: "Jose".to_string() };
+        assert!(matches!(sacado.to_bytes(), Err(FixedWidthError::InvalidChar { ch: 'o', pos: 5, .. })));
+        assert!(charset_contains("A-Z0-9 .-", '-') && !charset_contains("A-Z", 'a'));
+    }
+
+    #[test]
+    fn derived_structs_expose_their_spec() {
+        #[derive(FixedWidth)]
+        #[fw(width = 20)]
+        #[allow(dead_code)]
+        struct Header {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "4..13", alpha)]
+            empresa: String,
+            #[fw(pos = "14..20", filler)]
+            uso_febraban: (),
+        }
+
+        let names: Vec<_> = Header::spec().iter().map(|field| field.name).collect();
+        assert_eq!(names, ["banco", "empresa", "uso_febraban"]);
+        let empresa = Header::field_by_name("empresa").unwrap();
+        assert_eq!(empresa.pos, FieldPos::new_const(4, 13));
+        assert!(matches!(empresa.kind, FieldKind::Alpha));
+        assert!(Header::field_by_name("agencia").is_none());
+        assert_eq!(Header::total_width(), 20);
+    }
+
+    #[test]
+    fn borrowed_structs_parse_without_copying() {
+        #[derive(FixedWidth, Debug)]
+        #[fw_record(write)]
+        struct Titulo<'a> {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "4..13", alpha)]
+            sacado: &'a str,
+            #[fw(pos = "14..18", alpha)]
+            cidade: Option<&'a str>,
+        }
+
+        let line = String::from("341JOSÉ          \r\n");
+        let titulo = Titulo::parse_ref(&line).unwrap();
+        assert_eq!((titulo.banco, titulo.sacado, titulo.cidade), (341, "JOSÉ", None));
+        assert!(std::ptr::eq(titulo.sacado.as_ptr(), line[3..].as_ptr()));
+        assert_eq!(titulo.to_line().unwrap(), line.trim_end_matches("\r\n"));
+
+        // O campo corta o "É" ao meio
+        let line = "341ABCDEFGHIÉ    ";
+        assert!(matches!(Titulo::parse_ref(line), Err(FixedWidthError::CharBoundary { field: "sacado", pos: 13 })));
+        assert!(matches!(Titulo::parse_ref("341JOSE"), Err(FixedWidthError::LineTooShort { .. })));
+    }
+
+    #[test]
+    fn parse_field_ref_borrows_alpha_text() {
+        let nome = FieldSpec::new("nome", FieldPos::new_const(4, 13), FieldKind::Alpha);
+        let banco = FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Numeric);
+        let utf8 = ParseOptions::with_encoding(Encoding::Utf8);
+
+        let line = "341JOAO      ".as_bytes();
+        let value = parse_field_ref(line, &nome, &utf8).unwrap();
+        assert!(matches!(&value, ValueRef::Alpha(Cow::Borrowed("JOAO"))));
+        assert_eq!(parse_field_ref(line, &banco, &utf8).unwrap().as_i64(), Some(341));
+        assert_eq!(value.into_owned(), Value::Alpha("JOAO".to_string()));
+
+        // Latin-1 com acento precisa ser decodificado: o texto passa a ser owned
+        let line = b"341JO\xc3O      ";
+        let value = parse_field_ref(line, &nome, &ParseOptions::default()).unwrap();
+        assert!(matches!(&value, ValueRef::Alpha(Cow::Owned(text)) if text == "JOÃO"));
+    }
+
+    #[test]
+    fn utf8_positions_never_split_characters() {
+        let fields = [
+            FieldSpec::new("nome", FieldPos::new_const(1, 5), FieldKind::Alpha),
+            FieldSpec::new("valor", FieldPos::new_const(6, 8), FieldKind::Numeric),
+        ];
+        // "GONÇALO" convertido para UTF-8: o "Ç" ocupa os bytes 4 e 5
+        let line = "GONÇA123".as_bytes();
+        let utf8 = ParseOptions::with_encoding(Encoding::Utf8);
+        let err = parse_field(line, &FieldSpec::new("nome", FieldPos::new_const(1, 4), FieldKind::Alpha), &utf8);
+        assert!(matches!(err, Err(FixedWidthError::CharBoundary { field: "nome", pos: 4 })));
+        let err = parse_field(line, &FieldSpec::new("x", FieldPos::new_const(5, 6), FieldKind::Alpha), &utf8);
+        assert!(matches!(err, Err(FixedWidthError::CharBoundary { pos: 5, .. })));
+
+        // Posições em caracteres: o "Ç" ocupa uma única posição
+        let chars = ParseOptions { unit: PositionUnit::Chars, ..utf8 };
+        let record = parse_line_bytes_with(line, &fields, &chars).unwrap();
+        assert_eq!(record.get_str("nome"), Some("GONÇA"));
+        assert_eq!(record.get_i64("valor"), Some(123));
+        assert!(matches!(
+            parse_field(line, &FieldSpec::new("x", FieldPos::new_const(8, 9), FieldKind::Alpha), &chars),
+            Err(FixedWidthError::LineTooShort { len: 8, needed: 9 })
+        ));
+    }
+
+    #[test]
+    fn char_unit_structs_count_characters() {
+        #[derive(FixedWidth, Debug)]
+        struct Cidade {
+            #[fw(pos = "1..5", alpha)]
+            nome: String,
+        }
+
+        #[derive(FixedWidth, Debug)]
+        #[fw(unit = "chars")]
+        struct Cliente {
+            #[fw(pos = "1..7", alpha)]
+            nome: String,
+            #[fw(pos = "8..12", numeric)]
+            codigo: u32,
+            #[fw(nested, offset = 12)]
+            cidade: Cidade,
+        }
+
+        #[derive(FixedWidth, Debug)]
+        #[fw(width = 12, strict, unit = "chars")]
+        #[allow(dead_code)]
+        struct Codigo {
+            #[fw(pos = "1..12", alpha)]
+            texto: String,
+        }
+
+        let cliente = Cliente::parse("GONÇALO00042SÃO P").unwrap();
+        assert_eq!((cliente.nome.as_str(), cliente.codigo, cliente.cidade.nome.as_str()), ("GONÇALO", 42, "SÃO P"));
+        assert!(Codigo::parse("GONÇALO00042").is_ok());
+        assert!(matches!(Codigo::parse("GONÇALO0004"), Err(FixedWidthError::WrongLength { len: 11, expected: 12 })));
+    }
+
+    #[test]
+    fn fast_path_reads_clean_digits_only() {
+        #[derive(FixedWidth, Debug)]
+        #[fw(width = 14)]
+        struct Lancamento {
+            #[fw(pos = "1..5", numeric)]
+            conta: u32,
+            #[fw(pos = "6..12", decimal = 2)]
+            valor: f64,
+            #[fw(pos = "13..14", numeric)]
+            parcela: Option<u8>,
+        }
+
+        let fast = ParseOptions { fast: true, ..Default::default() };
+        let lancamento = Lancamento::parse_bytes(b"000420012345  ", &fast).unwrap();
+        assert_eq!((lancamento.conta, lancamento.valor, lancamento.parcela), (42, 123.45, None));
+        assert_eq!(Lancamento::parse_bytes(b"00042001234507", &fast).unwrap().parcela, Some(7));
+
+        // Sem aparar espaços: o caminho normal aceita, o rápido rejeita
+        let line = b"   420012345 1";
+        assert_eq!(Lancamento::parse_bytes(line, &ParseOptions::default()).unwrap().conta, 42);
+        assert!(matches!(
+            Lancamento::parse_bytes(line, &fast),
+            Err(FixedWidthError::InvalidNumeric { field: "conta", .. })
+        ));
+        // O tamanho da linha é validado uma única vez, contra o registro inteiro
+        assert!(matches!(
+            Lancamento::parse_bytes(b"0004200123450", &fast),
+            Err(FixedWidthError::LineTooShort { len: 13, needed: 14 })
+        ));
+    }
+
+    #[test]
+    fn read_only_and_write_only_fields_serve_both_directions() {
+        #[derive(FixedWidth, Debug, PartialEq)]
+        #[fw_record(write)]
+        struct Titulo {
+            #[fw(pos = "1..5", alpha)]
+            nosso_numero: String,
+            /// Instrução só vai na remessa
+            #[fw(pos = "6..7", numeric, write_only)]
+            instrucao: u8,
+            /// Valor pago só vem no retorno
+            #[fw(pos = "8..12", decimal = 2, read_only)]
+            valor_pago: f64,
+            #[fw(pos = "13..15", alpha, read_only)]
+            motivo: String,
+        }
+
+        let remessa = Titulo { nosso_numero: "123".into(), instrucao: 9, valor_pago: 1.5, motivo: "A1".into() };
+        assert_eq!(remessa.to_line().unwrap(), "123  0900000   ");
+
+        // No retorno a posição da instrução traz outro conteúdo, que não é lido
+        let retorno = Titulo::parse("123  XX00150A1 ").unwrap();
+        assert_eq!(retorno, Titulo { nosso_numero: "123".into(), instrucao: 0, valor_pago: 1.5, motivo: "A1".into() });
+    }
+
+    #[test]
+    fn parse_line_keeps_layout_order() {
+        let fields = [
+            FieldSpec::new("zeta", FieldPos::new_const(1, 1), FieldKind::Numeric),
+            FieldSpec::new("alfa", FieldPos::new_const(2, 2), FieldKind::Numeric),
+            FieldSpec::new("uso_febraban", FieldPos::new_const(3, 3), FieldKind::Filler),
+            FieldSpec::new("meio", FieldPos::new_const(4, 4), FieldKind::Alpha),
+        ];
+        let record = parse_line("12 X", &fields).unwrap();
+        let names: Vec<_> = record.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["zeta", "alfa", "meio"]);
+        assert_eq!(record.to_json(), r#"{"zeta":1,"alfa":2,"meio":"X"}"#);
+        assert_eq!(record.to_canonical_json(), r#"{"alfa":2,"meio":"X","zeta":1}"#);
+        assert_eq!(record.into_map()["meio"], Value::Alpha("X".to_string()));
+    }
+}
@@ -0,0 +1,166 @@
+//! # Arquivos Mapeados em Memória
+//!
+//! Para arquivos muito grandes, [`FixedWidthFile::open`] mapeia o arquivo em memória
+//! (feature `mmap`) e os registros saem como fatias do próprio mapeamento, sem copiar
+//! cada linha para um buffer. Com structs de campos emprestados
+//! ([`FixedWidthParseRef`](crate::FixedWidthParseRef)), o parse não aloca nada para os
+//! textos:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::mmap::FixedWidthFile;
+//! use cnab_fixedwidth::FixedWidth;
+//!
+//! #[derive(FixedWidth)]
+//! struct Titulo<'a> {
+//!     #[fw(pos = "38..57", alpha)]
+//!     nosso_numero: &'a str,
+//! }
+//!
+//! let arquivo = FixedWidthFile::open("retorno.ret")?;
+//! for titulo in arquivo.parse_ref::<Titulo>() {
+//!     let titulo = titulo?;
+//! }
+//! # Ok::<(), cnab_fixedwidth::FixedWidthError>(())
+//! ```
+
+use crate::reader::{Split, Splitter};
+use crate::{FixedWidthError, FixedWidthParse, FixedWidthParseRef, Framing, ParseOptions, Redaction, Result};
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+
+/// Arquivo mapeado em memória, lido registro a registro.
+#[derive(Debug)]
+pub struct FixedWidthFile {
+    map: Mmap,
+    framing: Framing,
+}
+
+impl FixedWidthFile {
+    /// Mapeia o arquivo em `path`, com um registro por linha.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: o mapeamento é só leitura; alterar ou truncar o arquivo enquanto ele está
+        // mapeado é responsabilidade de quem o abre, como em qualquer leitura com mmap.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self { map, framing: Framing::Lines })
+    }
+
+    /// Divide o arquivo em registros com `framing`, como o [`RecordReader`](crate::RecordReader).
+    ///
+    /// `Framing::Wrapped` não é aceito: um registro em várias linhas físicas não é uma
+    /// fatia contínua do arquivo, e devolvê-lo exigiria copiar as partes.
+    pub fn with_framing(mut self, framing: Framing) -> Result<Self> {
+        if let Framing::Wrapped { .. } = framing {
+            let message = "Framing::Wrapped não é suportado em arquivos mapeados em memória";
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, message).into());
+        }
+        self.framing = framing;
+        Ok(self)
+    }
+
+    /// Conteúdo do arquivo.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Registros do arquivo (sem separadores), emprestados do mapeamento.
+    pub fn records(&self) -> Records<'_> {
+        Records { rest: &self.map, framing: self.framing }
+    }
+
+    /// Faz o parse de cada registro para uma struct derivada.
+    ///
+    /// Erros de parse saem com o número do registro (`FixedWidthError::Line`).
+    pub fn parse<T: FixedWidthParse>(&self, options: &ParseOptions) -> impl Iterator<Item = Result<T>> {
+        self.records().zip(1..).map(move |(record, number)| {
+            T::parse_bytes(record, options).map_err(|e| e.at_line_with(number, record, &[], options.redaction))
+        })
+    }
+
+    /// Faz o parse de cada registro para uma struct com campos emprestados do arquivo.
+    ///
+    /// O arquivo deve ser UTF-8; um registro inválido resulta em `InvalidUtf8`, com o
+    /// número do registro.
+    pub fn parse_ref<'a, T: FixedWidthParseRef<'a>>(&'a self) -> impl Iterator<Item = Result<T>> + 'a {
+        self.records().zip(1..).map(|(record, number)| {
+            std::str::from_utf8(record)
+                .map_err(|_| FixedWidthError::InvalidUtf8)
+                .and_then(T::parse_ref)
+                .map_err(|e| e.at_line_with(number, record, &[], Redaction::default_global()))
+        })
+    }
+}
+
+/// Iterador dos registros de um [`FixedWidthFile`].
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    rest: &'a [u8],
+    framing: Framing,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        // Em `Lines` e `FixedLength` os trechos de um registro são contíguos: o registro é
+        // a fatia entre o início do primeiro e o fim do último
+        let mut splitter = Splitter::new(self.framing, 0);
+        let (mut start, mut end) = (None, 0);
+        let mut offset = 0;
+        loop {
+            let record = start.map_or(&[][..], |start| &self.rest[start..end]);
+            let step = splitter.feed(&self.rest[offset..], record);
+            if !step.take.is_empty() {
+                start.get_or_insert(offset + step.take.start);
+                end = offset + step.take.end;
+            }
+            end -= step.trim;
+            offset += step.consumed;
+            match step.split {
+                Split::More => {}
+                Split::Record => {
+                    let record = start.map_or(&[][..], |start| &self.rest[start..end]);
+                    self.rest = &self.rest[offset..];
+                    return Some(record);
+                }
+                // `Invalid` só ocorre em `Wrapped`, recusado em `with_framing`
+                Split::End | Split::Invalid(_) => {
+                    self.rest = &[];
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedWidth;
+
+    #[derive(FixedWidth, Debug, PartialEq)]
+    struct Titulo<'a> {
+        #[fw(pos = "1..3", numeric)]
+        banco: u16,
+        #[fw(pos = "4..8", alpha)]
+        nome: &'a str,
+    }
+
+    #[test]
+    fn iterates_records_from_the_mapping() {
+        let path = std::env::temp_dir().join(format!("cnab-mmap-{}.rem", std::process::id()));
+        std::fs::write(&path, "341ANA  \r\n001BIA  \r\n23XCAU  \r\n").unwrap();
+
+        let arquivo = FixedWidthFile::open(&path).unwrap();
+        assert_eq!(arquivo.records().collect::<Vec<_>>(), [&b"341ANA  "[..], b"001BIA  ", b"23XCAU  "]);
+        let titulos: Vec<Result<Titulo>> = arquivo.parse_ref().collect();
+        assert_eq!(titulos[1].as_ref().unwrap(), &Titulo { banco: 1, nome: "BIA" });
+        assert!(matches!(titulos[2], Err(FixedWidthError::Line { line_number: 3, .. })));
+
+        let arquivo = arquivo.with_framing(Framing::FixedLength(4)).unwrap();
+        assert_eq!(arquivo.records().collect::<Vec<_>>(), [&b"341A"[..], b"NA  ", b"001B", b"IA  ", b"23XC", b"AU  "]);
+        assert!(arquivo.with_framing(Framing::Wrapped { lines: 2, width: 4 }).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
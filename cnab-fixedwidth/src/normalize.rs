@@ -0,0 +1,54 @@
+//! # Normalização de Texto (feature `translit`)
+//!
+//! Os layouts FEBRABAN aceitam apenas letras maiúsculas sem acento, dígitos e um
+//! conjunto restrito de símbolos. [`normalize`] translitera qualquer texto Unicode para
+//! esse conjunto (`"José Gonçalves"` vira `"JOSE GONCALVES"`), e campos com
+//! `#[fw(normalize)]` passam por ela na escrita.
+
+/// Símbolos aceitos além de `A-Z` e `0-9`; os demais caracteres viram espaço.
+pub const ALLOWED_SYMBOLS: &str = " .,-/&()*:;@+";
+
+/// Converte o texto para o conjunto de caracteres CNAB: transliteração para ASCII
+/// (`"Ø"` vira `"O"`, `"Æ"` vira `"AE"`), maiúsculas e símbolos fora de
+/// [`ALLOWED_SYMBOLS`] trocados por espaço.
+///
+/// A transliteração pode mudar o tamanho do texto; a largura do campo continua sendo
+/// verificada pelo writer (veja `WritePolicy::on_overflow`).
+pub fn normalize(text: &str) -> String {
+    deunicode::deunicode_with_tofu(text, " ")
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            c if c.is_ascii_alphanumeric() || ALLOWED_SYMBOLS.contains(c) => c,
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// Verifica se o texto já está no conjunto de caracteres CNAB.
+pub fn is_normalized(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || ALLOWED_SYMBOLS.contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixedWidth, FixedWidthWrite};
+
+    #[test]
+    fn transliterates_to_cnab_charset() {
+        assert_eq!(normalize("José Gonçalves"), "JOSE GONCALVES");
+        assert_eq!(normalize("Ærø Ltda. – “filial”"), "AERO LTDA. -  FILIAL ");
+        assert_eq!(normalize("Rua São João, nº 12"), "RUA SAO JOAO, NO 12");
+        assert!(is_normalized(&normalize("Ação #1 ✓")));
+        assert!(!is_normalized("Ação"));
+
+        #[derive(FixedWidth)]
+        #[fw_record(write)]
+        struct Sacado {
+            #[fw(pos = "1..16", alpha, normalize)]
+            nome: String,
+        }
+        let sacado = Sacado { nome: "José Gonçalves".to_string() };
+        assert_eq!(sacado.to_line().unwrap(), "JOSE GONCALVES  ");
+    }
+}
@@ -0,0 +1,308 @@
+//! # Nosso Número
+//!
+//! Cada banco compõe o "nosso número" de um jeito: quantidade de dígitos, carteira ou
+//! convênio embutidos no número e o algoritmo do dígito verificador. Uma
+//! [`NossoNumeroStrategy`] concentra essas regras para os dois sentidos:
+//! - na remessa, [`NossoNumeroStrategy::format`] monta o valor do campo a partir do
+//!   sequencial do título;
+//! - no retorno, [`NossoNumeroStrategy::key`] normaliza o valor recebido (com ou sem DV,
+//!   com separadores) para a mesma chave usada na conciliação.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::nosso_numero::{NossoNumeroError, NossoNumeroRule, NossoNumeroStrategy};
+//!
+//! let regra = NossoNumeroRule::bradesco();
+//! let campo = regra.format("19", 2)?;                         // "000000000028"
+//! assert_eq!(regra.key("19/00000000002-8"), regra.key(&campo));
+//! # Ok::<(), NossoNumeroError>(())
+//! ```
+//!
+//! [`NossoNumeroRule`] traz as regras dos principais bancos; outras composições
+//! implementam a trait.
+
+use thiserror::Error;
+
+/// Erros ao compor ou interpretar um nosso número.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NossoNumeroError {
+    /// O sequencial não cabe na quantidade de dígitos do banco.
+    #[error("sequencial {numero} excede os {digits} dígitos do nosso número")]
+    TooManyDigits { numero: u64, digits: usize },
+
+    /// Carteira/convênio com tamanho ou caracteres inválidos.
+    #[error("prefixo '{prefix}' inválido: esperados {width} dígitos")]
+    InvalidPrefix { prefix: String, width: usize },
+
+    /// Valor com tamanho ou caracteres fora da composição do banco.
+    #[error("nosso número '{value}' fora do formato do banco")]
+    InvalidFormat { value: String },
+
+    /// O dígito verificador informado não confere.
+    #[error("nosso número '{value}': dígito verificador {found}, esperado {expected}")]
+    CheckDigit { value: String, expected: char, found: char },
+}
+
+/// Nosso número decomposto: prefixo (carteira/convênio), sequencial e DV.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NossoNumero {
+    /// Carteira, convênio ou modalidade embutidos no número (vazio se o banco não usa).
+    pub prefix: String,
+    /// Sequencial do título, com os zeros à esquerda.
+    pub numero: String,
+    /// Dígito verificador, quando presente no valor.
+    pub dv: Option<char>,
+}
+
+impl NossoNumero {
+    /// Chave de conciliação: prefixo e sequencial, sem o DV (que nem todo arquivo traz).
+    pub fn key(&self) -> String {
+        format!("{}{}", self.prefix, self.numero)
+    }
+}
+
+/// Regras de composição do nosso número de um banco.
+pub trait NossoNumeroStrategy {
+    /// Valor do campo na remessa para o título `numero` da carteira/convênio `prefix`.
+    fn format(&self, prefix: &str, numero: u64) -> Result<String, NossoNumeroError>;
+
+    /// Decompõe o valor informado no retorno (ou digitado pelo usuário).
+    fn parse(&self, value: &str) -> Result<NossoNumero, NossoNumeroError>;
+
+    /// Chave de conciliação do valor, ou `None` se ele não segue as regras do banco.
+    fn key(&self, value: &str) -> Option<String> {
+        self.parse(value).ok().map(|nosso_numero| nosso_numero.key())
+    }
+}
+
+/// Papel da carteira/convênio no nosso número.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefix {
+    /// O banco não usa prefixo.
+    None,
+    /// Faz parte do número (ex: convênio de 7 dígitos do Banco do Brasil).
+    Embedded { width: usize },
+    /// Só entra no cálculo do DV (ex: carteira do Bradesco); não é escrito no campo.
+    CheckDigitOnly { width: usize },
+}
+
+/// Algoritmo do dígito verificador.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckDigit {
+    /// Sem DV no campo.
+    None,
+    /// Módulo 10, pesos 2 e 1 da direita para a esquerda.
+    Mod10,
+    /// Módulo 11, pesos de 2 a `max_weight` da direita para a esquerda. O DV é
+    /// `11 - resto`; os casos 10 e 11 viram `on_ten` e `on_eleven` (variam por banco).
+    Mod11 { max_weight: u32, on_ten: char, on_eleven: char },
+}
+
+impl CheckDigit {
+    /// Calcula o DV dos dígitos informados (`None` em `CheckDigit::None`).
+    pub fn compute(&self, digits: &str) -> Option<char> {
+        let values = digits.bytes().rev().map(|b| u32::from(b - b'0'));
+        match *self {
+            CheckDigit::None => None,
+            CheckDigit::Mod10 => {
+                let sum: u32 = values
+                    .zip([2, 1].into_iter().cycle())
+                    .map(|(digit, weight)| {
+                        let product = digit * weight;
+                        product / 10 + product % 10
+                    })
+                    .sum();
+                char::from_digit((10 - sum % 10) % 10, 10)
+            }
+            CheckDigit::Mod11 { max_weight, on_ten, on_eleven } => {
+                let sum: u32 = values.zip((2..=max_weight).cycle()).map(|(digit, weight)| digit * weight).sum();
+                match 11 - sum % 11 {
+                    10 => Some(on_ten),
+                    11 => Some(on_eleven),
+                    dv => char::from_digit(dv, 10),
+                }
+            }
+        }
+    }
+}
+
+/// Composição declarativa: prefixo, quantidade de dígitos do sequencial e DV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NossoNumeroRule {
+    /// Carteira/convênio embutidos ou usados no DV.
+    pub prefix: Prefix,
+    /// Dígitos do sequencial (preenchido com zeros à esquerda).
+    pub digits: usize,
+    /// Algoritmo do DV, escrito logo após o sequencial.
+    pub check_digit: CheckDigit,
+}
+
+impl NossoNumeroRule {
+    /// Itaú (carteiras simples): 8 dígitos; o DV do boleto usa agência/conta e não vai
+    /// no campo da remessa.
+    pub const fn itau() -> Self {
+        Self { prefix: Prefix::None, digits: 8, check_digit: CheckDigit::None }
+    }
+
+    /// Bradesco: 11 dígitos e DV módulo 11 (base 7) sobre carteira + número; resto 1
+    /// vira `P`.
+    pub const fn bradesco() -> Self {
+        Self {
+            prefix: Prefix::CheckDigitOnly { width: 2 },
+            digits: 11,
+            check_digit: CheckDigit::Mod11 { max_weight: 7, on_ten: 'P', on_eleven: '0' },
+        }
+    }
+
+    /// Banco do Brasil, convênio de 7 dígitos: convênio + sequencial de 10, sem DV.
+    pub const fn banco_do_brasil() -> Self {
+        Self { prefix: Prefix::Embedded { width: 7 }, digits: 10, check_digit: CheckDigit::None }
+    }
+
+    /// Caixa (SIGCB): modalidade de 2 dígitos (ex: "14") + sequencial de 15, sem DV no
+    /// campo do CNAB 240.
+    pub const fn caixa() -> Self {
+        Self { prefix: Prefix::Embedded { width: 2 }, digits: 15, check_digit: CheckDigit::None }
+    }
+
+    /// Santander: 12 dígitos e DV módulo 11 (base 9); restos 0 e 1 viram `0`.
+    pub const fn santander() -> Self {
+        Self {
+            prefix: Prefix::None,
+            digits: 12,
+            check_digit: CheckDigit::Mod11 { max_weight: 9, on_ten: '0', on_eleven: '0' },
+        }
+    }
+
+    /// Sicredi: ano (2 dígitos) e byte de geração (1) + sequencial de 5. O DV que segue
+    /// o número é calculado com cooperativa, posto e beneficiário, então fica fora da regra.
+    pub const fn sicredi() -> Self {
+        Self { prefix: Prefix::Embedded { width: 3 }, digits: 5, check_digit: CheckDigit::None }
+    }
+
+    /// Largura do valor no campo (prefixo embutido, sequencial e DV).
+    pub fn width(&self) -> usize {
+        let prefix = match self.prefix {
+            Prefix::Embedded { width } => width,
+            _ => 0,
+        };
+        prefix + self.digits + usize::from(self.check_digit != CheckDigit::None)
+    }
+
+    /// Valida o prefixo contra a largura declarada.
+    fn check_prefix<'a>(&self, prefix: &'a str) -> Result<&'a str, NossoNumeroError> {
+        let width = match self.prefix {
+            Prefix::None => return Ok(""),
+            Prefix::Embedded { width } | Prefix::CheckDigitOnly { width } => width,
+        };
+        if prefix.len() != width || !prefix.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(NossoNumeroError::InvalidPrefix { prefix: prefix.to_string(), width });
+        }
+        Ok(prefix)
+    }
+}
+
+impl NossoNumeroStrategy for NossoNumeroRule {
+    fn format(&self, prefix: &str, numero: u64) -> Result<String, NossoNumeroError> {
+        let prefix = self.check_prefix(prefix)?;
+        let numero_text = format!("{:0width$}", numero, width = self.digits);
+        if numero_text.len() > self.digits {
+            return Err(NossoNumeroError::TooManyDigits { numero, digits: self.digits });
+        }
+        let mut value = match self.prefix {
+            Prefix::Embedded { .. } => format!("{prefix}{numero_text}"),
+            _ => numero_text.clone(),
+        };
+        if let Some(dv) = self.check_digit.compute(&format!("{prefix}{numero_text}")) {
+            value.push(dv);
+        }
+        Ok(value)
+    }
+
+    /// Aceita o valor com ou sem DV, com espaços e separadores (`/`, `-`, `.`), e com
+    /// zeros à esquerda além da largura do banco (campos de retorno mais largos).
+    ///
+    /// Com `Prefix::CheckDigitOnly`, a carteira pode vir antes do número
+    /// (ex: `"19/00000000002-8"`); sem ela, o DV não pode ser conferido e fica só registrado.
+    fn parse(&self, value: &str) -> Result<NossoNumero, NossoNumeroError> {
+        let invalid = || NossoNumeroError::InvalidFormat { value: value.to_string() };
+        let compact: String = value.chars().filter(|c| !matches!(c, ' ' | '/' | '-' | '.')).collect();
+        let (embedded, check_only) = match self.prefix {
+            Prefix::None => (0, 0),
+            Prefix::Embedded { width } => (width, 0),
+            Prefix::CheckDigitOnly { width } => (0, width),
+        };
+        let has_dv = self.check_digit != CheckDigit::None;
+        let base = embedded + self.digits;
+
+        // Tamanhos possíveis, do mais completo ao mais curto; zeros à esquerda extras só
+        // são removidos quando nenhum bate exatamente, e nunca viram carteira
+        let candidates = [(check_only, has_dv), (0, has_dv), (0, false)];
+        let trimmed = compact.trim_start_matches('0');
+        let (carteira, digits, dv) = [false, true]
+            .into_iter()
+            .flat_map(|padded| candidates.iter().map(move |&candidate| (padded, candidate)))
+            .filter(|&(padded, (carteira, dv))| (!dv || has_dv) && !(padded && carteira > 0))
+            .find_map(|(padded, (carteira, dv))| {
+                let len = carteira + base + usize::from(dv);
+                let text = match compact.len() {
+                    n if n == len => compact.as_str(),
+                    n if padded && n > len && trimmed.len() <= len => &compact[n - len..],
+                    _ => return None,
+                };
+                let (carteira_text, rest) = text.split_at(carteira);
+                let (digits, dv) = if dv { rest.split_at(rest.len() - 1) } else { (rest, "") };
+                (carteira_text.bytes().chain(digits.bytes()).all(|b| b.is_ascii_digit()))
+                    .then(|| (carteira_text.to_string(), digits.to_string(), dv.chars().next()))
+            })
+            .ok_or_else(invalid)?;
+
+        if let Some(found) = dv
+            && (check_only == 0 || !carteira.is_empty())
+        {
+            let expected = self.check_digit.compute(&format!("{carteira}{digits}"));
+            if expected != Some(found) {
+                return Err(NossoNumeroError::CheckDigit {
+                    value: value.to_string(),
+                    expected: expected.unwrap_or(' '),
+                    found,
+                });
+            }
+        }
+        let (prefix, numero) = digits.split_at(embedded);
+        Ok(NossoNumero { prefix: prefix.to_string(), numero: numero.to_string(), dv })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_normalizes_per_bank() {
+        let bradesco = NossoNumeroRule::bradesco();
+        assert_eq!(bradesco.format("19", 2).unwrap(), "000000000028");
+        assert_eq!(bradesco.format("09", 2).unwrap(), "00000000002P");
+        assert_eq!(bradesco.key("19/00000000002-8"), Some("00000000002".to_string()));
+        assert_eq!(bradesco.key("000000000028"), bradesco.key(" 00000000002"));
+        // Zeros de um campo de retorno mais largo não são lidos como carteira
+        let padded = bradesco.parse("000000000000028").unwrap();
+        assert_eq!((padded.numero.as_str(), padded.dv), ("00000000002", Some('8')));
+        assert_eq!(bradesco.key("000000000000028"), bradesco.key("000000000028"));
+        assert!(matches!(
+            bradesco.parse("19/00000000002-7"),
+            Err(NossoNumeroError::CheckDigit { expected: '8', found: '7', .. })
+        ));
+        assert!(matches!(bradesco.format("19", 100_000_000_000), Err(NossoNumeroError::TooManyDigits { .. })));
+
+        let bb = NossoNumeroRule::banco_do_brasil();
+        let campo = bb.format("1234567", 42).unwrap();
+        assert_eq!(campo, "12345670000000042");
+        let parsed = bb.parse(&format!("000{campo}")).unwrap();
+        assert_eq!((parsed.prefix.as_str(), parsed.numero.as_str(), parsed.dv), ("1234567", "0000000042", None));
+        assert!(matches!(bb.format("123", 1), Err(NossoNumeroError::InvalidPrefix { width: 7, .. })));
+
+        assert_eq!(NossoNumeroRule::itau().format("", 1234).unwrap(), "00001234");
+        assert_eq!(NossoNumeroRule::santander().width(), 13);
+        assert_eq!(CheckDigit::Mod10.compute("0063504142"), Some('9'));
+    }
+}
@@ -0,0 +1,236 @@
+//! # Ocorrências do Retorno
+//!
+//! Tabelas dos códigos de ocorrência (movimento) do retorno de cobrança e dos motivos de
+//! rejeição, para as aplicações não embutirem essas tabelas:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::banks::Bank;
+//! use cnab_fixedwidth::{ocorrencias, CnabFormat};
+//!
+//! # fn exemplo(titulo: &cnab_fixedwidth::cnab240::TituloRetorno) {
+//! # let movimento = titulo.movimento();
+//! let ocorrencia = ocorrencias::ocorrencia(titulo.t.codigo_movimento, CnabFormat::Cnab240, Some(Bank::Itau));
+//! println!("{}", ocorrencia.descricao()); // "Liquidação"
+//! for motivo in movimento.motivos() {
+//!     println!("  {motivo}: {}", ocorrencias::motivo_rejeicao(motivo).unwrap_or("motivo não catalogado"));
+//! }
+//! # }
+//! ```
+//!
+//! O CNAB 240 segue a tabela FEBRABAN em todos os bancos. No CNAB 400, Itaú e Bradesco
+//! têm tabelas próprias (ex: `17` é "alteração de dados rejeitada" no Itaú e "liquidação
+//! após baixa" no Bradesco); os demais bancos usam os códigos FEBRABAN.
+
+use crate::banks::Bank;
+use crate::CnabFormat;
+
+/// Ocorrência do retorno de cobrança, independente do código usado pelo banco.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Ocorrencia {
+    EntradaConfirmada,
+    EntradaRejeitada,
+    Liquidacao,
+    LiquidacaoCartorio,
+    LiquidacaoAposBaixa,
+    Baixa,
+    BaixaRejeitada,
+    AbatimentoConcedido,
+    AbatimentoCancelado,
+    VencimentoAlterado,
+    ProtestoConfirmado,
+    SustacaoProtestoConfirmada,
+    RemessaCartorio,
+    RetiradaCartorio,
+    ProtestadoBaixado,
+    InstrucaoRejeitada,
+    AlteracaoDadosRejeitada,
+    TarifaDebitada,
+    /// Código sem ocorrência catalogada na tabela do banco.
+    Outra(u8),
+}
+
+impl Ocorrencia {
+    /// Descrição para exibição (ex: em telas de conciliação e relatórios).
+    pub fn descricao(&self) -> &'static str {
+        match self {
+            Ocorrencia::EntradaConfirmada => "Entrada confirmada",
+            Ocorrencia::EntradaRejeitada => "Entrada rejeitada",
+            Ocorrencia::Liquidacao => "Liquidação",
+            Ocorrencia::LiquidacaoCartorio => "Liquidação em cartório",
+            Ocorrencia::LiquidacaoAposBaixa => "Liquidação após baixa ou título não registrado",
+            Ocorrencia::Baixa => "Baixa",
+            Ocorrencia::BaixaRejeitada => "Baixa rejeitada",
+            Ocorrencia::AbatimentoConcedido => "Confirmação de abatimento",
+            Ocorrencia::AbatimentoCancelado => "Confirmação de cancelamento de abatimento",
+            Ocorrencia::VencimentoAlterado => "Confirmação de alteração de vencimento",
+            Ocorrencia::ProtestoConfirmado => "Confirmação de instrução de protesto",
+            Ocorrencia::SustacaoProtestoConfirmada => "Confirmação de sustação de protesto",
+            Ocorrencia::RemessaCartorio => "Remessa a cartório",
+            Ocorrencia::RetiradaCartorio => "Retirada de cartório e manutenção em carteira",
+            Ocorrencia::ProtestadoBaixado => "Protestado e baixado",
+            Ocorrencia::InstrucaoRejeitada => "Instrução rejeitada",
+            Ocorrencia::AlteracaoDadosRejeitada => "Alteração de dados rejeitada",
+            Ocorrencia::TarifaDebitada => "Débito de tarifas/custas",
+            Ocorrencia::Outra(_) => "Ocorrência não catalogada",
+        }
+    }
+
+    /// Indica se a ocorrência é um pagamento do título.
+    pub fn is_liquidacao(&self) -> bool {
+        matches!(self, Ocorrencia::Liquidacao | Ocorrencia::LiquidacaoCartorio | Ocorrencia::LiquidacaoAposBaixa)
+    }
+
+    /// Indica se o banco recusou a entrada ou uma instrução da remessa (os motivos vêm no
+    /// registro; veja [`motivo_rejeicao`]).
+    pub fn is_rejeicao(&self) -> bool {
+        matches!(
+            self,
+            Ocorrencia::EntradaRejeitada
+                | Ocorrencia::BaixaRejeitada
+                | Ocorrencia::InstrucaoRejeitada
+                | Ocorrencia::AlteracaoDadosRejeitada
+        )
+    }
+}
+
+/// Ocorrência do código de movimento `codigo` no formato e banco do arquivo.
+///
+/// Sem banco, ou para bancos sem tabela própria, vale a tabela FEBRABAN.
+pub fn ocorrencia(codigo: u8, format: CnabFormat, bank: Option<Bank>) -> Ocorrencia {
+    use Ocorrencia::*;
+    match (format, bank) {
+        (CnabFormat::Cnab400, Some(Bank::Itau)) => match codigo {
+            2 => EntradaConfirmada,
+            3 => EntradaRejeitada,
+            6 => Liquidacao,
+            8 => LiquidacaoCartorio,
+            9 | 10 => Baixa,
+            12 => AbatimentoConcedido,
+            13 => AbatimentoCancelado,
+            14 => VencimentoAlterado,
+            15 => BaixaRejeitada,
+            16 => InstrucaoRejeitada,
+            17 => AlteracaoDadosRejeitada,
+            19 => ProtestoConfirmado,
+            20 => SustacaoProtestoConfirmada,
+            23 => RemessaCartorio,
+            28 => TarifaDebitada,
+            32 => ProtestadoBaixado,
+            codigo => Outra(codigo),
+        },
+        (CnabFormat::Cnab400, Some(Bank::Bradesco)) => match codigo {
+            2 => EntradaConfirmada,
+            // 24: entrada rejeitada por CEP irregular
+            3 | 24 => EntradaRejeitada,
+            6 => Liquidacao,
+            9 | 10 => Baixa,
+            12 => AbatimentoConcedido,
+            13 => AbatimentoCancelado,
+            14 => VencimentoAlterado,
+            15 => LiquidacaoCartorio,
+            17 => LiquidacaoAposBaixa,
+            19 => ProtestoConfirmado,
+            20 => SustacaoProtestoConfirmada,
+            23 => RemessaCartorio,
+            27 => BaixaRejeitada,
+            28 => TarifaDebitada,
+            30 => AlteracaoDadosRejeitada,
+            32 => InstrucaoRejeitada,
+            codigo => Outra(codigo),
+        },
+        _ => match codigo {
+            2 => EntradaConfirmada,
+            3 => EntradaRejeitada,
+            6 => Liquidacao,
+            9 => Baixa,
+            12 => AbatimentoConcedido,
+            13 => AbatimentoCancelado,
+            14 => VencimentoAlterado,
+            17 => LiquidacaoAposBaixa,
+            19 => ProtestoConfirmado,
+            20 => SustacaoProtestoConfirmada,
+            23 => RemessaCartorio,
+            24 => RetiradaCartorio,
+            25 => ProtestadoBaixado,
+            26 => InstrucaoRejeitada,
+            28 => TarifaDebitada,
+            30 => AlteracaoDadosRejeitada,
+            codigo => Outra(codigo),
+        },
+    }
+}
+
+/// Descrição de um motivo de rejeição (ocorrências 03, 26 e 30) pela tabela FEBRABAN
+/// 240, ou `None` se o código não está catalogado.
+pub fn motivo_rejeicao(codigo: &str) -> Option<&'static str> {
+    let descricao = match codigo {
+        "01" => "Código do banco inválido",
+        "02" => "Código do registro detalhe inválido",
+        "03" => "Código do segmento inválido",
+        "04" => "Código de movimento não permitido para a carteira",
+        "05" => "Código de movimento inválido",
+        "06" => "Tipo/número de inscrição do beneficiário inválidos",
+        "07" => "Agência/conta/DV inválido",
+        "08" => "Nosso número inválido",
+        "09" => "Nosso número duplicado",
+        "10" => "Carteira inválida",
+        "11" => "Forma de cadastramento do título inválida",
+        "12" => "Tipo de documento inválido",
+        "13" => "Identificação da emissão do boleto inválida",
+        "14" => "Identificação da distribuição do boleto inválida",
+        "15" => "Características da cobrança incompatíveis",
+        "16" => "Data de vencimento inválida",
+        "17" => "Data de vencimento anterior à data de emissão",
+        "18" => "Vencimento fora do prazo de operação",
+        "20" => "Valor do título inválido",
+        "21" => "Espécie do título inválida",
+        "22" => "Espécie do título não permitida para a carteira",
+        "23" => "Aceite inválido",
+        "24" => "Data de emissão inválida",
+        "25" => "Data de emissão posterior à data de entrada",
+        "26" => "Código de juros de mora inválido",
+        "27" => "Valor/taxa de juros de mora inválido",
+        "28" => "Código do desconto inválido",
+        "29" => "Valor do desconto maior ou igual ao valor do título",
+        "32" => "Valor do IOF inválido",
+        "33" => "Valor do abatimento inválido",
+        "34" => "Valor do abatimento maior ou igual ao valor do título",
+        "37" => "Código para protesto inválido",
+        "38" => "Prazo para protesto inválido",
+        "39" => "Pedido de protesto não permitido para o título",
+        "40" => "Título com ordem de protesto emitida",
+        "42" => "Código para baixa/devolução inválido",
+        "43" => "Prazo para baixa/devolução inválido",
+        "44" => "Código da moeda inválido",
+        "45" => "Nome do pagador não informado",
+        "46" => "Tipo/número de inscrição do pagador inválidos",
+        "47" => "Endereço do pagador não informado",
+        "48" => "CEP inválido",
+        _ => return None,
+    };
+    Some(descricao)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_map_per_bank_and_format() {
+        let febraban = ocorrencia(17, CnabFormat::Cnab240, Some(Bank::Itau));
+        assert_eq!(febraban, Ocorrencia::LiquidacaoAposBaixa);
+        assert!(febraban.is_liquidacao());
+
+        let itau_400 = ocorrencia(17, CnabFormat::Cnab400, Some(Bank::Itau));
+        assert_eq!(itau_400.descricao(), "Alteração de dados rejeitada");
+        assert!(itau_400.is_rejeicao());
+        assert_eq!(ocorrencia(15, CnabFormat::Cnab400, Some(Bank::Bradesco)), Ocorrencia::LiquidacaoCartorio);
+        // Bancos sem tabela própria no 400 usam a FEBRABAN
+        assert_eq!(ocorrencia(25, CnabFormat::Cnab400, Some(Bank::Santander)), Ocorrencia::ProtestadoBaixado);
+        assert_eq!(ocorrencia(99, CnabFormat::Cnab240, None), Ocorrencia::Outra(99));
+
+        assert_eq!(motivo_rejeicao("09"), Some("Nosso número duplicado"));
+        assert_eq!(motivo_rejeicao("ZZ"), None);
+    }
+}
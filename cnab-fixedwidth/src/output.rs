@@ -0,0 +1,193 @@
+//! # Arquivos de Saída
+//!
+//! Nomes e empacotamento dos arquivos de remessa gerados:
+//! - [`FileNamer`] define a convenção de nomes (ex: `CBDDMM??.REM`, exigida por vários bancos);
+//! - [`write_archive`] grava os arquivos sem compressão, em `.gz` (feature `gzip`) ou
+//!   em um `.zip` com vários arquivos (feature `zip`), como algumas VANs exigem.
+
+use crate::Result;
+use std::io::{Seek, Write};
+
+/// Data usada na composição do nome do arquivo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// Convenção de nomes dos arquivos gerados.
+pub trait FileNamer {
+    /// Nome do arquivo de `date` com o número sequencial do dia.
+    fn file_name(&self, date: FileDate, sequence: u32) -> String;
+}
+
+/// Nomes a partir de um padrão, ex: `CBDDMM??.REM`.
+///
+/// - `AAAA`/`AA`: ano com 4 ou 2 dígitos;
+/// - `MM`, `DD`: mês e dia;
+/// - `?` repetido: sequencial com zeros à esquerda (sequenciais maiores que a
+///   quantidade de `?` são escritos por inteiro, nunca truncados);
+/// - demais caracteres são copiados.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternNamer {
+    pattern: String,
+}
+
+impl PatternNamer {
+    /// Cria o nomeador com o padrão informado.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self { pattern: pattern.into() }
+    }
+}
+
+impl FileNamer for PatternNamer {
+    fn file_name(&self, date: FileDate, sequence: u32) -> String {
+        let mut out = String::with_capacity(self.pattern.len());
+        let mut rest = self.pattern.as_str();
+        while let Some(c) = rest.chars().next() {
+            if let Some(tail) = rest.strip_prefix("AAAA") {
+                out.push_str(&format!("{:04}", date.year));
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix("AA") {
+                out.push_str(&format!("{:02}", date.year % 100));
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix("MM") {
+                out.push_str(&format!("{:02}", date.month));
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix("DD") {
+                out.push_str(&format!("{:02}", date.day));
+                rest = tail;
+            } else if c == '?' {
+                let width = rest.chars().take_while(|&c| c == '?').count();
+                out.push_str(&format!("{:0width$}", sequence));
+                rest = &rest[width..];
+            } else {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        out
+    }
+}
+
+/// Empacotamento dos arquivos gravados por [`write_archive`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Arquivo gravado como está (apenas um arquivo).
+    #[default]
+    None,
+    /// Gzip (apenas um arquivo; o nome vai no cabeçalho do `.gz`).
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zip com um ou mais arquivos.
+    #[cfg(feature = "zip")]
+    Zip,
+}
+
+impl Compression {
+    /// Nome do arquivo gravado para um pacote com o nome `name`
+    /// (ex: `CB161001.REM` vira `CB161001.REM.gz` ou `CB161001.zip`).
+    pub fn archive_name(self, name: &str) -> String {
+        match self {
+            Compression::None => name.to_string(),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => format!("{name}.gz"),
+            #[cfg(feature = "zip")]
+            Compression::Zip => {
+                let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+                format!("{stem}.zip")
+            }
+        }
+    }
+}
+
+/// Grava os arquivos `(nome, conteúdo)` em `writer` com a compressão informada.
+///
+/// `Compression::None` e `Gzip` aceitam exatamente um arquivo; `Zip` aceita vários.
+pub fn write_archive<W: Write + Seek>(mut writer: W, compression: Compression, files: &[(&str, &[u8])]) -> Result<()> {
+    let single = || match files {
+        [file] => Ok(*file),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{:?} aceita exatamente um arquivo, recebeu {}", compression, files.len()),
+        )),
+    };
+
+    match compression {
+        Compression::None => {
+            let (_, content) = single()?;
+            writer.write_all(content)?;
+        }
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            let (name, content) = single()?;
+            let mut encoder = flate2::GzBuilder::new()
+                .filename(name.as_bytes())
+                .write(writer, flate2::Compression::default());
+            encoder.write_all(content)?;
+            encoder.finish()?;
+        }
+        #[cfg(feature = "zip")]
+        Compression::Zip => {
+            let mut zip = zip::ZipWriter::new(writer);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (name, content) in files {
+                zip.start_file(*name, options).map_err(std::io::Error::other)?;
+                zip.write_all(content)?;
+            }
+            zip.finish().map_err(std::io::Error::other)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_files_from_pattern() {
+        let date = FileDate { year: 2026, month: 3, day: 16 };
+        assert_eq!(PatternNamer::new("CBDDMM??.REM").file_name(date, 1), "CB160301.REM");
+        assert_eq!(PatternNamer::new("REM_AAAAMMDD_???.txt").file_name(date, 12), "REM_20260316_012.txt");
+        assert_eq!(PatternNamer::new("CBDDMM??.REM").file_name(date, 123), "CB1603123.REM");
+
+        let mut out = std::io::Cursor::new(Vec::new());
+        write_archive(&mut out, Compression::None, &[("CB160301.REM", b"0341")]).unwrap();
+        assert_eq!(out.into_inner(), b"0341");
+        let two = write_archive(std::io::Cursor::new(Vec::new()), Compression::None, &[("a", b""), ("b", b"")]);
+        assert!(two.is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compresses_remessa_files() {
+        use std::io::Read;
+
+        let mut gz = std::io::Cursor::new(Vec::new());
+        write_archive(&mut gz, Compression::Gzip, &[("CB160301.REM", b"0341")]).unwrap();
+        let mut decoder = flate2::read::GzDecoder::new(gz.get_ref().as_slice());
+        let mut content = String::new();
+        decoder.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "0341");
+        assert_eq!(decoder.header().unwrap().filename(), Some(b"CB160301.REM".as_slice()));
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zips_remessa_files() {
+        use std::io::Read;
+
+        let mut zip = std::io::Cursor::new(Vec::new());
+        write_archive(&mut zip, Compression::Zip, &[("CB160301.REM", b"0341"), ("CB160302.REM", b"0001")]).unwrap();
+        let mut archive = zip::ZipArchive::new(zip).unwrap();
+        assert_eq!(archive.len(), 2);
+        let mut second = String::new();
+        archive.by_name("CB160302.REM").unwrap().read_to_string(&mut second).unwrap();
+        assert_eq!(second, "0001");
+        assert_eq!(Compression::Zip.archive_name("CB160301.REM"), "CB160301.zip");
+    }
+}
@@ -0,0 +1,172 @@
+//! # DataFrames do Polars (feature `polars`)
+//!
+//! Lê um arquivo CNAB direto para um `DataFrame`, com uma coluna tipada por campo do
+//! layout, sem passar por texto e recortes de colunas depois:
+//!
+//! | Campo | Tipo Polars |
+//! |---|---|
+//! | `Alpha` | `String` |
+//! | `Numeric`, `Packed` sem escala | `Int64` |
+//! | `Decimal`, `Zoned`, `Packed` com escala | `Decimal(precisão, escala)` |
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{polars, FieldKind, LayoutBuilder};
+//!
+//! let layout = LayoutBuilder::new().width(240).field("banco", 1, 3, FieldKind::Numeric)/* ... */.build()?;
+//! let df = polars::read_cnab_to_dataframe("retorno.ret", &layout)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Fillers ficam de fora; opcionais em branco viram nulos.
+
+use crate::layout::Layout;
+use crate::reader::{Framing, RecordReader};
+use crate::{DynRecord, FieldKind, FieldSpec, FixedWidthError, ParseOptions, Result, Value};
+use ::polars::prelude::{Column, DataFrame, DataType, Int128Chunked, IntoColumn, IntoSeries, NewChunkedArray};
+use std::io::BufRead;
+use std::path::Path;
+
+/// Precisão máxima de `Decimal` no Polars.
+const MAX_PRECISION: usize = 38;
+
+/// Lê o arquivo em `path`, uma linha por registro, com os campos de `layout`.
+///
+/// Linhas vazias são ignoradas; erros de parse saem com o número da linha e o campo.
+pub fn read_cnab_to_dataframe(path: impl AsRef<Path>, layout: &Layout) -> Result<DataFrame> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    to_dataframe_with(file, layout.fields(), &ParseOptions::default(), |line, options| {
+        layout.parse_bytes(line, options)
+    })
+}
+
+/// Como [`read_cnab_to_dataframe`], para qualquer leitor, só com os campos.
+pub fn to_dataframe<R: BufRead>(reader: R, fields: &[FieldSpec], options: &ParseOptions) -> Result<DataFrame> {
+    to_dataframe_with(reader, fields, options, |line, options| crate::parse_line_bytes_with(line, fields, options))
+}
+
+/// Tipo Polars do campo (`None` para fillers).
+pub fn dtype(field: &FieldSpec) -> Option<DataType> {
+    let decimal = |digits: usize, scale: u8| {
+        let precision = digits.clamp(usize::from(scale).max(1), MAX_PRECISION);
+        DataType::Decimal(Some(precision), Some(usize::from(scale)))
+    };
+    match field.kind {
+        FieldKind::Alpha => Some(DataType::String),
+        FieldKind::Numeric | FieldKind::Packed { scale: 0 } => Some(DataType::Int64),
+        FieldKind::Decimal { scale } | FieldKind::Zoned { scale } => Some(decimal(field.pos.width(), scale)),
+        // Dois dígitos por byte, menos o meio byte do sinal
+        FieldKind::Packed { scale } => Some(decimal(field.pos.width() * 2 - 1, scale)),
+        FieldKind::Filler => None,
+    }
+}
+
+/// Valores acumulados de uma coluna.
+enum Values {
+    String(Vec<Option<String>>),
+    Int64(Vec<Option<i64>>),
+    Decimal { values: Vec<Option<i128>>, precision: usize, scale: usize },
+}
+
+impl Values {
+    fn new(dtype: &DataType) -> Self {
+        match dtype {
+            DataType::String => Values::String(Vec::new()),
+            DataType::Decimal(precision, scale) => Values::Decimal {
+                values: Vec::new(),
+                precision: precision.unwrap_or(MAX_PRECISION),
+                scale: scale.unwrap_or(0),
+            },
+            _ => Values::Int64(Vec::new()),
+        }
+    }
+
+    fn push(&mut self, value: &Value) {
+        match (self, value) {
+            (Values::String(values), Value::Alpha(text)) => values.push(Some(text.clone())),
+            (Values::Int64(values), Value::Numeric(n) | Value::Decimal { raw: n, .. }) => values.push(Some(*n)),
+            (Values::Decimal { values, .. }, Value::Decimal { raw, .. }) => values.push(Some(i128::from(*raw))),
+            (Values::String(values), _) => values.push(None),
+            (Values::Int64(values), _) => values.push(None),
+            (Values::Decimal { values, .. }, _) => values.push(None),
+        }
+    }
+
+    fn into_column(self, name: &str) -> Column {
+        match self {
+            Values::String(values) => Column::new(name.into(), values),
+            Values::Int64(values) => Column::new(name.into(), values),
+            Values::Decimal { values, precision, scale } => Int128Chunked::from_iter_options(name.into(), values.into_iter())
+                .into_decimal_unchecked(Some(precision), scale)
+                .into_series()
+                .into_column(),
+        }
+    }
+}
+
+fn to_dataframe_with<R: BufRead>(
+    reader: R,
+    fields: &[FieldSpec],
+    options: &ParseOptions,
+    parse: impl Fn(&[u8], &ParseOptions) -> Result<DynRecord>,
+) -> Result<DataFrame> {
+    let fields: Vec<_> = fields.iter().filter_map(|field| dtype(field).map(|dtype| (field, dtype))).collect();
+    let mut columns: Vec<_> = fields.iter().map(|(_, dtype)| Values::new(dtype)).collect();
+    let specs: Vec<_> = fields.iter().map(|(field, _)| (*field).clone()).collect();
+    let mut records = RecordReader::new(reader, Framing::Lines);
+    let mut line = Vec::new();
+
+    while records.read_record(&mut line)? {
+        let line_number = records.records_read();
+        if line.is_empty() {
+            continue;
+        }
+        let record = parse(&line, options).map_err(|e| e.at_line_with(line_number, &line, &specs, options.redaction))?;
+        for ((field, _), column) in fields.iter().zip(&mut columns) {
+            let value = record.get(field.name).unwrap_or(&Value::Null);
+            if !field.matches(value) {
+                let message = format!("valor {value:?} incompatível com a coluna {}", field.kind);
+                return Err(FixedWidthError::Conversion { field: field.name, message }
+                    .at_line_with(line_number, &line, &specs, options.redaction));
+            }
+            column.push(value);
+        }
+    }
+
+    let columns = fields.iter().zip(columns).map(|((field, _), values)| values.into_column(field.name)).collect();
+    DataFrame::new(columns).map_err(|e| FixedWidthError::Export(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutBuilder;
+
+    #[test]
+    fn reads_typed_columns_into_a_dataframe() {
+        let layout = LayoutBuilder::new()
+            .width(17)
+            .field("banco", 1, 3, FieldKind::Numeric)
+            .field("brancos", 4, 5, FieldKind::Filler)
+            .field("nome", 6, 10, FieldKind::Alpha)
+            .optional_field("valor", 11, 17, FieldKind::Decimal { scale: 2 })
+            .build()
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("cnab-polars-{}.txt", std::process::id()));
+        std::fs::write(&path, "341  ANA  0001050\n\n237  BIA         \n").unwrap();
+        let df = read_cnab_to_dataframe(&path, &layout).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(df.shape(), (2, 3));
+        assert_eq!(
+            df.dtypes(),
+            [DataType::Int64, DataType::String, DataType::Decimal(Some(7), Some(2))]
+        );
+        assert_eq!(df.column("banco").unwrap().i64().unwrap().get(1), Some(237));
+        assert_eq!(df.column("nome").unwrap().str().unwrap().get(0), Some("ANA"));
+        let valor = df.column("valor").unwrap().decimal().unwrap();
+        assert_eq!((valor.physical().get(0), valor.physical().get(1)), (Some(1050), None));
+
+        let err = to_dataframe(b"34X  ANA  0001050\n" as &[u8], layout.fields(), &ParseOptions::default()).unwrap_err();
+        assert!(matches!(err, FixedWidthError::Field { line_number: 1, .. }), "{err}");
+    }
+}
@@ -0,0 +1,203 @@
+//! # Pré-visualização
+//!
+//! Amostra de um arquivo grande para exibição rápida (ex: a tela de upload de um retorno
+//! de 2 GB): header, trailer e os primeiros/últimos registros de cada tipo, sem montar o
+//! arquivo inteiro em memória.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::preview;
+//!
+//! # let registry = cnab_fixedwidth::LayoutRegistry::new();
+//! let amostra = preview::preview("retorno.ret", &registry, 5)?;
+//! for tipo in &amostra.types {
+//!     println!("{}: {} registros", tipo.name, tipo.count);
+//! }
+//! # Ok::<(), cnab_fixedwidth::FixedWidthError>(())
+//! ```
+
+use crate::reader::{Framing, RecordReader};
+use crate::{DynRecord, FieldError, LayoutRegistry, ParseOptions, Result};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Um registro da amostra.
+#[derive(Debug)]
+pub struct PreviewRecord {
+    /// Número do registro no arquivo (1-based).
+    pub line_number: u64,
+    /// Layout que identificou o registro (`None` se nenhum se aplica).
+    pub layout: Option<&'static str>,
+    /// Bytes do registro, sem o fim de linha.
+    pub raw: Vec<u8>,
+    /// Campos lidos (vazio sem layout); campos com erro ficam de fora.
+    pub record: DynRecord,
+    /// Erros dos campos, sem interromper a amostra.
+    pub errors: Vec<FieldError>,
+}
+
+/// Amostra de um tipo de registro.
+#[derive(Debug)]
+pub struct RecordTypePreview {
+    /// Nome do layout.
+    pub name: &'static str,
+    /// Quantidade de registros desse tipo no arquivo.
+    pub count: u64,
+    /// Os primeiros `n` registros.
+    pub first: Vec<PreviewRecord>,
+    /// Os últimos `n` registros, sem repetir os de `first`.
+    pub last: Vec<PreviewRecord>,
+}
+
+/// Resultado de [`preview`].
+#[derive(Debug, Default)]
+pub struct Preview {
+    /// Primeiro registro do arquivo.
+    pub header: Option<PreviewRecord>,
+    /// Último registro do arquivo (`None` se o arquivo tem um só registro).
+    pub trailer: Option<PreviewRecord>,
+    /// Amostras por tipo, na ordem em que cada tipo aparece no arquivo.
+    pub types: Vec<RecordTypePreview>,
+    /// Registros sem layout no registro.
+    pub unknown: u64,
+    /// Total de registros do arquivo.
+    pub records: u64,
+}
+
+/// Amostra de um tipo durante a leitura: só os bytes, o parse fica para o fim.
+struct Amostra {
+    name: &'static str,
+    count: u64,
+    first: Vec<(u64, Vec<u8>)>,
+    last: VecDeque<(u64, Vec<u8>)>,
+}
+
+/// Amostra o arquivo em `path`, um registro por linha, em Latin-1.
+///
+/// Veja [`preview_reader`].
+pub fn preview(path: impl AsRef<Path>, layout: &LayoutRegistry, n: usize) -> Result<Preview> {
+    let file = BufReader::new(std::fs::File::open(path)?);
+    preview_reader(file, Framing::Lines, layout, n, &ParseOptions::default())
+}
+
+/// Amostra um arquivo: header, trailer e os primeiros/últimos `n` registros de cada tipo
+/// (resolvido em `layout`).
+///
+/// O arquivo é lido uma vez, em sequência; a memória usada depende de `n` e da quantidade
+/// de tipos, não do tamanho do arquivo. Só os registros da amostra passam pelo parse, que
+/// coleta os erros de cada campo em vez de falhar. Erros de leitura interrompem a amostra.
+pub fn preview_reader<R: BufRead>(
+    reader: R,
+    framing: Framing,
+    layout: &LayoutRegistry,
+    n: usize,
+    options: &ParseOptions,
+) -> Result<Preview> {
+    let mut reader = RecordReader::new(reader, framing);
+    let mut buf = Vec::new();
+    let mut header = None;
+    // Último registro lido (candidato a trailer), num buffer reaproveitado a cada registro
+    let mut trailer = Vec::new();
+    let mut trailer_line = None;
+    let mut amostras: Vec<Amostra> = Vec::new();
+    let mut unknown = 0;
+
+    while reader.read_record(&mut buf)? {
+        let line_number = reader.records_read();
+        if line_number == 1 {
+            header = Some(buf.clone());
+        } else {
+            trailer.clone_from(&buf);
+            trailer_line = Some(line_number);
+        }
+        let Some(record_layout) = layout.resolve(&buf) else {
+            unknown += 1;
+            continue;
+        };
+        let index = match amostras.iter().position(|amostra| amostra.name == record_layout.name) {
+            Some(index) => index,
+            None => {
+                let name = record_layout.name;
+                amostras.push(Amostra { name, count: 0, first: Vec::new(), last: VecDeque::new() });
+                amostras.len() - 1
+            }
+        };
+        let amostra = &mut amostras[index];
+        amostra.count += 1;
+        if amostra.first.len() < n {
+            amostra.first.push((line_number, buf.clone()));
+        } else if n > 0 {
+            // Janela cheia: o buffer do mais antigo é reaproveitado para o novo
+            let mut raw = if amostra.last.len() == n {
+                amostra.last.pop_front().map(|(_, raw)| raw).unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            raw.clone_from(&buf);
+            amostra.last.push_back((line_number, raw));
+        }
+    }
+
+    let parse = |(line_number, raw): (u64, Vec<u8>)| {
+        let record_layout = layout.resolve(&raw);
+        let (record, errors) = match record_layout {
+            Some(record_layout) => crate::parse_line_bytes_collect_with(&raw, &record_layout.fields, options),
+            None => (DynRecord::from_values(Vec::new()), Vec::new()),
+        };
+        PreviewRecord { line_number, layout: record_layout.map(|l| l.name), raw, record, errors }
+    };
+    Ok(Preview {
+        header: header.map(|raw| parse((1, raw))),
+        trailer: trailer_line.map(|line_number| parse((line_number, trailer))),
+        types: amostras
+            .into_iter()
+            .map(|amostra| RecordTypePreview {
+                name: amostra.name,
+                count: amostra.count,
+                first: amostra.first.into_iter().map(parse).collect(),
+                last: amostra.last.into_iter().map(parse).collect(),
+            })
+            .collect(),
+        unknown,
+        records: reader.records_read(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldKind, FieldPos, FieldSpec, FixedWidthError, RecordLayout, Value};
+
+    #[test]
+    fn samples_first_and_last_records_of_each_type() {
+        let tipo = FieldPos::new_const(1, 1);
+        let valor = FieldSpec::new("valor", FieldPos::new_const(2, 4), FieldKind::Numeric);
+        let mut registry = LayoutRegistry::new();
+        registry
+            .register(RecordLayout::new("header", vec![]).when(tipo, "0"))
+            .register(RecordLayout::new("detalhe", vec![valor.clone()]).when(tipo, "1"))
+            .register(RecordLayout::new("trailer", vec![valor]).when(tipo, "9"));
+
+        let mut file = String::from("0\n");
+        for i in 1..=7 {
+            file.push_str(&format!("1{i:03}\n"));
+        }
+        file.push_str("1X\nZZZ\n9008\n");
+        let amostra = preview_reader(file.as_bytes(), Framing::Lines, &registry, 2, &ParseOptions::default()).unwrap();
+
+        assert_eq!((amostra.records, amostra.unknown), (11, 1));
+        assert_eq!(amostra.header.as_ref().unwrap().layout, Some("header"));
+        let trailer = amostra.trailer.as_ref().unwrap();
+        assert_eq!((trailer.line_number, trailer.record.get("valor")), (11, Some(&Value::Numeric(8))));
+
+        let names: Vec<_> = amostra.types.iter().map(|t| (t.name, t.count)).collect();
+        assert_eq!(names, [("header", 1), ("detalhe", 8), ("trailer", 1)]);
+        let detalhe = &amostra.types[1];
+        let lines = |records: &[PreviewRecord]| records.iter().map(|r| r.line_number).collect::<Vec<_>>();
+        assert_eq!((lines(&detalhe.first), lines(&detalhe.last)), (vec![2, 3], vec![8, 9]));
+        // O registro com erro entra na amostra, com o erro do campo
+        assert_eq!(detalhe.last[1].raw, b"1X");
+        assert!(matches!(detalhe.last[1].errors[0].error, FixedWidthError::LineTooShort { .. }));
+        assert!(amostra.types[0].last.is_empty());
+    }
+}
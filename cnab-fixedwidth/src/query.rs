@@ -0,0 +1,539 @@
+//! # Consultas
+//!
+//! Uma linguagem mínima de filtro e projeção sobre os registros de um arquivo, para
+//! responder perguntas do dia a dia ("quais títulos foram liquidados?") sem exportar o
+//! arquivo para um banco de dados:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::query;
+//!
+//! # let registry = cnab_fixedwidth::LayoutRegistry::new();
+//! let file = std::io::BufReader::new(std::fs::File::open("retorno.ret")?);
+//! let result = query::run(file, &registry, "select nosso_numero, valor where ocorrencia = '06'")?;
+//! for row in &result.rows {
+//!     println!("{} {}", row.values[0], row.values[1]);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Sintaxe (palavras-chave sem diferenciar maiúsculas):
+//!
+//! ```text
+//! select <campo>[, <campo>...] | *
+//!   [from <tipo de registro>]
+//!   [where <campo> <op> <literal> [and|or ...]]
+//! ```
+//!
+//! Operadores: `=`, `!=` (ou `<>`), `<`, `<=`, `>`, `>=`; `not` e parênteses agrupam
+//! condições. Literais são textos entre aspas simples (`'06'`, com `''` para uma aspa),
+//! números (`12.34`) ou `null` (campo opcional em branco). Números são comparados pelo
+//! valor, então `'06'` encontra tanto o texto `06` quanto o numérico `6`.
+
+use crate::reader::{Framing, RecordReader};
+use crate::{DynRecord, FixedWidthError, LayoutRegistry, ParseOptions, Value};
+use std::cmp::Ordering;
+use std::io::BufRead;
+use thiserror::Error;
+
+/// Erros de uma consulta.
+#[derive(Debug, Error)]
+pub enum QueryError {
+    /// Consulta malformada; `pos` é o deslocamento (0-based) no texto da consulta.
+    #[error("consulta inválida na posição {pos}: {message}")]
+    Syntax { pos: usize, message: String },
+
+    /// Campo que não existe no tipo de registro do `from`.
+    #[error("campo '{field}' não existe no registro '{record}'")]
+    UnknownField { field: String, record: String },
+
+    /// Tipo de registro do `from` que não está no registro de layouts.
+    #[error("tipo de registro '{0}' não existe no registro de layouts")]
+    UnknownRecord(String),
+
+    /// Erro ao ler ou interpretar o arquivo.
+    #[error(transparent)]
+    File(#[from] FixedWidthError),
+}
+
+/// Operador de comparação.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Literal do lado direito de uma comparação.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Text(String),
+    /// Número com as casas decimais escritas (`12.30` vira `raw: 1230, scale: 2`).
+    Number { raw: i64, scale: u8 },
+    Null,
+}
+
+/// Condição do `where`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare { field: String, op: CompareOp, value: Literal },
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+/// Consulta interpretada, reutilizável em vários arquivos.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Campos selecionados; vazio em `select *`.
+    pub columns: Vec<String>,
+    /// Tipo de registro do `from`.
+    pub record: Option<String>,
+    /// Condição do `where`.
+    pub condition: Option<Condition>,
+}
+
+/// Uma linha do resultado.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryRow {
+    /// Número do registro no arquivo (1-based).
+    pub line_number: u64,
+    /// Tipo de registro (nome do layout).
+    pub record: &'static str,
+    /// Valores das colunas, na ordem de `QueryResult::columns` (`Value::Null` quando o
+    /// registro não tem o campo).
+    pub values: Vec<Value>,
+}
+
+/// Resultado de [`run`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    /// Nomes das colunas. Em `select *`, os campos do primeiro registro selecionado.
+    pub columns: Vec<String>,
+    pub rows: Vec<QueryRow>,
+}
+
+/// Executa `query` sobre o arquivo, com as opções padrão (`Encoding::Latin1`).
+///
+/// Veja [`run_with`].
+pub fn run<R: BufRead>(reader: R, layouts: &LayoutRegistry, query: &str) -> Result<QueryResult, QueryError> {
+    run_with(reader, layouts, query, &ParseOptions::default())
+}
+
+/// Executa `query` sobre o arquivo, um registro por linha.
+///
+/// Registros sem layout correspondente são ignorados. Sem `where`, entram os registros
+/// que têm ao menos um dos campos selecionados; uma comparação com um campo que o
+/// registro não tem é falsa. Erros de parse saem com o número da linha e o campo.
+pub fn run_with<R: BufRead>(
+    reader: R,
+    layouts: &LayoutRegistry,
+    query: &str,
+    options: &ParseOptions,
+) -> Result<QueryResult, QueryError> {
+    let query = Query::parse(query)?;
+    query.check(layouts)?;
+
+    let mut records = RecordReader::new(reader, Framing::Lines);
+    let mut line = Vec::new();
+    let mut result = QueryResult { columns: query.columns.clone(), rows: Vec::new() };
+    while records.read_record(&mut line)? {
+        let line_number = records.records_read();
+        let (layout, parsed) = match layouts.resolve_parse(&line, options) {
+            None => continue,
+            Some(Ok(resolved)) => resolved,
+            Some(Err(e)) => {
+                let fields = layouts.resolve(&line).map_or(&[][..], |layout| &layout.fields);
+                return Err(e.at_line_with(line_number, &line, fields, options.redaction).into());
+            }
+        };
+        if query.record.as_deref().is_some_and(|record| record != layout.name) {
+            continue;
+        }
+        if !query.matches(&parsed) {
+            continue;
+        }
+        if query.columns.is_empty() {
+            if result.columns.is_empty() {
+                result.columns = parsed.iter().map(|(name, _)| name.to_string()).collect();
+            }
+            let values = result.columns.iter().map(|c| parsed.get(c).cloned().unwrap_or(Value::Null)).collect();
+            result.rows.push(QueryRow { line_number, record: layout.name, values });
+        } else if let Some(values) = query.project(&parsed) {
+            result.rows.push(QueryRow { line_number, record: layout.name, values });
+        }
+    }
+    Ok(result)
+}
+
+impl Query {
+    /// Interpreta o texto da consulta.
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, next: 0, end: text.len() };
+        let query = parser.query()?;
+        match parser.tokens.get(parser.next) {
+            None => Ok(query),
+            Some((pos, _)) => Err(syntax(*pos, "texto após o fim da consulta")),
+        }
+    }
+
+    /// Indica se o registro atende ao `where` (sempre, sem `where`).
+    pub fn matches(&self, record: &DynRecord) -> bool {
+        self.condition.as_ref().is_none_or(|condition| condition.eval(record))
+    }
+
+    /// Valores das colunas selecionadas, ou `None` se o registro não tem nenhuma delas.
+    /// Em `select *`, todos os valores do registro.
+    pub fn project(&self, record: &DynRecord) -> Option<Vec<Value>> {
+        if self.columns.is_empty() {
+            return Some(record.iter().map(|(_, value)| value.clone()).collect());
+        }
+        let values: Vec<_> = self.columns.iter().map(|c| record.get(c).cloned()).collect();
+        values.iter().any(Option::is_some).then(|| values.into_iter().map(|v| v.unwrap_or(Value::Null)).collect())
+    }
+
+    /// Com `from`, confere o tipo de registro e os campos citados contra o layout.
+    fn check(&self, layouts: &LayoutRegistry) -> Result<(), QueryError> {
+        let Some(record) = &self.record else { return Ok(()) };
+        let layout = layouts
+            .layouts()
+            .iter()
+            .find(|layout| layout.name == record)
+            .ok_or_else(|| QueryError::UnknownRecord(record.clone()))?;
+        let mut fields: Vec<&str> = self.columns.iter().map(String::as_str).collect();
+        if let Some(condition) = &self.condition {
+            condition.fields(&mut fields);
+        }
+        match fields.into_iter().find(|field| !layout.fields.iter().any(|f| f.name == *field)) {
+            Some(field) => Err(QueryError::UnknownField { field: field.to_string(), record: record.clone() }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Condition {
+    /// Avalia a condição sobre o registro.
+    pub fn eval(&self, record: &DynRecord) -> bool {
+        match self {
+            Condition::Compare { field, op, value } => {
+                let Some(found) = record.get(field) else { return false };
+                // Com null, só igualdade e diferença fazem sentido
+                if matches!(found, Value::Null) || matches!(value, Literal::Null) {
+                    let both = matches!(found, Value::Null) && matches!(value, Literal::Null);
+                    return match op {
+                        CompareOp::Eq => both,
+                        CompareOp::Ne => !both,
+                        _ => false,
+                    };
+                }
+                compare(found, value).is_some_and(|ordering| match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::Ne => ordering != Ordering::Equal,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::Le => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::Ge => ordering != Ordering::Less,
+                })
+            }
+            Condition::And(left, right) => left.eval(record) && right.eval(record),
+            Condition::Or(left, right) => left.eval(record) || right.eval(record),
+            Condition::Not(inner) => !inner.eval(record),
+        }
+    }
+
+    /// Campos citados na condição.
+    fn fields<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Condition::Compare { field, .. } => out.push(field),
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.fields(out);
+                right.fields(out);
+            }
+            Condition::Not(inner) => inner.fields(out),
+        }
+    }
+}
+
+/// Compara o valor do campo com o literal; `None` quando não são comparáveis.
+///
+/// Números (e textos que são números) comparam pelo valor, com qualquer escala.
+fn compare(value: &Value, literal: &Literal) -> Option<Ordering> {
+    let number = match value {
+        Value::Numeric(n) => Some((*n, 0)),
+        Value::Decimal { raw, scale } => Some((*raw, *scale)),
+        Value::Alpha(text) => parse_number(text),
+        Value::Null => None,
+    };
+    let literal_number = match literal {
+        Literal::Number { raw, scale } => Some((*raw, *scale)),
+        Literal::Text(text) => parse_number(text),
+        Literal::Null => None,
+    };
+    match (number, literal_number) {
+        (Some(a), Some(b)) => Some(scaled(a, b.1).cmp(&scaled(b, a.1))),
+        _ => match (value, literal) {
+            (Value::Alpha(text), Literal::Text(expected)) => Some(text.as_str().cmp(expected)),
+            _ => None,
+        },
+    }
+}
+
+/// `raw` na escala combinada com `other_scale` (sem perdas, em i128).
+fn scaled((raw, scale): (i64, u8), other_scale: u8) -> i128 {
+    i128::from(raw) * 10i128.pow(u32::from(other_scale.max(scale) - scale))
+}
+
+/// Número com sinal e casas decimais opcionais (`-12.30`).
+fn parse_number(text: &str) -> Option<(i64, u8)> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty() || !all_digits(int) || !all_digits(frac) || frac.len() > 18 {
+        return None;
+    }
+    let raw: i64 = format!("{int}{frac}").parse().ok()?;
+    Some((if negative { -raw } else { raw }, frac.len() as u8))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(String),
+    Symbol(&'static str),
+}
+
+fn syntax(pos: usize, message: &str) -> QueryError {
+    QueryError::Syntax { pos, message: message.to_string() }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(pos, ch)) = chars.peek() {
+        let token = match ch {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek().filter(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    word.push(c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = String::from(c);
+                chars.next();
+                while let Some(&(_, c)) = chars.peek().filter(|(_, c)| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                    chars.next();
+                }
+                Token::Number(number)
+            }
+            '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) if chars.peek().is_some_and(|(_, c)| *c == '\'') => {
+                            chars.next();
+                            value.push('\'');
+                        }
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => value.push(c),
+                        None => return Err(syntax(pos, "texto sem aspa de fechamento")),
+                    }
+                }
+                Token::Text(value)
+            }
+            _ => {
+                let rest = &text[pos..];
+                let symbol = ["<=", ">=", "!=", "<>", "=", "<", ">", ",", "(", ")", "*"]
+                    .into_iter()
+                    .find(|symbol| rest.starts_with(symbol))
+                    .ok_or_else(|| syntax(pos, &format!("caractere inesperado '{ch}'")))?;
+                for _ in 0..symbol.len() {
+                    chars.next();
+                }
+                Token::Symbol(symbol)
+            }
+        };
+        tokens.push((pos, token));
+    }
+    Ok(tokens)
+}
+
+/// Parser descendente recursivo sobre os tokens.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// Tamanho do texto, para apontar erros no fim da consulta.
+    end: usize,
+}
+
+impl Parser {
+    fn pos(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |(pos, _)| *pos)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        self.next += usize::from(found);
+        found
+    }
+
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(found)) if *found == symbol);
+        self.next += usize::from(found);
+        found
+    }
+
+    fn ident(&mut self, what: &str) -> Result<String, QueryError> {
+        match self.peek() {
+            Some(Token::Word(word)) if !is_keyword(word) => {
+                let word = word.clone();
+                self.next += 1;
+                Ok(word)
+            }
+            _ => Err(syntax(self.pos(), &format!("esperado {what}"))),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, QueryError> {
+        if !self.keyword("select") {
+            return Err(syntax(self.pos(), "esperado 'select'"));
+        }
+        let mut columns = Vec::new();
+        if !self.symbol("*") {
+            columns.push(self.ident("nome de campo")?);
+            while self.symbol(",") {
+                columns.push(self.ident("nome de campo")?);
+            }
+        }
+        let record = if self.keyword("from") { Some(self.ident("tipo de registro")?) } else { None };
+        let condition = if self.keyword("where") { Some(self.or()?) } else { None };
+        Ok(Query { columns, record, condition })
+    }
+
+    fn or(&mut self) -> Result<Condition, QueryError> {
+        let mut left = self.and()?;
+        while self.keyword("or") {
+            left = Condition::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Condition, QueryError> {
+        let mut left = self.unary()?;
+        while self.keyword("and") {
+            left = Condition::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Condition, QueryError> {
+        if self.keyword("not") {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        if self.symbol("(") {
+            let inner = self.or()?;
+            if !self.symbol(")") {
+                return Err(syntax(self.pos(), "esperado ')'"));
+            }
+            return Ok(inner);
+        }
+        let field = self.ident("nome de campo")?;
+        let op = match self.peek() {
+            Some(Token::Symbol("=")) => CompareOp::Eq,
+            Some(Token::Symbol("!=" | "<>")) => CompareOp::Ne,
+            Some(Token::Symbol("<")) => CompareOp::Lt,
+            Some(Token::Symbol("<=")) => CompareOp::Le,
+            Some(Token::Symbol(">")) => CompareOp::Gt,
+            Some(Token::Symbol(">=")) => CompareOp::Ge,
+            _ => return Err(syntax(self.pos(), "esperado operador de comparação")),
+        };
+        self.next += 1;
+        let pos = self.pos();
+        let value = match self.peek() {
+            Some(Token::Text(text)) => Literal::Text(text.clone()),
+            Some(Token::Number(number)) => {
+                let (raw, scale) = parse_number(number).ok_or_else(|| syntax(pos, "número inválido"))?;
+                Literal::Number { raw, scale }
+            }
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("null") => Literal::Null,
+            _ => return Err(syntax(pos, "esperado texto entre aspas, número ou null")),
+        };
+        self.next += 1;
+        Ok(Condition::Compare { field, op, value })
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    ["select", "from", "where", "and", "or", "not", "null"].iter().any(|k| word.eq_ignore_ascii_case(k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldKind, FieldPos, FieldSpec, RecordLayout};
+
+    fn registry() -> LayoutRegistry {
+        let mut registry = LayoutRegistry::new();
+        registry
+            .register(
+                RecordLayout::new("header", vec![FieldSpec::new("banco", FieldPos::new_const(2, 4), FieldKind::Numeric)])
+                    .when(FieldPos::new_const(1, 1), "0"),
+            )
+            .register(
+                RecordLayout::new(
+                    "detalhe",
+                    vec![
+                        FieldSpec::new("nosso_numero", FieldPos::new_const(2, 6), FieldKind::Alpha),
+                        FieldSpec::new("ocorrencia", FieldPos::new_const(7, 8), FieldKind::Numeric),
+                        FieldSpec::new("valor", FieldPos::new_const(9, 13), FieldKind::Decimal { scale: 2 }),
+                    ],
+                )
+                .when(FieldPos::new_const(1, 1), "1"),
+            );
+        registry
+    }
+
+    #[test]
+    fn selects_and_filters_records() {
+        let file = b"0341\n1A001 0601000\n1B002 0200550\n1C003 0612345\n9\n" as &[u8];
+        let result = run(file, &registry(), "select nosso_numero, valor where ocorrencia = '06'").unwrap();
+        assert_eq!(result.columns, ["nosso_numero", "valor"]);
+        let rows: Vec<_> = result.rows.iter().map(|r| (r.line_number, r.values[0].to_string(), r.values[1].to_string())).collect();
+        assert_eq!(rows, [(2, "'A001'".into(), "10.00".into()), (4, "'C003'".into(), "123.45".into())]);
+
+        let query = "SELECT * FROM detalhe WHERE valor >= 10 AND NOT (nosso_numero = 'C003' OR ocorrencia <> 6)";
+        let result = run(file, &registry(), query).unwrap();
+        assert_eq!(result.columns, ["nosso_numero", "ocorrencia", "valor"]);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].values[0], Value::Alpha("A001".into()));
+
+        // Sem where: só os registros que têm algum dos campos
+        assert_eq!(run(file, &registry(), "select banco").unwrap().rows.len(), 1);
+
+        assert!(matches!(
+            run(file, &registry(), "select valor from detalhe where banco = 1"),
+            Err(QueryError::UnknownField { .. })
+        ));
+        let err = Query::parse("select valor where valor >").unwrap_err();
+        assert_eq!(err.to_string(), "consulta inválida na posição 26: esperado texto entre aspas, número ou null");
+    }
+}
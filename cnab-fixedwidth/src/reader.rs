@@ -0,0 +1,643 @@
+//! # Leitura de Arquivos
+//!
+//! Divide um arquivo em registros (framing) sem carregá-lo inteiro em memória.
+//! Os registros saem como bytes brutos, prontos para [`parse_line_bytes_with`](crate::parse_line_bytes_with)
+//! ou [`FixedWidthParse::parse_bytes`](crate::FixedWidthParse::parse_bytes).
+//!
+//! Com [`RecordReader::with_lifecycle`], o leitor também avisa a abertura e o fechamento
+//! do arquivo e de cada lote (ex: para abrir um batch no banco de dados por lote), e com
+//! [`RecordReader::with_cancel`] a leitura pode ser interrompida de outra thread.
+
+use crate::{
+    parse_line_bytes_with, DynRecord, FieldPos, FieldSpec, FixedWidthError, FixedWidthParse, LayoutRegistry,
+    ParseOptions, RecordLayout, Result,
+};
+use std::io::BufRead;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Como o arquivo é dividido em registros.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// Um registro por linha, separado por `\n` (com `\r` opcional).
+    #[default]
+    Lines,
+
+    /// Registros com tamanho fixo em bytes (ex: 240 ou 400).
+    ///
+    /// Quebras de linha **entre** registros são descartadas, mas dentro do registro
+    /// são tratadas como dados: um `\n` perdido em um campo de texto livre não quebra o registro.
+    /// Para não cortar um `\n` no fim do último campo, faça o parse com
+    /// `ParseOptions { trim_line_ending: false, .. }` (e `replace_newlines: true` para trocá-los por espaços).
+    FixedLength(usize),
+
+    /// Registros quebrados em várias linhas físicas (ex: registros de 400 posições em
+    /// duas linhas de 200, em sistemas legados).
+    ///
+    /// Cada registro junta `lines` linhas, e cada linha deve ter exatamente `width`
+    /// posições (sem o fim de linha); uma quebra fora desse tamanho é rejeitada com
+    /// `WrappedLine`, pois indica linhas desalinhadas. Um registro incompleto no fim do
+    /// arquivo é devolvido como está.
+    Wrapped { lines: usize, width: usize },
+}
+
+/// Sinal de cancelamento compartilhado entre threads.
+///
+/// Clones apontam para o mesmo sinal: o serviço guarda um clone e chama
+/// [`cancel`](Self::cancel) (ex: o usuário percebeu que enviou o arquivo errado); os
+/// leitores que receberam o token param no próximo ponto de verificação, sem matar threads.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Cria um token ainda não cancelado.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pede o cancelamento de todas as leituras que usam o token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Indica se o cancelamento foi pedido.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Falha com `Cancelled` se `cancel` pede a parada e `records` cai em um ponto de
+/// verificação (veja [`RecordReader::with_cancel`]).
+pub(crate) fn check_cancel(cancel: &Option<(CancelToken, u64)>, records: u64) -> Result<()> {
+    match cancel {
+        Some((token, every)) if records.is_multiple_of(*every) && token.is_cancelled() => {
+            Err(FixedWidthError::Cancelled { records })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Resultado de [`Splitter::feed`] para o trecho disponível.
+#[derive(Debug)]
+pub(crate) enum Split {
+    /// O registro continua no próximo trecho.
+    More,
+    /// O registro está completo.
+    Record,
+    /// Fim do arquivo, sem mais registros.
+    End,
+    /// Registro inválido para o framing (ex: `WrappedLine`); o trecho já foi consumido.
+    Invalid(FixedWidthError),
+}
+
+/// Passo de [`Splitter::feed`]: `available[take]` entra no registro, `trim` bytes saem do
+/// fim dele (quebras de linha que começaram no trecho anterior) e `consumed` bytes do
+/// buffer do leitor foram usados.
+#[derive(Debug)]
+pub(crate) struct Step {
+    pub take: Range<usize>,
+    pub trim: usize,
+    pub consumed: usize,
+    pub split: Split,
+}
+
+/// Divisão de um registro conforme o [`Framing`], sem E/S: recebe o que está no buffer do
+/// leitor e diz o que pertence ao registro. É a mesma para o leitor síncrono, o
+/// assíncrono e o arquivo mapeado em memória.
+#[derive(Debug)]
+pub(crate) struct Splitter {
+    framing: Framing,
+    /// Número do registro (1-based), para os erros.
+    record: u64,
+    /// Algum byte do registro já foi consumido.
+    started: bool,
+    /// Linha física atual (`Wrapped`), onde ela começa no registro e se já tem bytes.
+    part: usize,
+    part_start: usize,
+    part_started: bool,
+}
+
+impl Splitter {
+    pub(crate) fn new(framing: Framing, record: u64) -> Self {
+        Self { framing, record, started: false, part: 0, part_start: 0, part_started: false }
+    }
+
+    /// Avança sobre `available` (vazio no fim do arquivo); `record` são os bytes do
+    /// registro acumulados até aqui.
+    pub(crate) fn feed(&mut self, available: &[u8], record: &[u8]) -> Step {
+        let eof = available.is_empty();
+        match self.framing {
+            Framing::Lines => {
+                if eof && !self.started {
+                    return Step { take: 0..0, trim: 0, consumed: 0, split: Split::End };
+                }
+                self.started = true;
+                let (take, trim, consumed, ended) = self.line(available, record);
+                let split = if ended { Split::Record } else { Split::More };
+                Step { take, trim, consumed, split }
+            }
+            Framing::FixedLength(len) => {
+                let skip = if self.started {
+                    0
+                } else {
+                    available.iter().take_while(|b| matches!(b, b'\r' | b'\n')).count()
+                };
+                let complete = |total: usize| if total == 0 { Split::End } else { Split::Record };
+                if eof || record.len() >= len {
+                    return Step { take: 0..0, trim: 0, consumed: 0, split: complete(record.len()) };
+                }
+                if skip == available.len() {
+                    return Step { take: 0..0, trim: 0, consumed: skip, split: Split::More };
+                }
+                self.started = true;
+                let end = skip + (len - record.len()).min(available.len() - skip);
+                let total = record.len() + end - skip;
+                let split = if total == len { complete(total) } else { Split::More };
+                Step { take: skip..end, trim: 0, consumed: end, split }
+            }
+            Framing::Wrapped { lines, width } => {
+                if eof && !self.part_started {
+                    let split = if record.is_empty() { Split::End } else { Split::Record };
+                    return Step { take: 0..0, trim: 0, consumed: 0, split };
+                }
+                self.part_started = true;
+                let (take, trim, consumed, ended) = self.line(available, record);
+                if !ended {
+                    return Step { take, trim, consumed, split: Split::More };
+                }
+                let part_end = record.len() + take.len() - trim;
+                let len = part_end - self.part_start;
+                self.part += 1;
+                let split = if len != width {
+                    let error = FixedWidthError::WrappedLine { record: self.record, part: self.part, len, expected: width };
+                    Split::Invalid(error)
+                } else if eof || self.part == lines {
+                    Split::Record
+                } else {
+                    self.part_start = part_end;
+                    self.part_started = false;
+                    Split::More
+                };
+                Step { take, trim, consumed, split }
+            }
+        }
+    }
+
+    /// Como [`feed`](Self::feed), acumulando o registro em `buf`. Devolve quantos bytes
+    /// consumir do leitor e o resultado.
+    pub(crate) fn feed_into(&mut self, available: &[u8], buf: &mut Vec<u8>) -> (usize, Split) {
+        let step = self.feed(available, buf);
+        buf.extend_from_slice(&available[step.take]);
+        buf.truncate(buf.len() - step.trim);
+        (step.consumed, step.split)
+    }
+
+    /// Trecho de uma linha física, sem a quebra de linha (`\n` e os `\r` antes dele, como
+    /// em [`crate::trim_line_ending`]). Devolve o trecho, o que cortar do que veio antes,
+    /// o consumo e se a linha terminou (quebra ou fim do arquivo).
+    fn line(&self, available: &[u8], record: &[u8]) -> (Range<usize>, usize, usize, bool) {
+        let content_end = available.iter().position(|&b| b == b'\n');
+        let (content, consumed, ended) = match content_end {
+            Some(end) => (&available[..end], end + 1, true),
+            None => (available, available.len(), available.is_empty()),
+        };
+        if !ended {
+            return (0..content.len(), 0, consumed, false);
+        }
+        let take = crate::trim_line_ending(content).len();
+        // Só `\r` neste trecho: os `\r` do fim do trecho anterior também saem
+        let trim = if take == 0 {
+            let part = &record[self.part_start.min(record.len())..];
+            part.len() - crate::trim_line_ending(part).len()
+        } else {
+            0
+        };
+        (0..take, trim, consumed, true)
+    }
+}
+
+/// Leitor de registros brutos sobre qualquer `BufRead`.
+#[derive(Debug)]
+pub struct RecordReader<R> {
+    inner: R,
+    framing: Framing,
+    records: u64,
+    /// Token e intervalo (em registros) entre as verificações.
+    cancel: Option<(CancelToken, u64)>,
+    /// Encerra a iteração depois do primeiro erro (cancelamento, E/S, `WrappedLine`).
+    done: bool,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    /// Cria um leitor com o framing informado.
+    pub fn new(inner: R, framing: Framing) -> Self {
+        Self { inner, framing, records: 0, cancel: None, done: false }
+    }
+
+    /// Verifica `token` a cada `every` registros (e antes do primeiro): depois do
+    /// cancelamento, a próxima leitura verificada falha com `FixedWidthError::Cancelled`.
+    ///
+    /// A verificação é uma leitura atômica; intervalos pequenos (ex: 1.000) já respondem
+    /// rápido sem custo perceptível. `every = 0` é tratado como 1.
+    pub fn with_cancel(mut self, token: CancelToken, every: u64) -> Self {
+        self.cancel = Some((token, every.max(1)));
+        self
+    }
+
+    /// Quantidade de registros lidos até agora (o número do último registro, 1-based).
+    pub fn records_read(&self) -> u64 {
+        self.records
+    }
+
+    /// Lê o próximo registro em `buf` (sem separadores), reaproveitando a alocação.
+    ///
+    /// Retorna `false` no fim do arquivo. Um último registro incompleto é devolvido
+    /// como está; o parse reportará `LineTooShort`.
+    pub fn read_record(&mut self, buf: &mut Vec<u8>) -> Result<bool> {
+        buf.clear();
+        check_cancel(&self.cancel, self.records)?;
+        let mut splitter = Splitter::new(self.framing, self.records + 1);
+        let found = loop {
+            let available = self.inner.fill_buf()?;
+            let (consumed, split) = splitter.feed_into(available, buf);
+            self.inner.consume(consumed);
+            match split {
+                Split::More => {}
+                Split::Record => break true,
+                Split::End => break false,
+                Split::Invalid(e) => return Err(e),
+            }
+        };
+
+        if found {
+            self.records += 1;
+        }
+        Ok(found)
+    }
+
+    /// Lê o próximo registro e faz o parse com `fields`.
+    ///
+    /// Erros de parse saem com o número do registro, a posição e o trecho do campo
+    /// (veja [`FixedWidthError::at_line`](crate::FixedWidthError::at_line)).
+    pub fn next_fields(
+        &mut self,
+        buf: &mut Vec<u8>,
+        fields: &[FieldSpec],
+        options: &ParseOptions,
+    ) -> Result<Option<DynRecord>> {
+        if !self.read_record(buf)? {
+            return Ok(None);
+        }
+        self.parse_fields(buf, fields, options).map(Some)
+    }
+
+    /// Lê o próximo registro e faz o parse com o layout que `layouts` resolver para ele,
+    /// tentando os layouts alternativos do tipo de registro
+    /// (veja [`LayoutRegistry::resolve_parse`]).
+    ///
+    /// Devolve o layout usado junto com o registro. Registros sem layout resultam em
+    /// `FixedWidthError::UnknownRecord`; erros de parse saem com o número do registro.
+    pub fn next_resolved<'l>(
+        &mut self,
+        buf: &mut Vec<u8>,
+        layouts: &'l LayoutRegistry,
+        options: &ParseOptions,
+    ) -> Result<Option<(&'l RecordLayout, DynRecord)>> {
+        if !self.read_record(buf)? {
+            return Ok(None);
+        }
+        let line_number = self.records;
+        match layouts.resolve_parse(buf, options) {
+            None => Err(FixedWidthError::UnknownRecord { line_number }),
+            Some(Ok(resolved)) => Ok(Some(resolved)),
+            Some(Err(e)) => {
+                let fields = layouts.resolve(buf).map_or(&[][..], |layout| &layout.fields);
+                Err(e.at_line_with(line_number, buf, fields, options.redaction))
+            }
+        }
+    }
+
+    /// Lê o próximo registro e faz o parse para uma struct derivada.
+    ///
+    /// Erros de parse saem com o número do registro (`FixedWidthError::Line`).
+    pub fn next_parsed<T: FixedWidthParse>(&mut self, buf: &mut Vec<u8>, options: &ParseOptions) -> Result<Option<T>> {
+        if !self.read_record(buf)? {
+            return Ok(None);
+        }
+        self.parse_struct(buf, options).map(Some)
+    }
+
+    /// Envolve o leitor para disparar os callbacks de `lifecycle` a cada registro lido.
+    pub fn with_lifecycle<'a>(self, lifecycle: Lifecycle<'a>) -> LifecycleReader<'a, R> {
+        LifecycleReader { reader: self, lifecycle }
+    }
+
+    /// Parse do registro atual com `fields`, com a localização nos erros.
+    fn parse_fields(&self, buf: &[u8], fields: &[FieldSpec], options: &ParseOptions) -> Result<DynRecord> {
+        parse_line_bytes_with(buf, fields, options)
+            .map_err(|e| e.at_line_with(self.records, buf, fields, options.redaction))
+    }
+
+    /// Parse do registro atual para uma struct derivada, com o número do registro nos erros.
+    fn parse_struct<T: FixedWidthParse>(&self, buf: &[u8], options: &ParseOptions) -> Result<T> {
+        T::parse_bytes(buf, options).map_err(|e| e.at_line_with(self.records, buf, &[], options.redaction))
+    }
+}
+
+/// A iteração termina no fim do arquivo ou depois do primeiro erro: um erro de leitura
+/// se repetiria a cada chamada.
+impl<R: BufRead> Iterator for RecordReader<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut buf = Vec::new();
+        match self.read_record(&mut buf) {
+            Ok(true) => Some(Ok(buf)),
+            Ok(false) => None,
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Callback de estrutura: recebe o número do registro (1-based) e seus bytes.
+type Callback<'a> = Box<dyn FnMut(u64, &[u8]) + 'a>;
+
+/// Callbacks de header/trailer de arquivo e de lote, identificados pelo tipo de registro.
+///
+/// No CNAB 240 o tipo fica na posição 8 (`0` header de arquivo, `1` header de lote,
+/// `5` trailer de lote, `9` trailer de arquivo); no CNAB 400, na posição 1, sem lotes.
+pub struct Lifecycle<'a> {
+    tag_pos: FieldPos,
+    lotes: bool,
+    on_file_header: Option<Callback<'a>>,
+    on_lote_open: Option<Callback<'a>>,
+    on_lote_close: Option<Callback<'a>>,
+    on_file_trailer: Option<Callback<'a>>,
+}
+
+impl std::fmt::Debug for Lifecycle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lifecycle").field("tag_pos", &self.tag_pos).field("lotes", &self.lotes).finish_non_exhaustive()
+    }
+}
+
+impl<'a> Lifecycle<'a> {
+    /// Estrutura do CNAB 240: tipo de registro na posição 8, com lotes.
+    pub fn cnab240() -> Self {
+        Self::with_tag(FieldPos::new_const(8, 8), true)
+    }
+
+    /// Estrutura do CNAB 400: tipo de registro na posição 1, sem lotes.
+    pub fn cnab400() -> Self {
+        Self::with_tag(FieldPos::new_const(1, 1), false)
+    }
+
+    fn with_tag(tag_pos: FieldPos, lotes: bool) -> Self {
+        Self { tag_pos, lotes, on_file_header: None, on_lote_open: None, on_lote_close: None, on_file_trailer: None }
+    }
+
+    /// Chamado no header de arquivo (tipo `0`).
+    pub fn on_file_header(mut self, callback: impl FnMut(u64, &[u8]) + 'a) -> Self {
+        self.on_file_header = Some(Box::new(callback));
+        self
+    }
+
+    /// Chamado no header de lote (tipo `1`, só no CNAB 240).
+    pub fn on_lote_open(mut self, callback: impl FnMut(u64, &[u8]) + 'a) -> Self {
+        self.on_lote_open = Some(Box::new(callback));
+        self
+    }
+
+    /// Chamado no trailer de lote (tipo `5`, só no CNAB 240).
+    pub fn on_lote_close(mut self, callback: impl FnMut(u64, &[u8]) + 'a) -> Self {
+        self.on_lote_close = Some(Box::new(callback));
+        self
+    }
+
+    /// Chamado no trailer de arquivo (tipo `9`).
+    pub fn on_file_trailer(mut self, callback: impl FnMut(u64, &[u8]) + 'a) -> Self {
+        self.on_file_trailer = Some(Box::new(callback));
+        self
+    }
+
+    /// Dispara o callback correspondente ao tipo do registro, se houver.
+    fn dispatch(&mut self, record_number: u64, record: &[u8]) {
+        let callback = match (record.get(self.tag_pos.as_range()), self.lotes) {
+            (Some(b"0"), _) => &mut self.on_file_header,
+            (Some(b"1"), true) => &mut self.on_lote_open,
+            (Some(b"5"), true) => &mut self.on_lote_close,
+            (Some(b"9"), _) => &mut self.on_file_trailer,
+            _ => return,
+        };
+        if let Some(callback) = callback {
+            callback(record_number, record);
+        }
+    }
+}
+
+/// [`RecordReader`] que dispara os callbacks de um [`Lifecycle`] antes de devolver
+/// cada registro (veja [`RecordReader::with_lifecycle`]).
+#[derive(Debug)]
+pub struct LifecycleReader<'a, R> {
+    reader: RecordReader<R>,
+    lifecycle: Lifecycle<'a>,
+}
+
+impl<R: BufRead> LifecycleReader<'_, R> {
+    /// Quantidade de registros lidos até agora.
+    pub fn records_read(&self) -> u64 {
+        self.reader.records_read()
+    }
+
+    /// Lê o próximo registro em `buf`, disparando o callback do seu tipo.
+    pub fn read_record(&mut self, buf: &mut Vec<u8>) -> Result<bool> {
+        let found = self.reader.read_record(buf)?;
+        if found {
+            self.lifecycle.dispatch(self.reader.records, buf);
+        }
+        Ok(found)
+    }
+
+    /// Como [`RecordReader::next_fields`], disparando os callbacks.
+    pub fn next_fields(
+        &mut self,
+        buf: &mut Vec<u8>,
+        fields: &[FieldSpec],
+        options: &ParseOptions,
+    ) -> Result<Option<DynRecord>> {
+        if !self.read_record(buf)? {
+            return Ok(None);
+        }
+        self.reader.parse_fields(buf, fields, options).map(Some)
+    }
+
+    /// Como [`RecordReader::next_parsed`], disparando os callbacks.
+    pub fn next_parsed<T: FixedWidthParse>(&mut self, buf: &mut Vec<u8>, options: &ParseOptions) -> Result<Option<T>> {
+        if !self.read_record(buf)? {
+            return Ok(None);
+        }
+        self.reader.parse_struct(buf, options).map(Some)
+    }
+}
+
+/// Como o iterador de [`RecordReader`], termina depois do primeiro erro.
+impl<R: BufRead> Iterator for LifecycleReader<'_, R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reader.done {
+            return None;
+        }
+        let mut buf = Vec::new();
+        match self.read_record(&mut buf) {
+            Ok(true) => Some(Ok(buf)),
+            Ok(false) => None,
+            Err(e) => {
+                self.reader.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldKind, FieldPos, Value};
+
+    #[test]
+    fn fixed_length_keeps_embedded_newlines_as_data() {
+        // Dois registros de 8 bytes; o primeiro tem um \n perdido no texto livre
+        let input = b"001AB\nC \r\n002XYZ \n" as &[u8];
+        let records: Vec<_> = RecordReader::new(input, Framing::FixedLength(8))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(records, vec![b"001AB\nC ".to_vec(), b"002XYZ \n".to_vec()]);
+
+        let fields = [
+            FieldSpec::new("seq", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("texto", FieldPos::new_const(4, 8), FieldKind::Alpha),
+        ];
+        let options = ParseOptions {
+            trim_line_ending: false,
+            replace_newlines: true,
+            ..Default::default()
+        };
+        let first = parse_line_bytes_with(&records[0], &fields, &options).unwrap();
+        assert_eq!(first["texto"], Value::Alpha("AB C".to_string()));
+        let second = parse_line_bytes_with(&records[1], &fields, &options).unwrap();
+        assert_eq!(second["texto"], Value::Alpha("XYZ".to_string()));
+    }
+
+    #[test]
+    fn wrapped_records_join_physical_lines() {
+        let input = b"001AB\r\nCDE  \n002XY\nZ    \n" as &[u8];
+        let framing = Framing::Wrapped { lines: 2, width: 5 };
+        let records: Vec<_> = RecordReader::new(input, framing).collect::<Result<_>>().unwrap();
+        assert_eq!(records, vec![b"001ABCDE  ".to_vec(), b"002XYZ    ".to_vec()]);
+
+        // Linha física com tamanho errado: a quebra está desalinhada
+        let input = b"001AB\nCDE  \n002X\nYZ   \n" as &[u8];
+        let mut reader = RecordReader::new(input, framing);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next().unwrap(),
+            Err(FixedWidthError::WrappedLine { record: 2, part: 1, len: 4, expected: 5 })
+        ));
+    }
+
+    #[test]
+    fn framing_does_not_depend_on_buffer_boundaries() {
+        let cases: [(&[u8], Framing); 3] = [
+            (b"001AB\r\n\r\n002\r\r\n003", Framing::Lines),
+            (b"\r\n001AB\n\r\n002XY\r\n00", Framing::FixedLength(5)),
+            (b"001\r\nABC\r\n002\nXYZ\n003\r\n", Framing::Wrapped { lines: 2, width: 3 }),
+        ];
+        for (input, framing) in cases {
+            let whole: Vec<_> = RecordReader::new(input, framing).collect();
+            for capacity in 1..=4 {
+                let chunked: Vec<_> = RecordReader::new(std::io::BufReader::with_capacity(capacity, input), framing).collect();
+                assert_eq!(format!("{chunked:?}"), format!("{whole:?}"), "{framing:?}, capacidade {capacity}");
+            }
+        }
+        let lines: Vec<_> = RecordReader::new(cases[0].0, Framing::Lines).collect::<Result<_>>().unwrap();
+        assert_eq!(lines, [&b"001AB"[..], b"", b"002", b"003"]);
+        let wrapped: Vec<_> = RecordReader::new(cases[2].0, cases[2].1).collect::<Result<_>>().unwrap();
+        assert_eq!(wrapped, [&b"001ABC"[..], b"002XYZ", b"003"]);
+    }
+
+    #[test]
+    fn parse_errors_carry_line_number_and_field() {
+        let fields = [
+            FieldSpec::new("seq", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("texto", FieldPos::new_const(4, 8), FieldKind::Alpha),
+        ];
+        let input = b"001ABCDE\n002XY\n" as &[u8];
+        let mut reader = RecordReader::new(input, Framing::Lines);
+        let mut buf = Vec::new();
+        let options = ParseOptions::default();
+
+        assert!(reader.next_fields(&mut buf, &fields, &options).unwrap().is_some());
+        let err = reader.next_fields(&mut buf, &fields, &options).unwrap_err();
+        assert!(matches!(
+            &err,
+            FixedWidthError::Field { field: "texto", line_number: 2, snippet, .. } if snippet == "XY"
+        ));
+        assert!(matches!(err.root(), FixedWidthError::LineTooShort { len: 5, needed: 8 }));
+        assert_eq!(err.line_number(), Some(2));
+    }
+
+    #[test]
+    fn cancel_token_stops_reading_at_the_next_check() {
+        let input = b"1\n2\n3\n4\n5\n6\n7\n" as &[u8];
+        let token = CancelToken::new();
+        let mut reader = RecordReader::new(input, Framing::Lines).with_cancel(token.clone(), 3);
+        let mut buf = Vec::new();
+
+        assert!(reader.read_record(&mut buf).unwrap());
+        token.cancel();
+        // A verificação acontece a cada 3 registros: o 2 e o 3 ainda são lidos
+        assert!(reader.read_record(&mut buf).unwrap());
+        assert!(reader.read_record(&mut buf).unwrap());
+        assert!(matches!(reader.read_record(&mut buf), Err(FixedWidthError::Cancelled { records: 3 })));
+        assert_eq!(reader.records_read(), 3);
+
+        let cancelled = RecordReader::new(input, Framing::Lines).with_cancel(token.clone(), 1000).next().unwrap();
+        assert_eq!(cancelled.unwrap_err().to_string(), "leitura cancelada após 0 registros");
+
+        // Os iteradores terminam depois do erro em vez de repeti-lo
+        let records: Vec<_> = RecordReader::new(input, Framing::Lines).with_cancel(token.clone(), 3).collect();
+        assert_eq!(records.len(), 1);
+        assert!(matches!(records[0], Err(FixedWidthError::Cancelled { records: 0 })));
+        let lifecycle = RecordReader::new(input, Framing::Lines).with_cancel(token, 3).with_lifecycle(Lifecycle::cnab400());
+        assert_eq!(lifecycle.filter_map(Result::ok).count(), 0);
+    }
+
+    #[test]
+    fn lifecycle_callbacks_follow_file_structure() {
+        let input = b"3410000000\n3410001100\n3410001300\n3410001500\n3410002100\n3410002500\n3419999900\n" as &[u8];
+        let events = std::cell::RefCell::new(Vec::new());
+        let lifecycle = Lifecycle::cnab240()
+            .on_file_header(|n, _| events.borrow_mut().push(format!("arquivo {n}")))
+            .on_lote_open(|n, record| {
+                let lote = String::from_utf8_lossy(&record[3..7]);
+                events.borrow_mut().push(format!("lote {lote} aberto {n}"));
+            })
+            .on_lote_close(|n, _| events.borrow_mut().push(format!("lote fechado {n}")))
+            .on_file_trailer(|n, _| events.borrow_mut().push(format!("fim {n}")));
+
+        let records = RecordReader::new(input, Framing::Lines).with_lifecycle(lifecycle).count();
+        assert_eq!(records, 7);
+        assert_eq!(
+            events.into_inner(),
+            ["arquivo 1", "lote 0001 aberto 2", "lote fechado 4", "lote 0002 aberto 5", "lote fechado 6", "fim 7"]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! # Conciliação
+//!
+//! Cruza os títulos enviados na remessa com os movimentos do retorno, pelo nosso número
+//! e, na falta dele, pelo seu número, e diz o que aconteceu com cada título: liquidado,
+//! rejeitado, baixado ou ainda pendente, além dos movimentos que não correspondem a
+//! nenhum título enviado.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::cnab240;
+//! use cnab_fixedwidth::nosso_numero::NossoNumeroRule;
+//! use cnab_fixedwidth::reconcile::{self, TituloRemessa};
+//!
+//! # fn exemplo(file: impl std::io::BufRead, titulos: Vec<TituloRemessa>) -> cnab_fixedwidth::Result<()> {
+//! let retorno = cnab240::parse_retorno_cobranca(file)?;
+//! let movimentos: Vec<_> = retorno.titulos().map(|t| t.movimento()).collect();
+//! let conciliacao = reconcile::reconcile(&titulos, &movimentos, Some(&NossoNumeroRule::itau()));
+//! for titulo in conciliacao.liquidados() {
+//!     println!("{} pago {} em {}", titulo.titulo.nosso_numero, titulo.valor_pago, titulo.data_credito);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::nosso_numero::NossoNumeroStrategy;
+use crate::retorno::{MovimentoRetorno, RetornoEvent};
+
+/// Título enviado na remessa.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TituloRemessa {
+    pub nosso_numero: String,
+    /// Seu número (identificação do título na empresa).
+    pub seu_numero: String,
+    /// Valor nominal, em centavos.
+    pub valor: i64,
+    /// Vencimento (`DDMMAAAA`).
+    pub vencimento: u32,
+}
+
+/// Situação do título depois do retorno.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Situacao {
+    /// Pago (inclusive após baixa).
+    Liquidado,
+    /// Entrada rejeitada pelo banco.
+    Rejeitado,
+    /// Baixado sem pagamento.
+    Baixado,
+    /// Sem movimento conclusivo: não veio no retorno, ou só teve a entrada confirmada.
+    Pendente,
+}
+
+/// Título da remessa com o resultado dos seus movimentos no retorno.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TituloConciliado {
+    pub titulo: TituloRemessa,
+    pub situacao: Situacao,
+    /// Valor pago, em centavos (0 se não liquidado).
+    pub valor_pago: i64,
+    /// Soma das tarifas cobradas no título, em centavos.
+    pub tarifas: i64,
+    /// Data do movimento que definiu a situação (0 se pendente sem movimento).
+    pub data_ocorrencia: u32,
+    /// Data do crédito da liquidação (0 se não liquidado).
+    pub data_credito: u32,
+    /// Motivos da rejeição.
+    pub motivos: Vec<String>,
+    /// Movimentos do retorno atribuídos ao título, na ordem do arquivo.
+    pub movimentos: Vec<MovimentoRetorno>,
+}
+
+impl TituloConciliado {
+    /// Valor pago menos o valor nominal (juros e multa positivos, descontos negativos).
+    pub fn diferenca(&self) -> i64 {
+        match self.situacao {
+            Situacao::Liquidado => self.valor_pago - self.titulo.valor,
+            _ => 0,
+        }
+    }
+}
+
+/// Resultado de [`reconcile`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conciliacao {
+    /// Títulos da remessa, na ordem recebida.
+    pub titulos: Vec<TituloConciliado>,
+    /// Movimentos do retorno sem título correspondente na remessa.
+    pub sem_remessa: Vec<MovimentoRetorno>,
+}
+
+impl Conciliacao {
+    /// Títulos em uma situação.
+    pub fn por_situacao(&self, situacao: Situacao) -> impl Iterator<Item = &TituloConciliado> {
+        self.titulos.iter().filter(move |titulo| titulo.situacao == situacao)
+    }
+
+    pub fn liquidados(&self) -> impl Iterator<Item = &TituloConciliado> {
+        self.por_situacao(Situacao::Liquidado)
+    }
+
+    pub fn rejeitados(&self) -> impl Iterator<Item = &TituloConciliado> {
+        self.por_situacao(Situacao::Rejeitado)
+    }
+
+    pub fn pendentes(&self) -> impl Iterator<Item = &TituloConciliado> {
+        self.por_situacao(Situacao::Pendente)
+    }
+
+    /// Soma dos valores pagos, em centavos.
+    pub fn total_liquidado(&self) -> i64 {
+        self.liquidados().map(|titulo| titulo.valor_pago).sum()
+    }
+}
+
+/// Concilia os títulos da remessa com os movimentos do retorno.
+///
+/// O nosso número é comparado pela chave de `nosso_numero` (com ou sem DV e separadores,
+/// veja [`NossoNumeroStrategy::key`]) ou, sem regra, sem espaços e zeros à esquerda.
+/// Movimentos sem nosso número correspondente são atribuídos pelo seu número.
+///
+/// Com vários movimentos para o mesmo título, a liquidação prevalece sobre a baixa, a
+/// baixa sobre a rejeição e a rejeição sobre a entrada confirmada; as tarifas de todos os
+/// movimentos são somadas.
+pub fn reconcile(
+    titulos: &[TituloRemessa],
+    movimentos: &[MovimentoRetorno],
+    nosso_numero: Option<&dyn NossoNumeroStrategy>,
+) -> Conciliacao {
+    let key = |value: &str| match nosso_numero {
+        Some(rule) => rule.key(value),
+        None => {
+            let trimmed = value.trim().trim_start_matches('0');
+            (!value.trim().is_empty()).then(|| trimmed.to_string())
+        }
+    };
+    let seu_numero = |value: &str| Some(value.trim()).filter(|v| !v.is_empty()).map(str::to_string);
+    let nossos: Vec<_> = titulos.iter().map(|titulo| key(&titulo.nosso_numero)).collect();
+    let seus: Vec<_> = titulos.iter().map(|titulo| seu_numero(&titulo.seu_numero)).collect();
+
+    let mut conciliacao = Conciliacao {
+        titulos: titulos
+            .iter()
+            .map(|titulo| TituloConciliado {
+                titulo: titulo.clone(),
+                situacao: Situacao::Pendente,
+                valor_pago: 0,
+                tarifas: 0,
+                data_ocorrencia: 0,
+                data_credito: 0,
+                motivos: Vec::new(),
+                movimentos: Vec::new(),
+            })
+            .collect(),
+        sem_remessa: Vec::new(),
+    };
+    for movimento in movimentos {
+        let by_nosso = key(&movimento.nosso_numero).and_then(|k| nossos.iter().position(|n| n.as_ref() == Some(&k)));
+        let index = by_nosso.or_else(|| {
+            seu_numero(&movimento.seu_numero).and_then(|s| seus.iter().position(|n| n.as_ref() == Some(&s)))
+        });
+        match index {
+            Some(index) => apply(&mut conciliacao.titulos[index], movimento),
+            None => conciliacao.sem_remessa.push(movimento.clone()),
+        }
+    }
+    conciliacao
+}
+
+/// Prioridade da situação quando um título tem vários movimentos.
+fn rank(situacao: Situacao) -> u8 {
+    match situacao {
+        Situacao::Pendente => 0,
+        Situacao::Rejeitado => 1,
+        Situacao::Baixado => 2,
+        Situacao::Liquidado => 3,
+    }
+}
+
+fn apply(titulo: &mut TituloConciliado, movimento: &MovimentoRetorno) {
+    titulo.movimentos.push(movimento.clone());
+    for event in RetornoEvent::from_movimento(movimento) {
+        let situacao = match event {
+            RetornoEvent::TituloLiquidado { valor_pago, data_credito, .. }
+                if rank(titulo.situacao) <= rank(Situacao::Liquidado) =>
+            {
+                titulo.valor_pago = valor_pago;
+                titulo.data_credito = data_credito;
+                Situacao::Liquidado
+            }
+            RetornoEvent::EntradaRejeitada { motivos, .. } if rank(titulo.situacao) <= rank(Situacao::Rejeitado) => {
+                titulo.motivos = motivos;
+                Situacao::Rejeitado
+            }
+            RetornoEvent::TituloBaixado { .. } if rank(titulo.situacao) <= rank(Situacao::Baixado) => Situacao::Baixado,
+            RetornoEvent::TarifaCobrada { valor, .. } => {
+                titulo.tarifas += valor;
+                continue;
+            }
+            _ => continue,
+        };
+        titulo.situacao = situacao;
+        titulo.data_ocorrencia = movimento.data_ocorrencia;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nosso_numero::NossoNumeroRule;
+
+    fn titulo(nosso_numero: &str, seu_numero: &str, valor: i64) -> TituloRemessa {
+        TituloRemessa { nosso_numero: nosso_numero.into(), seu_numero: seu_numero.into(), valor, vencimento: 10112026 }
+    }
+
+    fn movimento(codigo: u8, nosso_numero: &str, seu_numero: &str, valor_pago: i64) -> MovimentoRetorno {
+        MovimentoRetorno {
+            codigo_movimento: codigo,
+            nosso_numero: nosso_numero.into(),
+            seu_numero: seu_numero.into(),
+            valor_pago,
+            data_ocorrencia: 15102026,
+            data_credito: 16102026,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_titles_by_nosso_numero_then_seu_numero() {
+        let titulos = [
+            titulo("000000000028", "NF-1", 10_000),
+            titulo("000000000036", "NF-2", 5_000),
+            titulo("000000000044", "NF-3", 7_000),
+            titulo("", "NF-4", 2_000),
+        ];
+        let mut rejeicao = movimento(3, "00000000003-6", "", 0);
+        rejeicao.motivos = "0809".into();
+        let movimentos = [
+            movimento(2, "19/00000000002-8", "", 0),
+            movimento(6, "000000000028", "NF-1", 10_150),
+            rejeicao,
+            movimento(6, "", "NF-4", 2_000),
+            movimento(6, "000000000999", "NF-9", 1_000),
+        ];
+
+        let conciliacao = reconcile(&titulos, &movimentos, Some(&NossoNumeroRule::bradesco()));
+        let situacoes: Vec<_> = conciliacao.titulos.iter().map(|t| t.situacao).collect();
+        assert_eq!(situacoes, [Situacao::Liquidado, Situacao::Rejeitado, Situacao::Pendente, Situacao::Liquidado]);
+
+        let pago = &conciliacao.titulos[0];
+        assert_eq!((pago.valor_pago, pago.diferenca(), pago.data_credito, pago.movimentos.len()), (10_150, 150, 16102026, 2));
+        assert_eq!(conciliacao.titulos[1].motivos, ["08", "09"]);
+        assert_eq!(conciliacao.total_liquidado(), 12_150);
+        assert_eq!(conciliacao.pendentes().count(), 1);
+        assert_eq!(conciliacao.sem_remessa.len(), 1);
+        assert_eq!(conciliacao.sem_remessa[0].seu_numero, "NF-9");
+    }
+}
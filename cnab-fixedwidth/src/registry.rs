@@ -0,0 +1,327 @@
+//! # Registro de Layouts
+//!
+//! Um arquivo CNAB mistura vários tipos de registro (header, segmentos, trailers),
+//! cada um com seu próprio layout. O [`LayoutRegistry`] guarda esses layouts e
+//! descobre qual deles se aplica a cada linha, olhando campos discriminadores
+//! (ex: tipo de registro na posição 8 e código do segmento na posição 14 no CNAB 240).
+//!
+//! Um tipo de registro pode ter layouts alternativos ([`RecordLayout::or_fallback`]):
+//! quando o banco troca a versão do layout no meio do mês, cada registro é lido com o
+//! primeiro candidato que o interpreta sem erros, e o resultado diz qual foi.
+//!
+//! Para quem processa arquivos de vários bancos, o [`FileLayoutRegistry`] guarda um
+//! `LayoutRegistry` por banco, formato (240/400/150) e versão do layout, e escolhe o
+//! certo a partir do header do arquivo:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{CnabFormat, FileLayoutRegistry, LayoutKey, LayoutRegistry};
+//!
+//! # let (itau_240, bradesco_400, first_record) = (LayoutRegistry::new(), LayoutRegistry::new(), Vec::new());
+//! let mut files = FileLayoutRegistry::new();
+//! files
+//!     .register(LayoutKey::new(341, CnabFormat::Cnab240, Some(40)), itau_240)
+//!     .register(LayoutKey::new(237, CnabFormat::Cnab400, None), bradesco_400);
+//! let (key, layouts) = files.resolve_file(&first_record).ok_or("layout não cadastrado")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{parse_line_bytes_with, DynRecord, FieldPos, FieldSpec, FixedWidthSpec, ParseOptions, Result};
+
+/// Layout de um tipo de registro, junto com as condições que identificam suas linhas.
+#[derive(Debug, Clone)]
+pub struct RecordLayout {
+    /// Nome do tipo de registro (ex: "header_arquivo", "segmento_p").
+    pub name: &'static str,
+
+    /// Campos do registro.
+    pub fields: Vec<FieldSpec>,
+
+    /// Condições (posição, conteúdo esperado) que a linha precisa atender.
+    /// Sem condições, o layout aceita qualquer linha.
+    pub matchers: Vec<(FieldPos, &'static str)>,
+
+    /// Layouts alternativos para o mesmo tipo de registro, tentados em ordem quando este
+    /// não interpreta a linha (veja [`LayoutRegistry::resolve_parse`]).
+    pub fallbacks: Vec<RecordLayout>,
+}
+
+impl RecordLayout {
+    /// Cria um layout sem condições de identificação.
+    pub fn new(name: &'static str, fields: Vec<FieldSpec>) -> Self {
+        Self { name, fields, matchers: Vec::new(), fallbacks: Vec::new() }
+    }
+
+    /// Layout com os campos de uma struct derivada (`#[derive(FixedWidth)]`).
+    pub fn of<T: FixedWidthSpec>(name: &'static str) -> Self {
+        Self::new(name, T::spec().to_vec())
+    }
+
+    /// Adiciona uma condição: os bytes em `pos` devem ser exatamente `expected`.
+    ///
+    /// Exemplo: `.when(FieldPos::new_const(8, 8), "3")` para registros de detalhe CNAB 240.
+    pub fn when(mut self, pos: FieldPos, expected: &'static str) -> Self {
+        self.matchers.push((pos, expected));
+        self
+    }
+
+    /// Verifica se a linha atende a todas as condições do layout.
+    pub fn matches(&self, line: &[u8]) -> bool {
+        self.matchers
+            .iter()
+            .all(|(pos, expected)| line.get(pos.as_range()) == Some(expected.as_bytes()))
+    }
+
+    /// Adiciona um layout alternativo (ex: a versão anterior do mesmo registro), tentado
+    /// depois deste e dos já adicionados.
+    ///
+    /// A linha é identificada pelas condições deste layout; as condições do alternativo
+    /// (ex: o número da versão) são conferidas só na hora de tentá-lo.
+    pub fn or_fallback(mut self, layout: RecordLayout) -> Self {
+        self.fallbacks.push(layout);
+        self
+    }
+}
+
+/// Conjunto ordenado de layouts de registro.
+///
+/// A resolução devolve o **primeiro** layout registrado cujas condições são atendidas,
+/// portanto layouts mais específicos devem ser registrados antes dos genéricos.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutRegistry {
+    layouts: Vec<RecordLayout>,
+}
+
+impl LayoutRegistry {
+    /// Cria um registro vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra um layout (ao final da ordem de resolução).
+    pub fn register(&mut self, layout: RecordLayout) -> &mut Self {
+        self.layouts.push(layout);
+        self
+    }
+
+    /// Encontra o layout que se aplica à linha (sem a quebra de linha).
+    pub fn resolve(&self, line: &[u8]) -> Option<&RecordLayout> {
+        self.layouts.iter().find(|layout| layout.matches(line))
+    }
+
+    /// Identifica a linha e faz o parse com o primeiro candidato do tipo de registro
+    /// (o layout e seus [`fallbacks`](RecordLayout::fallbacks)) que a interpreta sem erros.
+    ///
+    /// Devolve o layout usado junto com o registro, `None` se nenhum layout se aplica à
+    /// linha, ou o erro do layout principal se nenhum candidato servir.
+    pub fn resolve_parse(&self, line: &[u8], options: &ParseOptions) -> Option<Result<(&RecordLayout, DynRecord)>> {
+        let layout = self.resolve(line)?;
+        let candidates = std::iter::once(layout).chain(layout.fallbacks.iter().filter(|f| f.matches(line)));
+        let mut first_error = None;
+        for candidate in candidates {
+            match parse_line_bytes_with(line, &candidate.fields, options) {
+                Ok(record) => return Some(Ok((candidate, record))),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        first_error.map(Err)
+    }
+
+    /// Layouts registrados, na ordem de resolução.
+    pub fn layouts(&self) -> &[RecordLayout] {
+        &self.layouts
+    }
+}
+
+/// Formato (largura do registro) de um arquivo CNAB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CnabFormat {
+    /// FEBRABAN 240 (cobrança, pagamentos, extrato).
+    Cnab240,
+    /// CNAB 400 (cobrança).
+    Cnab400,
+    /// FEBRABAN 150 (débito automático).
+    Cnab150,
+}
+
+impl CnabFormat {
+    /// Posições de um registro.
+    pub const fn record_width(self) -> usize {
+        match self {
+            CnabFormat::Cnab240 => 240,
+            CnabFormat::Cnab400 => 400,
+            CnabFormat::Cnab150 => 150,
+        }
+    }
+
+    /// Formato pela largura do registro.
+    pub fn from_width(width: usize) -> Option<Self> {
+        [CnabFormat::Cnab240, CnabFormat::Cnab400, CnabFormat::Cnab150]
+            .into_iter()
+            .find(|format| format.record_width() == width)
+    }
+}
+
+/// Chave de um layout de arquivo: banco, formato e versão do layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayoutKey {
+    /// Código do banco na compensação.
+    pub banco: u16,
+    pub format: CnabFormat,
+    /// Versão do layout informada no header (ex: `040` no CNAB 240). `None` no registro
+    /// vale para qualquer versão; em uma chave lida de arquivo, o formato não informa versão.
+    pub version: Option<u16>,
+}
+
+impl LayoutKey {
+    /// Cria a chave (`version: None` para qualquer versão).
+    pub const fn new(banco: u16, format: CnabFormat, version: Option<u16>) -> Self {
+        Self { banco, format, version }
+    }
+
+    /// Lê banco, formato e versão do header de arquivo (o primeiro registro). Formato e
+    /// banco vêm de [`detect::sniff_bytes`](crate::detect::sniff_bytes); a versão fica em
+    /// 164..166 no CNAB 240 e em 80..81 no FEBRABAN 150 (o CNAB 400 não tem).
+    pub fn from_header(header: &[u8]) -> Option<Self> {
+        let detected = crate::detect::sniff_bytes(header)?;
+        let header = crate::trim_line_ending(header);
+        let number = |start: usize, end: usize| -> Option<u16> {
+            let bytes = header.get(start - 1..end)?;
+            if !bytes.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            std::str::from_utf8(bytes).ok()?.parse().ok()
+        };
+        let version = match detected.format {
+            CnabFormat::Cnab240 => number(164, 166),
+            CnabFormat::Cnab400 => None,
+            CnabFormat::Cnab150 => number(80, 81),
+        };
+        Some(Self { banco: detected.bank_code, format: detected.format, version })
+    }
+}
+
+/// Layouts de arquivo por banco, formato e versão.
+///
+/// Os layouts são resolvidos pela versão exata e, se ela não estiver cadastrada, pelo
+/// layout do banco e formato registrado sem versão.
+#[derive(Debug, Clone, Default)]
+pub struct FileLayoutRegistry {
+    entries: Vec<(LayoutKey, LayoutRegistry)>,
+}
+
+impl FileLayoutRegistry {
+    /// Cria um registro vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra os layouts de registro de um arquivo; uma chave repetida substitui a anterior.
+    pub fn register(&mut self, key: LayoutKey, layouts: LayoutRegistry) -> &mut Self {
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = layouts,
+            None => self.entries.push((key, layouts)),
+        }
+        self
+    }
+
+    /// Layouts para a chave: a versão exata ou, na falta dela, o registro sem versão.
+    pub fn get(&self, key: &LayoutKey) -> Option<&LayoutRegistry> {
+        let find = |version| self.entries.iter().find(|(k, _)| *k == LayoutKey { version, ..*key });
+        find(key.version).or_else(|| find(None)).map(|(_, layouts)| layouts)
+    }
+
+    /// Identifica o arquivo pelo header (veja [`LayoutKey::from_header`]) e devolve a
+    /// chave lida junto com os layouts. `None` se o header não é reconhecido ou não há
+    /// layouts para ele.
+    pub fn resolve_file(&self, header: &[u8]) -> Option<(LayoutKey, &LayoutRegistry)> {
+        let key = LayoutKey::from_header(header)?;
+        self.get(&key).map(|layouts| (key, layouts))
+    }
+
+    /// Como [`resolve_file`](Self::resolve_file), lendo o header do buffer do leitor sem
+    /// consumi-lo (veja [`detect::peek`](crate::detect::peek)).
+    pub fn peek_file<R: std::io::BufRead>(&self, reader: &mut R) -> Result<Option<(LayoutKey, &LayoutRegistry)>> {
+        let buffered = reader.fill_buf()?;
+        let header = buffered.split(|&b| b == b'\n').next().unwrap_or_default();
+        Ok(self.resolve_file(header))
+    }
+
+    /// Chaves registradas, na ordem de registro.
+    pub fn keys(&self) -> impl Iterator<Item = &LayoutKey> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldKind, FixedWidthError, Value};
+
+    #[test]
+    fn fallback_chain_tags_the_layout_that_parsed() {
+        let tipo = FieldPos::new_const(1, 1);
+        let v2 = RecordLayout::new(
+            "detalhe_v2",
+            vec![
+                FieldSpec::new("valor", FieldPos::new_const(2, 6), FieldKind::Numeric),
+                FieldSpec::new("codigo", FieldPos::new_const(7, 8), FieldKind::Numeric),
+            ],
+        )
+        .when(tipo, "1");
+        // Versão anterior: código antes do valor
+        let v1 = RecordLayout::new(
+            "detalhe_v1",
+            vec![
+                FieldSpec::new("codigo", FieldPos::new_const(2, 3), FieldKind::Alpha),
+                FieldSpec::new("valor", FieldPos::new_const(4, 8), FieldKind::Numeric),
+            ],
+        );
+        let mut registry = LayoutRegistry::new();
+        registry.register(v2.or_fallback(v1));
+
+        let options = ParseOptions::default();
+        let (layout, record) = registry.resolve_parse(b"10012306", &options).unwrap().unwrap();
+        assert_eq!((layout.name, record.get("valor")), ("detalhe_v2", Some(&Value::Numeric(123))));
+        let (layout, record) = registry.resolve_parse(b"1AB00123", &options).unwrap().unwrap();
+        assert_eq!((layout.name, record.get("valor")), ("detalhe_v1", Some(&Value::Numeric(123))));
+
+        // Nenhum candidato serve: o erro é o do layout principal
+        let error = registry.resolve_parse(b"1AB0012X", &options).unwrap().unwrap_err();
+        assert!(matches!(error, FixedWidthError::InvalidNumeric { field: "valor", .. }));
+        assert!(registry.resolve_parse(b"9", &options).is_none());
+    }
+
+    #[test]
+    fn file_registry_resolves_by_bank_format_and_version() {
+        let registry = |name| {
+            let mut layouts = LayoutRegistry::new();
+            layouts.register(RecordLayout::new(name, Vec::new()));
+            layouts
+        };
+        let mut files = FileLayoutRegistry::new();
+        files
+            .register(LayoutKey::new(341, CnabFormat::Cnab240, Some(40)), registry("itau_v040"))
+            .register(LayoutKey::new(341, CnabFormat::Cnab240, None), registry("itau"))
+            .register(LayoutKey::new(237, CnabFormat::Cnab400, None), registry("bradesco_400"));
+        let name = |header: &[u8]| files.resolve_file(header).map(|(_, layouts)| layouts.layouts()[0].name);
+
+        let mut header_240 = vec![b'0'; 240];
+        header_240[..3].copy_from_slice(b"341");
+        header_240[163..166].copy_from_slice(b"040");
+        assert_eq!(name(&header_240), Some("itau_v040"));
+        header_240[163..166].copy_from_slice(b"089");
+        assert_eq!(name(&header_240), Some("itau"));
+
+        let mut header_400 = vec![b' '; 400];
+        header_400[76..79].copy_from_slice(b"237");
+        header_400.extend_from_slice(b"\r\n");
+        assert_eq!(files.resolve_file(&header_400).unwrap().0, LayoutKey::new(237, CnabFormat::Cnab400, None));
+        header_400[76..79].copy_from_slice(b"001");
+        assert_eq!(name(&header_400), None);
+        assert_eq!(name(b"0341"), None);
+        let mut reader = std::io::BufReader::new(&header_240[..]);
+        assert!(files.peek_file(&mut reader).unwrap().is_some());
+    }
+}
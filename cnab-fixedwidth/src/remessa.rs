@@ -0,0 +1,458 @@
+//! # Montagem de Remessas de Cobrança (feature `remessa`)
+//!
+//! [`RemessaBuilder`] monta uma remessa de cobrança completa a partir dos dados do
+//! beneficiário e dos títulos, sem o chamador lidar com registros: headers, segmentos
+//! P e Q (e R, quando há multa ou mensagem), trailers, numeração dos registros, nosso
+//! número na regra do banco e textos normalizados (maiúsculas, sem acentos, cortados na
+//! largura do campo).
+//!
+//! ```no_run
+//! use cnab_fixedwidth::remessa::{Beneficiario, Pagador, RemessaBuilder, Titulo};
+//! use cnab_fixedwidth::CnabFormat;
+//!
+//! # fn exemplo(beneficiario: Beneficiario, pagador: Pagador) -> Result<(), Box<dyn std::error::Error>> {
+//! let remessa = RemessaBuilder::new(beneficiario, 16102026)
+//!     .sequencial_arquivo(42)
+//!     .add_titulo(Titulo { nosso_numero: 1234, valor: 10_000, vencimento: 10112026, pagador, ..Default::default() })
+//!     .build(CnabFormat::Cnab240)?;
+//! std::fs::write("CB161001.REM", remessa)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::banks::Bank;
+use crate::cnab240::{FileWriter, HeaderArquivo, HeaderLote, TrailerLote};
+use crate::layouts::cobranca240::{SegmentoP, SegmentoQ, SegmentoR};
+use crate::layouts::cobranca400::{self, RemessaTipo1, RemessaWriter};
+use crate::nosso_numero::{NossoNumeroError, NossoNumeroStrategy};
+use crate::writer::{WriteOptions, WritePolicy};
+use crate::{CnabFormat, FieldPos, FixedWidthError};
+use std::io::Write;
+use thiserror::Error;
+
+/// Erro na montagem da remessa.
+#[derive(Debug, Error)]
+pub enum RemessaError {
+    /// O nosso número do título não segue a regra do banco (ex: sequencial longo demais).
+    #[error("título {index}: nosso número inválido: {source}")]
+    NossoNumero { index: usize, source: NossoNumeroError },
+
+    /// Carteira com caracteres não numéricos ou mais dígitos do que cabem nas posições
+    /// da carteira no layout do banco.
+    #[error("carteira '{carteira}' inválida para o banco {banco:?}: esperados até {width} dígitos")]
+    Carteira { banco: Bank, carteira: String, width: usize },
+
+    /// Formato sem layout de remessa para o banco.
+    #[error("remessa {format:?} não suportada para o banco {banco:?}")]
+    Unsupported { banco: Bank, format: CnabFormat },
+
+    /// Erro na escrita de um registro (ex: valor que não cabe no campo).
+    #[error(transparent)]
+    File(#[from] FixedWidthError),
+}
+
+/// Empresa titular da conta de cobrança.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Beneficiario {
+    pub banco: Bank,
+    /// 1 = CPF, 2 = CNPJ.
+    pub tipo_inscricao: u8,
+    pub inscricao: u64,
+    pub nome: String,
+    pub agencia: u32,
+    pub agencia_dv: String,
+    pub conta: u64,
+    pub conta_dv: String,
+    /// Código do beneficiário (convênio) no banco.
+    pub convenio: String,
+    /// Carteira: nas posições próprias do banco no CNAB 240 (ex: `"109"` no Itaú) ou,
+    /// nos demais, o código FEBRABAN (`"1"` = cobrança simples).
+    pub carteira: String,
+    /// Carteira, convênio ou modalidade que compõe o nosso número, conforme a regra do
+    /// banco (veja `NossoNumeroRule`); vazio para bancos sem prefixo.
+    pub prefixo_nosso_numero: String,
+}
+
+/// Pagador do título.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pagador {
+    /// 1 = CPF, 2 = CNPJ.
+    pub tipo_inscricao: u8,
+    pub inscricao: u64,
+    pub nome: String,
+    pub endereco: String,
+    pub bairro: String,
+    /// CEP com os 8 dígitos.
+    pub cep: u32,
+    pub cidade: String,
+    pub uf: String,
+}
+
+/// Título a registrar.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Titulo {
+    /// Sequencial do nosso número; prefixo e DV vêm da regra do banco.
+    pub nosso_numero: u64,
+    /// Seu número (número do documento na empresa).
+    pub seu_numero: String,
+    /// Valor nominal, em centavos.
+    pub valor: i64,
+    /// Vencimento (`DDMMAAAA`).
+    pub vencimento: u32,
+    /// Emissão (`DDMMAAAA`).
+    pub emissao: u32,
+    /// Espécie (ex: 02 = duplicata mercantil).
+    pub especie: u8,
+    /// Juros de mora por dia de atraso, em centavos (0 = isento).
+    pub juros_dia: i64,
+    /// Multa após o vencimento, em centavos (0 = sem multa).
+    pub multa: i64,
+    /// Mensagem impressa no boleto.
+    pub mensagem: String,
+    pub pagador: Pagador,
+}
+
+/// Construtor de remessas de cobrança CNAB 240 ou 400.
+///
+/// No CNAB 240, DVs de agência e conta deixados em branco no [`Beneficiario`] são
+/// calculados com as regras do banco (veja [`crate::dv`]).
+#[derive(Debug, Clone)]
+pub struct RemessaBuilder {
+    beneficiario: Beneficiario,
+    data_geracao: u32,
+    hora_geracao: u32,
+    sequencial_arquivo: u32,
+    versao_layout: (u16, u16),
+    titulos: Vec<Titulo>,
+}
+
+impl RemessaBuilder {
+    /// Remessa do beneficiário gerada em `data_geracao` (`DDMMAAAA`).
+    pub fn new(beneficiario: Beneficiario, data_geracao: u32) -> Self {
+        Self {
+            beneficiario,
+            data_geracao,
+            hora_geracao: 0,
+            sequencial_arquivo: 1,
+            versao_layout: (103, 60),
+            titulos: Vec::new(),
+        }
+    }
+
+    /// Hora da geração (`HHMMSS`), no header do CNAB 240.
+    pub fn hora_geracao(mut self, hora: u32) -> Self {
+        self.hora_geracao = hora;
+        self
+    }
+
+    /// Número sequencial do arquivo (NSA); padrão 1.
+    pub fn sequencial_arquivo(mut self, sequencial: u32) -> Self {
+        self.sequencial_arquivo = sequencial;
+        self
+    }
+
+    /// Versões de layout do header de arquivo e do header de lote do CNAB 240. Padrão:
+    /// FEBRABAN v10 (103 e 060); alguns bancos exigem outras (ex: Itaú, 040 e 030).
+    pub fn versao_layout(mut self, arquivo: u16, lote: u16) -> Self {
+        self.versao_layout = (arquivo, lote);
+        self
+    }
+
+    /// Acrescenta um título (código de movimento 01, entrada de título).
+    pub fn add_titulo(mut self, titulo: Titulo) -> Self {
+        self.titulos.push(titulo);
+        self
+    }
+
+    /// Monta a remessa no formato informado.
+    pub fn build(&self, format: CnabFormat) -> Result<Vec<u8>, RemessaError> {
+        self.write_to(Vec::new(), format)
+    }
+
+    /// Escreve a remessa em `out` e o devolve.
+    ///
+    /// CNAB 240 é suportado para todos os [`Bank`]; CNAB 400, para Itaú e Bradesco,
+    /// com os trechos próprios do banco além do nosso número em branco.
+    pub fn write_to<W: Write>(&self, out: W, format: CnabFormat) -> Result<W, RemessaError> {
+        match format {
+            CnabFormat::Cnab240 => self.write_240(out),
+            CnabFormat::Cnab400 if matches!(self.beneficiario.banco, Bank::Itau | Bank::Bradesco) => self.write_400(out),
+            _ => Err(RemessaError::Unsupported { banco: self.beneficiario.banco, format }),
+        }
+    }
+
+    fn options(&self) -> WriteOptions {
+        let policy = WritePolicy::DEFAULT.uppercase().strip_accents().truncate();
+        #[cfg(feature = "translit")]
+        let policy = policy.normalize();
+        WriteOptions::default().with_policy(policy).with_check_digits(self.beneficiario.banco)
+    }
+
+    /// Nosso número do título na regra do banco.
+    fn nosso_numero(&self, index: usize, titulo: &Titulo) -> Result<String, RemessaError> {
+        let rule = self.beneficiario.banco.preset().nosso_numero;
+        rule.format(&self.beneficiario.prefixo_nosso_numero, titulo.nosso_numero)
+            .map_err(|source| RemessaError::NossoNumero { index, source })
+    }
+
+    fn write_240<W: Write>(&self, out: W) -> Result<W, RemessaError> {
+        let b = &self.beneficiario;
+        let preset = b.banco.preset();
+        let width = preset.carteira_240.width();
+        if b.carteira.is_empty() || b.carteira.len() > width || !b.carteira.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(RemessaError::Carteira { banco: b.banco, carteira: b.carteira.clone(), width });
+        }
+        let header = HeaderArquivo {
+            banco: b.banco.codigo(),
+            tipo_inscricao: b.tipo_inscricao,
+            numero_inscricao: b.inscricao,
+            convenio: b.convenio.clone(),
+            agencia: b.agencia,
+            agencia_dv: b.agencia_dv.clone(),
+            conta: b.conta,
+            conta_dv: b.conta_dv.clone(),
+            nome_empresa: b.nome.clone(),
+            nome_banco: nome_banco(b.banco).into(),
+            codigo_remessa_retorno: 1,
+            data_geracao: self.data_geracao,
+            hora_geracao: self.hora_geracao,
+            sequencial_arquivo: self.sequencial_arquivo,
+            versao_layout: self.versao_layout.0,
+            densidade: 1600,
+            ..Default::default()
+        };
+        let mut writer = FileWriter::with_options(out, header, self.options())?;
+        writer.begin_lote(&HeaderLote {
+            operacao: "R".into(),
+            servico: 1,
+            versao_layout: self.versao_layout.1,
+            tipo_inscricao: b.tipo_inscricao,
+            numero_inscricao: b.inscricao,
+            convenio: b.convenio.clone(),
+            agencia: b.agencia,
+            agencia_dv: b.agencia_dv.clone(),
+            conta: b.conta,
+            conta_dv: b.conta_dv.clone(),
+            nome_empresa: b.nome.clone(),
+            numero_remessa_retorno: self.sequencial_arquivo,
+            data_gravacao: self.data_geracao,
+            ..Default::default()
+        })?;
+
+        // Carteira na posição 58 (padrão) ou dentro do campo do nosso número
+        let carteira_padrao = preset.carteira_240 == FieldPos::new_const(58, 58);
+        for (index, titulo) in self.titulos.iter().enumerate() {
+            let mut identificacao = [b' '; 20];
+            let mut place = |pos: FieldPos, text: &str| {
+                let start = pos.start() - 38;
+                let text = format!("{text:0>width$}", width = pos.width());
+                identificacao[start..start + text.len()].copy_from_slice(text.as_bytes());
+            };
+            if !carteira_padrao {
+                place(preset.carteira_240, &b.carteira);
+            }
+            place(preset.nosso_numero_240, &self.nosso_numero(index, titulo)?);
+            // Zeros entre a carteira e o nosso número, brancos depois dele
+            let end = preset.nosso_numero_240.end() - 37;
+            identificacao[..end].iter_mut().filter(|b| **b == b' ').for_each(|b| *b = b'0');
+
+            writer.append(&SegmentoP {
+                segmento: "P".into(),
+                codigo_movimento: 1,
+                agencia: b.agencia,
+                agencia_dv: b.agencia_dv.clone(),
+                conta: b.conta,
+                conta_dv: b.conta_dv.clone(),
+                nosso_numero: String::from_utf8_lossy(&identificacao).into_owned(),
+                carteira: if carteira_padrao { b.carteira.parse().unwrap_or(1) } else { 1 },
+                forma_cadastramento: 1,
+                tipo_documento: "1".into(),
+                emissao_boleto: 2,
+                distribuicao_boleto: "2".into(),
+                numero_documento: titulo.seu_numero.clone(),
+                vencimento: titulo.vencimento,
+                valor_titulo: titulo.valor,
+                especie: titulo.especie,
+                aceite: "N".into(),
+                data_emissao: titulo.emissao,
+                codigo_juros: if titulo.juros_dia > 0 { 1 } else { 3 },
+                data_juros: if titulo.juros_dia > 0 { titulo.vencimento } else { 0 },
+                juros_mora: titulo.juros_dia,
+                uso_empresa: titulo.seu_numero.clone(),
+                codigo_protesto: 3,
+                codigo_baixa: 2,
+                codigo_moeda: 9,
+                ..Default::default()
+            })?;
+            let pagador = &titulo.pagador;
+            writer.append(&SegmentoQ {
+                segmento: "Q".into(),
+                codigo_movimento: 1,
+                pagador_tipo_inscricao: pagador.tipo_inscricao,
+                pagador_inscricao: pagador.inscricao,
+                pagador_nome: pagador.nome.clone(),
+                pagador_endereco: pagador.endereco.clone(),
+                pagador_bairro: pagador.bairro.clone(),
+                pagador_cep: pagador.cep / 1000,
+                pagador_cep_sufixo: (pagador.cep % 1000) as u16,
+                pagador_cidade: pagador.cidade.clone(),
+                pagador_uf: pagador.uf.clone(),
+                ..Default::default()
+            })?;
+            if titulo.multa > 0 || !titulo.mensagem.is_empty() {
+                writer.append(&SegmentoR {
+                    segmento: "R".into(),
+                    codigo_movimento: 1,
+                    codigo_multa: if titulo.multa > 0 { 1 } else { 0 },
+                    data_multa: if titulo.multa > 0 { titulo.vencimento } else { 0 },
+                    multa: titulo.multa,
+                    mensagem_3: titulo.mensagem.clone(),
+                    ..Default::default()
+                })?;
+            }
+        }
+        writer.end_lote::<TrailerLote>()?;
+        Ok(writer.finish()?)
+    }
+
+    fn write_400<W: Write>(&self, out: W) -> Result<W, RemessaError> {
+        let b = &self.beneficiario;
+        let header = cobranca400::HeaderArquivo {
+            operacao: 1,
+            literal_operacao: "REMESSA".into(),
+            servico: 1,
+            literal_servico: "COBRANCA".into(),
+            codigo_empresa: b.convenio.clone(),
+            nome_empresa: b.nome.clone(),
+            banco: b.banco.codigo(),
+            nome_banco: nome_banco(b.banco).into(),
+            data_gravacao: ddmmaa(self.data_geracao),
+            ..Default::default()
+        };
+        let mut writer = RemessaWriter::with_options(out, &header, self.options())?;
+        let pos = b.banco.preset().nosso_numero_400.expect("Itaú e Bradesco têm o nosso número no CNAB 400");
+        for (index, titulo) in self.titulos.iter().enumerate() {
+            // O nosso número fica no trecho do banco que começa na posição 63
+            let offset = pos.start() - 63;
+            let nosso_numero = format!("{:offset$}{:0>width$}", "", self.nosso_numero(index, titulo)?, width = pos.width());
+            let pagador = &titulo.pagador;
+            writer.append(&RemessaTipo1 {
+                tipo_registro: 1,
+                uso_empresa: titulo.seu_numero.clone(),
+                reservado_banco_2: nosso_numero,
+                codigo_ocorrencia: 1,
+                numero_documento: titulo.seu_numero.clone(),
+                vencimento: ddmmaa(titulo.vencimento),
+                valor_titulo: titulo.valor,
+                banco_cobrador: b.banco.codigo(),
+                especie: titulo.especie,
+                aceite: "N".into(),
+                data_emissao: ddmmaa(titulo.emissao),
+                juros_mora: titulo.juros_dia,
+                pagador_tipo_inscricao: pagador.tipo_inscricao,
+                pagador_inscricao: pagador.inscricao,
+                pagador_nome: pagador.nome.clone(),
+                pagador_endereco: pagador.endereco.clone(),
+                reservado_banco_3: pagador.bairro.clone(),
+                pagador_cep: pagador.cep,
+                reservado_banco_4: format!("{:15}{:2}", pagador.cidade, pagador.uf),
+                ..Default::default()
+            })?;
+        }
+        Ok(writer.finish()?)
+    }
+}
+
+/// Nome do banco no header do arquivo.
+fn nome_banco(banco: Bank) -> &'static str {
+    match banco {
+        Bank::Itau => "BANCO ITAU SA",
+        Bank::Bradesco => "BRADESCO",
+        Bank::BancoDoBrasil => "BANCO DO BRASIL S.A.",
+        Bank::Caixa => "CAIXA",
+        Bank::Santander => "BANCO SANTANDER",
+        Bank::Sicredi => "SICREDI",
+    }
+}
+
+/// `DDMMAAAA` para o `DDMMAA` do CNAB 400.
+fn ddmmaa(data: u32) -> u32 {
+    data / 10_000 * 100 + data % 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedWidthParse;
+
+    fn beneficiario(banco: Bank) -> Beneficiario {
+        Beneficiario {
+            banco,
+            tipo_inscricao: 2,
+            inscricao: 12_345_678_000_195,
+            nome: "Padaria Pão Quente Ltda".into(),
+            agencia: 1234,
+            agencia_dv: "5".into(),
+            conta: 67_890,
+            conta_dv: "1".into(),
+            convenio: "12345".into(),
+            carteira: "109".into(),
+            prefixo_nosso_numero: "".into(),
+        }
+    }
+
+    fn titulo(nosso_numero: u64, multa: i64) -> Titulo {
+        Titulo {
+            nosso_numero,
+            seu_numero: format!("NF-{nosso_numero}"),
+            valor: 15_000,
+            vencimento: 10112026,
+            emissao: 16102026,
+            especie: 2,
+            multa,
+            pagador: Pagador { tipo_inscricao: 1, nome: "José Gonçalves".into(), cep: 1_310_100, uf: "SP".into(), ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builds_complete_remessas_from_titles() {
+        let builder = RemessaBuilder::new(beneficiario(Bank::Itau), 16102026)
+            .sequencial_arquivo(7)
+            .add_titulo(titulo(1234, 0))
+            .add_titulo(titulo(1235, 300));
+        let remessa = String::from_utf8(builder.build(CnabFormat::Cnab240).unwrap()).unwrap();
+        let lines: Vec<_> = remessa.lines().collect();
+        let tipos: Vec<_> = lines.iter().map(|line| &line[7..8]).collect();
+        assert_eq!(tipos, ["0", "1", "3", "3", "3", "3", "3", "5", "9"]);
+        // DAC da agência/conta, carteira e nosso número do Itaú, sequencial no lote, nome normalizado
+        assert_eq!(&lines[2][..57], "3410001300001P 010123450000000678901010900001234         ");
+        assert_eq!(&lines[3][13..14], "Q");
+        assert_eq!(&lines[3][33..47], "JOSE GONCALVES");
+        assert_eq!(&lines[6][8..14], "00005R");
+        assert_eq!(SegmentoR::parse(lines[6]).unwrap().multa, 300);
+        assert_eq!(&lines[7][17..23], "000007");
+        assert_eq!(&lines[8][17..29], "000001000009");
+        assert_eq!(HeaderArquivo::parse(lines[0]).unwrap().nome_empresa, "PADARIA PAO QUENTE LTDA");
+        // O arquivo gerado passa pela mesma conferência de trailers da leitura
+        assert!(crate::cnab240::parse_retorno_cobranca(remessa.as_bytes()).is_ok());
+
+        let remessa = String::from_utf8(builder.build(CnabFormat::Cnab400).unwrap()).unwrap();
+        let lines: Vec<_> = remessa.lines().collect();
+        assert_eq!(lines.len(), 4);
+        let detalhe = RemessaTipo1::parse(lines[1]).unwrap();
+        assert_eq!((&lines[1][62..70], detalhe.vencimento, detalhe.sequencial), ("00001234", 101126, 2));
+
+        let err = RemessaBuilder::new(beneficiario(Bank::Caixa), 16102026).build(CnabFormat::Cnab400).unwrap_err();
+        assert!(matches!(err, RemessaError::Unsupported { banco: Bank::Caixa, .. }));
+        let err = builder.add_titulo(titulo(1_000_000_000, 0)).build(CnabFormat::Cnab240).unwrap_err();
+        assert!(matches!(err, RemessaError::NossoNumero { index: 2, .. }), "{err}");
+
+        // Carteira maior que as posições do banco é recusada, sem invadir o nosso número
+        for carteira in ["1090", "1090000000000000000000000", "1A9"] {
+            let beneficiario = Beneficiario { carteira: carteira.into(), ..beneficiario(Bank::Itau) };
+            let err = RemessaBuilder::new(beneficiario, 16102026).add_titulo(titulo(1, 0)).build(CnabFormat::Cnab240).unwrap_err();
+            assert!(matches!(err, RemessaError::Carteira { banco: Bank::Itau, width: 3, .. }), "{err}");
+        }
+    }
+}
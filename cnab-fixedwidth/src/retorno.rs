@@ -0,0 +1,135 @@
+//! # Eventos de Retorno
+//!
+//! Modelo de negócio sobre os registros de retorno de cobrança: em vez de interpretar
+//! códigos de movimento em cada aplicação, cada título do retorno vira uma lista de
+//! [`RetornoEvent`] (liquidação, baixa, rejeição, tarifa...).
+//!
+//! Os códigos seguem a tabela de movimento de retorno FEBRABAN 240 (campo
+//! "código de movimento" do segmento T), também usada pela maioria dos bancos no 400.
+
+/// Dados de um título no retorno, já agregados dos segmentos (T + U no CNAB 240).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MovimentoRetorno {
+    /// Código de movimento do retorno (ex: 6 = liquidação).
+    pub codigo_movimento: u8,
+    /// Identificação do título no banco.
+    pub nosso_numero: String,
+    /// Valor pago, em centavos (segmento U).
+    pub valor_pago: i64,
+    /// Valor da tarifa/custas, em centavos (segmento T).
+    pub valor_tarifa: i64,
+    /// Motivos da ocorrência, como no arquivo: até 5 códigos de 2 posições.
+    pub motivos: String,
+    /// Seu número (identificação do título na empresa).
+    pub seu_numero: String,
+    /// Data da ocorrência (`DDMMAAAA`; 0 se não informada).
+    pub data_ocorrencia: u32,
+    /// Data do crédito na conta (`DDMMAAAA`; 0 se não informada).
+    pub data_credito: u32,
+}
+
+impl MovimentoRetorno {
+    /// Códigos de motivo informados, ignorando posições em branco ou zeradas.
+    pub fn motivos(&self) -> Vec<&str> {
+        let bytes = self.motivos.as_bytes();
+        bytes
+            .chunks(2)
+            .filter_map(|c| std::str::from_utf8(c).ok())
+            .map(str::trim)
+            .filter(|c| !c.is_empty() && *c != "00")
+            .collect()
+    }
+}
+
+/// Evento de negócio derivado de um movimento de retorno.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RetornoEvent {
+    /// Entrada do título confirmada pelo banco (02).
+    EntradaConfirmada { nosso_numero: String },
+    /// Entrada do título rejeitada (03), com os códigos de motivo.
+    EntradaRejeitada { nosso_numero: String, motivos: Vec<String> },
+    /// Título pago (06, ou 17 quando liquidado após baixa), valor em centavos e data do
+    /// crédito (`DDMMAAAA`; 0 se não informada).
+    TituloLiquidado { nosso_numero: String, valor_pago: i64, data_credito: u32 },
+    /// Título baixado sem pagamento (09, ou 25 quando protestado e baixado).
+    TituloBaixado { nosso_numero: String },
+    /// Tarifa ou custas debitadas (28, ou tarifa informada em outro movimento), em centavos.
+    TarifaCobrada { nosso_numero: String, valor: i64 },
+    /// Movimento sem evento específico; o código é mantido para tratamento manual.
+    Outro { nosso_numero: String, codigo_movimento: u8 },
+}
+
+impl RetornoEvent {
+    /// Deriva os eventos de um título do retorno.
+    ///
+    /// Um movimento pode gerar mais de um evento: uma liquidação com tarifa gera
+    /// `TituloLiquidado` e `TarifaCobrada`.
+    pub fn from_movimento(movimento: &MovimentoRetorno) -> Vec<RetornoEvent> {
+        let nosso_numero = movimento.nosso_numero.clone();
+        let mut events = Vec::with_capacity(2);
+
+        match movimento.codigo_movimento {
+            2 => events.push(Self::EntradaConfirmada { nosso_numero: nosso_numero.clone() }),
+            3 => events.push(Self::EntradaRejeitada {
+                nosso_numero: nosso_numero.clone(),
+                motivos: movimento.motivos().into_iter().map(String::from).collect(),
+            }),
+            6 | 17 => events.push(Self::TituloLiquidado {
+                nosso_numero: nosso_numero.clone(),
+                valor_pago: movimento.valor_pago,
+                data_credito: movimento.data_credito,
+            }),
+            9 | 25 => events.push(Self::TituloBaixado { nosso_numero: nosso_numero.clone() }),
+            // Débito de tarifas: o evento sai abaixo, a partir do valor da tarifa
+            28 => {}
+            codigo => events.push(Self::Outro { nosso_numero: nosso_numero.clone(), codigo_movimento: codigo }),
+        }
+
+        if movimento.valor_tarifa > 0 {
+            events.push(Self::TarifaCobrada { nosso_numero, valor: movimento.valor_tarifa });
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_events_from_movement_codes() {
+        let liquidacao = MovimentoRetorno {
+            codigo_movimento: 6,
+            nosso_numero: "12345".into(),
+            valor_pago: 10_050,
+            valor_tarifa: 250,
+            data_credito: 16102026,
+            ..Default::default()
+        };
+        assert_eq!(
+            RetornoEvent::from_movimento(&liquidacao),
+            [
+                RetornoEvent::TituloLiquidado { nosso_numero: "12345".into(), valor_pago: 10_050, data_credito: 16102026 },
+                RetornoEvent::TarifaCobrada { nosso_numero: "12345".into(), valor: 250 },
+            ]
+        );
+
+        let rejeicao = MovimentoRetorno {
+            codigo_movimento: 3,
+            nosso_numero: "777".into(),
+            motivos: "0809000000".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            RetornoEvent::from_movimento(&rejeicao),
+            [RetornoEvent::EntradaRejeitada { nosso_numero: "777".into(), motivos: vec!["08".into(), "09".into()] }]
+        );
+
+        let tarifa = MovimentoRetorno { codigo_movimento: 28, valor_tarifa: 180, ..Default::default() };
+        assert_eq!(
+            RetornoEvent::from_movimento(&tarifa),
+            [RetornoEvent::TarifaCobrada { nosso_numero: String::new(), valor: 180 }]
+        );
+    }
+}
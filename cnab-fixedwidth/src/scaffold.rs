@@ -0,0 +1,273 @@
+//! # Geração de Crates de Layout
+//!
+//! Gera o esqueleto de um crate com as structs `#[derive(FixedWidth)]` de um banco a
+//! partir de layouts em tempo de execução (ex: carregados de YAML), para que times de
+//! plataforma produzam crates de layout por banco sempre no mesmo formato:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::scaffold::CrateTemplate;
+//! use std::path::Path;
+//!
+//! # let (header, trailer) = (cnab_fixedwidth::LayoutBuilder::new().build()?, cnab_fixedwidth::LayoutBuilder::new().build()?);
+//! // `header` e `trailer`: layouts carregados com `layout::from_yaml` (feature `yaml`)
+//! let template = CrateTemplate::new("layouts-itau-240")
+//!     .record("HeaderArquivo", header)
+//!     .record("TrailerArquivo", trailer);
+//! template.write_to(Path::new("crates/layouts-itau-240"))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! O crate gerado contém `Cargo.toml`, `README.md` e `src/lib.rs` com uma struct por
+//! registro, documentada com a tabela de campos e com `#[fw_record(gen_tests)]`
+//! (testes de fronteira gerados pela própria macro).
+
+use crate::{Align, FieldKind, FieldSpec, Layout, Overflow, Sign};
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Modelo de um crate de layouts.
+#[derive(Debug, Clone)]
+pub struct CrateTemplate {
+    name: String,
+    description: Option<String>,
+    records: Vec<(String, Layout)>,
+}
+
+impl CrateTemplate {
+    /// Cria o modelo de um crate com o nome informado (ex: "layouts-itau-240").
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), description: None, records: Vec::new() }
+    }
+
+    /// Descrição do crate (Cargo.toml e documentação).
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adiciona um registro, gerado como a struct `struct_name` (ex: "HeaderArquivo").
+    pub fn record(mut self, struct_name: impl Into<String>, layout: Layout) -> Self {
+        self.records.push((struct_name.into(), layout));
+        self
+    }
+
+    /// Arquivos do crate, com caminhos relativos à raiz do crate.
+    pub fn render(&self) -> Vec<(PathBuf, String)> {
+        vec![
+            (PathBuf::from("Cargo.toml"), self.render_manifest()),
+            (PathBuf::from("README.md"), self.render_readme()),
+            (PathBuf::from("src/lib.rs"), self.render_lib()),
+        ]
+    }
+
+    /// Grava os arquivos do crate em `dir`, criando os diretórios necessários.
+    pub fn write_to(&self, dir: &Path) -> io::Result<()> {
+        for (path, content) in self.render() {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+        Ok(())
+    }
+
+    fn description_or_default(&self) -> String {
+        self.description.clone().unwrap_or_else(|| format!("Layouts CNAB do crate {}", self.name))
+    }
+
+    fn render_manifest(&self) -> String {
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2024\"\ndescription = \"{}\"\n\n\
+             [dependencies]\ncnab-fixedwidth = \"{}\"\n",
+            self.name,
+            self.description_or_default().replace('"', "\\\""),
+            env!("CARGO_PKG_VERSION"),
+        )
+    }
+
+    fn render_readme(&self) -> String {
+        let mut out = format!(
+            "# {}\n\n{}\n\nGenerated from layout definitions with `cnab_fixedwidth::scaffold`.\n",
+            self.name,
+            self.description_or_default()
+        );
+        for (name, layout) in &self.records {
+            let _ = write!(out, "\n## {name}\n\n");
+            if let Some(width) = layout.width() {
+                let _ = writeln!(out, "Record width: {width}.\n");
+            }
+            out.push_str(&field_table(layout.fields(), ""));
+        }
+        out
+    }
+
+    fn render_lib(&self) -> String {
+        let mut out = format!(
+            "//! {}\n//!\n//! Gerado por `cnab_fixedwidth::scaffold`.\n\nuse cnab_fixedwidth::FixedWidth;\n",
+            self.description_or_default()
+        );
+        for (name, layout) in &self.records {
+            out.push('\n');
+            out.push_str(&field_table(layout.fields(), "/// "));
+            out.push_str("#[derive(Debug, Clone, FixedWidth)]\n#[fw_record(gen_tests)]\n");
+            if let Some(width) = layout.width() {
+                let _ = writeln!(out, "#[fw(width = {width})]");
+            }
+            let _ = writeln!(out, "pub struct {name} {{");
+            for field in layout.fields() {
+                let _ = writeln!(out, "    #[fw({})]", field_attributes(field));
+                let _ = writeln!(out, "    pub {}: {},", field_ident(field.name), field_type(field));
+            }
+            out.push_str("}\n");
+        }
+        out
+    }
+}
+
+/// Tabela markdown dos campos, com `prefix` em cada linha (ex: "/// " para doc comments).
+fn field_table(fields: &[FieldSpec], prefix: &str) -> String {
+    let mut out = format!("{prefix}| Campo | Posição | Tipo |\n{prefix}|---|---|---|\n");
+    for field in fields {
+        let _ = writeln!(
+            out,
+            "{prefix}| {} | {}..{} | {} |",
+            field.name,
+            field.pos.start(),
+            field.pos.end(),
+            field_attributes(field).split(", ").nth(1).unwrap_or_default(),
+        );
+    }
+    out
+}
+
+/// Atributos `#[fw(...)]` do campo.
+fn field_attributes(field: &FieldSpec) -> String {
+    let kind = match field.kind {
+        FieldKind::Alpha => "alpha".to_string(),
+        FieldKind::Numeric => "numeric".to_string(),
+        FieldKind::Decimal { scale } => format!("decimal = {scale}"),
+        FieldKind::Zoned { scale } => format!("zoned = {scale}"),
+        FieldKind::Packed { scale } => format!("packed = {scale}"),
+        FieldKind::Filler => "filler".to_string(),
+    };
+    let mut attrs = format!("pos = \"{}..{}\", {kind}", field.pos.start(), field.pos.end());
+    match field.sign {
+        Sign::Unsigned => {}
+        Sign::Inline => attrs.push_str(", signed"),
+        Sign::Indicator { pos, credit, debit } => {
+            let _ = write!(attrs, ", sign_pos = \"{}..{}\", credit = \"{credit}\"", pos.start(), pos.end());
+            if debit != "D" {
+                let _ = write!(attrs, ", debit = \"{debit}\"");
+            }
+        }
+    }
+    if let Some(pad) = field.pad {
+        let _ = write!(attrs, ", pad = {:?}", pad as char);
+    }
+    match field.align {
+        Some(Align::Left) => attrs.push_str(", align = \"left\""),
+        Some(Align::Right) => attrs.push_str(", align = \"right\""),
+        None => {}
+    }
+    if let Some(charset) = field.charset {
+        let _ = write!(attrs, ", charset = {charset:?}");
+    }
+    if let Some(policy) = field.policy {
+        if policy.on_overflow == Overflow::Truncate {
+            attrs.push_str(", truncate");
+        }
+        if policy.uppercase {
+            attrs.push_str(", uppercase");
+        }
+        if policy.strip_accents {
+            attrs.push_str(", strip_accents");
+        }
+        #[cfg(feature = "translit")]
+        if policy.normalize {
+            attrs.push_str(", normalize");
+        }
+    }
+    attrs
+}
+
+/// Tipo Rust do campo: inteiros pelo número de dígitos, decimais como `f64`.
+fn field_type(field: &FieldSpec) -> String {
+    let signed = !matches!(field.sign, Sign::Unsigned);
+    let ty = match field.kind {
+        FieldKind::Alpha => "String",
+        FieldKind::Numeric | FieldKind::Packed { scale: 0 } => {
+            let digits = match field.kind {
+                // Cada byte compactado guarda dois dígitos (o último, um dígito e o sinal)
+                FieldKind::Packed { .. } => field.pos.width() * 2 - 1,
+                _ => field.pos.width(),
+            };
+            match (signed || matches!(field.kind, FieldKind::Packed { .. }), digits) {
+                (false, 0..=9) => "u32",
+                (false, _) => "u64",
+                (true, 0..=9) => "i32",
+                (true, _) => "i64",
+            }
+        }
+        FieldKind::Decimal { .. } | FieldKind::Zoned { .. } | FieldKind::Packed { .. } => "f64",
+        FieldKind::Filler => "()",
+    };
+    if field.optional && !matches!(field.kind, FieldKind::Filler) {
+        format!("Option<{ty}>")
+    } else {
+        ty.to_string()
+    }
+}
+
+/// Nome do campo como identificador Rust válido (snake_case, sem palavras reservadas).
+fn field_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    const KEYWORDS: &[&str] = &[
+        "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl",
+        "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "static", "struct",
+        "super", "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn", "gen",
+    ];
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.insert_str(0, "r#");
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LayoutBuilder;
+
+    #[test]
+    fn renders_crate_with_derived_structs() {
+        let header = LayoutBuilder::new()
+            .width(20)
+            .field("codigo_banco", 1, 3, FieldKind::Numeric)
+            .field("Nome Empresa", 4, 13, FieldKind::Alpha)
+            .optional_field("valor", 14, 18, FieldKind::Decimal { scale: 2 })
+            .field("type", 19, 20, FieldKind::Filler)
+            .build()
+            .unwrap();
+
+        let files = CrateTemplate::new("layouts-teste").record("HeaderArquivo", header).render();
+        let file = |name: &str| &files.iter().find(|(path, _)| path == Path::new(name)).unwrap().1;
+
+        assert!(file("Cargo.toml").contains("name = \"layouts-teste\""));
+        assert!(file("Cargo.toml").contains(&format!("cnab-fixedwidth = \"{}\"", env!("CARGO_PKG_VERSION"))));
+        assert!(file("README.md").contains("| codigo_banco | 1..3 | numeric |"));
+
+        let lib = file("src/lib.rs");
+        assert!(lib.contains("#[fw_record(gen_tests)]\n#[fw(width = 20)]\npub struct HeaderArquivo {"));
+        assert!(lib.contains("    #[fw(pos = \"1..3\", numeric)]\n    pub codigo_banco: u32,"));
+        assert!(lib.contains("    pub nome_empresa: String,"));
+        assert!(lib.contains("    #[fw(pos = \"14..18\", decimal = 2)]\n    pub valor: Option<f64>,"));
+        assert!(lib.contains("    pub r#type: (),"));
+    }
+}
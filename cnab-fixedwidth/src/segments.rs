@@ -0,0 +1,224 @@
+//! # Agrupamento de Segmentos
+//!
+//! No CNAB 240 um título ocupa vários registros de detalhe consecutivos: P, Q e R na
+//! remessa, T e U no retorno. [`SegmentGroups`] lê o arquivo e entrega cada título como um
+//! [`SegmentGroup`], conferindo lote, sequenciais e segmentos obrigatórios; os tipos
+//! compostos (ex: [`crate::cnab240::TituloRetorno`]) são montados a partir do grupo.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::cnab240::TituloRetorno;
+//! use cnab_fixedwidth::segments::{Pairing, SegmentGroups};
+//! use std::io::BufReader;
+//!
+//! let file = std::fs::File::open("retorno.ret")?;
+//! let groups = SegmentGroups::new(BufReader::new(file), Pairing::RETORNO_COBRANCA);
+//! for group in groups {
+//!     let titulo = TituloRetorno::from_group(&group?)?;
+//!     println!("{} pago {}", titulo.t.nosso_numero, titulo.u.valor_pago);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::reader::{Framing, RecordReader};
+use crate::{FixedWidthError, FixedWidthParse, ParseOptions, Result};
+use std::io::BufRead;
+
+/// Segmentos que compõem um título.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pairing {
+    /// Segmento que abre o título.
+    pub first: u8,
+    /// Segmentos que o título precisa ter (incluindo o primeiro).
+    pub required: &'static [u8],
+}
+
+impl Pairing {
+    /// Remessa de cobrança: P e Q obrigatórios, R (e outros) opcionais.
+    pub const REMESSA_COBRANCA: Pairing = Pairing { first: b'P', required: b"PQ" };
+    /// Retorno de cobrança: T e U obrigatórios, Y (e outros) opcionais.
+    pub const RETORNO_COBRANCA: Pairing = Pairing { first: b'T', required: b"TU" };
+}
+
+/// Um registro de detalhe do grupo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Letra do segmento (posição 14).
+    pub segmento: u8,
+    /// Número do registro no arquivo.
+    pub line_number: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// Registros de detalhe consecutivos de um mesmo título.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentGroup {
+    pub lote: u16,
+    /// Segmentos na ordem do arquivo; o primeiro é o [`Pairing::first`].
+    pub segments: Vec<Segment>,
+}
+
+impl SegmentGroup {
+    /// Número do registro do primeiro segmento.
+    pub fn line_number(&self) -> u64 {
+        self.segments.first().map_or(0, |segment| segment.line_number)
+    }
+
+    /// Registro de um segmento, se o título o tem.
+    pub fn get(&self, segmento: u8) -> Option<&Segment> {
+        self.segments.iter().find(|segment| segment.segmento == segmento)
+    }
+
+    /// Faz o parse de um segmento, se o título o tem; erros vêm com o número do registro.
+    pub fn parse<T: FixedWidthParse>(&self, segmento: u8) -> Result<Option<T>> {
+        let options = ParseOptions::default();
+        self.get(segmento)
+            .map(|segment| {
+                T::parse_bytes(&segment.bytes, &options)
+                    .map_err(|e| e.at_line_with(segment.line_number, &segment.bytes, &[], options.redaction))
+            })
+            .transpose()
+    }
+
+    /// Como [`parse`](Self::parse), para segmentos obrigatórios.
+    pub fn require<T: FixedWidthParse>(&self, segmento: u8) -> Result<T> {
+        self.parse(segmento)?.ok_or_else(|| FixedWidthError::Structure {
+            line_number: self.line_number(),
+            message: format!("título sem o segmento {}", segmento as char),
+        })
+    }
+}
+
+/// Lê um arquivo CNAB 240 e agrupa os segmentos de cada título.
+///
+/// Headers e trailers são ignorados. Um título começa no segmento [`Pairing::first`] e
+/// vai até o próximo título, registro que não seja de detalhe ou fim do arquivo. Resultam
+/// em `FixedWidthError::Structure`: segmento antes do primeiro do título, segmento
+/// repetido, sequencial que não segue o anterior, mudança de lote no meio do título e
+/// título sem um segmento obrigatório. Depois de um erro, a leitura termina.
+pub struct SegmentGroups<R: BufRead> {
+    reader: RecordReader<R>,
+    pairing: Pairing,
+    buf: Vec<u8>,
+    /// Grupo em montagem e sequencial do último segmento.
+    current: Option<(SegmentGroup, u32)>,
+    done: bool,
+}
+
+impl<R: BufRead> SegmentGroups<R> {
+    pub fn new(reader: R, pairing: Pairing) -> Self {
+        Self {
+            reader: RecordReader::new(reader, Framing::FixedLength(240)),
+            pairing,
+            buf: Vec::with_capacity(240),
+            current: None,
+            done: false,
+        }
+    }
+
+    fn close(&mut self) -> Result<Option<SegmentGroup>> {
+        let Some((group, _)) = self.current.take() else { return Ok(None) };
+        if let Some(&missing) = self.pairing.required.iter().find(|&&s| group.get(s).is_none()) {
+            let message = format!("título sem o segmento {}", missing as char);
+            return Err(FixedWidthError::Structure { line_number: group.line_number(), message });
+        }
+        Ok(Some(group))
+    }
+
+    fn read(&mut self) -> Result<Option<SegmentGroup>> {
+        loop {
+            if !self.reader.read_record(&mut self.buf)? {
+                self.done = true;
+                return self.close();
+            }
+            let line_number = self.reader.records_read();
+            if self.buf.get(7) != Some(&b'3') {
+                match self.close()? {
+                    Some(group) => return Ok(Some(group)),
+                    None => continue,
+                }
+            }
+            let structure = |message: String| FixedWidthError::Structure { line_number, message };
+            let number = |range: std::ops::Range<usize>| {
+                let digits = self.buf.get(range).unwrap_or_default();
+                std::str::from_utf8(digits).ok().and_then(|text| text.parse::<u32>().ok())
+            };
+            let (Some(lote), Some(sequencial), Some(&segmento)) = (number(3..7), number(8..13), self.buf.get(13)) else {
+                return Err(structure("lote, sequencial ou segmento inválido".to_string()));
+            };
+            let segment = Segment { segmento, line_number, bytes: self.buf.clone() };
+
+            if segmento == self.pairing.first {
+                let closed = self.close()?;
+                let group = SegmentGroup { lote: lote as u16, segments: vec![segment] };
+                self.current = Some((group, sequencial));
+                match closed {
+                    Some(group) => return Ok(Some(group)),
+                    None => continue,
+                }
+            }
+            let first = self.pairing.first as char;
+            let Some((group, last)) = &mut self.current else {
+                return Err(structure(format!("segmento {} sem o segmento {first}", segmento as char)));
+            };
+            if u32::from(group.lote) != lote || sequencial != *last + 1 {
+                let message = format!("segmento {} fora de sequência no título do segmento {first}", segmento as char);
+                return Err(structure(message));
+            }
+            if group.get(segmento).is_some() {
+                return Err(structure(format!("segmento {} repetido no título", segmento as char)));
+            }
+            group.segments.push(segment);
+            *last = sequencial;
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SegmentGroups<R> {
+    type Item = Result<SegmentGroup>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let group = self.read();
+        if group.is_err() {
+            self.done = true;
+        }
+        group.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tipo: &str, sequencial: &str, segmento: &str) -> String {
+        format!("3410001{tipo}{sequencial}{segmento}{}", " ".repeat(226))
+    }
+
+    #[test]
+    fn groups_consecutive_segments_of_a_title() {
+        let file = [
+            record("1", "00000", " "),
+            record("3", "00001", "P"),
+            record("3", "00002", "Q"),
+            record("3", "00003", "R"),
+            record("3", "00004", "P"),
+            record("3", "00005", "Q"),
+            record("5", "00000", " "),
+        ]
+        .join("\n");
+        let groups: Vec<_> = SegmentGroups::new(file.as_bytes(), Pairing::REMESSA_COBRANCA).collect::<Result<_>>().unwrap();
+        let segmentos: Vec<Vec<u8>> = groups.iter().map(|g| g.segments.iter().map(|s| s.segmento).collect()).collect();
+        assert_eq!(segmentos, [b"PQR".to_vec(), b"PQ".to_vec()]);
+        assert_eq!((groups[1].lote, groups[1].line_number(), groups[1].get(b'R')), (1, 5, None));
+
+        let broken = |lines: &[String]| SegmentGroups::new(lines.join("\n").as_bytes(), Pairing::REMESSA_COBRANCA).last().unwrap();
+        let err = broken(&[record("3", "00001", "P"), record("3", "00002", "R")]).unwrap_err();
+        assert_eq!(err.to_string(), "linha 1: estrutura do arquivo inválida: título sem o segmento Q");
+        let err = broken(&[record("3", "00001", "Q")]).unwrap_err();
+        assert_eq!(err.to_string(), "linha 1: estrutura do arquivo inválida: segmento Q sem o segmento P");
+        let err = broken(&[record("3", "00001", "P"), record("3", "00003", "Q")]).unwrap_err();
+        assert!(matches!(err, FixedWidthError::Structure { line_number: 2, .. }), "{err}");
+    }
+}
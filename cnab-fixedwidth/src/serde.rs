@@ -0,0 +1,815 @@
+//! # Serde (feature `serde`)
+//!
+//! Structs já modeladas com `#[derive(Deserialize, Serialize)]` podem ser lidas e
+//! escritas com um layout, sem a macro `FixedWidth`: os campos do layout são casados
+//! pelo nome com os campos da struct.
+//!
+//! ```no_run
+//! use cnab_fixedwidth::{FieldKind, LayoutBuilder};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Header {
+//!     codigo_banco: u16,
+//!     nome_empresa: String,
+//!     valor: f64,
+//!     data_credito: Option<u32>,
+//! }
+//!
+//! let layout = LayoutBuilder::new()
+//!     .width(240)
+//!     .field("codigo_banco", 1, 3, FieldKind::Numeric)
+//!     .field("nome_empresa", 73, 102, FieldKind::Alpha)
+//!     .field("valor", 103, 117, FieldKind::Decimal { scale: 2 })
+//!     .optional_field("data_credito", 118, 125, FieldKind::Numeric)
+//!     .build()?;
+//!
+//! # let line = "";
+//! let header: Header = cnab_fixedwidth::serde::from_line(line, layout.fields())?;
+//! let line = cnab_fixedwidth::serde::to_line(&header, layout.fields(), 240)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Na leitura, campos numéricos podem ir para inteiros, `f64` ou `String`; decimais,
+//! para `f64`; textos, para `String` ou enums de variantes unitárias (pelo nome da
+//! variante). Campos opcionais em branco são `None`. Campos da struct que não estão no
+//! layout precisam de `#[serde(default)]`.
+//!
+//! Na escrita, cada valor é convertido conforme o tipo do campo no layout (a escala de
+//! `Decimal`, por exemplo); campos da struct fora do layout são ignorados e campos do
+//! layout sem valor ficam em branco.
+//!
+//! Com a feature, os tipos públicos também implementam `Serialize`/`Deserialize`, para
+//! enviar registros e layouts por APIs e filas ou registrá-los em JSON: `Value`,
+//! `DynRecord` (um mapa na ordem do layout), `FieldSpec`, `FieldPos`, `Layout` (validado
+//! de novo na leitura, como no `LayoutBuilder`) e, só na escrita, os erros
+//! (`FixedWidthError` vira `{ kind, message, line_number, field }`).
+
+use crate::layout::{intern, DynRecord};
+use crate::writer::{self, ToValue, WriteOptions, WritePolicy};
+use crate::{
+    parse_line_bytes_with, Align, Encoding, FieldKind, FieldPos, FieldSpec, FixedWidthError, Layout, LayoutBuilder,
+    ParseOptions, Result, Sign, Value,
+};
+use ::serde::de::{self, Deserialize, DeserializeOwned, Deserializer, IntoDeserializer, MapAccess, Visitor};
+use ::serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
+use std::fmt::Display;
+
+impl de::Error for FixedWidthError {
+    fn custom<T: Display>(msg: T) -> Self {
+        FixedWidthError::Serde(msg.to_string())
+    }
+}
+
+impl ser::Error for FixedWidthError {
+    fn custom<T: Display>(msg: T) -> Self {
+        FixedWidthError::Serde(msg.to_string())
+    }
+}
+
+/// Lê uma linha UTF-8 com os campos `fields` para a struct `T`.
+pub fn from_line<T: DeserializeOwned>(line: &str, fields: &[FieldSpec]) -> Result<T> {
+    from_line_bytes_with(line.as_bytes(), fields, &ParseOptions::with_encoding(Encoding::Utf8))
+}
+
+/// Lê uma linha em bytes brutos, com as opções informadas, para a struct `T`.
+pub fn from_line_bytes_with<T: DeserializeOwned>(line: &[u8], fields: &[FieldSpec], options: &ParseOptions) -> Result<T> {
+    from_record(parse_line_bytes_with(line, fields, options)?)
+}
+
+/// Converte um registro já lido (ex: por `Layout::parse`) para a struct `T`.
+pub fn from_record<T: DeserializeOwned>(record: DynRecord) -> Result<T> {
+    T::deserialize(RecordDeserializer { values: record.into_iter() })
+}
+
+/// Escreve `value` como uma linha de texto (UTF-8) de `width` posições.
+pub fn to_line<T: Serialize + ?Sized>(value: &T, fields: &[FieldSpec], width: usize) -> Result<String> {
+    let bytes = to_bytes_with(value, fields, width, &WriteOptions::with_encoding(Encoding::Utf8))?;
+    String::from_utf8(bytes).map_err(|_| FixedWidthError::InvalidUtf8)
+}
+
+/// Escreve `value` como um registro de `width` posições, com as opções informadas.
+pub fn to_bytes_with<T: Serialize + ?Sized>(
+    value: &T,
+    fields: &[FieldSpec],
+    width: usize,
+    options: &WriteOptions,
+) -> Result<Vec<u8>> {
+    let mut serializer = RecordSerializer { fields, values: vec![Value::Null; fields.len()] };
+    value.serialize(&mut serializer)?;
+    writer::write_line(fields, &serializer.values, width, options)
+}
+
+/// Erro de conversão de um valor, associado ao campo.
+fn in_field(field: &'static str, error: FixedWidthError) -> FixedWidthError {
+    match error {
+        FixedWidthError::Serde(message) => FixedWidthError::Conversion { field, message },
+        error => error,
+    }
+}
+
+// --- Leitura ---
+
+/// O registro inteiro, visto como um mapa `nome do campo -> valor`.
+struct RecordDeserializer {
+    values: std::vec::IntoIter<(&'static str, Value)>,
+}
+
+impl<'de> de::Deserializer<'de> for RecordDeserializer {
+    type Error = FixedWidthError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(RecordAccess { values: self.values, value: None })
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct RecordAccess {
+    values: std::vec::IntoIter<(&'static str, Value)>,
+    value: Option<(&'static str, Value)>,
+}
+
+impl<'de> MapAccess<'de> for RecordAccess {
+    type Error = FixedWidthError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some((field, value)) = self.values.next() else { return Ok(None) };
+        self.value = Some((field, value));
+        seed.deserialize(field.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (field, value) = self.value.take().expect("next_value_seed chamado antes de next_key_seed");
+        seed.deserialize(ValueDeserializer(value)).map_err(|e| in_field(field, e))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+/// O valor de um campo.
+struct ValueDeserializer(Value);
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = FixedWidthError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::Alpha(text) => visitor.visit_string(text),
+            Value::Numeric(n) => visitor.visit_i64(n),
+            value @ Value::Decimal { .. } => visitor.visit_f64(value.as_f64().unwrap_or_default()),
+            Value::Null => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    /// Números também podem ir para `String` (ex: códigos numéricos modelados como texto).
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::Numeric(n) => visitor.visit_string(n.to_string()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Enums de variantes unitárias, pelo texto do campo (sem os espaços das pontas).
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.0 {
+            Value::Alpha(text) => visitor.visit_enum(text.trim().to_string().into_deserializer()),
+            Value::Numeric(n) => visitor.visit_enum(n.to_string().into_deserializer()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        bytes byte_buf unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+// --- Escrita ---
+
+/// Recebe os campos de uma struct e guarda o valor de cada campo do layout.
+struct RecordSerializer<'f> {
+    fields: &'f [FieldSpec],
+    values: Vec<Value>,
+}
+
+/// Só structs (e newtypes de structs) viram registros.
+fn not_a_record() -> FixedWidthError {
+    FixedWidthError::Serde("só structs podem ser escritas como registro".to_string())
+}
+
+macro_rules! reject_record {
+    ($($method:ident($($ty:ty),*)),* $(,)?) => {$(
+        fn $method(self, $(_: $ty),*) -> Result<Self::Ok> {
+            Err(not_a_record())
+        }
+    )*};
+}
+
+impl<'a, 'f> ser::Serializer for &'a mut RecordSerializer<'f> {
+    type Ok = ();
+    type Error = FixedWidthError;
+    type SerializeSeq = Impossible<(), FixedWidthError>;
+    type SerializeTuple = Impossible<(), FixedWidthError>;
+    type SerializeTupleStruct = Impossible<(), FixedWidthError>;
+    type SerializeTupleVariant = Impossible<(), FixedWidthError>;
+    type SerializeMap = Impossible<(), FixedWidthError>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), FixedWidthError>;
+
+    reject_record! {
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64),
+        serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64),
+        serialize_f32(f32), serialize_f64(f64), serialize_char(char), serialize_str(&str),
+        serialize_bytes(&[u8]), serialize_none(), serialize_unit(), serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str),
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(not_a_record())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(not_a_record())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(not_a_record())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(not_a_record())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(not_a_record())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(not_a_record())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(not_a_record())
+    }
+}
+
+impl ser::SerializeStruct for &mut RecordSerializer<'_> {
+    type Ok = ();
+    type Error = FixedWidthError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        if let Some(index) = self.fields.iter().position(|f| f.name == key && !matches!(f.kind, FieldKind::Filler)) {
+            let field = &self.fields[index];
+            self.values[index] = value.serialize(ValueSerializer(field.kind)).map_err(|e| in_field(field.name, e))?;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Converte o valor de um campo para `Value`, conforme o tipo do campo no layout.
+struct ValueSerializer(FieldKind);
+
+impl ValueSerializer {
+    fn value(self, value: impl ToValue) -> Result<Value> {
+        Ok(value.to_value(self.0))
+    }
+}
+
+/// Só valores simples cabem em um campo.
+fn not_a_value() -> FixedWidthError {
+    FixedWidthError::Serde("valor composto não cabe em um campo".to_string())
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = FixedWidthError;
+    type SerializeSeq = Impossible<Value, FixedWidthError>;
+    type SerializeTuple = Impossible<Value, FixedWidthError>;
+    type SerializeTupleStruct = Impossible<Value, FixedWidthError>;
+    type SerializeTupleVariant = Impossible<Value, FixedWidthError>;
+    type SerializeMap = Impossible<Value, FixedWidthError>;
+    type SerializeStruct = Impossible<Value, FixedWidthError>;
+    type SerializeStructVariant = Impossible<Value, FixedWidthError>;
+
+    fn serialize_bool(self, value: bool) -> Result<Value> {
+        Err(ser::Error::custom(format!("booleano {value} sem representação no campo")))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Value> {
+        self.value(f64::from(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<Value> {
+        Ok(Value::Alpha(value.to_string()))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Value> {
+        self.value(value)
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<Value> {
+        Err(not_a_value())
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    /// Variantes unitárias são escritas pelo nome (o inverso da leitura).
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Value> {
+        Ok(Value::Alpha(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value> {
+        Err(not_a_value())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(not_a_value())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(not_a_value())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(not_a_value())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(not_a_value())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(not_a_value())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(not_a_value())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(not_a_value())
+    }
+}
+
+// --- Tipos públicos ---
+
+/// Campo como lido de um arquivo ou mensagem: os textos são internados para caber nos
+/// `&'static str` de `FieldSpec` (veja `layout::intern`).
+#[derive(::serde::Deserialize)]
+#[serde(rename = "FieldSpec")]
+struct FieldSpecRepr {
+    name: String,
+    pos: FieldPos,
+    kind: FieldKind,
+    #[serde(default)]
+    optional: bool,
+    #[serde(default)]
+    sign: Sign,
+    #[serde(default)]
+    pad: Option<u8>,
+    #[serde(default)]
+    align: Option<Align>,
+    #[serde(default)]
+    policy: Option<WritePolicy>,
+    #[serde(default)]
+    charset: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for FieldSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = FieldSpecRepr::deserialize(deserializer)?;
+        Ok(FieldSpec {
+            name: intern(&repr.name),
+            pos: repr.pos,
+            kind: repr.kind,
+            optional: repr.optional,
+            sign: repr.sign,
+            pad: repr.pad,
+            align: repr.align,
+            policy: repr.policy,
+            charset: repr.charset.as_deref().map(intern),
+        })
+    }
+}
+
+/// Posição como `{ "start": 1, "end": 3 }`.
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename = "FieldPos")]
+struct PosRepr {
+    start: usize,
+    end: usize,
+}
+
+impl Serialize for FieldPos {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        PosRepr { start: self.start(), end: self.end() }.serialize(serializer)
+    }
+}
+
+/// Posições inválidas (`start` 0 ou `end` antes de `start`) são rejeitadas.
+impl<'de> Deserialize<'de> for FieldPos {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let PosRepr { start, end } = PosRepr::deserialize(deserializer)?;
+        FieldPos::new(start, end).map_err(de::Error::custom)
+    }
+}
+
+/// Sinal com o indicador de crédito como texto próprio (internado na leitura).
+#[derive(::serde::Deserialize)]
+#[serde(rename = "Sign")]
+enum SignRepr {
+    Unsigned,
+    Inline,
+    Indicator {
+        pos: FieldPos,
+        credit: String,
+        #[serde(default = "default_debit")]
+        debit: String,
+    },
+}
+
+fn default_debit() -> String {
+    "D".to_string()
+}
+
+impl<'de> Deserialize<'de> for Sign {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(match SignRepr::deserialize(deserializer)? {
+            SignRepr::Unsigned => Sign::Unsigned,
+            SignRepr::Inline => Sign::Inline,
+            SignRepr::Indicator { pos, credit, debit } => {
+                Sign::Indicator { pos, credit: intern(&credit), debit: intern(&debit) }
+            }
+        })
+    }
+}
+
+/// Registro como mapa `nome -> valor`, na ordem do layout.
+impl Serialize for DynRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (name, value) in self.iter() {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Mantém a ordem das chaves da entrada.
+impl<'de> Deserialize<'de> for DynRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct RecordVisitor;
+
+        impl<'de> Visitor<'de> for RecordVisitor {
+            type Value = DynRecord;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("um mapa de campos e valores")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<DynRecord, A::Error> {
+                let mut values = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((name, value)) = map.next_entry::<String, Value>()? {
+                    values.push((intern(&name), value));
+                }
+                Ok(values.into_iter().collect())
+            }
+        }
+
+        deserializer.deserialize_map(RecordVisitor)
+    }
+}
+
+/// Layout como `{ width, fields, converters }`.
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename = "Layout")]
+struct LayoutRepr {
+    #[serde(default)]
+    width: Option<usize>,
+    fields: Vec<FieldSpec>,
+    /// Pares `(campo, tag)` de `LayoutBuilder::convert`.
+    #[serde(default)]
+    converters: Vec<(String, String)>,
+}
+
+impl Serialize for Layout {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        LayoutRepr {
+            width: self.width(),
+            fields: self.fields().to_vec(),
+            converters: self.converters().map(|(field, tag)| (field.to_string(), tag.to_string())).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Passa pelas validações do `LayoutBuilder` (sobreposição, largura, nomes repetidos).
+impl<'de> Deserialize<'de> for Layout {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let repr = LayoutRepr::deserialize(deserializer)?;
+        let mut builder = LayoutBuilder::new();
+        if let Some(width) = repr.width {
+            builder = builder.width(width);
+        }
+        builder = repr.fields.into_iter().fold(builder, LayoutBuilder::spec);
+        for (field, tag) in &repr.converters {
+            builder = builder.convert(field, tag);
+        }
+        builder.build().map_err(de::Error::custom)
+    }
+}
+
+/// Só para registro e transporte: `kind` é a variante do erro original (sem o contexto
+/// de linha e campo), `message` o texto completo.
+impl Serialize for FixedWidthError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let field = match self {
+            FixedWidthError::Field { field, .. } => Some(*field),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("FixedWidthError", 4)?;
+        state.serialize_field("kind", kind_name(self.root()))?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("line_number", &self.line_number())?;
+        state.serialize_field("field", &field)?;
+        state.end()
+    }
+}
+
+/// Nome da variante do erro.
+fn kind_name(error: &FixedWidthError) -> &'static str {
+    match error {
+        FixedWidthError::LineTooShort { .. } => "LineTooShort",
+        FixedWidthError::WrongLength { .. } => "WrongLength",
+        FixedWidthError::InvalidNumeric { .. } => "InvalidNumeric",
+        FixedWidthError::NumericOverflow { .. } => "NumericOverflow",
+        FixedWidthError::InvalidUtf8 => "InvalidUtf8",
+        FixedWidthError::InvalidAscii { .. } => "InvalidAscii",
+        FixedWidthError::Conversion { .. } => "Conversion",
+        FixedWidthError::ValueTooLong { .. } => "ValueTooLong",
+        FixedWidthError::NegativeUnsigned { .. } => "NegativeUnsigned",
+        FixedWidthError::Unencodable { .. } => "Unencodable",
+        FixedWidthError::InvalidChar { .. } => "InvalidChar",
+        FixedWidthError::CharBoundary { .. } => "CharBoundary",
+        FixedWidthError::WrappedLine { .. } => "WrappedLine",
+        FixedWidthError::UnknownCode { .. } => "UnknownCode",
+        FixedWidthError::UnknownRecordType { .. } => "UnknownRecordType",
+        FixedWidthError::UnknownRecord { .. } => "UnknownRecord",
+        FixedWidthError::Structure { .. } => "Structure",
+        FixedWidthError::Integrity { .. } => "Integrity",
+        FixedWidthError::Validation(_) => "Validation",
+        FixedWidthError::Cancelled { .. } => "Cancelled",
+        FixedWidthError::Field { .. } => "Field",
+        FixedWidthError::Line { .. } => "Line",
+        FixedWidthError::Serde(_) => "Serde",
+        FixedWidthError::Export(_) => "Export",
+        FixedWidthError::Io(_) => "Io",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldPos, LayoutBuilder};
+    use ::serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Especie {
+        DM,
+        DS,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Titulo {
+        banco: u16,
+        nosso_numero: String,
+        especie: Especie,
+        valor: f64,
+        data_pagamento: Option<u32>,
+        #[serde(default, skip_serializing)]
+        observacao: String,
+    }
+
+    #[test]
+    fn reads_and_writes_serde_structs_with_a_layout() {
+        let layout = LayoutBuilder::new()
+            .width(30)
+            .field("banco", 1, 3, FieldKind::Numeric)
+            .field("nosso_numero", 4, 11, FieldKind::Numeric)
+            .field("especie", 12, 13, FieldKind::Alpha)
+            .field("valor", 14, 22, FieldKind::Decimal { scale: 2 })
+            .optional_field("data_pagamento", 23, 30, FieldKind::Numeric)
+            .build()
+            .unwrap();
+
+        let line = "34100012345DM000012550        ";
+        let titulo: Titulo = from_line(line, layout.fields()).unwrap();
+        assert_eq!(
+            titulo,
+            Titulo {
+                banco: 341,
+                nosso_numero: "12345".into(),
+                especie: Especie::DM,
+                valor: 125.5,
+                data_pagamento: None,
+                observacao: String::new(),
+            }
+        );
+
+        let pago = Titulo { data_pagamento: Some(16_102_026), especie: Especie::DS, ..titulo };
+        assert_eq!(to_line(&pago, layout.fields(), 30).unwrap(), "34100012345DS00001255016102026");
+
+        // Valor que não cabe no tipo da struct: erro de conversão no campo
+        let fields = [FieldSpec::new("banco", FieldPos::new_const(1, 3), FieldKind::Alpha)];
+        #[derive(Debug, Deserialize)]
+        struct SoBanco {
+            #[allow(dead_code)]
+            banco: u16,
+        }
+        let err = from_line::<SoBanco>("ABC", &fields).unwrap_err();
+        assert!(matches!(err, FixedWidthError::Conversion { field: "banco", .. }), "{err}");
+        assert!(matches!(to_line(&1_u8, &fields, 3), Err(FixedWidthError::Serde(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn public_types_round_trip_through_json() {
+        let layout = LayoutBuilder::new()
+            .width(20)
+            .field("banco", 1, 3, FieldKind::Numeric)
+            .spec(
+                FieldSpec::new("valor", FieldPos::new_const(4, 12), FieldKind::Decimal { scale: 2 })
+                    .sign_indicator_with(FieldPos::new_const(13, 13), "C", "-"),
+            )
+            .optional_field("nome", 14, 20, FieldKind::Alpha)
+            .convert("nome", "maiusculas")
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&layout).unwrap();
+        let copia: Layout = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&copia).unwrap(), json);
+        assert_eq!(copia.fields()[1].sign, Sign::Indicator { pos: FieldPos::new_const(13, 13), credit: "C", debit: "-" });
+        assert_eq!(copia.converters().collect::<Vec<_>>(), [("nome", "maiusculas")]);
+
+        // Definições inválidas passam pelas mesmas validações do LayoutBuilder
+        let sobreposto = r#"{"fields": [
+            {"name": "a", "pos": {"start": 1, "end": 3}, "kind": "Numeric"},
+            {"name": "b", "pos": {"start": 3, "end": 5}, "kind": "Alpha"}]}"#;
+        assert!(serde_json::from_str::<Layout>(sobreposto).unwrap_err().to_string().contains("sobrepõem"));
+        assert!(serde_json::from_str::<FieldPos>(r#"{"start": 0, "end": 3}"#).is_err());
+
+        let record = layout.parse("341000012550CFULANO ").unwrap();
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"banco":{"Numeric":341},"valor":{"Decimal":{"raw":12550,"scale":2}},"nome":{"Alpha":"FULANO"}}"#);
+        assert_eq!(serde_json::from_str::<DynRecord>(&json).unwrap(), record);
+
+        let err = layout.parse("34X").unwrap_err().at_line(7, b"34X", layout.fields());
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({
+                "kind": "InvalidNumeric",
+                "message": err.to_string(),
+                "line_number": 7,
+                "field": "banco",
+            })
+        );
+    }
+}
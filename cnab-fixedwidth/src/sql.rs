@@ -0,0 +1,231 @@
+//! # Geração de SQL (feature `sql`)
+//!
+//! DDL e INSERTs parametrizados a partir dos campos de um layout, para carregar arquivos
+//! CNAB no Postgres ou SQLite sem driver nenhum no crate:
+//!
+//! | Campo | Coluna |
+//! |---|---|
+//! | `Alpha` | `VARCHAR(n)`, com `n` a largura do campo |
+//! | `Numeric`, `Packed` sem escala | `BIGINT` |
+//! | `Decimal`, `Zoned`, `Packed` com escala | `NUMERIC(precisão, escala)` |
+//!
+//! ```no_run
+//! use cnab_fixedwidth::sql::{self, Dialect, SqlValue};
+//! use cnab_fixedwidth::{DynRecord, FixedWidth, FixedWidthSpec};
+//!
+//! # #[derive(FixedWidth)]
+//! # struct SegmentoT {
+//! #     #[fw(pos = "38..57", alpha)]
+//! #     nosso_numero: String,
+//! # }
+//! # struct Db;
+//! # impl Db {
+//! #     fn execute(&self, _: &str, _: &[SqlValue]) -> Result<(), Box<dyn std::error::Error>> { Ok(()) }
+//! # }
+//! # let (db, records) = (Db, Vec::<cnab_fixedwidth::Result<DynRecord>>::new());
+//! db.execute(&sql::create_table_for::<SegmentoT>("segmento_t"), &[])?;
+//! let insert = sql::insert_for::<SegmentoT>("segmento_t", Dialect::Postgres);
+//! for record in records {
+//!     db.execute(&insert, &sql::params(SegmentoT::spec(), &record?))?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Fillers ficam de fora e só campos `optional` aceitam `NULL`. Decimais vão como texto
+//! (`"10.50"`, sem passar por `f64`) e o INSERT converte com `CAST(... AS NUMERIC(p,s))`.
+//! Com a feature `sqlx`, [`bind`] faz o bind dos valores numa `sqlx::query`.
+
+use crate::{DynRecord, FieldKind, FieldSpec, FixedWidthSpec, Value};
+
+/// Estilo dos parâmetros do INSERT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// `$1, $2, ...`
+    #[default]
+    Postgres,
+    /// `?, ?, ...`
+    Sqlite,
+}
+
+/// Valor de um parâmetro do INSERT, na representação usada no bind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlValue {
+    Null,
+    Text(String),
+    Integer(i64),
+    /// Decimal como texto, com exatamente `scale` casas (ex: `"10.50"`).
+    Numeric(String),
+}
+
+/// Tipo da coluna do campo (`None` para fillers).
+pub fn column_type(field: &FieldSpec) -> Option<String> {
+    let numeric = |digits: usize, scale: u8| format!("NUMERIC({}, {scale})", digits.max(usize::from(scale)).max(1));
+    match field.kind {
+        FieldKind::Alpha => Some(format!("VARCHAR({})", field.pos.width())),
+        FieldKind::Numeric | FieldKind::Packed { scale: 0 } => Some("BIGINT".to_string()),
+        FieldKind::Decimal { scale } | FieldKind::Zoned { scale } => Some(numeric(field.pos.width(), scale)),
+        // Dois dígitos por byte, menos o meio byte do sinal
+        FieldKind::Packed { scale } => Some(numeric(field.pos.width() * 2 - 1, scale)),
+        FieldKind::Filler => None,
+    }
+}
+
+/// `CREATE TABLE` com uma coluna por campo (sem os fillers), na ordem do layout.
+pub fn create_table(table: &str, fields: &[FieldSpec]) -> String {
+    let columns: Vec<_> = columns(fields)
+        .map(|(field, ty)| format!("    {} {ty}{}", ident(field.name), if field.optional { "" } else { " NOT NULL" }))
+        .collect();
+    format!("CREATE TABLE {table} (\n{}\n);", columns.join(",\n"))
+}
+
+/// [`create_table`] com os campos de uma struct derivada.
+pub fn create_table_for<T: FixedWidthSpec>(table: &str) -> String {
+    create_table(table, T::spec())
+}
+
+/// `INSERT` parametrizado, com os parâmetros na ordem de [`params`].
+pub fn insert(table: &str, fields: &[FieldSpec], dialect: Dialect) -> String {
+    let (names, placeholders): (Vec<_>, Vec<_>) = columns(fields)
+        .enumerate()
+        .map(|(i, (field, ty))| {
+            let placeholder = match dialect {
+                Dialect::Postgres => format!("${}", i + 1),
+                Dialect::Sqlite => "?".to_string(),
+            };
+            let placeholder = match field.kind {
+                FieldKind::Decimal { .. } | FieldKind::Zoned { .. } | FieldKind::Packed { scale: 1.. } => {
+                    format!("CAST({placeholder} AS {ty})")
+                }
+                _ => placeholder,
+            };
+            (ident(field.name), placeholder)
+        })
+        .unzip();
+    format!("INSERT INTO {table} ({}) VALUES ({});", names.join(", "), placeholders.join(", "))
+}
+
+/// [`insert`] com os campos de uma struct derivada.
+pub fn insert_for<T: FixedWidthSpec>(table: &str, dialect: Dialect) -> String {
+    insert(table, T::spec(), dialect)
+}
+
+/// Parâmetros do INSERT para um registro, buscados pelo nome do campo; campos ausentes
+/// do registro são `NULL`.
+pub fn params(fields: &[FieldSpec], record: &DynRecord) -> Vec<SqlValue> {
+    columns(fields)
+        .map(|(field, _)| match (field.kind, record.get(field.name)) {
+            (FieldKind::Numeric | FieldKind::Packed { scale: 0 }, Some(Value::Decimal { raw, .. })) => {
+                SqlValue::Integer(*raw)
+            }
+            (_, Some(Value::Alpha(text))) => SqlValue::Text(text.clone()),
+            (_, Some(Value::Numeric(n))) => SqlValue::Integer(*n),
+            (_, Some(Value::Decimal { raw, scale })) => {
+                let mut text = String::new();
+                crate::json::write_decimal(&mut text, *raw, *scale);
+                SqlValue::Numeric(text)
+            }
+            _ => SqlValue::Null,
+        })
+        .collect()
+}
+
+/// Faz o bind dos parâmetros de [`params`] numa query do `sqlx` montada com [`insert`].
+///
+/// ```no_run
+/// # use cnab_fixedwidth::{DynRecord, Layout};
+/// # async fn exemplo<'c, DB, E>(pool: E, layout: &Layout, record: &DynRecord) -> Result<(), sqlx::Error>
+/// # where
+/// #     DB: sqlx::Database,
+/// #     E: sqlx::Executor<'c, Database = DB>,
+/// #     for<'q> Option<i64>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+/// #     for<'q> Option<String>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+/// #     for<'q> <DB as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, DB>,
+/// # {
+/// use cnab_fixedwidth::sql::{self, Dialect};
+///
+/// let insert = sql::insert("detalhe", layout.fields(), Dialect::Postgres);
+/// sql::bind(sqlx::query(&insert), layout.fields(), record).execute(pool).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "sqlx")]
+pub fn bind<'q, DB>(
+    mut query: sqlx::query::Query<'q, DB, <DB as sqlx::Database>::Arguments<'q>>,
+    fields: &[FieldSpec],
+    record: &DynRecord,
+) -> sqlx::query::Query<'q, DB, <DB as sqlx::Database>::Arguments<'q>>
+where
+    DB: sqlx::Database,
+    Option<i64>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<String>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+{
+    for value in params(fields, record) {
+        query = match value {
+            SqlValue::Null => query.bind(None::<String>),
+            SqlValue::Integer(n) => query.bind(Some(n)),
+            SqlValue::Text(text) | SqlValue::Numeric(text) => query.bind(Some(text)),
+        };
+    }
+    query
+}
+
+/// Campos com coluna, com o tipo da coluna.
+fn columns(fields: &[FieldSpec]) -> impl Iterator<Item = (&FieldSpec, String)> {
+    fields.iter().filter_map(|field| column_type(field).map(|ty| (field, ty)))
+}
+
+/// Nome de coluna entre aspas duplas, que valem no Postgres e no SQLite.
+fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedWidth;
+
+    #[derive(FixedWidth, Debug)]
+    #[fw(width = 20)]
+    #[allow(dead_code)]
+    struct Detalhe {
+        #[fw(pos = "1..3", numeric)]
+        banco: u16,
+        #[fw(pos = "4..5", filler)]
+        brancos: (),
+        #[fw(pos = "6..10", alpha)]
+        nome: String,
+        #[fw(pos = "11..17", decimal = 2)]
+        valor: Option<f64>,
+        #[fw(pos = "18..20", numeric)]
+        ocorrencia: u8,
+    }
+
+    #[test]
+    fn generates_ddl_inserts_and_params() {
+        assert_eq!(
+            create_table_for::<Detalhe>("cnab.detalhe"),
+            "CREATE TABLE cnab.detalhe (\n    \"banco\" BIGINT NOT NULL,\n    \"nome\" VARCHAR(5) NOT NULL,\n    \
+             \"valor\" NUMERIC(7, 2),\n    \"ocorrencia\" BIGINT NOT NULL\n);"
+        );
+        assert_eq!(
+            insert_for::<Detalhe>("detalhe", Dialect::Postgres),
+            "INSERT INTO detalhe (\"banco\", \"nome\", \"valor\", \"ocorrencia\") \
+             VALUES ($1, $2, CAST($3 AS NUMERIC(7, 2)), $4);"
+        );
+        assert!(insert_for::<Detalhe>("detalhe", Dialect::Sqlite).ends_with("VALUES (?, ?, CAST(? AS NUMERIC(7, 2)), ?);"));
+
+        let fields = Detalhe::spec();
+        let record = crate::parse_line("341  ANA  0001050006", fields).unwrap();
+        assert_eq!(
+            params(fields, &record),
+            [
+                SqlValue::Integer(341),
+                SqlValue::Text("ANA".into()),
+                SqlValue::Numeric("10.50".into()),
+                SqlValue::Integer(6),
+            ]
+        );
+        let blank = crate::parse_line("341  ANA         006", fields).unwrap();
+        assert_eq!(params(fields, &blank)[2], SqlValue::Null);
+    }
+}
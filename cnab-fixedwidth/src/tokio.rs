@@ -0,0 +1,161 @@
+//! # Leitura Assíncrona
+//!
+//! Versão de [`RecordReader`](crate::RecordReader) sobre `tokio::io::AsyncBufRead`
+//! (feature `tokio`), para arquivos que chegam por SFTP ou object storage sem bloquear o
+//! runtime. O framing é o mesmo ([`Framing`]), e os registros podem ser lidos um a um ou
+//! consumidos como `Stream`:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::tokio::AsyncFixedWidthReader;
+//! use cnab_fixedwidth::{FixedWidth, Framing, ParseOptions};
+//! use futures_util::StreamExt;
+//! use std::pin::pin;
+//! use tokio::io::BufReader;
+//!
+//! # #[derive(FixedWidth)]
+//! # struct RetornoTipo1 {
+//! #     #[fw(pos = "1..1", numeric)]
+//! #     tipo: u8,
+//! # }
+//! # async fn exemplo(arquivo: impl tokio::io::AsyncRead + Unpin) -> cnab_fixedwidth::Result<()> {
+//! let reader = AsyncFixedWidthReader::new(BufReader::new(arquivo), Framing::FixedLength(400));
+//! let mut titulos = pin!(reader.into_stream::<RetornoTipo1>(ParseOptions::default()));
+//! while let Some(titulo) = titulos.next().await {
+//!     let titulo = titulo?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::reader::{check_cancel, CancelToken, Split, Splitter};
+use crate::{FixedWidthParse, Framing, ParseOptions, Result};
+use ::tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use futures_util::Stream;
+
+/// Leitor assíncrono de registros sobre qualquer `AsyncBufRead`.
+#[derive(Debug)]
+pub struct AsyncFixedWidthReader<R> {
+    inner: R,
+    framing: Framing,
+    records: u64,
+    cancel: Option<(CancelToken, u64)>,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncFixedWidthReader<R> {
+    /// Cria um leitor com o framing informado.
+    pub fn new(inner: R, framing: Framing) -> Self {
+        Self { inner, framing, records: 0, cancel: None }
+    }
+
+    /// Verifica `token` a cada `every` registros, como
+    /// [`RecordReader::with_cancel`](crate::RecordReader::with_cancel).
+    pub fn with_cancel(mut self, token: CancelToken, every: u64) -> Self {
+        self.cancel = Some((token, every.max(1)));
+        self
+    }
+
+    /// Quantidade de registros lidos até agora (o número do último registro, 1-based).
+    pub fn records_read(&self) -> u64 {
+        self.records
+    }
+
+    /// Lê o próximo registro em `buf`, como
+    /// [`RecordReader::read_record`](crate::RecordReader::read_record).
+    pub async fn read_record(&mut self, buf: &mut Vec<u8>) -> Result<bool> {
+        buf.clear();
+        check_cancel(&self.cancel, self.records)?;
+        let mut splitter = Splitter::new(self.framing, self.records + 1);
+        let found = loop {
+            let available = self.inner.fill_buf().await?;
+            let (consumed, split) = splitter.feed_into(available, buf);
+            self.inner.consume(consumed);
+            match split {
+                Split::More => {}
+                Split::Record => break true,
+                Split::End => break false,
+                Split::Invalid(e) => return Err(e),
+            }
+        };
+
+        if found {
+            self.records += 1;
+        }
+        Ok(found)
+    }
+
+    /// Lê o próximo registro e faz o parse para uma struct derivada.
+    ///
+    /// Erros de parse saem com o número do registro (`FixedWidthError::Line`).
+    pub async fn next_parsed<T: FixedWidthParse>(&mut self, buf: &mut Vec<u8>, options: &ParseOptions) -> Result<Option<T>> {
+        if !self.read_record(buf).await? {
+            return Ok(None);
+        }
+        Ok(Some(self.parse(buf, options)?))
+    }
+
+    /// Consome o leitor como um `Stream` de registros já convertidos.
+    ///
+    /// Um registro com erro de parse não encerra o stream: os seguintes continuam sendo
+    /// lidos. Erros de leitura (E/S, cancelamento, `WrappedLine`) são devolvidos uma vez e
+    /// encerram o stream.
+    pub fn into_stream<T: FixedWidthParse>(self, options: ParseOptions) -> impl Stream<Item = Result<T>> {
+        let state = Some((self, Vec::new(), options));
+        futures_util::stream::unfold(state, |state| async move {
+            let (mut reader, mut buf, options) = state?;
+            match reader.read_record(&mut buf).await {
+                Ok(true) => {
+                    let record = reader.parse(&buf, &options);
+                    Some((record, Some((reader, buf, options))))
+                }
+                Ok(false) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// Parse do registro atual, com o número do registro nos erros.
+    fn parse<T: FixedWidthParse>(&self, buf: &[u8], options: &ParseOptions) -> Result<T> {
+        T::parse_bytes(buf, options).map_err(|e| e.at_line_with(self.records, buf, &[], options.redaction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixedWidth, FixedWidthError};
+    use futures_util::{FutureExt, StreamExt};
+
+    #[derive(FixedWidth, Debug, PartialEq)]
+    struct Titulo {
+        #[fw(pos = "1..3", numeric)]
+        banco: u16,
+        #[fw(pos = "4..8", alpha)]
+        nome: String,
+    }
+
+    #[test]
+    fn streams_parsed_records() {
+        // Um `&[u8]` nunca fica pendente, então os futures completam na primeira consulta
+        let file: &[u8] = b"341ANA  \r\n001BIA  23XCAU  \r\n";
+        let reader = AsyncFixedWidthReader::new(file, Framing::FixedLength(8));
+        let titulos: Vec<Result<Titulo>> = reader.into_stream(ParseOptions::default()).collect().now_or_never().unwrap();
+
+        assert_eq!(titulos.len(), 3);
+        assert_eq!(titulos[0].as_ref().unwrap(), &Titulo { banco: 341, nome: "ANA".to_string() });
+        assert_eq!(titulos[1].as_ref().unwrap(), &Titulo { banco: 1, nome: "BIA".to_string() });
+        assert!(matches!(titulos[2], Err(FixedWidthError::Line { line_number: 3, .. })));
+
+        let mut reader = AsyncFixedWidthReader::new(&b"341ANA\n001BIA\n"[..], Framing::Lines);
+        let mut buf = Vec::new();
+        assert!(reader.read_record(&mut buf).now_or_never().unwrap().unwrap());
+        assert_eq!(buf, b"341ANA");
+        assert_eq!(reader.records_read(), 1);
+
+        // Depois de um erro de leitura o stream termina
+        let token = CancelToken::new();
+        token.cancel();
+        let reader = AsyncFixedWidthReader::new(file, Framing::FixedLength(8)).with_cancel(token, 100);
+        let titulos: Vec<Result<Titulo>> = reader.into_stream(ParseOptions::default()).collect().now_or_never().unwrap();
+        assert!(matches!(titulos[..], [Err(FixedWidthError::Cancelled { records: 0 })]));
+    }
+}
@@ -0,0 +1,150 @@
+//! # Ferramentas para arquivos existentes
+//!
+//! Utilitários que alteram arquivos já gerados sem remontá-los a partir dos registros:
+//! apenas os campos envolvidos são reescritos, o resto dos bytes fica intacto.
+
+use crate::writer::{self, WriteOptions};
+use crate::{Encoding, FieldSpec, FixedWidthError, LayoutRegistry, Result, Value};
+
+/// Nome do campo de trailer que recebe a quantidade total de registros do arquivo em
+/// [`renumber`].
+pub const RECORD_COUNT_FIELD: &str = "quantidade_registros";
+
+/// Renumera os registros de uma remessa depois de inserir ou remover linhas.
+///
+/// Em cada registro cujo layout (resolvido em `layout`) tem o campo `sequence_field`,
+/// o campo recebe a posição do registro no arquivo (1, 2, 3...). Campos chamados
+/// [`RECORD_COUNT_FIELD`] recebem a nova quantidade de registros do arquivo.
+///
+/// Quebras de linha (`\r\n` ou `\n`), registros sem layout e todos os outros campos são
+/// preservados byte a byte. Um número que não cabe no campo resulta em `ValueTooLong`.
+pub fn renumber(file: &[u8], layout: &LayoutRegistry, sequence_field: &str) -> Result<Vec<u8>> {
+    let lines = || file.split_inclusive(|&b| b == b'\n');
+    // Linhas vazias (só a quebra) não são registros
+    let total = lines().filter(|line| !crate::trim_line_ending(line).is_empty()).count();
+    let options = WriteOptions::default();
+
+    let mut out = Vec::with_capacity(file.len());
+    let mut sequence = 0;
+    for line in lines() {
+        let content = crate::trim_line_ending(line);
+        if content.is_empty() {
+            out.extend_from_slice(line);
+            continue;
+        }
+        sequence += 1;
+        let mut record = content.to_vec();
+        if let Some(record_layout) = layout.resolve(content) {
+            for field in &record_layout.fields {
+                let value = if field.name == sequence_field {
+                    sequence
+                } else if field.name == RECORD_COUNT_FIELD {
+                    total
+                } else {
+                    continue;
+                };
+                writer::write_field(&mut record, field, &Value::Numeric(value as i64), &options)?;
+            }
+        }
+        out.extend_from_slice(&record);
+        out.extend_from_slice(&line[content.len()..]);
+    }
+    Ok(out)
+}
+
+/// Troca o valor de um campo em uma linha já gerada, sem passar pelo parse do resto.
+///
+/// Só as posições de `spec` são reescritas, com o alinhamento e o preenchimento do campo
+/// (como em [`writer::write_field`]); fillers, áreas não mapeadas e a quebra de linha
+/// ficam como estavam. Veja [`patch_fields`] para vários campos de uma vez.
+pub fn patch_line(line: &str, spec: &FieldSpec, new_value: &Value) -> Result<String> {
+    patch_fields(line, &[(spec, new_value.clone())])
+}
+
+/// Como [`patch_line`], para vários campos da mesma linha.
+///
+/// As posições são de bytes, como no parse de `&str`: textos são escritos em UTF-8 e um
+/// campo que começa ou termina no meio de um caractere multibyte resulta em
+/// `CharBoundary`.
+pub fn patch_fields(line: &str, fields: &[(&FieldSpec, Value)]) -> Result<String> {
+    for (field, _) in fields {
+        let mut bounds = [field.pos.start() - 1, field.pos.end()].into_iter();
+        if let Some(pos) = bounds.find(|&pos| pos < line.len() && !line.is_char_boundary(pos)) {
+            return Err(FixedWidthError::CharBoundary { field: field.name, pos: pos + 1 });
+        }
+    }
+    let mut bytes = line.as_bytes().to_vec();
+    patch_bytes(&mut bytes, fields, &WriteOptions::with_encoding(Encoding::Utf8))?;
+    String::from_utf8(bytes).map_err(|_| FixedWidthError::InvalidUtf8)
+}
+
+/// Reescreve os campos de `fields` nos bytes de um registro, com a codificação e as
+/// políticas de `options`.
+///
+/// A quebra de linha no fim de `line`, se houver, não conta nas posições e é mantida.
+/// Um campo além do fim do registro resulta em `LineTooShort`, sem alterar a linha.
+pub fn patch_bytes(line: &mut [u8], fields: &[(&FieldSpec, Value)], options: &WriteOptions) -> Result<()> {
+    let len = crate::trim_line_ending(line).len();
+    let mut record = line[..len].to_vec();
+    for (field, value) in fields {
+        writer::write_field(&mut record, field, value, options)?;
+    }
+    line[..len].copy_from_slice(&record);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldKind, FieldPos, FieldSpec, FixedWidthError, RecordLayout};
+
+    #[test]
+    fn renumbers_sequence_and_trailer_count() {
+        let sequencial = FieldSpec::new("sequencial", FieldPos::new_const(9, 11), FieldKind::Numeric);
+        let mut registry = LayoutRegistry::new();
+        registry
+            .register(
+                RecordLayout::new(
+                    "trailer",
+                    vec![
+                        FieldSpec::new("quantidade_registros", FieldPos::new_const(2, 4), FieldKind::Numeric),
+                        sequencial.clone(),
+                    ],
+                )
+                .when(FieldPos::new_const(1, 1), "9"),
+            )
+            .register(RecordLayout::new("registro", vec![sequencial]));
+
+        // O título do meio foi removido: sequenciais e quantidade ficaram defasados
+        let file = b"0 BANCO 001\r\n1 TIT A 002\r\n1 TIT C 004\r\n9005    005\r\n";
+        let renumbered = renumber(file, &registry, "sequencial").unwrap();
+        assert_eq!(renumbered, b"0 BANCO 001\r\n1 TIT A 002\r\n1 TIT C 003\r\n9004    004\r\n".to_vec());
+
+        // Sem quebra de linha final, e com um sequencial que não cabe no campo
+        let file = b"1 TIT A 999\n".repeat(1000);
+        let result = renumber(&file, &registry, "sequencial");
+        assert!(matches!(result, Err(FixedWidthError::ValueTooLong { .. })));
+        assert_eq!(renumber(b"1 TIT A 005", &registry, "sequencial").unwrap(), b"1 TIT A 001".to_vec());
+    }
+
+    #[test]
+    fn patches_only_the_given_fields() {
+        let nome = FieldSpec::new("nome", FieldPos::new_const(4, 9), FieldKind::Alpha);
+        let valor = FieldSpec::new("valor", FieldPos::new_const(12, 17), FieldKind::Decimal { scale: 2 });
+
+        // As posições 10..11 não estão no layout e guardam dados do banco
+        let line = "001ANA   XY000150\r\n";
+        assert_eq!(patch_line(line, &nome, &Value::Alpha("JOSÉ".into())).unwrap(), "001JOSÉ XY000150\r\n");
+        let patched = patch_fields(line, &[(&nome, Value::Null), (&valor, Value::Decimal { raw: 2599, scale: 2 })]).unwrap();
+        assert_eq!(patched, "001      XY002599\r\n");
+
+        assert!(matches!(
+            patch_line(line, &nome, &Value::Alpha("MARIA JOSE".into())),
+            Err(FixedWidthError::ValueTooLong { field: "nome", .. })
+        ));
+        let fora = FieldSpec::new("fora", FieldPos::new_const(18, 19), FieldKind::Alpha);
+        assert!(matches!(patch_line(line.trim_end(), &fora, &Value::Null), Err(FixedWidthError::LineTooShort { .. })));
+        let meio = FieldSpec::new("meio", FieldPos::new_const(8, 9), FieldKind::Alpha);
+        assert!(matches!(patch_line("001JOSÉ XY", &meio, &Value::Null), Err(FixedWidthError::CharBoundary { pos: 8, .. })));
+    }
+}
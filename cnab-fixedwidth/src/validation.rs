@@ -0,0 +1,275 @@
+//! # Conferências de Campos
+//!
+//! Regras de negócio simples declaradas no próprio campo, conferidas logo após o parse:
+//!
+//! ```no_run
+//! use cnab_fixedwidth::FixedWidth;
+//!
+//! #[derive(FixedWidth)]
+//! struct SegmentoP {
+//!     #[fw(pos = "16..17", numeric, validate(one_of = "01,02,06,09"))]
+//!     codigo_movimento: u8,
+//!     #[fw(pos = "86..100", decimal = 2, validate(min = 0.01))]
+//!     valor_titulo: f64,
+//!     #[fw(pos = "63..77", alpha, validate(not_blank))]
+//!     numero_documento: String,
+//! }
+//! ```
+//!
+//! Valores fora da regra resultam em `FixedWidthError::Validation`, com o nome do campo,
+//! o valor lido e a regra. Campos opcionais em branco (`None`) só falham em `not_blank`.
+//! Em numéricos não opcionais, branco é lido como zero: use `min = 1` ou `Option<T>`.
+
+use crate::{FixedWidthError, Result, ValidationError};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Número de um campo ou de um limite de `validate`: inteiros são comparados como
+/// inteiros (sem passar por `f64`), e decimais pelo valor exato.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    /// Campos inteiros e limites inteiros.
+    Int(i128),
+    /// Campos `f64` e limites com casas decimais.
+    Float(f64),
+    /// Campos `Decimal`.
+    #[cfg(feature = "rust_decimal")]
+    Decimal(rust_decimal::Decimal),
+}
+
+impl Number {
+    /// Lê um número escrito como texto (ex: os itens de `one_of`).
+    pub fn parse(text: &str) -> Option<Number> {
+        text.parse::<i128>().map(Number::Int).ok().or_else(|| text.parse::<f64>().map(Number::Float).ok())
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(n) => n as f64,
+            Number::Float(n) => n,
+            #[cfg(feature = "rust_decimal")]
+            Number::Decimal(n) => rust_decimal::prelude::ToPrimitive::to_f64(&n).unwrap_or(f64::NAN),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        match (*self, *other) {
+            (Number::Int(a), Number::Int(b)) => Some(a.cmp(&b)),
+            #[cfg(feature = "rust_decimal")]
+            (Number::Decimal(a), Number::Decimal(b)) => Some(a.cmp(&b)),
+            #[cfg(feature = "rust_decimal")]
+            (Number::Decimal(a), Number::Int(b)) => match rust_decimal::Decimal::try_from_i128_with_scale(b, 0) {
+                Ok(b) => Some(a.cmp(&b)),
+                Err(_) => self.as_f64().partial_cmp(&other.as_f64()),
+            },
+            #[cfg(feature = "rust_decimal")]
+            (Number::Int(_), Number::Decimal(_)) => other.partial_cmp(self).map(Ordering::reverse),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(n) => n.fmt(f),
+            Number::Float(n) => n.fmt(f),
+            #[cfg(feature = "rust_decimal")]
+            Number::Decimal(n) => n.fmt(f),
+        }
+    }
+}
+
+macro_rules! impl_number_from_int {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Number {
+            fn from(n: $ty) -> Number {
+                Number::Int(n as i128)
+            }
+        }
+    )*};
+}
+
+impl_number_from_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, i128);
+
+impl From<f64> for Number {
+    fn from(n: f64) -> Number {
+        Number::Float(n)
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl From<rust_decimal::Decimal> for Number {
+    fn from(n: rust_decimal::Decimal) -> Number {
+        Number::Decimal(n)
+    }
+}
+
+/// Valor de campo que pode ser conferido pelas regras de `#[fw(validate(...))]`.
+pub trait FieldValue {
+    /// Valor numérico, para `min`/`max` e `one_of` em números.
+    fn number(&self) -> Option<Number> {
+        None
+    }
+
+    /// Valor como texto; `None` quando não informado (`Option` vazio).
+    fn text(&self) -> Option<String>;
+}
+
+impl FieldValue for str {
+    fn text(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl FieldValue for String {
+    fn text(&self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl<T: FieldValue + ?Sized> FieldValue for &T {
+    fn number(&self) -> Option<Number> {
+        (**self).number()
+    }
+
+    fn text(&self) -> Option<String> {
+        (**self).text()
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn number(&self) -> Option<Number> {
+        self.as_ref().and_then(FieldValue::number)
+    }
+
+    fn text(&self) -> Option<String> {
+        self.as_ref().and_then(FieldValue::text)
+    }
+}
+
+macro_rules! impl_field_value_number {
+    ($($ty:ty),*) => {$(
+        impl FieldValue for $ty {
+            fn number(&self) -> Option<Number> {
+                Some(Number::from(*self))
+            }
+
+            fn text(&self) -> Option<String> {
+                Some(self.to_string())
+            }
+        }
+    )*};
+}
+
+impl_field_value_number!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f64);
+
+#[cfg(feature = "rust_decimal")]
+impl FieldValue for rust_decimal::Decimal {
+    fn number(&self) -> Option<Number> {
+        Some(Number::Decimal(*self))
+    }
+
+    fn text(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+/// Falha de `field` com o valor lido e a regra descumprida.
+pub fn failure<T: FieldValue + ?Sized>(field: &'static str, value: &T, rule: impl Into<String>) -> FixedWidthError {
+    let value = value.text().unwrap_or_default();
+    FixedWidthError::Validation(ValidationError { field, value, rule: rule.into() })
+}
+
+/// `validate(not_blank)`: o valor precisa ser informado e não pode ser só espaços.
+pub fn not_blank<T: FieldValue + ?Sized>(field: &'static str, value: &T) -> Result<()> {
+    match value.text() {
+        Some(text) if !text.trim().is_empty() => Ok(()),
+        _ => Err(failure(field, value, "campo obrigatório em branco")),
+    }
+}
+
+/// `validate(min = .., max = ..)`: o número precisa estar na faixa (limites inclusivos).
+pub fn range<T: FieldValue + ?Sized>(field: &'static str, value: &T, min: Option<Number>, max: Option<Number>) -> Result<()> {
+    let Some(number) = value.number() else { return Ok(()) };
+    if let Some(min) = min
+        && number < min
+    {
+        return Err(failure(field, value, format!("menor que o mínimo {min}")));
+    }
+    if let Some(max) = max
+        && number > max
+    {
+        return Err(failure(field, value, format!("maior que o máximo {max}")));
+    }
+    Ok(())
+}
+
+/// `validate(one_of = "01,02,03")`: o valor precisa ser um dos listados. Números são
+/// comparados pelo valor (`"01"` aceita `1`); textos, sem os espaços das pontas.
+pub fn one_of<T: FieldValue + ?Sized>(field: &'static str, value: &T, allowed: &[&str]) -> Result<()> {
+    let found = match (value.number(), value.text()) {
+        (Some(number), _) => allowed.iter().any(|item| Number::parse(item.trim()).and_then(|item| number.partial_cmp(&item)) == Some(Ordering::Equal)),
+        (None, Some(text)) => allowed.iter().any(|item| item.trim() == text.trim()),
+        (None, None) => true,
+    };
+    if !found {
+        return Err(failure(field, value, format!("fora dos valores aceitos ({})", allowed.join(", "))));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixedWidth, FixedWidthParse};
+
+    #[derive(FixedWidth, Debug)]
+    struct Detalhe {
+        #[fw(pos = "1..2", numeric, validate(one_of = "01,02,06"))]
+        codigo_movimento: u8,
+        #[fw(pos = "3..5", numeric, validate(min = 1, max = 500))]
+        quantidade: u16,
+        #[fw(pos = "6..10", decimal = 2, validate(min = 0.01))]
+        valor: Option<f64>,
+        #[fw(pos = "11..15", alpha, validate(not_blank, one_of = "AB,CD"))]
+        especie: String,
+    }
+
+    #[test]
+    fn checks_declared_rules_after_parsing() {
+        let detalhe = Detalhe::parse("0610000100AB   ").unwrap();
+        assert_eq!((detalhe.codigo_movimento, detalhe.quantidade, detalhe.valor, detalhe.especie.as_str()), (6, 100, Some(1.0), "AB"));
+        assert!(Detalhe::parse("01001     CD   ").is_ok());
+
+        let err = |line: &str| match Detalhe::parse(line).unwrap_err() {
+            FixedWidthError::Validation(error) => error,
+            other => panic!("{other}"),
+        };
+        let error = err("0310000100AB   ");
+        assert_eq!((error.field, error.value.as_str()), ("codigo_movimento", "3"));
+        assert_eq!(error.to_string(), "campo 'codigo_movimento' com valor '3' inválido: fora dos valores aceitos (01, 02, 06)");
+        assert_eq!(err("0160000100AB   ").rule, "maior que o máximo 500");
+        assert_eq!(err("0100000100AB   ").rule, "menor que o mínimo 1");
+        assert_eq!(err("0110000000AB   ").rule, "menor que o mínimo 0.01");
+        assert_eq!(err("0110000100     ").rule, "campo obrigatório em branco");
+        assert_eq!(err("0110000100XY   ").value, "XY");
+    }
+
+    #[test]
+    fn compares_integers_without_rounding_through_f64() {
+        #[derive(FixedWidth, Debug)]
+        struct Controle {
+            #[fw(pos = "1..16", numeric, validate(max = 9_007_199_254_740_992))]
+            sequencial: u64,
+        }
+
+        // 2^53 + 1 vira 2^53 em f64 e passaria pelo máximo
+        assert_eq!(Controle::parse("9007199254740992").unwrap().sequencial, 9_007_199_254_740_992);
+        let error = Controle::parse("9007199254740993").unwrap_err();
+        assert!(matches!(error, FixedWidthError::Validation(ValidationError { rule, .. }) if rule == "maior que o máximo 9007199254740992"));
+        assert!(Number::Int(1) < Number::Float(1.5) && Number::parse("01") == Some(Number::Int(1)));
+    }
+}
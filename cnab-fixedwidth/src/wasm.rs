@@ -0,0 +1,61 @@
+//! # Bindings para JavaScript (feature `wasm-bindgen`)
+//!
+//! Expõe o parser dinâmico para ferramentas no navegador (depuradores de layout,
+//! validadores no cliente), com a mesma lógica de parsing do servidor.
+//!
+//! ```js
+//! import init, { parseToJson } from "./pkg/cnab_fixedwidth.js";
+//!
+//! await init();
+//! const layout = JSON.stringify({
+//!   width: 240,
+//!   fields: [{ name: "codigo_banco", pos: "1..3", kind: "numeric" }],
+//! });
+//! const record = JSON.parse(parseToJson(linha, layout));
+//! ```
+
+use crate::layout::from_json;
+use crate::{Encoding, ParseOptions};
+use wasm_bindgen::prelude::*;
+
+/// Faz o parse de uma linha com um layout em JSON (o formato de [`from_json`]) e
+/// devolve os campos como um objeto JSON, na ordem do layout.
+///
+/// A linha vem como string JavaScript: cada caractere deve estar na faixa Latin-1
+/// (como ao ler o arquivo com `TextDecoder("latin1")`), para que as posições
+/// continuem contando bytes do arquivo original.
+#[wasm_bindgen(js_name = parseToJson)]
+pub fn parse_to_json(line: &str, layout_json: &str) -> Result<String, JsError> {
+    parse(line, layout_json).map_err(|message| JsError::new(&message))
+}
+
+fn parse(line: &str, layout_json: &str) -> Result<String, String> {
+    let layout = from_json(layout_json).map_err(|e| e.to_string())?;
+    let bytes = line
+        .chars()
+        .map(|c| u8::try_from(c).map_err(|_| format!("caractere fora do Latin-1 na linha: '{c}'")))
+        .collect::<Result<Vec<u8>, String>>()?;
+    let record = layout
+        .parse_bytes(&bytes, &ParseOptions::with_encoding(Encoding::Latin1))
+        .map_err(|e| e.to_string())?;
+    Ok(record.to_json())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_with_json_layout() {
+        let layout = r#"{"fields":[
+            {"name":"banco","pos":"1..3","kind":"numeric"},
+            {"name":"nome","pos":"4..8","kind":"alpha"},
+            {"name":"valor","pos":"9..13","kind":"decimal","scale":2}
+        ]}"#;
+        assert_eq!(
+            parse("341JOÃO 01234", layout).unwrap(),
+            r#"{"banco":341,"nome":"JOÃO","valor":12.34}"#
+        );
+        assert!(parse("341JO€O 01234", layout).unwrap_err().contains("Latin-1"));
+    }
+}
@@ -0,0 +1,696 @@
+//! # Escrita de Registros
+//!
+//! Formata valores nas posições do layout, no sentido inverso do parse: alfanuméricos
+//! alinhados à esquerda com espaços, numéricos à direita com zeros, e lacunas/fillers
+//! em branco.
+//!
+//! Structs derivadas com `#[fw_record(write)]` implementam [`FixedWidthWrite`]; layouts
+//! em tempo de execução usam [`crate::Layout::to_bytes`].
+
+use crate::banks::Bank;
+use crate::{
+    Align, Encoding, FieldKind, FieldSpec, FixedWidthError, FixedWidthParse, FixedWidthSpec, ParseOptions, RecordSpan,
+    Result, Sign, Value,
+};
+
+/// Opções que controlam a escrita de uma linha.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Codificação dos campos de texto (padrão: Latin-1, como os bancos esperam).
+    pub encoding: Encoding,
+    /// Tratamento de textos dos campos sem política própria (`FieldSpec::write_policy`).
+    pub policy: WritePolicy,
+    /// Banco cujas regras preenchem os DVs de agência e conta deixados em branco (usado
+    /// por `cnab240::FileWriter`; veja [`crate::dv`]).
+    pub check_digits: Option<Bank>,
+}
+
+impl WriteOptions {
+    /// Opções padrão, com outra codificação.
+    pub fn with_encoding(encoding: Encoding) -> Self {
+        Self { encoding, ..Self::default() }
+    }
+
+    /// Usa `policy` nos campos de texto que não declaram política própria.
+    pub fn with_policy(mut self, policy: WritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Preenche os DVs de agência e conta em branco com as regras de `banco`.
+    pub fn with_check_digits(mut self, banco: Bank) -> Self {
+        self.check_digits = Some(banco);
+        self
+    }
+}
+
+/// Tratamento de um texto antes de ser escrito em um campo alfanumérico.
+///
+/// A política do campo (`#[fw(truncate)]`, `FieldSpec::write_policy`) tem precedência
+/// sobre a de `WriteOptions`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WritePolicy {
+    /// O que fazer quando o texto não cabe na largura do campo.
+    pub on_overflow: Overflow,
+    /// Converte o texto para maiúsculas.
+    pub uppercase: bool,
+    /// Troca letras acentuadas pela letra sem acento (`"Gonçalves"` vira `"Goncalves"`).
+    pub strip_accents: bool,
+    /// Converte o texto para o conjunto de caracteres CNAB com [`crate::normalize::normalize`].
+    #[cfg(feature = "translit")]
+    pub normalize: bool,
+}
+
+/// Texto maior que a largura do campo.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Overflow {
+    /// Rejeita o valor com `ValueTooLong` (padrão: nada é descartado sem aviso).
+    #[default]
+    Error,
+    /// Descarta o excedente à direita.
+    Truncate,
+}
+
+impl WritePolicy {
+    /// Política padrão (erro em textos longos, sem conversões), utilizável em `const`.
+    pub const DEFAULT: WritePolicy = WritePolicy {
+        on_overflow: Overflow::Error,
+        uppercase: false,
+        strip_accents: false,
+        #[cfg(feature = "translit")]
+        normalize: false,
+    };
+
+    /// Corta o excedente de textos longos em vez de rejeitá-los.
+    pub const fn truncate(mut self) -> Self {
+        self.on_overflow = Overflow::Truncate;
+        self
+    }
+
+    /// Converte o texto para maiúsculas.
+    pub const fn uppercase(mut self) -> Self {
+        self.uppercase = true;
+        self
+    }
+
+    /// Remove os acentos das letras.
+    pub const fn strip_accents(mut self) -> Self {
+        self.strip_accents = true;
+        self
+    }
+
+    /// Translitera o texto para o conjunto de caracteres CNAB.
+    #[cfg(feature = "translit")]
+    pub const fn normalize(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+
+    /// Aplica as conversões de texto (maiúsculas, acentos) antes da codificação.
+    pub fn apply(&self, text: &str) -> String {
+        let text: String = if self.strip_accents { text.chars().map(strip_accent).collect() } else { text.to_string() };
+        let text = if self.uppercase { text.to_uppercase() } else { text };
+        #[cfg(feature = "translit")]
+        if self.normalize {
+            return crate::normalize::normalize(&text);
+        }
+        text
+    }
+}
+
+/// Letra sem acento, para as letras acentuadas do Latin-1; os demais caracteres não mudam.
+fn strip_accent(c: char) -> char {
+    match c {
+        'À'..='Å' => 'A',
+        'à'..='å' => 'a',
+        'Ç' => 'C',
+        'ç' => 'c',
+        'È'..='Ë' => 'E',
+        'è'..='ë' => 'e',
+        'Ì'..='Ï' => 'I',
+        'ì'..='ï' => 'i',
+        'Ñ' => 'N',
+        'ñ' => 'n',
+        'Ò'..='Ö' | 'Ø' => 'O',
+        'ò'..='ö' | 'ø' => 'o',
+        'Ù'..='Ü' => 'U',
+        'ù'..='ü' => 'u',
+        'Ý' => 'Y',
+        'ý' | 'ÿ' => 'y',
+        c => c,
+    }
+}
+
+/// Trait implementada pela macro derive com `#[fw_record(write)]`.
+/// Monta a linha do registro a partir da struct.
+pub trait FixedWidthWrite {
+    /// Escreve o registro completo (sem quebra de linha).
+    fn write_bytes(&self, options: &WriteOptions) -> Result<Vec<u8>>;
+
+    /// Escreve o registro em Latin-1.
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        self.write_bytes(&WriteOptions::default())
+    }
+
+    /// Escreve o registro como texto (UTF-8), o inverso de `FixedWidthParse::parse`.
+    fn to_line(&self) -> Result<String> {
+        let bytes = self.write_bytes(&WriteOptions::with_encoding(Encoding::Utf8))?;
+        String::from_utf8(bytes).map_err(|_| FixedWidthError::InvalidUtf8)
+    }
+}
+
+/// Registro lido junto com a linha original, para reescrever o arquivo sem perder o que
+/// o layout não modela.
+///
+/// Na escrita, os campos saem dos valores de `record`; posições não mapeadas, fillers
+/// declarados na struct e o que vier depois do fim do layout são copiados da linha
+/// original. Um registro lido e escrito sem alterações sai byte a byte igual, mesmo com
+/// dados do banco nas áreas reservadas:
+///
+/// ```no_run
+/// use cnab_fixedwidth::{FixedWidth, FixedWidthWrite, ParseOptions, RawRecord};
+///
+/// #[derive(FixedWidth)]
+/// #[fw(width = 20)]
+/// #[fw_record(write)]
+/// struct Detalhe {
+///     #[fw(pos = "1..15", decimal = 2)]
+///     valor: f64,
+/// }
+///
+/// # let linha = Vec::new();
+/// let mut titulo = RawRecord::<Detalhe>::parse_bytes(&linha, &ParseOptions::default())?;
+/// titulo.valor = 99.9;
+/// let linha = titulo.to_bytes()?;
+/// # Ok::<(), cnab_fixedwidth::FixedWidthError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawRecord<T> {
+    /// Os campos lidos, que podem ser alterados antes da escrita.
+    pub record: T,
+    raw: Vec<u8>,
+}
+
+impl<T> RawRecord<T> {
+    /// Junta um registro à linha de onde ele veio (sem quebra de linha).
+    pub fn new(record: T, raw: impl Into<Vec<u8>>) -> Self {
+        Self { record, raw: raw.into() }
+    }
+
+    /// A linha original, sem a quebra de linha.
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Descarta a linha original.
+    pub fn into_inner(self) -> T {
+        self.record
+    }
+}
+
+impl<T: FixedWidthParse> RawRecord<T> {
+    /// Faz o parse de uma linha já decodificada (UTF-8), guardando a linha.
+    pub fn parse(line: &str) -> Result<Self> {
+        Self::parse_bytes(line.as_bytes(), &ParseOptions::with_encoding(Encoding::Utf8))
+    }
+
+    /// Faz o parse de uma linha em bytes brutos, guardando a linha.
+    pub fn parse_bytes(line: &[u8], options: &ParseOptions) -> Result<Self> {
+        let line = crate::trim_line_ending(line);
+        Ok(Self::new(T::parse_bytes(line, options)?, line))
+    }
+}
+
+impl<T> std::ops::Deref for RawRecord<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.record
+    }
+}
+
+impl<T> std::ops::DerefMut for RawRecord<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.record
+    }
+}
+
+impl<T: FixedWidthWrite + FixedWidthSpec + RecordSpan> FixedWidthWrite for RawRecord<T> {
+    fn write_bytes(&self, options: &WriteOptions) -> Result<Vec<u8>> {
+        let mut line = self.record.write_bytes(options)?;
+        let fillers = T::spec().iter().filter(|field| field.kind == FieldKind::Filler).map(|field| field.pos);
+        for pos in crate::spec_gaps(T::mapped(), T::END).into_iter().chain(fillers) {
+            // Linha original mais curta que o layout: o que faltar fica como foi escrito
+            let (start, end) = (pos.start() - 1, pos.end().min(line.len()).min(self.raw.len()));
+            if start < end {
+                line[start..end].copy_from_slice(&self.raw[start..end]);
+            }
+        }
+        if self.raw.len() > line.len() {
+            line.extend_from_slice(&self.raw[line.len()..]);
+        }
+        Ok(line)
+    }
+}
+
+/// Conversão do valor de um campo da struct para `Value`, o inverso de `TryFrom<i64>`/
+/// `FromDecimal` no parse. Usada pela macro derive.
+pub trait ToValue {
+    /// Converte o valor conforme o tipo do campo (ex: a escala de `decimal = 2`).
+    fn to_value(&self, kind: FieldKind) -> Value;
+}
+
+impl ToValue for String {
+    fn to_value(&self, kind: FieldKind) -> Value {
+        self.as_str().to_value(kind)
+    }
+}
+
+impl ToValue for &str {
+    fn to_value(&self, _kind: FieldKind) -> Value {
+        Value::Alpha(self.to_string())
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self, kind: FieldKind) -> Value {
+        self.as_ref().map_or(Value::Null, |value| value.to_value(kind))
+    }
+}
+
+impl ToValue for () {
+    fn to_value(&self, _kind: FieldKind) -> Value {
+        Value::Null
+    }
+}
+
+/// Inteiros: em campos decimais, o valor é multiplicado pela escala (`12` com `decimal = 2`
+/// é escrito como `1200`). Valores fora de `i64` saturam e são rejeitados pela largura.
+macro_rules! impl_to_value_int {
+    ($($ty:ty),*) => {$(
+        impl ToValue for $ty {
+            fn to_value(&self, kind: FieldKind) -> Value {
+                let value = i64::try_from(*self).unwrap_or(i64::MAX);
+                match decimal_scale(kind) {
+                    Some(scale) => Value::Decimal { raw: value.saturating_mul(10_i64.pow(scale.into())), scale },
+                    None => Value::Numeric(value),
+                }
+            }
+        }
+    )*};
+}
+
+impl_to_value_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl ToValue for f64 {
+    fn to_value(&self, kind: FieldKind) -> Value {
+        match decimal_scale(kind) {
+            Some(scale) => Value::Decimal { raw: (self * 10_f64.powi(scale.into())).round() as i64, scale },
+            None => Value::Numeric(self.round() as i64),
+        }
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl ToValue for rust_decimal::Decimal {
+    fn to_value(&self, kind: FieldKind) -> Value {
+        use rust_decimal::prelude::ToPrimitive;
+        match decimal_scale(kind) {
+            Some(scale) => {
+                let raw = (self * rust_decimal::Decimal::from(10_i64.pow(scale.into()))).round();
+                Value::Decimal { raw: raw.to_i64().unwrap_or(i64::MAX), scale }
+            }
+            None => Value::Numeric(self.round().to_i64().unwrap_or(i64::MAX)),
+        }
+    }
+}
+
+/// Escala de campos com casas decimais implícitas (`None` para inteiros e texto).
+fn decimal_scale(kind: FieldKind) -> Option<u8> {
+    match kind {
+        FieldKind::Decimal { scale } | FieldKind::Zoned { scale } => Some(scale),
+        FieldKind::Packed { scale } if scale > 0 => Some(scale),
+        _ => None,
+    }
+}
+
+/// Monta um registro de `width` posições com os valores de cada campo, na ordem de `fields`.
+///
+/// Posições não mapeadas e fillers ficam em branco.
+pub fn write_line(fields: &[FieldSpec], values: &[Value], width: usize, options: &WriteOptions) -> Result<Vec<u8>> {
+    let mut line = vec![b' '; width];
+    for (field, value) in fields.iter().zip(values) {
+        write_field(&mut line, field, value, options)?;
+    }
+    Ok(line)
+}
+
+/// Escreve o valor "vazio" do campo: brancos em texto, zero em numéricos e decimais.
+///
+/// Usado nos campos `#[fw(read_only)]`, que só têm conteúdo no retorno.
+pub fn write_blank(line: &mut [u8], field: &FieldSpec, options: &WriteOptions) -> Result<()> {
+    let value = match field.kind {
+        FieldKind::Numeric => Value::Numeric(0),
+        FieldKind::Decimal { scale } | FieldKind::Zoned { scale } | FieldKind::Packed { scale } => {
+            Value::Decimal { raw: 0, scale }
+        }
+        FieldKind::Alpha | FieldKind::Filler => Value::Null,
+    };
+    write_field(line, field, &value, options)
+}
+
+/// Escreve o valor de um campo nas suas posições da linha (e, com `sign_pos`, o indicador D/C).
+///
+/// Fillers recebem o caractere de preenchimento do campo (padrão: espaço) e valores
+/// `Null` deixam o campo em branco.
+pub fn write_field(line: &mut [u8], field: &FieldSpec, value: &Value, options: &WriteOptions) -> Result<()> {
+    let width = field.pos.width();
+    if field.pos.end() > line.len() {
+        return Err(FixedWidthError::LineTooShort { len: line.len(), needed: field.pos.end() });
+    }
+    let range = field.pos.as_range();
+    let pad = field.pad.unwrap_or(match field.kind {
+        FieldKind::Numeric | FieldKind::Decimal { .. } | FieldKind::Zoned { .. } => b'0',
+        _ => b' ',
+    });
+
+    if let FieldKind::Filler = field.kind {
+        line[range].fill(pad);
+        return Ok(());
+    }
+    if let Value::Null = value {
+        line[range].fill(b' ');
+        return Ok(());
+    }
+
+    let bytes = match field.kind {
+        FieldKind::Alpha => {
+            let text = match value {
+                Value::Alpha(text) => text.clone(),
+                Value::Numeric(n) => n.to_string(),
+                _ => {
+                    return Err(FixedWidthError::Conversion {
+                        field: field.name,
+                        message: "valor decimal em campo alfanumérico".to_string(),
+                    });
+                }
+            };
+            let policy = field.policy.unwrap_or(options.policy);
+            let text = policy.apply(&text);
+            field.check_charset(&text, options.encoding)?;
+            let mut bytes = options.encoding.encode_field(&text, field.name)?;
+            if bytes.len() > width {
+                if policy.on_overflow == Overflow::Error {
+                    return Err(FixedWidthError::ValueTooLong { field: field.name, value: text, width });
+                }
+                // Em UTF-8 o corte recua até o início do caractere, sem partir um multibyte
+                let end = match options.encoding {
+                    Encoding::Utf8 => (0..=width).rev().find(|&end| text.is_char_boundary(end)).unwrap_or(0),
+                    _ => width,
+                };
+                bytes.truncate(end);
+            }
+            match field.align {
+                Some(Align::Right) => {
+                    let mut padded = vec![pad; width - bytes.len()];
+                    padded.extend(bytes);
+                    padded
+                }
+                _ => {
+                    bytes.resize(width, pad);
+                    bytes
+                }
+            }
+        }
+        FieldKind::Numeric | FieldKind::Decimal { .. } => {
+            let raw = raw_value(field, value)?;
+            let digits = raw.unsigned_abs().to_string();
+            let negative = raw < 0;
+            let sign = match field.sign {
+                Sign::Unsigned if negative => {
+                    return Err(FixedWidthError::NegativeUnsigned { field: field.name, value: raw });
+                }
+                Sign::Inline if negative => "-",
+                Sign::Indicator { pos, credit, debit } => {
+                    let len = line.len();
+                    let indicator = line
+                        .get_mut(pos.as_range())
+                        .ok_or(FixedWidthError::LineTooShort { len, needed: pos.end() })?;
+                    let flag = if negative { debit } else { credit };
+                    indicator.fill(b' ');
+                    let len = flag.len().min(indicator.len());
+                    indicator[..len].copy_from_slice(&flag.as_bytes()[..len]);
+                    ""
+                }
+                _ => "",
+            };
+            // O sinal fica sempre no início, antes do preenchimento com zeros
+            let text = match field.align {
+                Some(Align::Left) => {
+                    let mut text = format!("{sign}{digits}");
+                    text.extend(std::iter::repeat_n(pad as char, width.saturating_sub(text.len())));
+                    text
+                }
+                _ => format!("{sign}{}", left_pad(&digits, width.saturating_sub(sign.len()), pad)),
+            };
+            if text.len() > width {
+                return Err(FixedWidthError::ValueTooLong { field: field.name, value: raw.to_string(), width });
+            }
+            text.into_bytes()
+        }
+        FieldKind::Zoned { .. } => {
+            let raw = raw_value(field, value)?;
+            let mut text = left_pad(&raw.unsigned_abs().to_string(), width, b'0').into_bytes();
+            if text.len() > width {
+                return Err(FixedWidthError::ValueTooLong { field: field.name, value: raw.to_string(), width });
+            }
+            // Sinal sobreposto no último dígito: `{`/`A`..`I` positivos, `}`/`J`..`R` negativos
+            if let Some(last) = text.last_mut() {
+                let digit = *last - b'0';
+                *last = match (raw < 0, digit) {
+                    (false, 0) => b'{',
+                    (false, d) => b'A' + d - 1,
+                    (true, 0) => b'}',
+                    (true, d) => b'J' + d - 1,
+                };
+            }
+            text
+        }
+        FieldKind::Packed { .. } => {
+            let raw = raw_value(field, value)?;
+            let digits = raw.unsigned_abs().to_string();
+            // Cada byte guarda dois dígitos; o último, um dígito e o sinal
+            if digits.len() > width * 2 - 1 {
+                return Err(FixedWidthError::ValueTooLong { field: field.name, value: raw.to_string(), width });
+            }
+            let sign = if raw < 0 { 0x0D } else { 0x0C };
+            let nibbles: Vec<u8> = left_pad(&digits, width * 2 - 1, b'0')
+                .bytes()
+                .map(|d| d - b'0')
+                .chain([sign])
+                .collect();
+            nibbles.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect()
+        }
+        FieldKind::Filler => unreachable!("filler tratado acima"),
+    };
+    line[range].copy_from_slice(&bytes);
+    Ok(())
+}
+
+/// Copia um bloco aninhado já escrito para a linha, a partir do deslocamento.
+pub fn write_nested(line: &mut [u8], offset: usize, block: &[u8]) -> Result<()> {
+    let len = line.len();
+    line.get_mut(offset..offset + block.len())
+        .ok_or(FixedWidthError::LineTooShort { len, needed: offset + block.len() })?
+        .copy_from_slice(block);
+    Ok(())
+}
+
+/// Inteiro bruto de um campo numérico/decimal, na escala do campo.
+fn raw_value(field: &FieldSpec, value: &Value) -> Result<i64> {
+    let scale = decimal_scale(field.kind).unwrap_or(0);
+    match value {
+        Value::Numeric(n) => 10_i64.checked_pow(scale.into()).and_then(|factor| n.checked_mul(factor)).ok_or(FixedWidthError::ValueTooLong {
+            field: field.name,
+            value: n.to_string(),
+            width: field.pos.width(),
+        }),
+        Value::Decimal { raw, scale: value_scale } if *value_scale <= scale => 10_i64
+            .checked_pow((scale - value_scale).into())
+            .and_then(|factor| raw.checked_mul(factor))
+            .ok_or(FixedWidthError::ValueTooLong {
+                field: field.name,
+                value: value.to_string(),
+                width: field.pos.width(),
+            }),
+        Value::Decimal { scale: value_scale, .. } => Err(FixedWidthError::Conversion {
+            field: field.name,
+            message: format!("valor com {} casas decimais em campo com {}", value_scale, scale),
+        }),
+        // Texto só com dígitos (ex: nosso número guardado como String)
+        Value::Alpha(text) if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) => {
+            text.parse().map_err(|_| FixedWidthError::ValueTooLong {
+                field: field.name,
+                value: text.clone(),
+                width: field.pos.width(),
+            })
+        }
+        Value::Alpha(text) => Err(FixedWidthError::InvalidNumeric { field: field.name, snippet: text.clone() }),
+        Value::Null => Ok(0),
+    }
+}
+
+/// Completa `digits` à esquerda com `pad` até `width` caracteres.
+fn left_pad(digits: &str, width: usize, pad: u8) -> String {
+    let mut text = String::with_capacity(width);
+    text.extend(std::iter::repeat_n(pad as char, width.saturating_sub(digits.len())));
+    text.push_str(digits);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldPos, FixedWidth, FixedWidthParse};
+
+    #[test]
+    fn writes_fields_with_cnab_padding() {
+        let fields = [
+            FieldSpec::new("codigo", FieldPos::new_const(1, 3), FieldKind::Numeric),
+            FieldSpec::new("nome", FieldPos::new_const(4, 10), FieldKind::Alpha),
+            FieldSpec::new("uso_febraban", FieldPos::new_const(11, 12), FieldKind::Filler).pad(b'0'),
+            FieldSpec::new("valor", FieldPos::new_const(13, 18), FieldKind::Decimal { scale: 2 }).signed(),
+            FieldSpec::new("ajuste", FieldPos::new_const(19, 22), FieldKind::Zoned { scale: 0 }),
+            FieldSpec::new("qtd", FieldPos::new_const(23, 24), FieldKind::Packed { scale: 0 }),
+        ];
+        let values = [
+            Value::Numeric(7),
+            Value::Alpha("JOSÉ".to_string()),
+            Value::Null,
+            Value::Decimal { raw: -1234, scale: 2 },
+            Value::Numeric(-12),
+            Value::Numeric(-42),
+        ];
+        let line = write_line(&fields, &values, 26, &WriteOptions::default()).unwrap();
+        assert_eq!(&line[..22], b"007JOS\xc9   00-01234001K");
+        assert_eq!(&line[22..], [0x04, 0x2D, b' ', b' ']);
+
+        let too_long = Value::Alpha("NOME GRANDE".to_string());
+        assert!(matches!(
+            write_line(&fields[1..2], &[too_long], 10, &WriteOptions::default()),
+            Err(FixedWidthError::ValueTooLong { field: "nome", width: 7, .. })
+        ));
+
+        // Indicador de débito próprio do layout
+        let indicador = FieldSpec::new("valor", FieldPos::new_const(1, 4), FieldKind::Numeric)
+            .sign_indicator_with(FieldPos::new_const(5, 5), "+", "-");
+        let line = write_line(&[indicador], &[Value::Numeric(-12)], 5, &WriteOptions::default()).unwrap();
+        assert_eq!(line, b"0012-");
+
+        // Reescalar para as casas do campo não pode estourar o i64
+        let huge = Value::Decimal { raw: i64::MAX, scale: 0 };
+        assert!(matches!(
+            write_line(&fields[3..4], &[huge], 18, &WriteOptions::default()),
+            Err(FixedWidthError::ValueTooLong { field: "valor", .. })
+        ));
+    }
+
+    #[test]
+    fn derived_records_round_trip() {
+        #[derive(FixedWidth, Debug, PartialEq)]
+        #[fw(width = 20)]
+        #[fw_record(write)]
+        struct Detalhe {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "4..8", filler)]
+            uso_febraban: (),
+            #[fw(pos = "9..13", alpha)]
+            nome: String,
+            #[fw(pos = "14..19", decimal = 2, sign_pos = "20..20")]
+            valor: f64,
+        }
+
+        let detalhe = Detalhe { banco: 341, uso_febraban: (), nome: "ANA".to_string(), valor: -12.5 };
+        let line = detalhe.to_line().unwrap();
+        assert_eq!(line, "341     ANA  001250D");
+        assert_eq!(Detalhe::parse(&line).unwrap(), detalhe);
+    }
+
+    #[test]
+    fn raw_records_keep_unmapped_bytes() {
+        #[derive(FixedWidth, Debug, PartialEq)]
+        #[fw(width = 20)]
+        #[fw_record(write)]
+        struct Detalhe {
+            #[fw(pos = "1..3", numeric)]
+            banco: u16,
+            #[fw(pos = "4..5", filler)]
+            uso_febraban: (),
+            #[fw(pos = "6..10", alpha)]
+            nome: String,
+            #[fw(pos = "14..19", decimal = 2)]
+            valor: f64,
+        }
+
+        // Filler com "XY", posições 11..13 e 20 não mapeadas e dados além do layout
+        let line = "341XYANA  A7B0012509EXTRA\r\n";
+        let mut detalhe = RawRecord::<Detalhe>::parse(line).unwrap();
+        assert_eq!(detalhe.raw(), b"341XYANA  A7B0012509EXTRA");
+        assert_eq!(detalhe.to_line().unwrap(), "341XYANA  A7B0012509EXTRA");
+
+        detalhe.valor = 99.9;
+        detalhe.nome = "BIA".to_string();
+        assert_eq!(detalhe.to_line().unwrap(), "341XYBIA  A7B0099909EXTRA");
+        assert_eq!(detalhe.into_inner().to_line().unwrap(), "341  BIA     009990 ");
+    }
+
+    #[test]
+    fn align_and_pad_override_conventions() {
+        #[derive(FixedWidth, Debug, PartialEq)]
+        #[fw_record(write)]
+        struct Titulo {
+            // Nosso número alfanumérico, alinhado à direita e preenchido com zeros
+            #[fw(pos = "1..10", alpha, align = "right", pad = '0')]
+            nosso_numero: String,
+            #[fw(pos = "11..16", numeric, align = "left", pad = ' ')]
+            sequencial: u32,
+            #[fw(pos = "17..20", numeric, pad = '*')]
+            parcela: u16,
+        }
+
+        let titulo = Titulo { nosso_numero: "12345A".to_string(), sequencial: 42, parcela: 3 };
+        let line = titulo.to_line().unwrap();
+        assert_eq!(line, "000012345A42    ***3");
+        assert_eq!(Titulo::parse(&line).unwrap(), titulo);
+    }
+
+    #[test]
+    fn write_policy_truncates_and_normalizes_text() {
+        #[derive(FixedWidth, Debug)]
+        #[fw_record(write(uppercase, strip_accents))]
+        struct Sacado {
+            #[fw(pos = "1..10", alpha, truncate)]
+            nome: String,
+            #[fw(pos = "11..15", alpha)]
+            cidade: String,
+        }
+
+        let sacado = Sacado { nome: "José Gonçalves".to_string(), cidade: "Itú".to_string() };
+        assert_eq!(sacado.to_line().unwrap(), "JOSE GONCAITU  ");
+
+        // Sem truncate no campo, o excedente continua sendo um erro
+        let sacado = Sacado { nome: "Ana".to_string(), cidade: "São Paulo".to_string() };
+        assert!(matches!(sacado.to_bytes(), Err(FixedWidthError::ValueTooLong { field: "cidade", width: 5, .. })));
+
+        // Em UTF-8, o corte não parte um caractere multibyte
+        let nome = FieldSpec::new("nome", FieldPos::new_const(1, 3), FieldKind::Alpha);
+        let options = WriteOptions::with_encoding(Encoding::Utf8)
+            .with_policy(WritePolicy { on_overflow: Overflow::Truncate, ..WritePolicy::default() });
+        let line = write_line(&[nome], &[Value::Alpha("Joãozinho".to_string())], 3, &options).unwrap();
+        assert_eq!(line, b"Jo ");
+    }
+}